@@ -84,6 +84,7 @@ impl Game for GuiGame {
         }
         self.example.update(&mut self.gui);
         self.gui.update(input_actions);
+        window.set_cursor_icon(self.gui.desired_cursor());
     }
 }
 
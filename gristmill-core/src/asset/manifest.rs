@@ -0,0 +1,109 @@
+use super::{asset_base_path, open_reader, pak, AssetError, AssetResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, fs, io::Read, path::Path, sync::OnceLock};
+
+/// Name of the manifest file, next to the asset directories. Integrity checks are only done if it
+/// exists.
+pub const MANIFEST_FILE: &str = "manifest.yaml";
+
+fn hash_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// SHA-256 hashes of shipped asset files, used to detect corrupted or modified files.
+#[derive(Default, Serialize, Deserialize)]
+pub struct AssetManifest {
+    files: BTreeMap<String, String>,
+}
+
+impl AssetManifest {
+    /// Hash every file under `base_dir/prefix` for each prefix. Run this as part of the build
+    /// and save the result to `MANIFEST_FILE`.
+    pub fn generate(base_dir: &Path, prefixes: &[&str]) -> AssetResult<Self> {
+        let mut files = Vec::new();
+        for prefix in prefixes {
+            pak::collect_files(&base_dir.join(prefix), &mut files)?;
+        }
+        let mut manifest = AssetManifest::default();
+        for file in files {
+            let name = pak::entry_name(file.strip_prefix(base_dir).unwrap());
+            manifest.files.insert(name, hash_hex(&fs::read(&file)?));
+        }
+        Ok(manifest)
+    }
+    pub fn load(path: &Path) -> AssetResult<Self> {
+        let reader = fs::File::open(path)?;
+        serde_yaml::from_reader(reader).map_err(|err| AssetError::InvalidFormat(err.to_string()))
+    }
+    pub fn save(&self, path: &Path) -> AssetResult<()> {
+        let writer = fs::File::create(path)?;
+        serde_yaml::to_writer(writer, self)
+            .map_err(|err| AssetError::InvalidFormat(err.to_string()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains_key(&pak::entry_name(path))
+    }
+
+    /// Check `data` against the stored hash for `path`. Files not in the manifest always pass.
+    pub fn verify(&self, path: &Path, data: &[u8]) -> AssetResult<()> {
+        let name = pak::entry_name(path);
+        match self.files.get(&name) {
+            Some(expected) if *expected != hash_hex(data) => Err(AssetError::Other(format!(
+                "asset {name} failed integrity check"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+static LOADED_MANIFEST: OnceLock<Option<AssetManifest>> = OnceLock::new();
+
+/// The manifest next to the game, loaded the first time it is needed.
+pub(super) fn loaded_manifest() -> Option<&'static AssetManifest> {
+    LOADED_MANIFEST
+        .get_or_init(|| {
+            let path = asset_base_path().join(MANIFEST_FILE);
+            if !path.exists() {
+                return None;
+            }
+            match AssetManifest::load(&path) {
+                Ok(manifest) => {
+                    log::debug!("Loaded asset manifest ({} files)", manifest.len());
+                    Some(manifest)
+                }
+                Err(error) => {
+                    log::error!("Failed to load asset manifest: {error}");
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// Check every file in the asset manifest up front, returning the first that is missing or fails
+/// its integrity check. Does nothing if there is no manifest.
+pub fn verify_assets() -> AssetResult<()> {
+    let manifest = if let Some(manifest) = loaded_manifest() {
+        manifest
+    } else {
+        return Ok(());
+    };
+    for name in manifest.files.keys() {
+        // Opening a file checks it against the manifest.
+        let mut reader = open_reader(&asset_base_path().join(name))?;
+        reader.read_to_end(&mut Vec::new())?;
+    }
+    log::info!("Verified {} asset files", manifest.len());
+    Ok(())
+}
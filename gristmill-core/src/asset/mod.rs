@@ -0,0 +1,229 @@
+mod manifest;
+mod pak;
+mod progress;
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt, fs,
+    fs::File,
+    io::{Cursor, Error as IoError, ErrorKind, Read, Seek, Write},
+    path::{Path, PathBuf},
+    sync::{OnceLock, RwLock},
+};
+
+pub use image;
+pub use manifest::*;
+pub use pak::*;
+pub use progress::*;
+
+pub trait ReadSeek: Read + Seek {}
+impl<T: Read + Seek> ReadSeek for T {}
+
+pub type BufReader = std::io::BufReader<Box<dyn ReadSeek>>;
+pub type BufWriter = std::io::BufWriter<File>;
+
+// Debug: expect working dir to be cargo project, so look for assets relative to that
+#[cfg(debug_assertions)]
+fn asset_base_path() -> PathBuf {
+    PathBuf::new()
+}
+
+// Release: always look for assets relative to the executable
+#[cfg(not(debug_assertions))]
+fn asset_base_path() -> PathBuf {
+    // TODO cache this
+    let mut dir = std::env::current_exe().unwrap();
+    dir.pop();
+    dir
+}
+
+static USER_BASE_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+fn is_user_prefix(prefix: &str) -> bool {
+    prefix == "config" || prefix == "save"
+}
+
+/// Store the "config" and "save" prefixes in the platform config directory (`%APPDATA%` on Windows,
+/// `~/.config` on Linux, `~/Library/Application Support` on macOS) instead of next to the game.
+///
+/// Call this once at startup, before anything is loaded. Files that already exist in the old
+/// location are copied over the first time the new directory is created, so existing installs keep
+/// their settings.
+pub fn use_platform_config_dir(package_name: &str) -> AssetResult<()> {
+    let dirs = directories::ProjectDirs::from("", "", package_name)
+        .ok_or_else(|| AssetError::Other("no home directory found".to_owned()))?;
+    let user_path = dirs.config_dir().to_owned();
+    for prefix in ["config", "save"] {
+        let new_dir = user_path.join(prefix);
+        if new_dir.exists() {
+            continue;
+        }
+        fs::create_dir_all(&new_dir)?;
+        let old_dir = asset_base_path().join(prefix);
+        if let Ok(entries) = fs::read_dir(old_dir) {
+            for entry in entries.flatten() {
+                if entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+                    log::info!(
+                        "Migrating {} to {}",
+                        entry.path().display(),
+                        new_dir.display()
+                    );
+                    fs::copy(entry.path(), new_dir.join(entry.file_name()))?;
+                }
+            }
+        }
+    }
+    log::debug!("Using config directory: {}", user_path.display());
+    USER_BASE_PATH
+        .set(user_path)
+        .map_err(|_| AssetError::Other("config directory already set".to_owned()))
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    Io(IoError),
+    InvalidData,
+    InvalidFormat(String),
+    Other(String),
+}
+
+impl AssetError {
+    pub fn io_kind(&self) -> Option<std::io::ErrorKind> {
+        if let AssetError::Io(error) = self {
+            Some(error.kind())
+        } else {
+            None
+        }
+    }
+}
+
+impl From<IoError> for AssetError {
+    fn from(err: IoError) -> AssetError {
+        AssetError::Io(err)
+    }
+}
+impl From<image::ImageError> for AssetError {
+    fn from(error: image::ImageError) -> Self {
+        if let image::ImageError::IoError(io_error) = error {
+            AssetError::Io(io_error)
+        } else {
+            AssetError::InvalidFormat(error.to_string())
+        }
+    }
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Io(error) => write!(f, "{error}"),
+            AssetError::InvalidData => write!(f, "invalid data"),
+            AssetError::InvalidFormat(info) => write!(f, "{info}"),
+            AssetError::Other(info) => write!(f, "{info}"),
+        }
+    }
+}
+
+pub type AssetResult<T> = Result<T, AssetError>;
+
+/// A place asset files can be read from, such as a pak archive.
+pub trait VfsSource: Send + Sync {
+    /// Open the file at `path`, relative to the asset base directory. Returns `None` if this source
+    /// doesn't contain the file.
+    fn open(&self, path: &Path) -> AssetResult<Option<Box<dyn ReadSeek>>>;
+}
+
+static MOUNTED_SOURCES: RwLock<Vec<Box<dyn VfsSource>>> = RwLock::new(Vec::new());
+
+/// Add a source to search when a file doesn't exist on disk. Loose files always take priority, so
+/// they can be used to override packed assets. Sources are searched in the order they were mounted.
+pub fn mount<S: VfsSource + 'static>(source: S) {
+    MOUNTED_SOURCES.write().unwrap().push(Box::new(source));
+}
+pub fn mount_pak(file: &str) -> AssetResult<()> {
+    let pak = PakArchive::open(&asset_base_path().join(file))?;
+    log::debug!("Mounted pak {} ({} files)", file, pak.len());
+    mount(pak);
+    Ok(())
+}
+
+fn open_mounted(path: &Path) -> AssetResult<Box<dyn ReadSeek>> {
+    let relative_path = path.strip_prefix(asset_base_path()).unwrap_or(path);
+    for source in MOUNTED_SOURCES.read().unwrap().iter() {
+        if let Some(reader) = source.open(relative_path)? {
+            return Ok(reader);
+        }
+    }
+    Err(IoError::from(ErrorKind::NotFound).into())
+}
+
+pub fn get_path(prefix: &str, asset_path: &str) -> PathBuf {
+    let mut file_path = match USER_BASE_PATH.get() {
+        Some(user_path) if is_user_prefix(prefix) => user_path.clone(),
+        _ => asset_base_path(),
+    };
+    file_path.push(prefix);
+    file_path.push(asset_path);
+    file_path
+}
+pub fn open_reader(path: &Path) -> AssetResult<BufReader> {
+    log::trace!("Reading file: {}", path.to_string_lossy());
+    let mut reader: Box<dyn ReadSeek> = match File::open(path) {
+        Ok(file) => Box::new(file),
+        Err(error) if error.kind() == ErrorKind::NotFound => open_mounted(path)?,
+        Err(error) => return Err(error.into()),
+    };
+    if let Some(manifest) = loaded_manifest() {
+        let relative_path = path.strip_prefix(asset_base_path()).unwrap_or(path);
+        if manifest.contains(relative_path) {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            manifest.verify(relative_path, &data)?;
+            reader = Box::new(Cursor::new(data));
+        }
+    }
+    Ok(BufReader::new(reader))
+}
+pub fn open_writer(path: &Path) -> AssetResult<BufWriter> {
+    log::trace!("Writing file: {}", path.to_string_lossy());
+    Ok(BufWriter::new(File::create(path)?))
+}
+
+pub fn load_text_file(prefix: &str, file: &str) -> Result<String, AssetError> {
+    let path = get_path(prefix, file);
+    let mut reader = open_reader(&path)?;
+    let mut string = String::new();
+    reader.read_to_string(&mut string)?;
+    Ok(string)
+}
+pub fn save_text_file(prefix: &str, file: &str, value: &str) -> Result<(), AssetError> {
+    let path = get_path(prefix, file);
+    let mut writer = open_writer(&path)?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+pub fn load_yaml_file<T>(prefix: &str, file: &str) -> Result<T, AssetError>
+where
+    T: DeserializeOwned,
+{
+    let path = get_path(prefix, file);
+    let reader = open_reader(&path)?;
+    serde_yaml::from_reader(reader).map_err(|err| AssetError::InvalidFormat(err.to_string()))
+}
+pub fn save_yaml_file<T>(prefix: &str, file: &str, value: &T) -> Result<(), AssetError>
+where
+    T: Serialize,
+{
+    let path = get_path(prefix, file);
+    let writer = open_writer(&path)?;
+    serde_yaml::to_writer(writer, value).map_err(|err| AssetError::InvalidFormat(err.to_string()))
+}
+
+pub fn load_image_file(prefix: &str, file: &str) -> Result<image::DynamicImage, AssetError> {
+    let path = get_path(prefix, file);
+    let mut reader = image::io::Reader::new(open_reader(&path)?);
+    if let Ok(format) = image::ImageFormat::from_path(&path) {
+        reader.set_format(format);
+    }
+    Ok(reader.decode()?)
+}
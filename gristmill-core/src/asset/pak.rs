@@ -0,0 +1,335 @@
+use super::{AssetError, AssetResult, ReadSeek, VfsSource};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufWriter, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+};
+
+const PAK_MAGIC: &[u8; 4] = b"GPAK";
+const PAK_VERSION: u32 = 1;
+const HEADER_LEN: u64 = 12;
+/// The size of an index entry with an empty name.
+const ENTRY_LEN: u64 = 20;
+
+// Pak files are laid out as:
+//   magic, version: u32, entry count: u32
+//   for each entry: name length: u32, name (utf-8, '/' separated), offset: u64, length: u64
+//   file data
+// All integers are little-endian, and offsets are from the start of the pak.
+
+pub(super) fn entry_name(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> AssetResult<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+fn read_u64<R: Read>(reader: &mut R) -> AssetResult<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Whether `name` stays inside the directory it's unpacked into: relative, with no `..`.
+fn is_contained(name: &str) -> bool {
+    !name.is_empty()
+        && Path::new(name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+pub(super) fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> AssetResult<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+struct PakEntry {
+    offset: u64,
+    length: u64,
+}
+
+/// A read-only archive of asset files, with an index at the head for random access.
+pub struct PakArchive {
+    path: PathBuf,
+    entries: HashMap<String, PakEntry>,
+}
+
+impl PakArchive {
+    /// Open a pak and read its index. Sizes in the index are checked against the size of the
+    /// file, so a truncated or corrupt pak fails here instead of when its files are read.
+    pub fn open(path: &Path) -> AssetResult<Self> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = std::io::BufReader::new(file);
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != PAK_MAGIC {
+            return Err(AssetError::InvalidFormat(format!(
+                "{} is not a pak file",
+                path.display()
+            )));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != PAK_VERSION {
+            return Err(AssetError::InvalidFormat(format!(
+                "unsupported pak version {version}"
+            )));
+        }
+        let invalid =
+            |message: &str| AssetError::InvalidFormat(format!("{}: {}", path.display(), message));
+        let count = read_u32(&mut reader)?;
+        if u64::from(count) * ENTRY_LEN > file_len.saturating_sub(HEADER_LEN) {
+            return Err(invalid("index is larger than the file"));
+        }
+        let mut entries = HashMap::with_capacity(count as usize);
+        let mut index_end = HEADER_LEN;
+        for _ in 0..count {
+            let name_len = read_u32(&mut reader)?;
+            index_end += ENTRY_LEN + u64::from(name_len);
+            if index_end > file_len {
+                return Err(invalid("index is larger than the file"));
+            }
+            let mut name = vec![0; name_len as usize];
+            reader.read_exact(&mut name)?;
+            let name = String::from_utf8(name).map_err(|_| AssetError::InvalidData)?;
+            let offset = read_u64(&mut reader)?;
+            let length = read_u64(&mut reader)?;
+            if offset.checked_add(length).is_none_or(|end| end > file_len) {
+                return Err(invalid(&format!("\"{name}\" is past the end of the file")));
+            }
+            entries.insert(name, PakEntry { offset, length });
+        }
+        Ok(PakArchive {
+            path: path.to_owned(),
+            entries,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn contains(&self, path: &Path) -> bool {
+        self.entries.contains_key(&entry_name(path))
+    }
+    pub fn file_names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn read(&self, path: &Path) -> AssetResult<Option<Vec<u8>>> {
+        let entry = if let Some(entry) = self.entries.get(&entry_name(path)) {
+            entry
+        } else {
+            return Ok(None);
+        };
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut data = vec![0; entry.length as usize];
+        file.read_exact(&mut data)?;
+        Ok(Some(data))
+    }
+
+    /// Pack every file under `base_dir/prefix` for each prefix (for example `"assets"`) into a
+    /// pak file at `output`.
+    pub fn pack(base_dir: &Path, prefixes: &[&str], output: &Path) -> AssetResult<()> {
+        let mut files = Vec::new();
+        for prefix in prefixes {
+            collect_files(&base_dir.join(prefix), &mut files)?;
+        }
+        let names: Vec<String> = files
+            .iter()
+            .map(|file| entry_name(file.strip_prefix(base_dir).unwrap()))
+            .collect();
+        let mut offset = HEADER_LEN;
+        offset += names
+            .iter()
+            .map(|name| ENTRY_LEN + name.len() as u64)
+            .sum::<u64>();
+
+        let mut writer = BufWriter::new(File::create(output)?);
+        writer.write_all(PAK_MAGIC)?;
+        writer.write_all(&PAK_VERSION.to_le_bytes())?;
+        writer.write_all(&(files.len() as u32).to_le_bytes())?;
+        for (file, name) in files.iter().zip(names.iter()) {
+            let length = fs::metadata(file)?.len();
+            writer.write_all(&(name.len() as u32).to_le_bytes())?;
+            writer.write_all(name.as_bytes())?;
+            writer.write_all(&offset.to_le_bytes())?;
+            writer.write_all(&length.to_le_bytes())?;
+            offset += length;
+        }
+        for file in files.iter() {
+            std::io::copy(&mut File::open(file)?, &mut writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+    /// Extract every file in the pak into `output_dir`. Fails without writing anything if any
+    /// file name is absolute or contains `..`, since it would be written outside `output_dir`.
+    pub fn unpack(&self, output_dir: &Path) -> AssetResult<()> {
+        if let Some(name) = self.entries.keys().find(|name| !is_contained(name)) {
+            return Err(AssetError::InvalidFormat(format!(
+                "pak file name \"{name}\" is outside the output directory"
+            )));
+        }
+        for name in self.entries.keys() {
+            let path = output_dir.join(name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let data = self.read(Path::new(name))?.unwrap();
+            fs::write(path, data)?;
+        }
+        Ok(())
+    }
+}
+
+impl VfsSource for PakArchive {
+    fn open(&self, path: &Path) -> AssetResult<Option<Box<dyn ReadSeek>>> {
+        Ok(self
+            .read(path)?
+            .map(|data| -> Box<dyn ReadSeek> { Box::new(Cursor::new(data)) }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("gristmill_pak_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Write a pak by hand, so entries can have any name and size.
+    fn write_pak(path: &Path, count: u32, entries: &[(&str, u64, u64)], data: &[u8]) {
+        let mut bytes = PAK_MAGIC.to_vec();
+        bytes.extend_from_slice(&PAK_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&count.to_le_bytes());
+        for (name, offset, length) in entries {
+            bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            bytes.extend_from_slice(&length.to_le_bytes());
+        }
+        bytes.extend_from_slice(data);
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn pack_and_read() {
+        let dir = TempDir::new("round_trip");
+        fs::create_dir_all(dir.0.join("assets/sub")).unwrap();
+        fs::write(dir.0.join("assets/a.txt"), b"alpha").unwrap();
+        fs::write(dir.0.join("assets/sub/b.txt"), b"beta").unwrap();
+        let pak_path = dir.0.join("game.pak");
+        PakArchive::pack(&dir.0, &["assets"], &pak_path).unwrap();
+
+        let pak = PakArchive::open(&pak_path).unwrap();
+        assert_eq!(pak.len(), 2);
+        assert_eq!(
+            pak.read(Path::new("assets/sub/b.txt")).unwrap().unwrap(),
+            b"beta"
+        );
+        assert!(pak.read(Path::new("assets/c.txt")).unwrap().is_none());
+    }
+
+    #[test]
+    fn entry_count_larger_than_file_is_rejected() {
+        let dir = TempDir::new("count");
+        let pak_path = dir.0.join("bad.pak");
+        write_pak(&pak_path, u32::MAX, &[], &[]);
+        assert!(matches!(
+            PakArchive::open(&pak_path),
+            Err(AssetError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn name_length_larger_than_file_is_rejected() {
+        let dir = TempDir::new("name");
+        let pak_path = dir.0.join("bad.pak");
+        let mut bytes = PAK_MAGIC.to_vec();
+        bytes.extend_from_slice(&PAK_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&[0; 16]);
+        fs::write(&pak_path, bytes).unwrap();
+        assert!(matches!(
+            PakArchive::open(&pak_path),
+            Err(AssetError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn entry_past_end_of_file_is_rejected() {
+        let dir = TempDir::new("length");
+        let pak_path = dir.0.join("bad.pak");
+        write_pak(&pak_path, 1, &[("a.txt", 37, u64::MAX)], b"data");
+        assert!(matches!(
+            PakArchive::open(&pak_path),
+            Err(AssetError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn unpack_rejects_names_outside_output_dir() {
+        for name in [
+            "../escape.txt",
+            "/tmp/escape.txt",
+            "assets/../../escape.txt",
+        ] {
+            let dir = TempDir::new("escape");
+            let pak_path = dir.0.join("bad.pak");
+            let offset = HEADER_LEN + ENTRY_LEN + name.len() as u64;
+            write_pak(&pak_path, 1, &[(name, offset, 4)], b"data");
+            let pak = PakArchive::open(&pak_path).unwrap();
+            let output = dir.0.join("out");
+            assert!(pak.unpack(&output).is_err(), "{name} was unpacked");
+            assert!(!output.exists());
+        }
+    }
+
+    #[test]
+    fn unpack_writes_nested_files() {
+        let dir = TempDir::new("unpack");
+        let pak_path = dir.0.join("game.pak");
+        let name = "assets/sub/b.txt";
+        let offset = HEADER_LEN + ENTRY_LEN + name.len() as u64;
+        write_pak(&pak_path, 1, &[(name, offset, 4)], b"beta");
+        let output = dir.0.join("out");
+        PakArchive::open(&pak_path)
+            .unwrap()
+            .unpack(&output)
+            .unwrap();
+        assert_eq!(fs::read(output.join(name)).unwrap(), b"beta");
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+#[derive(Default)]
+struct ProgressState {
+    loaded: AtomicUsize,
+    total: AtomicUsize,
+    status: Mutex<String>,
+}
+
+/// A thread-safe counter for showing how far a background load has gotten. Clones share the same
+/// counter, so the loader thread can advance it while the main thread keeps rendering a loading
+/// screen.
+#[derive(Clone, Default)]
+pub struct LoadingProgress(Arc<ProgressState>);
+
+impl LoadingProgress {
+    pub fn new(total: usize) -> Self {
+        let progress = LoadingProgress::default();
+        progress.set_total(total);
+        progress
+    }
+
+    /// Run `load` on a new thread, passing it a handle to this progress.
+    pub fn spawn<F, T>(&self, load: F) -> JoinHandle<T>
+    where
+        F: FnOnce(LoadingProgress) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let progress = self.clone();
+        thread::spawn(move || load(progress))
+    }
+
+    pub fn set_total(&self, total: usize) {
+        self.0.total.store(total, Ordering::Relaxed);
+    }
+    /// Add to the total, for loaders that discover more work as they go.
+    pub fn add_total(&self, count: usize) {
+        self.0.total.fetch_add(count, Ordering::Relaxed);
+    }
+    /// Mark `count` more items as loaded.
+    pub fn advance(&self, count: usize) {
+        self.0.loaded.fetch_add(count, Ordering::Relaxed);
+    }
+    /// Set the text shown under the progress bar, such as the name of the file being loaded.
+    pub fn set_status<S: Into<String>>(&self, status: S) {
+        *self.0.status.lock().unwrap() = status.into();
+    }
+
+    pub fn loaded(&self) -> usize {
+        self.0.loaded.load(Ordering::Relaxed)
+    }
+    pub fn total(&self) -> usize {
+        self.0.total.load(Ordering::Relaxed)
+    }
+    pub fn status(&self) -> String {
+        self.0.status.lock().unwrap().clone()
+    }
+    /// How much has loaded, from 0 to 1. Nothing to load counts as finished.
+    pub fn fraction(&self) -> f32 {
+        let total = self.total();
+        if total == 0 {
+            1.0
+        } else {
+            (self.loaded() as f32 / total as f32).min(1.0)
+        }
+    }
+    pub fn is_done(&self) -> bool {
+        self.loaded() >= self.total()
+    }
+}
@@ -46,6 +46,74 @@ impl Rect {
         self.position + (self.size / 2.0)
     }
 
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.position.x <= point.x
+            && self.position.y <= point.y
+            && self.position.x + self.size.x > point.x
+            && self.position.y + self.size.y > point.y
+    }
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        point.clamp(self.position, self.position + self.size)
+    }
+    /// True if the rects share some area. Rects that only touch at an edge don't intersect.
+    pub fn intersects(&self, other: Rect) -> bool {
+        self.position.x < other.position.x + other.size.x
+            && other.position.x < self.position.x + self.size.x
+            && self.position.y < other.position.y + other.size.y
+            && other.position.y < self.position.y + self.size.y
+    }
+    /// The smallest rect containing both rects.
+    pub fn union(&self, other: Rect) -> Rect {
+        let min = self.position.min(other.position);
+        let max = (self.position + self.size).max(other.position + other.size);
+        Rect {
+            position: min,
+            size: max - min,
+        }
+    }
+
+    /// The largest rect with the given aspect ratio (width / height) that fits centered inside
+    /// this one, rounded to whole units.
+    pub fn fit_aspect(&self, aspect: f32) -> Rect {
+        let size = if self.size.x > self.size.y * aspect {
+            Vec2::new(self.size.y * aspect, self.size.y)
+        } else {
+            Vec2::new(self.size.x, self.size.x / aspect)
+        }
+        .round();
+        Rect {
+            position: (self.position + (self.size - size) / 2.0).floor(),
+            size,
+        }
+    }
+    /// The parts of this rect outside `inner`: full-width bars above and below, then bars to the
+    /// left and right between them. Empty bars are left out, so there are up to four.
+    pub fn margins_outside(&self, inner: Rect) -> Vec<Rect> {
+        let min = self.position;
+        let max = self.position + self.size;
+        let inner_min = inner.position.clamp(min, max);
+        let inner_max = (inner.position + inner.size).clamp(inner_min, max);
+        [
+            Rect::new(min.x, min.y, self.size.x, inner_min.y - min.y),
+            Rect::new(min.x, inner_max.y, self.size.x, max.y - inner_max.y),
+            Rect::new(
+                min.x,
+                inner_min.y,
+                inner_min.x - min.x,
+                inner_max.y - inner_min.y,
+            ),
+            Rect::new(
+                inner_max.x,
+                inner_min.y,
+                max.x - inner_max.x,
+                inner_max.y - inner_min.y,
+            ),
+        ]
+        .into_iter()
+        .filter(|bar| bar.size.x > 0.0 && bar.size.y > 0.0)
+        .collect()
+    }
+
     pub fn add_components(&self, other: Rect) -> Self {
         Rect {
             position: self.position + other.position,
@@ -75,12 +143,46 @@ impl From<Rect> for [f32; 4] {
     }
 }
 
+/// Deserializes from `{position: [x, y], size: [width, height]}`, `{x, y, width, height}`, or
+/// `[x, y, width, height]`.
 #[derive(Copy, Clone, Eq, PartialEq, Default, Debug, Serialize, Deserialize)]
+#[serde(from = "IRectDe")]
 pub struct IRect {
     pub position: IVec2,
     pub size: IVec2,
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IRectDe {
+    Fields {
+        position: IVec2,
+        size: IVec2,
+    },
+    Flat {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    List([i32; 4]),
+}
+
+impl From<IRectDe> for IRect {
+    fn from(rect: IRectDe) -> Self {
+        match rect {
+            IRectDe::Fields { position, size } => IRect { position, size },
+            IRectDe::Flat {
+                x,
+                y,
+                width,
+                height,
+            } => IRect::new(x, y, width, height),
+            IRectDe::List([x, y, width, height]) => IRect::new(x, y, width, height),
+        }
+    }
+}
+
 impl IRect {
     pub const ZERO: IRect = IRect {
         position: IVec2::ZERO,
@@ -123,6 +225,16 @@ impl IRect {
             && self.position.y + self.size.y > point.y
     }
 
+    /// The overlapping area of both rects. Has zero size if they don't overlap.
+    pub fn intersection(&self, other: IRect) -> IRect {
+        let min = self.position.max(other.position);
+        let max = (self.position + self.size).min(other.position + other.size);
+        IRect {
+            position: min,
+            size: (max - min).max(IVec2::ZERO),
+        }
+    }
+
     pub fn inset(&self, insets: EdgeRect) -> IRect {
         let inset_width = insets.left + insets.right;
         let inset_height = insets.top + insets.bottom;
@@ -145,6 +257,18 @@ impl IRect {
         }
     }
 
+    /// Scale by a factor, rounding each edge to the nearest pixel. Adjacent rects stay adjacent.
+    pub fn scale(&self, factor: f32) -> IRect {
+        let min = (self.position.as_vec2() * factor).round().as_ivec2();
+        let max = ((self.position + self.size).as_vec2() * factor)
+            .round()
+            .as_ivec2();
+        IRect {
+            position: min,
+            size: max - min,
+        }
+    }
+
     pub fn as_rect(&self) -> Rect {
         Rect {
             position: self.position.as_vec2(),
@@ -153,7 +277,14 @@ impl IRect {
     }
 }
 
+/// Deserializes from any of these, so layout files can use whichever reads best:
+/// - `8`: the same on all sides.
+/// - `[4, 8]`: vertical (top and bottom), then horizontal (left and right).
+/// - `[1, 2, 3, 4]`: top, right, bottom, left.
+/// - `{all: 8}`, `{vertical: 4, horizontal: 8}` or `{left: 4, top: 2}`: named sides, where more
+///   specific keys override `all` and missing sides are 0.
 #[derive(Copy, Clone, Eq, PartialEq, Default, Debug, Serialize, Deserialize)]
+#[serde(try_from = "EdgeRectDe")]
 pub struct EdgeRect {
     pub top: i32,
     pub right: i32,
@@ -184,4 +315,446 @@ impl EdgeRect {
             left: v,
         }
     }
+    /// Build from 1, 2 or 4 values, following the same shorthand as deserialization.
+    pub fn from_values(values: &[i32]) -> Option<Self> {
+        match *values {
+            [all] => Some(EdgeRect::splat(all)),
+            [vertical, horizontal] => {
+                Some(EdgeRect::new(vertical, horizontal, vertical, horizontal))
+            }
+            [top, right, bottom, left] => Some(EdgeRect::new(top, right, bottom, left)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EdgeSides {
+    all: Option<i32>,
+    vertical: Option<i32>,
+    horizontal: Option<i32>,
+    top: Option<i32>,
+    right: Option<i32>,
+    bottom: Option<i32>,
+    left: Option<i32>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EdgeRectDe {
+    All(i32),
+    List(Vec<i32>),
+    Sides(EdgeSides),
+}
+
+impl TryFrom<EdgeRectDe> for EdgeRect {
+    type Error = String;
+    fn try_from(edges: EdgeRectDe) -> Result<Self, Self::Error> {
+        match edges {
+            EdgeRectDe::All(all) => Ok(EdgeRect::splat(all)),
+            EdgeRectDe::List(values) => EdgeRect::from_values(&values)
+                .ok_or_else(|| format!("expected 1, 2 or 4 edge values, got {}", values.len())),
+            EdgeRectDe::Sides(EdgeSides {
+                all,
+                vertical,
+                horizontal,
+                top,
+                right,
+                bottom,
+                left,
+            }) => {
+                let all = all.unwrap_or(0);
+                let vertical = vertical.unwrap_or(all);
+                let horizontal = horizontal.unwrap_or(all);
+                Ok(EdgeRect {
+                    top: top.unwrap_or(vertical),
+                    right: right.unwrap_or(horizontal),
+                    bottom: bottom.unwrap_or(vertical),
+                    left: left.unwrap_or(horizontal),
+                })
+            }
+        }
+    }
+}
+
+/// A rectangle rotated by `rotation` radians around its center.
+#[derive(Copy, Clone, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct OrientedRect {
+    pub center: Vec2,
+    pub half_size: Vec2,
+    pub rotation: f32,
+}
+
+impl OrientedRect {
+    pub fn new(center: Vec2, size: Vec2, rotation: f32) -> Self {
+        OrientedRect {
+            center,
+            half_size: size / 2.0,
+            rotation,
+        }
+    }
+    pub fn from_rect(rect: Rect) -> Self {
+        OrientedRect {
+            center: rect.center(),
+            half_size: rect.size / 2.0,
+            rotation: 0.0,
+        }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.half_size * 2.0
+    }
+    pub fn axes(&self) -> [Vec2; 2] {
+        let (sin, cos) = self.rotation.sin_cos();
+        [Vec2::new(cos, sin), Vec2::new(-sin, cos)]
+    }
+    pub fn corners(&self) -> [Vec2; 4] {
+        let [x_axis, y_axis] = self.axes();
+        let x = x_axis * self.half_size.x;
+        let y = y_axis * self.half_size.y;
+        [
+            self.center - x - y,
+            self.center + x - y,
+            self.center + x + y,
+            self.center - x + y,
+        ]
+    }
+    /// The smallest axis-aligned rect containing this rect.
+    pub fn bounding_rect(&self) -> Rect {
+        let [x_axis, y_axis] = self.axes();
+        let extents = x_axis.abs() * self.half_size.x + y_axis.abs() * self.half_size.y;
+        Rect {
+            position: self.center - extents,
+            size: extents * 2.0,
+        }
+    }
+
+    /// Transform a point into this rect's local space, relative to its center.
+    pub fn to_local(&self, point: Vec2) -> Vec2 {
+        let [x_axis, y_axis] = self.axes();
+        let offset = point - self.center;
+        Vec2::new(offset.dot(x_axis), offset.dot(y_axis))
+    }
+    pub fn contains(&self, point: Vec2) -> bool {
+        let local = self.to_local(point);
+        local.x.abs() <= self.half_size.x && local.y.abs() <= self.half_size.y
+    }
+
+    fn projected_radius(&self, axis: Vec2) -> f32 {
+        let [x_axis, y_axis] = self.axes();
+        self.half_size.x * x_axis.dot(axis).abs() + self.half_size.y * y_axis.dot(axis).abs()
+    }
+    /// Separating axis test. Rects that only touch along an edge are considered intersecting.
+    pub fn intersects(&self, other: &OrientedRect) -> bool {
+        let offset = other.center - self.center;
+        self.axes().into_iter().chain(other.axes()).all(|axis| {
+            offset.dot(axis).abs() <= self.projected_radius(axis) + other.projected_radius(axis)
+        })
+    }
+    pub fn intersects_rect(&self, rect: Rect) -> bool {
+        self.intersects(&OrientedRect::from_rect(rect))
+    }
+}
+
+impl From<Rect> for OrientedRect {
+    fn from(rect: Rect) -> Self {
+        OrientedRect::from_rect(rect)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct Circle {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+impl Circle {
+    pub fn new(center: Vec2, radius: f32) -> Self {
+        Circle { center, radius }
+    }
+
+    pub fn bounding_rect(&self) -> Rect {
+        Rect {
+            position: self.center - self.radius,
+            size: Vec2::splat(self.radius * 2.0),
+        }
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.center.distance_squared(point) <= self.radius * self.radius
+    }
+    pub fn intersects(&self, other: &Circle) -> bool {
+        let radius = self.radius + other.radius;
+        self.center.distance_squared(other.center) <= radius * radius
+    }
+    pub fn intersects_rect(&self, rect: Rect) -> bool {
+        self.contains(rect.closest_point(self.center))
+    }
+    pub fn intersects_oriented_rect(&self, rect: &OrientedRect) -> bool {
+        let local = rect.to_local(self.center);
+        let closest = local.clamp(-rect.half_size, rect.half_size);
+        local.distance_squared(closest) <= self.radius * self.radius
+    }
+}
+
+/// Maps between screen space (pixels from the top-left of the window) and 2D world space.
+/// `center` is the world position shown at the middle of the viewport, and `zoom` is the number
+/// of screen pixels per world unit. Both spaces have +y pointing down.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CameraTransform {
+    pub center: Vec2,
+    pub zoom: f32,
+    pub viewport_size: Vec2,
+    /// Round the camera's screen offset to whole pixels, so world positions that land on the
+    /// pixel grid at this zoom stay there while the camera moves. This stops pixel art from
+    /// shimmering, at the cost of scrolling in one-pixel steps instead of smoothly. It applies to
+    /// everything placed with `world_to_screen`: sprites queued with
+    /// `TextureRectRenderer::queue_world`, and GUI text and markers that follow the world camera.
+    #[serde(default)]
+    pub pixel_snap: bool,
+}
+
+impl CameraTransform {
+    pub fn new(viewport_size: Vec2) -> Self {
+        CameraTransform {
+            center: Vec2::ZERO,
+            zoom: 1.0,
+            viewport_size,
+            pixel_snap: false,
+        }
+    }
+
+    /// The screen position of the world origin.
+    pub fn screen_offset(&self) -> Vec2 {
+        let offset = self.viewport_size / 2.0 - self.center * self.zoom;
+        if self.pixel_snap {
+            offset.round()
+        } else {
+            offset
+        }
+    }
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        (point - self.screen_offset()) / self.zoom
+    }
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        point * self.zoom + self.screen_offset()
+    }
+    /// Multiply the zoom by `factor`, keeping the world point under `screen_point` in place.
+    pub fn zoom_at(&mut self, screen_point: Vec2, factor: f32) {
+        let world_point = self.screen_to_world(screen_point);
+        self.zoom *= factor;
+        self.center = world_point - (screen_point - self.viewport_size / 2.0) / self.zoom;
+    }
+    /// The area of the world that is visible in the viewport.
+    pub fn visible_rect(&self) -> Rect {
+        Rect {
+            position: self.screen_to_world(Vec2::ZERO),
+            size: self.viewport_size / self.zoom,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letterbox_bars_above_and_below() {
+        let window = Rect::new(0.0, 0.0, 1000.0, 700.0);
+        let viewport = window.fit_aspect(16.0 / 9.0);
+        assert_eq!(viewport, Rect::new(0.0, 68.0, 1000.0, 563.0));
+        assert_eq!(
+            window.margins_outside(viewport),
+            vec![
+                Rect::new(0.0, 0.0, 1000.0, 68.0),
+                Rect::new(0.0, 631.0, 1000.0, 69.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn pillarbox_bars_left_and_right() {
+        let window = Rect::new(0.0, 0.0, 1280.0, 720.0);
+        let viewport = window.fit_aspect(4.0 / 3.0);
+        assert_eq!(viewport, Rect::new(160.0, 0.0, 960.0, 720.0));
+        assert_eq!(
+            window.margins_outside(viewport),
+            vec![
+                Rect::new(0.0, 0.0, 160.0, 720.0),
+                Rect::new(1120.0, 0.0, 160.0, 720.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn four_bars_around_centered_viewport() {
+        // Like an integer upscale that leaves a gap on every side.
+        let window = Rect::new(0.0, 0.0, 1000.0, 700.0);
+        let viewport = Rect::new(20.0, 80.0, 960.0, 540.0);
+        assert_eq!(
+            window.margins_outside(viewport),
+            vec![
+                Rect::new(0.0, 0.0, 1000.0, 80.0),
+                Rect::new(0.0, 620.0, 1000.0, 80.0),
+                Rect::new(0.0, 80.0, 20.0, 540.0),
+                Rect::new(980.0, 80.0, 20.0, 540.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_bars_when_viewport_fills_window() {
+        let window = Rect::new(0.0, 0.0, 1280.0, 720.0);
+        assert!(window
+            .margins_outside(window.fit_aspect(16.0 / 9.0))
+            .is_empty());
+    }
+
+    /// A 2x2 square turned 45°, so its corners are on the axes at distance √2.
+    fn diamond() -> OrientedRect {
+        OrientedRect::new(Vec2::ZERO, Vec2::splat(2.0), std::f32::consts::FRAC_PI_4)
+    }
+
+    #[test]
+    fn oriented_rects_touching_edges_intersect() {
+        let a = OrientedRect::new(Vec2::ZERO, Vec2::splat(2.0), 0.0);
+        let touching = OrientedRect::new(Vec2::new(2.0, 0.0), Vec2::splat(2.0), 0.0);
+        let apart = OrientedRect::new(Vec2::new(2.01, 0.0), Vec2::splat(2.0), 0.0);
+        assert!(a.intersects(&touching));
+        assert!(touching.intersects(&a));
+        assert!(!a.intersects(&apart));
+        assert!(!apart.intersects(&a));
+    }
+
+    #[test]
+    fn oriented_rect_containing_another_intersects() {
+        let outer = OrientedRect::new(Vec2::new(5.0, 5.0), Vec2::new(10.0, 4.0), 0.3);
+        let inner = OrientedRect::new(Vec2::new(5.5, 5.0), Vec2::splat(1.0), 1.2);
+        assert!(outer.intersects(&inner));
+        assert!(inner.intersects(&outer));
+        assert!(outer.contains(Vec2::new(5.5, 5.0)));
+    }
+
+    #[test]
+    fn rotated_rect_separated_only_on_its_own_axis() {
+        let diamond = diamond();
+        let bounds = diamond.bounding_rect();
+        assert!((bounds.position + Vec2::splat(2f32.sqrt())).length() < 1e-5);
+        assert!((bounds.size - Vec2::splat(2.0 * 2f32.sqrt())).length() < 1e-5);
+
+        // Inside the diamond's bounding rect, but past its diagonal edge.
+        let corner = Rect::new(1.0, 1.0, 1.0, 1.0);
+        assert!(bounds.intersects(corner));
+        assert!(!diamond.intersects_rect(corner));
+        assert!(!OrientedRect::from_rect(corner).intersects(&diamond));
+        // Reaching the corner on the x axis.
+        assert!(diamond.intersects_rect(Rect::new(1.2, -0.5, 1.0, 1.0)));
+        assert!(!diamond.intersects_rect(Rect::new(1.5, -0.5, 1.0, 1.0)));
+    }
+
+    #[test]
+    fn circles_touching_intersect() {
+        let a = Circle::new(Vec2::ZERO, 1.0);
+        assert!(a.intersects(&Circle::new(Vec2::new(3.0, 0.0), 2.0)));
+        assert!(!a.intersects(&Circle::new(Vec2::new(3.01, 0.0), 2.0)));
+        // One inside the other.
+        assert!(a.intersects(&Circle::new(Vec2::new(0.2, 0.0), 0.1)));
+    }
+
+    #[test]
+    fn circle_and_rect_edges_and_corners() {
+        let rect = Rect::new(-1.0, -1.0, 2.0, 2.0);
+        assert!(Circle::new(Vec2::new(2.0, 0.0), 1.0).intersects_rect(rect));
+        assert!(!Circle::new(Vec2::new(2.01, 0.0), 1.0).intersects_rect(rect));
+        // Near a corner, the bounding rects overlap but the circle misses the corner.
+        assert!(!Circle::new(Vec2::new(1.5, 1.5), 0.6).intersects_rect(rect));
+        assert!(Circle::new(Vec2::new(1.5, 1.5), 0.75).intersects_rect(rect));
+        // Containment either way.
+        assert!(Circle::new(Vec2::ZERO, 0.5).intersects_rect(rect));
+        assert!(Circle::new(Vec2::ZERO, 10.0).intersects_rect(rect));
+    }
+
+    #[test]
+    fn circle_and_rotated_rect() {
+        let diamond = diamond();
+        let sqrt2 = 2f32.sqrt();
+        assert!(!Circle::new(Vec2::new(2.0, 0.0), 0.5).intersects_oriented_rect(&diamond));
+        assert!(Circle::new(Vec2::new(2.0, 0.0), 0.6).intersects_oriented_rect(&diamond));
+        // Touching the middle of a diagonal edge, which is 1 from the center.
+        let edge = Vec2::new(1.0, 1.0).normalize() * 2.0;
+        assert!(Circle::new(edge, 1.0 + 1e-4).intersects_oriented_rect(&diamond));
+        assert!(!Circle::new(edge, 0.99).intersects_oriented_rect(&diamond));
+        // Inside the corner of the diamond's bounding rect, but off the diamond.
+        assert!(!Circle::new(Vec2::splat(sqrt2 - 0.1), 0.1).intersects_oriented_rect(&diamond));
+        // Containment either way.
+        assert!(Circle::new(Vec2::new(0.1, 0.2), 0.2).intersects_oriented_rect(&diamond));
+        assert!(Circle::new(Vec2::ZERO, 10.0).intersects_oriented_rect(&diamond));
+    }
+
+    #[test]
+    fn pixel_snap_rounds_camera_offset() {
+        let mut camera = CameraTransform::new(Vec2::new(320.0, 180.0));
+        camera.zoom = 2.0;
+        camera.center = Vec2::new(10.3, -4.2);
+        let sprite = Vec2::new(3.0, 7.0);
+        let smooth = camera.world_to_screen(sprite);
+        assert_ne!(smooth, smooth.round());
+
+        camera.pixel_snap = true;
+        let snapped = camera.world_to_screen(sprite);
+        assert_eq!(snapped, snapped.round());
+        assert!((snapped - smooth).abs().max_element() <= 0.5);
+        // Mapping back stays consistent with the snapped position.
+        assert!((camera.screen_to_world(snapped) - sprite).length() < 1e-5);
+    }
+
+    #[test]
+    fn zoom_at_keeps_point_under_cursor() {
+        let mut camera = CameraTransform::new(Vec2::new(800.0, 600.0));
+        camera.center = Vec2::new(50.0, -20.0);
+        let cursor = Vec2::new(620.0, 130.0);
+        let world = camera.screen_to_world(cursor);
+
+        for factor in [2.0, 0.25, 1.5] {
+            camera.zoom_at(cursor, factor);
+            assert!((camera.screen_to_world(cursor) - world).length() < 1e-4);
+        }
+        assert!((camera.zoom - 0.75).abs() < 1e-6);
+        // Zooming at the middle of the viewport doesn't move the camera.
+        let center = camera.center;
+        camera.zoom_at(camera.viewport_size / 2.0, 3.0);
+        assert!((camera.center - center).length() < 1e-4);
+    }
+
+    #[test]
+    fn edge_rect_shorthands() {
+        let parse = |yaml: &str| serde_yaml::from_str::<EdgeRect>(yaml).unwrap();
+        assert_eq!(parse("8"), EdgeRect::splat(8));
+        assert_eq!(parse("[4, 8]"), EdgeRect::new(4, 8, 4, 8));
+        assert_eq!(parse("[1, 2, 3, 4]"), EdgeRect::new(1, 2, 3, 4));
+        assert_eq!(parse("{all: 8, left: 2}"), EdgeRect::new(8, 8, 8, 2));
+        assert_eq!(
+            parse("{vertical: 4, horizontal: 8}"),
+            EdgeRect::new(4, 8, 4, 8)
+        );
+        assert!(serde_yaml::from_str::<EdgeRect>("[1, 2, 3]").is_err());
+        assert!(serde_yaml::from_str::<EdgeRect>("{middle: 1}").is_err());
+
+        let edges = EdgeRect::new(1, 2, 3, 4);
+        let yaml = serde_yaml::to_string(&edges).unwrap();
+        assert_eq!(parse(&yaml), edges);
+    }
+
+    #[test]
+    fn irect_shorthands() {
+        let parse = |yaml: &str| serde_yaml::from_str::<IRect>(yaml).unwrap();
+        let rect = IRect::new(1, 2, 30, 40);
+        assert_eq!(parse("{position: [1, 2], size: [30, 40]}"), rect);
+        assert_eq!(parse("{x: 1, y: 2, width: 30, height: 40}"), rect);
+        assert_eq!(parse("[1, 2, 30, 40]"), rect);
+
+        let yaml = serde_yaml::to_string(&rect).unwrap();
+        assert_eq!(parse(&yaml), rect);
+    }
 }
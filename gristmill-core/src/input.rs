@@ -1,11 +1,22 @@
+mod motion;
+
+pub use motion::*;
+
 use crate::{
     asset::{self, AssetError},
+    geom2d::CameraTransform,
     math::Vec2,
+    util::{Intern, Symbol},
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{Hash, Hasher},
+    time::Instant,
+};
 use winit::event::{
-    DeviceEvent, ElementState, Event, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent,
+    DeviceEvent, DeviceId, ElementState, Event, KeyboardInput, MouseButton, VirtualKeyCode,
+    WindowEvent,
 };
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -60,11 +71,24 @@ impl Default for InputState {
     }
 }
 
+impl InputState {
+    /// Released or zero, keeping the kind of input.
+    fn neutral(self) -> Self {
+        match self {
+            InputState::Button(_) => InputState::Button(false),
+            InputState::Axis1(_) => InputState::Axis1(0.0),
+            InputState::Axis2(_) => InputState::Axis2(Vec2::ZERO),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Default)]
 pub struct ActionState {
     changed: bool,
     state: InputState,
     pointer: Option<Vec2>,
+    timestamp: Option<Instant>,
+    disabled: bool,
 }
 
 impl ActionState {
@@ -73,15 +97,34 @@ impl ActionState {
             changed: false,
             state,
             pointer: None,
+            timestamp: None,
+            disabled: false,
         }
     }
 
     pub fn changed(&self) -> bool {
         self.changed
     }
+    /// False while disabled with `InputSystem::set_action_enabled`.
+    pub fn enabled(&self) -> bool {
+        !self.disabled
+    }
+    /// When the event that last changed this action was received. Only set while
+    /// `InputSystem::set_timestamps` is on, and `None` if the action was last changed by a reset.
+    pub fn timestamp(&self) -> Option<Instant> {
+        self.timestamp
+    }
+    /// Position of the mouse cursor when this action last changed. Only mouse button and mouse
+    /// position bindings provide this. Positions are in pixels from the top-left of whatever
+    /// cursor events were given in: the game loop maps them into the viewport, so they're in
+    /// viewport pixels, scaled to the internal resolution if there is one.
     pub fn pointer(&self) -> Option<Vec2> {
         self.pointer
     }
+    /// The pointer position converted to world space through `camera`.
+    pub fn pointer_world(&self, camera: &CameraTransform) -> Option<Vec2> {
+        self.pointer.map(|pointer| camera.screen_to_world(pointer))
+    }
 
     pub fn button_state(&self) -> bool {
         self.state.as_button()
@@ -108,7 +151,7 @@ impl ActionState {
 }
 
 #[derive(Default)]
-pub struct InputActions(HashMap<String, ActionState>);
+pub struct InputActions(HashMap<Symbol, ActionState>);
 
 impl InputActions {
     fn end_frame(&mut self) {
@@ -116,20 +159,51 @@ impl InputActions {
             action.changed = false;
         }
     }
-    fn set_state(&mut self, key: &str, state: InputState, pointer: Option<Vec2>) {
-        if let Some(action) = self.0.get_mut(key) {
+    fn set_state(&mut self, key: Symbol, state: InputState, pointer: Option<Vec2>) {
+        self.set_state_at(key, state, pointer, None);
+    }
+    fn set_state_at(
+        &mut self,
+        key: Symbol,
+        state: InputState,
+        pointer: Option<Vec2>,
+        timestamp: Option<Instant>,
+    ) {
+        if let Some(action) = self.0.get_mut(&key) {
+            let state = if action.disabled {
+                state.neutral()
+            } else {
+                state
+            };
             action.pointer = pointer;
             if action.state != state {
                 action.state = state;
                 action.changed = true;
+                action.timestamp = timestamp;
             }
         }
     }
     pub fn try_get(&self, key: &str) -> Option<&ActionState> {
-        self.0.get(key)
+        Intern::lookup(key).and_then(|key| self.try_get_symbol(key))
     }
+    /// The state of an action, or the default released state if it isn't bound. Unlike
+    /// `Symbol::new`, this doesn't intern `key`, so a misspelled name isn't leaked.
     pub fn get(&self, key: &str) -> ActionState {
-        if let Some(state) = self.0.get(key) {
+        match Intern::lookup(key) {
+            Some(key) => self.get_symbol(key),
+            None => {
+                log::error!("Input action \"{}\" not bound.", key);
+                ActionState::default()
+            }
+        }
+    }
+    /// Like `try_get`, without looking up the string. Keep the symbol for actions read every
+    /// frame.
+    pub fn try_get_symbol(&self, key: Symbol) -> Option<&ActionState> {
+        self.0.get(&key)
+    }
+    pub fn get_symbol(&self, key: Symbol) -> ActionState {
+        if let Some(state) = self.0.get(&key) {
             *state
         } else {
             log::error!("Input action \"{}\" not bound.", key);
@@ -138,8 +212,95 @@ impl InputActions {
     }
 }
 
+/// Simplified representation of the window events that bindings respond to.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum InputEvent {
+    Key {
+        key: VirtualKeyCode,
+        pressed: bool,
+    },
+    MouseButton {
+        button: MouseButton,
+        pressed: bool,
+    },
+    CursorMoved(Vec2),
+    /// The window's new inner size in physical pixels, or the size of the viewport when the game
+    /// loop maps cursor positions into it.
+    WindowResized(Vec2),
+    MouseMotion(Vec2),
+    /// A raw analog axis, such as a gamepad trigger or stick on platforms that report them.
+    /// `value` is in the device's own units. `device` is the same for every event from one
+    /// device while the game runs.
+    AxisMotion {
+        device: u64,
+        axis: u32,
+        value: f32,
+    },
+    /// The pointer was grabbed or released with `InputSystem::set_pointer_grabbed`. Only
+    /// recordings contain this, so that playback grabs the pointer when the recorded session did
+    /// and replayed mouse motion isn't dropped.
+    PointerGrabbed(bool),
+}
+
+impl InputEvent {
+    pub fn from_event(event: &Event<()>) -> Option<InputEvent> {
+        match event {
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state,
+                            virtual_keycode: Some(key),
+                            ..
+                        },
+                    ..
+                } => Some(InputEvent::Key {
+                    key: *key,
+                    pressed: *state == ElementState::Pressed,
+                }),
+                WindowEvent::MouseInput { state, button, .. } => Some(InputEvent::MouseButton {
+                    button: *button,
+                    pressed: *state == ElementState::Pressed,
+                }),
+                WindowEvent::CursorMoved { position, .. } => Some(InputEvent::CursorMoved(
+                    Vec2::new(position.x as f32, position.y as f32),
+                )),
+                WindowEvent::Resized(size) => Some(InputEvent::WindowResized(Vec2::new(
+                    size.width as f32,
+                    size.height as f32,
+                ))),
+                _ => None,
+            },
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta },
+                ..
+            } => Some(InputEvent::MouseMotion(Vec2::new(
+                delta.0 as f32,
+                delta.1 as f32,
+            ))),
+            Event::DeviceEvent {
+                device_id,
+                event: DeviceEvent::Motion { axis, value },
+            } => Some(InputEvent::AxisMotion {
+                device: device_number(device_id),
+                axis: *axis,
+                value: *value as f32,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A number for a winit device, the same for every event from it while the game runs.
+fn device_number(device_id: &DeviceId) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    device_id.hash(&mut hasher);
+    hasher.finish()
+}
+
 trait Binding {
-    fn event(&mut self, event: &Event<()>) -> bool;
+    fn event(&mut self, event: &InputEvent) -> bool;
+    fn reset(&mut self);
     fn state(&self) -> InputState;
     fn pointer(&self) -> Option<Vec2> {
         None
@@ -163,28 +324,18 @@ impl KeyBinding {
 }
 
 impl Binding for KeyBinding {
-    fn event(&mut self, event: &Event<()>) -> bool {
-        if let Event::WindowEvent {
-            event:
-                WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            state,
-                            virtual_keycode,
-                            ..
-                        },
-                    ..
-                },
-            ..
-        } = event
-        {
-            if *virtual_keycode == Some(self.key) {
-                self.pressed = *state == ElementState::Pressed;
+    fn event(&mut self, event: &InputEvent) -> bool {
+        if let InputEvent::Key { key, pressed } = *event {
+            if key == self.key {
+                self.pressed = pressed;
                 return true;
             }
         }
         false
     }
+    fn reset(&mut self) {
+        self.pressed = false;
+    }
     fn state(&self) -> InputState {
         InputState::Button(self.pressed)
     }
@@ -206,12 +357,16 @@ impl KeyAxis1Binding {
 }
 
 impl Binding for KeyAxis1Binding {
-    fn event(&mut self, event: &Event<()>) -> bool {
+    fn event(&mut self, event: &InputEvent) -> bool {
         let mut changed = false;
         changed |= self.up.event(event);
         changed |= self.down.event(event);
         changed
     }
+    fn reset(&mut self) {
+        self.up.reset();
+        self.down.reset();
+    }
     fn state(&self) -> InputState {
         let mut x = 0.;
         if self.up.pressed {
@@ -249,7 +404,7 @@ impl KeyAxis2Binding {
 }
 
 impl Binding for KeyAxis2Binding {
-    fn event(&mut self, event: &Event<()>) -> bool {
+    fn event(&mut self, event: &InputEvent) -> bool {
         let mut changed = false;
         changed |= self.up.event(event);
         changed |= self.down.event(event);
@@ -257,6 +412,12 @@ impl Binding for KeyAxis2Binding {
         changed |= self.right.event(event);
         changed
     }
+    fn reset(&mut self) {
+        self.up.reset();
+        self.down.reset();
+        self.left.reset();
+        self.right.reset();
+    }
     fn state(&self) -> InputState {
         let mut x = 0.;
         let mut y = 0.;
@@ -293,25 +454,21 @@ impl MouseButtonBinding {
 }
 
 impl Binding for MouseButtonBinding {
-    fn event(&mut self, event: &Event<()>) -> bool {
-        if let Event::WindowEvent {
-            event: WindowEvent::CursorMoved { position, .. },
-            ..
-        } = event
-        {
-            self.state.1 = position.cast::<f32>().into();
-            return true;
-        } else if let Event::WindowEvent {
-            event: WindowEvent::MouseInput { state, button, .. },
-            ..
-        } = event
-        {
-            if *button == self.button {
-                self.state.0 = *state == ElementState::Pressed;
-                return true;
+    fn event(&mut self, event: &InputEvent) -> bool {
+        match *event {
+            InputEvent::CursorMoved(position) => {
+                self.state.1 = position.into();
+                true
+            }
+            InputEvent::MouseButton { button, pressed } if button == self.button => {
+                self.state.0 = pressed;
+                true
             }
+            _ => false,
         }
-        false
+    }
+    fn reset(&mut self) {
+        self.state.0 = false;
     }
     fn state(&self) -> InputState {
         InputState::Button(self.state.0)
@@ -321,6 +478,60 @@ impl Binding for MouseButtonBinding {
     }
 }
 
+/// Binds the cursor position to an Axis2 action, without needing a button. The position is in
+/// physical window pixels from the top-left, or if `normalized` is set, from -1 to 1 across the
+/// window with +y down (the same as Vulkan NDC). The game loop maps cursor positions into the
+/// viewport, so with a fixed aspect or internal resolution they're relative to the viewport
+/// instead of the window.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MousePositionBinding {
+    #[serde(default)]
+    normalized: bool,
+    #[serde(skip)]
+    position: Vec2,
+    #[serde(skip)]
+    window_size: Vec2,
+}
+
+impl MousePositionBinding {
+    pub fn new(normalized: bool) -> Self {
+        MousePositionBinding {
+            normalized,
+            position: Vec2::ZERO,
+            window_size: Vec2::ZERO,
+        }
+    }
+}
+
+impl Binding for MousePositionBinding {
+    fn event(&mut self, event: &InputEvent) -> bool {
+        match *event {
+            InputEvent::CursorMoved(position) => {
+                self.position = position;
+                true
+            }
+            InputEvent::WindowResized(size) => {
+                self.window_size = size;
+                self.normalized
+            }
+            _ => false,
+        }
+    }
+    fn reset(&mut self) {}
+    fn state(&self) -> InputState {
+        if !self.normalized {
+            InputState::Axis2(self.position)
+        } else if self.window_size.min_element() > 0.0 {
+            InputState::Axis2(self.position / self.window_size * 2.0 - Vec2::ONE)
+        } else {
+            InputState::Axis2(Vec2::ZERO)
+        }
+    }
+    fn pointer(&self) -> Option<Vec2> {
+        Some(self.position)
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct MouseMotionBinding {
     sensitivity: f32,
@@ -338,39 +549,139 @@ impl MouseMotionBinding {
 }
 
 impl Binding for MouseMotionBinding {
-    fn event(&mut self, event: &Event<()>) -> bool {
-        if let Event::DeviceEvent {
-            event: DeviceEvent::MouseMotion { delta },
-            ..
-        } = event
-        {
-            self.motion.x += delta.0 as f32 * self.sensitivity;
-            self.motion.y += delta.1 as f32 * self.sensitivity;
+    fn event(&mut self, event: &InputEvent) -> bool {
+        if let InputEvent::MouseMotion(delta) = *event {
+            self.motion += delta * self.sensitivity;
         }
         false
     }
+    fn reset(&mut self) {
+        self.motion = Vec2::ZERO;
+    }
     fn state(&self) -> InputState {
         InputState::Axis2(self.motion)
     }
 }
 
+fn default_exponent() -> f32 {
+    1.0
+}
+fn default_range() -> [f32; 2] {
+    [0.0, 1.0]
+}
+
+/// Binds a raw analog axis to an Axis1 action. Raw values are first normalized with `range`,
+/// since winit reports axes in the device's own units. Normalized values inside the deadzone read
+/// as zero, and the rest of the range is rescaled to 0..1 and raised to `exponent` (1 is linear,
+/// higher values give finer control near zero). The sign of the input is kept.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnalogAxisBinding {
+    axis: u32,
+    #[serde(default)]
+    deadzone: f32,
+    #[serde(default = "default_exponent")]
+    exponent: f32,
+    /// The raw values that read as 0 and as full scale, such as `[0, 255]` for an 8-bit trigger
+    /// or `[0, 32767]` for a 16-bit stick (whose negative half then reads as -1..0). Defaults to
+    /// `[0, 1]`, for axes that are already normalized.
+    #[serde(default = "default_range")]
+    range: [f32; 2],
+    #[serde(skip)]
+    value: f32,
+    // The device the value came from, so it can be dropped if that turns out to be a mouse.
+    #[serde(skip)]
+    device: Option<u64>,
+}
+
+impl AnalogAxisBinding {
+    pub fn new(axis: u32, deadzone: f32, exponent: f32) -> Self {
+        AnalogAxisBinding {
+            axis,
+            deadzone,
+            exponent,
+            range: default_range(),
+            value: 0.0,
+            device: None,
+        }
+    }
+    /// Normalize raw values so `zero` reads as 0 and `full` as 1. See `range`.
+    pub fn with_range(mut self, zero: f32, full: f32) -> Self {
+        self.range = [zero, full];
+        self
+    }
+    fn normalize(&self, raw: f32) -> f32 {
+        let [zero, full] = self.range;
+        if full == zero {
+            0.0
+        } else {
+            (raw - zero) / (full - zero)
+        }
+    }
+    fn response(&self, raw: f32) -> f32 {
+        let value = self.normalize(raw);
+        let deadzone = self.deadzone.clamp(0.0, 0.99);
+        let magnitude = ((value.abs() - deadzone) / (1.0 - deadzone)).clamp(0.0, 1.0);
+        magnitude.powf(self.exponent) * value.signum()
+    }
+}
+
+impl Binding for AnalogAxisBinding {
+    fn event(&mut self, event: &InputEvent) -> bool {
+        if let InputEvent::AxisMotion {
+            device,
+            axis,
+            value,
+        } = *event
+        {
+            if axis == self.axis {
+                self.value = value;
+                self.device = Some(device);
+                return true;
+            }
+        }
+        false
+    }
+    fn reset(&mut self) {
+        self.value = 0.0;
+        self.device = None;
+    }
+    fn state(&self) -> InputState {
+        InputState::Axis1(self.response(self.value))
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 enum BindingEnum {
     Key(KeyBinding),
     KeyAxis1(KeyAxis1Binding),
     KeyAxis2(KeyAxis2Binding),
     MouseButton(MouseButtonBinding),
+    MousePosition(MousePositionBinding),
     MouseMotion(MouseMotionBinding),
+    AnalogAxis(AnalogAxisBinding),
 }
 
 impl Binding for BindingEnum {
-    fn event(&mut self, event: &Event<()>) -> bool {
+    fn event(&mut self, event: &InputEvent) -> bool {
         match self {
             BindingEnum::Key(binding) => binding.event(event),
             BindingEnum::KeyAxis1(binding) => binding.event(event),
             BindingEnum::KeyAxis2(binding) => binding.event(event),
             BindingEnum::MouseButton(binding) => binding.event(event),
+            BindingEnum::MousePosition(binding) => binding.event(event),
             BindingEnum::MouseMotion(binding) => binding.event(event),
+            BindingEnum::AnalogAxis(binding) => binding.event(event),
+        }
+    }
+    fn reset(&mut self) {
+        match self {
+            BindingEnum::Key(binding) => binding.reset(),
+            BindingEnum::KeyAxis1(binding) => binding.reset(),
+            BindingEnum::KeyAxis2(binding) => binding.reset(),
+            BindingEnum::MouseButton(binding) => binding.reset(),
+            BindingEnum::MousePosition(binding) => binding.reset(),
+            BindingEnum::MouseMotion(binding) => binding.reset(),
+            BindingEnum::AnalogAxis(binding) => binding.reset(),
         }
     }
     fn state(&self) -> InputState {
@@ -379,7 +690,9 @@ impl Binding for BindingEnum {
             BindingEnum::KeyAxis1(binding) => binding.state(),
             BindingEnum::KeyAxis2(binding) => binding.state(),
             BindingEnum::MouseButton(binding) => binding.state(),
+            BindingEnum::MousePosition(binding) => binding.state(),
             BindingEnum::MouseMotion(binding) => binding.state(),
+            BindingEnum::AnalogAxis(binding) => binding.state(),
         }
     }
     fn pointer(&self) -> Option<Vec2> {
@@ -388,29 +701,18 @@ impl Binding for BindingEnum {
             BindingEnum::KeyAxis1(binding) => binding.pointer(),
             BindingEnum::KeyAxis2(binding) => binding.pointer(),
             BindingEnum::MouseButton(binding) => binding.pointer(),
+            BindingEnum::MousePosition(binding) => binding.pointer(),
             BindingEnum::MouseMotion(binding) => binding.pointer(),
+            BindingEnum::AnalogAxis(binding) => binding.pointer(),
         }
     }
 }
 
 #[derive(Default, Clone, Serialize, Deserialize)]
 #[serde(transparent)]
-pub struct InputBindings(HashMap<String, BindingEnum>);
-
-impl InputBindings {
-    pub fn load_config() -> Result<InputBindings, AssetError> {
-        asset::load_yaml_file("config", "controls.yaml")
-    }
-    pub fn save_config(&self) -> Result<(), AssetError> {
-        asset::save_yaml_file("config", "controls.yaml", self)
-    }
-
-    fn create_actions(&self) -> InputActions {
-        InputActions(HashMap::from_iter(self.0.iter().map(|(key, binding)| {
-            (key.clone(), ActionState::new(binding.state()))
-        })))
-    }
+pub struct ActionBindings(HashMap<Symbol, BindingEnum>);
 
+impl ActionBindings {
     pub fn len(&self) -> usize {
         self.0.len()
     }
@@ -419,29 +721,256 @@ impl InputBindings {
     }
 
     pub fn add_key(&mut self, key: &str, binding: KeyBinding) {
-        self.0.insert(key.to_owned(), BindingEnum::Key(binding));
+        self.0.insert(Symbol::new(key), BindingEnum::Key(binding));
     }
     pub fn add_key_axis1(&mut self, key: &str, binding: KeyAxis1Binding) {
         self.0
-            .insert(key.to_owned(), BindingEnum::KeyAxis1(binding));
+            .insert(Symbol::new(key), BindingEnum::KeyAxis1(binding));
     }
     pub fn add_key_axis2(&mut self, key: &str, binding: KeyAxis2Binding) {
         self.0
-            .insert(key.to_owned(), BindingEnum::KeyAxis2(binding));
+            .insert(Symbol::new(key), BindingEnum::KeyAxis2(binding));
     }
     pub fn add_mouse_button(&mut self, key: &str, binding: MouseButtonBinding) {
         self.0
-            .insert(key.to_owned(), BindingEnum::MouseButton(binding));
+            .insert(Symbol::new(key), BindingEnum::MouseButton(binding));
+    }
+    pub fn add_mouse_position(&mut self, key: &str, binding: MousePositionBinding) {
+        self.0
+            .insert(Symbol::new(key), BindingEnum::MousePosition(binding));
     }
     pub fn add_mouse_motion(&mut self, key: &str, binding: MouseMotionBinding) {
         self.0
-            .insert(key.to_owned(), BindingEnum::MouseMotion(binding));
+            .insert(Symbol::new(key), BindingEnum::MouseMotion(binding));
+    }
+    pub fn add_analog_axis(&mut self, key: &str, binding: AnalogAxisBinding) {
+        self.0
+            .insert(Symbol::new(key), BindingEnum::AnalogAxis(binding));
+    }
+}
+
+/// Bindings in `global` are always active. Bindings in `contexts` are only active while their
+/// context is on top of the [InputSystem] context stack.
+///
+/// Controls files from before contexts, a flat map of actions to bindings, load as `global`.
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(try_from = "serde_yaml::Value")]
+pub struct InputBindings {
+    #[serde(default)]
+    global: ActionBindings,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    contexts: HashMap<String, ActionBindings>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ContextBindings {
+    #[serde(default)]
+    global: ActionBindings,
+    #[serde(default)]
+    contexts: HashMap<String, ActionBindings>,
+}
+
+impl TryFrom<serde_yaml::Value> for InputBindings {
+    type Error = serde_yaml::Error;
+    fn try_from(value: serde_yaml::Value) -> Result<Self, Self::Error> {
+        // Bindings are YAML tagged values, which untagged enums can't tell apart, so check the
+        // keys instead.
+        let is_flat = value.as_mapping().is_some_and(|mapping| {
+            mapping.keys().any(|key| {
+                key.as_str()
+                    .is_none_or(|key| key != "global" && key != "contexts")
+            })
+        });
+        if is_flat {
+            Ok(InputBindings {
+                global: serde_yaml::from_value(value)?,
+                contexts: HashMap::new(),
+            })
+        } else {
+            let ContextBindings { global, contexts } = serde_yaml::from_value(value)?;
+            Ok(InputBindings { global, contexts })
+        }
+    }
+}
+
+impl InputBindings {
+    /// The profile stored in "controls.yaml". Other profiles are stored in
+    /// "controls_<name>.yaml".
+    pub const DEFAULT_PROFILE: &'static str = "default";
+
+    fn profile_file(name: &str) -> Result<String, AssetError> {
+        if name == Self::DEFAULT_PROFILE {
+            Ok("controls.yaml".to_owned())
+        } else if !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            Ok(format!("controls_{name}.yaml"))
+        } else {
+            Err(AssetError::Other(format!(
+                "invalid control profile name \"{name}\""
+            )))
+        }
+    }
+
+    pub fn load_config() -> Result<InputBindings, AssetError> {
+        Self::load_profile(Self::DEFAULT_PROFILE)
+    }
+    pub fn save_config(&self) -> Result<(), AssetError> {
+        self.save_profile(Self::DEFAULT_PROFILE)
+    }
+    /// Profile names may only contain ASCII letters, digits, '_' and '-'.
+    pub fn load_profile(name: &str) -> Result<InputBindings, AssetError> {
+        asset::load_yaml_file("config", &Self::profile_file(name)?)
+    }
+    pub fn save_profile(&self, name: &str) -> Result<(), AssetError> {
+        asset::save_yaml_file("config", &Self::profile_file(name)?, self)
+    }
+    /// The names of the saved profiles, sorted.
+    pub fn list_profiles() -> Result<Vec<String>, AssetError> {
+        let mut profiles = Vec::new();
+        let dir = match std::fs::read_dir(asset::get_path("config", "")) {
+            Ok(dir) => dir,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(profiles),
+            Err(error) => return Err(error.into()),
+        };
+        for entry in dir {
+            let file_name = entry?.file_name();
+            let file_name = file_name.to_string_lossy();
+            if file_name == "controls.yaml" {
+                profiles.push(Self::DEFAULT_PROFILE.to_owned());
+            } else if let Some(name) = file_name
+                .strip_prefix("controls_")
+                .and_then(|name| name.strip_suffix(".yaml"))
+            {
+                profiles.push(name.to_owned());
+            }
+        }
+        profiles.sort();
+        Ok(profiles)
+    }
+
+    /// The bindings used when there is no controls file.
+    pub fn default_bindings() -> InputBindings {
+        type Key = VirtualKeyCode;
+        let mut bindings = InputBindings::default();
+        let global = bindings.global();
+        global.add_mouse_button("primary", MouseButtonBinding::new(MouseButton::Left));
+        global.add_mouse_button("secondary", MouseButtonBinding::new(MouseButton::Right));
+        global.add_mouse_motion("look", MouseMotionBinding::new(0.1));
+        global.add_key("console", KeyBinding::new(Key::Grave));
+        global.add_key("exit", KeyBinding::new(Key::Escape));
+        global.add_key_axis2("move", KeyAxis2Binding::new(Key::W, Key::S, Key::A, Key::D));
+        global.add_key("jump", KeyBinding::new(Key::Space));
+        global.add_key_axis1("fly", KeyAxis1Binding::new(Key::Space, Key::LShift));
+        bindings
+    }
+
+    fn create_actions(&self) -> InputActions {
+        let all_bindings = self.global.0.iter().chain(
+            self.contexts
+                .values()
+                .flat_map(|bindings| bindings.0.iter()),
+        );
+        InputActions(HashMap::from_iter(
+            all_bindings.map(|(key, binding)| (*key, ActionState::new(binding.state()))),
+        ))
+    }
+    fn active_bindings<'a>(
+        &'a self,
+        context: Option<&str>,
+    ) -> impl Iterator<Item = (&'a Symbol, &'a BindingEnum)> {
+        let context_bindings = context.and_then(|context| self.contexts.get(context));
+        self.global.0.iter().chain(
+            context_bindings
+                .into_iter()
+                .flat_map(|bindings| bindings.0.iter()),
+        )
+    }
+    fn active_bindings_mut<'a>(
+        &'a mut self,
+        context: Option<&str>,
+    ) -> impl Iterator<Item = (&'a Symbol, &'a mut BindingEnum)> {
+        let context_bindings = context.and_then(|context| self.contexts.get_mut(context));
+        self.global.0.iter_mut().chain(
+            context_bindings
+                .into_iter()
+                .flat_map(|bindings| bindings.0.iter_mut()),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.global.len()
+            + self
+                .contexts
+                .values()
+                .map(ActionBindings::len)
+                .sum::<usize>()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
+
+    pub fn global(&mut self) -> &mut ActionBindings {
+        &mut self.global
+    }
+    pub fn context(&mut self, context: &str) -> &mut ActionBindings {
+        self.contexts.entry(context.to_owned()).or_default()
+    }
+    pub fn has_context(&self, context: &str) -> bool {
+        self.contexts.contains_key(context)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub time: f64,
+    pub event: InputEvent,
 }
 
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InputRecording(Vec<RecordedEvent>);
+
+impl InputRecording {
+    pub fn load(file: &str) -> Result<InputRecording, AssetError> {
+        asset::load_yaml_file("recordings", file)
+    }
+    pub fn save(&self, file: &str) -> Result<(), AssetError> {
+        asset::save_yaml_file("recordings", file, self)
+    }
+
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.0
+    }
+    pub fn duration(&self) -> f64 {
+        self.0.last().map(|e| e.time).unwrap_or(0.0)
+    }
+}
+
+struct InputPlayback {
+    start: Instant,
+    recording: InputRecording,
+    next: usize,
+}
+
+/// Turns window events into action states, using one set of bindings. For split-screen, give
+/// each player their own `InputSystem` with `load_profile`.
 pub struct InputSystem {
     bindings: InputBindings,
+    profile: String,
     actions: InputActions,
+    context_stack: Vec<String>,
+    pointer_grabbed: bool,
+    window_size: Vec2,
+    timestamps: bool,
+    recording: Option<(Instant, InputRecording)>,
+    playback: Option<InputPlayback>,
+    // Devices that have sent mouse motion. Mice also report their movement as axis motion on some
+    // platforms (on X11, axes 0 and 1), which analog axis bindings must not pick up.
+    pointer_devices: HashSet<u64>,
 }
 
 impl InputSystem {
@@ -449,6 +978,14 @@ impl InputSystem {
         InputSystem {
             actions: bindings.create_actions(),
             bindings,
+            profile: InputBindings::DEFAULT_PROFILE.to_owned(),
+            context_stack: Vec::new(),
+            pointer_grabbed: false,
+            window_size: Vec2::ZERO,
+            timestamps: false,
+            recording: None,
+            playback: None,
+            pointer_devices: HashSet::new(),
         }
     }
     pub fn load_config() -> Self {
@@ -456,17 +993,7 @@ impl InputSystem {
             Ok(bindings) => Self::new(bindings),
             Err(load_error) => {
                 log::warn!("{}", load_error);
-                type Key = VirtualKeyCode;
-                let mut bindings = InputBindings::default();
-                bindings.add_mouse_button("primary", MouseButtonBinding::new(MouseButton::Left));
-                bindings.add_mouse_button("secondary", MouseButtonBinding::new(MouseButton::Right));
-                bindings.add_mouse_motion("look", MouseMotionBinding::new(0.1));
-                bindings.add_key("console", KeyBinding::new(Key::Grave));
-                bindings.add_key("exit", KeyBinding::new(Key::Escape));
-                bindings
-                    .add_key_axis2("move", KeyAxis2Binding::new(Key::W, Key::S, Key::A, Key::D));
-                bindings.add_key("jump", KeyBinding::new(Key::Space));
-                bindings.add_key_axis1("fly", KeyAxis1Binding::new(Key::Space, Key::LShift));
+                let bindings = InputBindings::default_bindings();
                 if load_error.io_kind() == Some(std::io::ErrorKind::NotFound) {
                     if let Err(save_error) = bindings.save_config() {
                         log::warn!("{}", save_error);
@@ -480,13 +1007,210 @@ impl InputSystem {
     pub fn actions(&self) -> &InputActions {
         &self.actions
     }
+    pub fn bindings(&self) -> &InputBindings {
+        &self.bindings
+    }
+    /// Replace the bindings. Every action starts released, and the context stack is kept.
+    pub fn set_bindings(&mut self, bindings: InputBindings) {
+        self.actions = bindings.create_actions();
+        self.bindings = bindings;
+        if self.window_size != Vec2::ZERO {
+            self.apply_event(&InputEvent::WindowResized(self.window_size));
+        }
+    }
+
+    /// The window size from the last resize event, or `Vec2::ZERO` before the first.
+    pub fn window_size(&self) -> Vec2 {
+        self.window_size
+    }
+    /// Tell the bindings the size of the window, as a resize event would. The game loop calls
+    /// this whenever the viewport changes size, passing the size that cursor positions are
+    /// measured in.
+    pub fn set_window_size(&mut self, size: Vec2) {
+        self.handle_event(InputEvent::WindowResized(size));
+    }
+
+    /// The name of the profile the bindings were last loaded from or saved to.
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+    /// Switch to the bindings saved as `name`. A missing or unreadable profile falls back to the
+    /// default bindings with a warning.
+    pub fn load_profile(&mut self, name: &str) {
+        let bindings = InputBindings::load_profile(name).unwrap_or_else(|error| {
+            log::warn!("Failed to load control profile \"{}\": {}", name, error);
+            InputBindings::default_bindings()
+        });
+        self.set_bindings(bindings);
+        self.profile = name.to_owned();
+    }
+    /// Save the current bindings as `name`, and make it the current profile.
+    pub fn save_profile(&mut self, name: &str) -> Result<(), AssetError> {
+        self.bindings.save_profile(name)?;
+        self.profile = name.to_owned();
+        Ok(())
+    }
+    pub fn list_profiles() -> Result<Vec<String>, AssetError> {
+        InputBindings::list_profiles()
+    }
+
+    /// The context whose bindings are currently active, in addition to the global bindings.
+    pub fn active_context(&self) -> Option<&str> {
+        self.context_stack.last().map(String::as_str)
+    }
+    pub fn push_context(&mut self, context: &str) {
+        if !self.bindings.has_context(context) {
+            log::warn!("Input context \"{}\" has no bindings.", context);
+        }
+        if let Some(previous) = self.context_stack.last().cloned() {
+            self.deactivate_context(&previous);
+        }
+        self.context_stack.push(context.to_owned());
+    }
+    pub fn pop_context(&mut self) -> Option<String> {
+        let context = self.context_stack.pop()?;
+        self.deactivate_context(&context);
+        Some(context)
+    }
+    fn deactivate_context(&mut self, context: &str) {
+        // Release everything held in the old context, so actions don't stay stuck while inactive.
+        if let Some(bindings) = self.bindings.contexts.get_mut(context) {
+            for (key, binding) in bindings.0.iter_mut() {
+                binding.reset();
+                self.actions
+                    .set_state(*key, binding.state(), binding.pointer());
+            }
+        }
+    }
+    /// True if a mouse motion binding is active. The pointer should only be grabbed while this is
+    /// true; the game loop releases it automatically when a context without mouse look is pushed.
+    pub fn mouse_look_active(&self) -> bool {
+        self.bindings
+            .active_bindings(self.active_context())
+            .any(|(_, binding)| matches!(binding, BindingEnum::MouseMotion(_)))
+    }
+    pub fn pointer_grabbed(&self) -> bool {
+        self.pointer_grabbed
+    }
+    /// Grab or release the pointer. The game loop confines and hides the cursor to match. Mouse
+    /// motion bindings only accumulate while the pointer is grabbed, so the camera doesn't move
+    /// while the cursor is being used for menus. Grabbing the cursor with the game window's
+    /// `grab_cursor` sets this too.
+    pub fn set_pointer_grabbed(&mut self, grabbed: bool) {
+        if self.pointer_grabbed == grabbed {
+            return;
+        }
+        self.record(InputEvent::PointerGrabbed(grabbed));
+        self.grab_pointer(grabbed);
+    }
+    fn grab_pointer(&mut self, grabbed: bool) {
+        if self.pointer_grabbed == grabbed {
+            return;
+        }
+        self.pointer_grabbed = grabbed;
+        if !grabbed {
+            let context = self.context_stack.last().map(String::as_str);
+            for (key, binding) in self.bindings.active_bindings_mut(context) {
+                if let BindingEnum::MouseMotion(binding) = binding {
+                    binding.reset();
+                    self.actions
+                        .set_state(*key, binding.state(), binding.pointer());
+                }
+            }
+        }
+    }
+
+    /// Disable an action without removing its bindings, such as "jump" while stunned. A disabled
+    /// action reports released (or zero) whatever the physical input. Disabling an action that is
+    /// held reports a release, and re-enabling it picks up the current input, so an action that is
+    /// still held reports a press.
+    pub fn set_action_enabled(&mut self, action: &str, enabled: bool) {
+        let Some(action) = Intern::lookup(action) else {
+            log::error!("Input action \"{}\" not bound.", action);
+            return;
+        };
+        match self.actions.0.get_mut(&action) {
+            Some(state) if state.disabled != enabled => return,
+            Some(state) => state.disabled = !enabled,
+            None => {
+                log::error!("Input action \"{}\" not bound.", action);
+                return;
+            }
+        }
+        let state = self.actions.0[&action].state;
+        self.actions.set_state(action, state, None);
+        let context = self.context_stack.last().map(String::as_str);
+        for (key, binding) in self.bindings.active_bindings(context) {
+            if *key == action {
+                self.actions
+                    .set_state(*key, binding.state(), binding.pointer());
+            }
+        }
+    }
+    pub fn action_enabled(&self, action: &str) -> bool {
+        self.actions
+            .try_get(action)
+            .is_none_or(ActionState::enabled)
+    }
+
+    pub fn timestamps(&self) -> bool {
+        self.timestamps
+    }
+    /// Stamp actions with the time of the event that changed them (see `ActionState::timestamp`),
+    /// for debouncing and measuring input latency. Off by default.
+    pub fn set_timestamps(&mut self, timestamps: bool) {
+        self.timestamps = timestamps;
+    }
+
+    /// Start recording every input event. Recording is off by default.
+    pub fn start_recording(&mut self) {
+        self.recording = Some((Instant::now(), InputRecording::default()));
+        // Playback starts with the pointer grabbed or not, as it is now.
+        self.record(InputEvent::PointerGrabbed(self.pointer_grabbed));
+    }
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recording.take().map(|(_, recording)| recording)
+    }
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Replay a recording. Live input is ignored until playback finishes. The pointer is grabbed
+    /// and released as it was while recording.
+    pub fn start_playback(&mut self, recording: InputRecording) {
+        self.playback = Some(InputPlayback {
+            start: Instant::now(),
+            recording,
+            next: 0,
+        });
+    }
+    pub fn stop_playback(&mut self) {
+        self.playback = None;
+    }
+    pub fn is_playing_back(&self) -> bool {
+        self.playback.is_some()
+    }
 
     pub fn start_frame(&mut self) {
+        if let Some(mut playback) = self.playback.take() {
+            let elapsed = playback.start.elapsed().as_secs_f64();
+            while let Some(recorded) = playback.recording.0.get(playback.next) {
+                if recorded.time > elapsed {
+                    break;
+                }
+                self.apply_event(&recorded.event);
+                playback.next += 1;
+            }
+            if playback.next < playback.recording.0.len() {
+                self.playback = Some(playback);
+            }
+        }
         // MouseMotionBindings work differently than others. The values are accumulated over each frame, then reset.
-        for (key, binding) in self.bindings.0.iter_mut() {
+        let context = self.context_stack.last().map(String::as_str);
+        for (key, binding) in self.bindings.active_bindings_mut(context) {
             if let BindingEnum::MouseMotion(binding) = binding {
                 self.actions
-                    .set_state(key, binding.state(), binding.pointer());
+                    .set_state(*key, binding.state(), binding.pointer());
                 binding.motion = Vec2::ZERO;
             }
         }
@@ -496,11 +1220,423 @@ impl InputSystem {
     }
 
     pub fn input_event(&mut self, event: Event<()>) {
-        for (key, binding) in self.bindings.0.iter_mut() {
-            if binding.event(&event) {
+        if let Event::DeviceEvent {
+            device_id,
+            event: DeviceEvent::MouseMotion { .. },
+        } = &event
+        {
+            let device = device_number(device_id);
+            if self.pointer_devices.insert(device) {
+                // Its first axis motion came before its first mouse motion, so drop that too.
+                self.release_axis_device(device);
+            }
+        }
+        if let Some(event) = InputEvent::from_event(&event) {
+            if let InputEvent::AxisMotion { device, .. } = event {
+                if self.pointer_devices.contains(&device) {
+                    return;
+                }
+            }
+            self.handle_event(event);
+        }
+    }
+    fn release_axis_device(&mut self, device: u64) {
+        let context = self.context_stack.last().map(String::as_str);
+        for (key, binding) in self.bindings.active_bindings_mut(context) {
+            if let BindingEnum::AnalogAxis(binding) = binding {
+                if binding.device == Some(device) {
+                    binding.reset();
+                    self.actions
+                        .set_state(*key, binding.state(), binding.pointer());
+                }
+            }
+        }
+    }
+    /// Like `input_event`, for an event that's already been converted, such as a cursor position
+    /// mapped into the viewport.
+    pub fn handle_event(&mut self, event: InputEvent) {
+        if self.playback.is_some() {
+            return;
+        }
+        self.record(event);
+        self.apply_event(&event);
+    }
+    fn record(&mut self, event: InputEvent) {
+        if let Some((start, recording)) = self.recording.as_mut() {
+            recording.0.push(RecordedEvent {
+                time: start.elapsed().as_secs_f64(),
+                event,
+            });
+        }
+    }
+    fn apply_event(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::MouseMotion(_) if !self.pointer_grabbed => return,
+            InputEvent::PointerGrabbed(grabbed) => {
+                self.grab_pointer(grabbed);
+                return;
+            }
+            InputEvent::WindowResized(size) => {
+                self.window_size = size;
+                // Inactive contexts need the size too, for when they're pushed.
+                for binding in self
+                    .bindings
+                    .contexts
+                    .values_mut()
+                    .flat_map(|bindings| bindings.0.values_mut())
+                {
+                    binding.event(event);
+                }
+            }
+            _ => {}
+        }
+        let timestamp = self.timestamps.then(Instant::now);
+        let context = self.context_stack.last().map(String::as_str);
+        for (key, binding) in self.bindings.active_bindings_mut(context) {
+            if binding.event(event) {
                 self.actions
-                    .set_state(key, binding.state(), binding.pointer());
+                    .set_state_at(*key, binding.state(), binding.pointer(), timestamp);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_action_reads_released_until_enabled() {
+        let mut bindings = InputBindings::default();
+        bindings
+            .global()
+            .add_key("jump", KeyBinding::new(VirtualKeyCode::Space));
+        let mut input = InputSystem::new(bindings);
+        input.apply_event(&InputEvent::Key {
+            key: VirtualKeyCode::Space,
+            pressed: true,
+        });
+        assert!(input.actions().get("jump").pressed());
+
+        input.set_action_enabled("jump", false);
+        assert!(!input.action_enabled("jump"));
+        assert!(input.actions().get("jump").just_released());
+
+        input.set_action_enabled("jump", true);
+        assert!(input.action_enabled("jump"));
+        assert!(input.actions().get("jump").just_pressed());
+        // Enabling an enabled action changes nothing.
+        input.end_frame();
+        input.set_action_enabled("jump", true);
+        assert!(!input.actions().get("jump").just_pressed());
+    }
+
+    #[test]
+    fn unbound_action_is_released_and_not_interned() {
+        let actions = InputActions::default();
+        let state = actions.get("unbound_action_that_is_never_interned");
+        assert!(!state.pressed());
+        assert!(Intern::lookup("unbound_action_that_is_never_interned").is_none());
+    }
+
+    #[test]
+    fn enabling_unbound_action_does_not_intern() {
+        let mut input = InputSystem::new(InputBindings::default());
+        input.set_action_enabled("unbound_action_that_is_never_enabled", false);
+        assert!(Intern::lookup("unbound_action_that_is_never_enabled").is_none());
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn half_pressed_trigger_reads_half() {
+        let trigger = AnalogAxisBinding::new(2, 0.0, 1.0).with_range(0.0, 255.0);
+        assert_close(trigger.response(0.0), 0.0);
+        assert_close(trigger.response(127.5), 0.5);
+        assert_close(trigger.response(255.0), 1.0);
+        // Already-normalized axes need no range.
+        assert_close(AnalogAxisBinding::new(2, 0.0, 1.0).response(0.5), 0.5);
+    }
+
+    #[test]
+    fn deadzone_zeroes_small_values() {
+        let stick = AnalogAxisBinding::new(0, 0.2, 1.0).with_range(0.0, 32767.0);
+        assert_eq!(stick.response(3000.0), 0.0);
+        assert_eq!(stick.response(-6000.0), 0.0);
+        // The rest of the range is rescaled, keeping the sign.
+        assert_close(stick.response(0.6 * 32767.0), 0.5);
+        assert_close(stick.response(-0.6 * 32767.0), -0.5);
+        assert_close(stick.response(-32768.0), -1.0);
+    }
+
+    #[test]
+    fn mouse_axis_motion_is_ignored() {
+        let mut bindings = InputBindings::default();
+        bindings
+            .global()
+            .add_analog_axis("trigger", AnalogAxisBinding::new(0, 0.0, 1.0));
+        let mut input = InputSystem::new(bindings);
+        let device_id = unsafe { DeviceId::dummy() };
+        let motion = |value: f64| Event::DeviceEvent {
+            device_id,
+            event: DeviceEvent::Motion { axis: 0, value },
+        };
+        input.input_event(motion(0.5));
+        assert_close(input.actions().get("trigger").axis1_state(), 0.5);
+
+        // The same device moves like a mouse, so its axes are a mouse's, not a trigger's.
+        input.input_event(Event::DeviceEvent {
+            device_id,
+            event: DeviceEvent::MouseMotion { delta: (4.0, 0.0) },
+        });
+        assert_eq!(input.actions().get("trigger").axis1_state(), 0.0);
+        input.input_event(motion(0.8));
+        assert_eq!(input.actions().get("trigger").axis1_state(), 0.0);
+    }
+
+    #[test]
+    fn flat_controls_load_as_global() {
+        // The format of controls.yaml from before input contexts.
+        let yaml = "\
+jump: !Key
+  key: Space
+move: !KeyAxis2
+  up:
+    key: W
+  down:
+    key: S
+  left:
+    key: A
+  right:
+    key: D
+primary: !MouseButton
+  button: Left
+";
+        let mut bindings: InputBindings = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(bindings.len(), 3);
+        assert_eq!(bindings.global().len(), 3);
+        assert!(!bindings.has_context("jump"));
+    }
+
+    #[test]
+    fn context_controls_round_trip() {
+        let mut bindings = InputBindings::default_bindings();
+        bindings
+            .context("menu")
+            .add_key("confirm", KeyBinding::new(VirtualKeyCode::Return));
+        let yaml = serde_yaml::to_string(&bindings).unwrap();
+        let loaded: InputBindings = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded.len(), bindings.len());
+        assert!(loaded.has_context("menu"));
+    }
+
+    #[test]
+    fn profile_save_load_round_trip() {
+        let name = format!("test_{}", std::process::id());
+        // Saving doesn't create the "config" folder.
+        std::fs::create_dir_all(asset::get_path("config", "")).unwrap();
+        let mut bindings = InputBindings::default_bindings();
+        bindings
+            .context("menu")
+            .add_key("confirm", KeyBinding::new(VirtualKeyCode::Return));
+        let mut input = InputSystem::new(bindings);
+        input.save_profile(&name).unwrap();
+        assert_eq!(input.profile(), name);
+        let listed = InputSystem::list_profiles();
+
+        let mut loaded = InputSystem::new(InputBindings::default());
+        loaded.load_profile(&name);
+        std::fs::remove_file(asset::get_path("config", &format!("controls_{name}.yaml"))).unwrap();
+
+        assert!(listed.unwrap().contains(&name));
+        assert_eq!(loaded.profile(), name);
+        assert_eq!(loaded.bindings().len(), input.bindings().len());
+        assert!(loaded.bindings().has_context("menu"));
+    }
+
+    #[test]
+    fn invalid_profile_names_are_rejected() {
+        for name in ["", "../controls", "two words"] {
+            assert!(InputBindings::profile_file(name).is_err());
+        }
+    }
+
+    #[test]
+    fn motion_only_accumulates_while_grabbed() {
+        let mut bindings = InputBindings::default();
+        bindings
+            .global()
+            .add_mouse_motion("look", MouseMotionBinding::new(0.5));
+        let mut input = InputSystem::new(bindings);
+        let look = |input: &InputSystem| input.actions().get("look").axis2_state();
+
+        input.handle_event(InputEvent::MouseMotion(Vec2::new(10.0, 4.0)));
+        input.start_frame();
+        assert_eq!(look(&input), Vec2::ZERO);
+
+        input.set_pointer_grabbed(true);
+        input.handle_event(InputEvent::MouseMotion(Vec2::new(10.0, 4.0)));
+        input.handle_event(InputEvent::MouseMotion(Vec2::new(2.0, 0.0)));
+        input.start_frame();
+        assert_eq!(look(&input), Vec2::new(6.0, 2.0));
+
+        // Motion from before the release is dropped, not applied next frame.
+        input.handle_event(InputEvent::MouseMotion(Vec2::new(10.0, 4.0)));
+        input.set_pointer_grabbed(false);
+        assert_eq!(look(&input), Vec2::ZERO);
+        input.start_frame();
+        assert_eq!(look(&input), Vec2::ZERO);
+    }
+
+    #[test]
+    fn pointer_maps_through_camera() {
+        let mut bindings = InputBindings::default();
+        bindings
+            .global()
+            .add_mouse_button("select", MouseButtonBinding::new(MouseButton::Left));
+        let mut input = InputSystem::new(bindings);
+        input.handle_event(InputEvent::CursorMoved(Vec2::new(500.0, 200.0)));
+        input.handle_event(InputEvent::MouseButton {
+            button: MouseButton::Left,
+            pressed: true,
+        });
+
+        let mut camera = CameraTransform::new(Vec2::new(800.0, 600.0));
+        camera.center = Vec2::new(100.0, 50.0);
+        camera.zoom = 2.0;
+        let select = input.actions().get("select");
+        assert_eq!(select.pointer(), Some(Vec2::new(500.0, 200.0)));
+        // 100 right of and 100 above the middle of the screen, at 2x zoom.
+        assert_eq!(select.pointer_world(&camera), Some(Vec2::new(150.0, 0.0)));
+        assert_eq!(ActionState::default().pointer_world(&camera), None);
+    }
+
+    #[test]
+    fn context_switch_changes_which_action_a_key_drives() {
+        let mut bindings = InputBindings::default();
+        bindings
+            .global()
+            .add_key("pause", KeyBinding::new(VirtualKeyCode::Escape));
+        bindings
+            .context("gameplay")
+            .add_key("jump", KeyBinding::new(VirtualKeyCode::Space));
+        bindings
+            .context("menu")
+            .add_key("confirm", KeyBinding::new(VirtualKeyCode::Space));
+        let mut input = InputSystem::new(bindings);
+        let key = |input: &mut InputSystem, key, pressed| {
+            input.handle_event(InputEvent::Key { key, pressed });
+        };
+        let pressed = |input: &InputSystem, action| input.actions().get(action).pressed();
+
+        input.push_context("gameplay");
+        key(&mut input, VirtualKeyCode::Space, true);
+        key(&mut input, VirtualKeyCode::Escape, true);
+        assert!(pressed(&input, "jump"));
+        assert!(!pressed(&input, "confirm"));
+        assert!(pressed(&input, "pause"));
+
+        // Switching releases what the old context was holding, but not global actions.
+        input.push_context("menu");
+        assert!(!pressed(&input, "jump"));
+        assert!(pressed(&input, "pause"));
+        key(&mut input, VirtualKeyCode::Space, false);
+        key(&mut input, VirtualKeyCode::Space, true);
+        assert!(pressed(&input, "confirm"));
+        assert!(!pressed(&input, "jump"));
+
+        assert_eq!(input.pop_context().as_deref(), Some("menu"));
+        assert_eq!(input.active_context(), Some("gameplay"));
+        assert!(!pressed(&input, "confirm"));
+        key(&mut input, VirtualKeyCode::Space, false);
+        key(&mut input, VirtualKeyCode::Space, true);
+        assert!(pressed(&input, "jump"));
+    }
+
+    #[test]
+    fn playback_reproduces_recorded_actions() {
+        let bindings = || {
+            let mut bindings = InputBindings::default();
+            let global = bindings.global();
+            global.add_key("jump", KeyBinding::new(VirtualKeyCode::Space));
+            global.add_mouse_motion("look", MouseMotionBinding::new(1.0));
+            bindings
+        };
+        let mut input = InputSystem::new(bindings());
+        input.set_pointer_grabbed(true);
+        input.start_recording();
+        input.handle_event(InputEvent::MouseMotion(Vec2::new(3.0, 4.0)));
+        input.handle_event(InputEvent::Key {
+            key: VirtualKeyCode::Space,
+            pressed: true,
+        });
+        input.start_frame();
+        let recording = input.stop_recording().unwrap();
+        let yaml = serde_yaml::to_string(&recording).unwrap();
+        let recording: InputRecording = serde_yaml::from_str(&yaml).unwrap();
+
+        // The recorded session had the pointer grabbed, so motion is replayed even though this
+        // one hasn't grabbed it.
+        let mut replay = InputSystem::new(bindings());
+        let duration = std::time::Duration::from_secs_f64(recording.duration());
+        replay.start_playback(recording);
+        std::thread::sleep(duration);
+        // Live input is ignored during playback.
+        replay.handle_event(InputEvent::Key {
+            key: VirtualKeyCode::Space,
+            pressed: false,
+        });
+        replay.start_frame();
+        assert!(!replay.is_playing_back());
+        assert!(replay.pointer_grabbed());
+        for action in ["jump", "look"] {
+            assert_eq!(
+                replay.actions().get(action).state,
+                input.actions().get(action).state,
+                "{action}"
+            );
+        }
+        assert_eq!(
+            replay.actions().get("look").axis2_state(),
+            Vec2::new(3.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn unknown_binding_type_fails_to_load() {
+        assert!(serde_yaml::from_str::<InputBindings>("jump: !Teleport\n  key: Space\n").is_err());
+    }
+
+    fn normalized_cursor_bindings() -> InputBindings {
+        let mut bindings = InputBindings::default();
+        bindings
+            .global()
+            .add_mouse_position("cursor", MousePositionBinding::new(true));
+        bindings
+    }
+
+    #[test]
+    fn normalized_mouse_position_uses_window_size() {
+        let mut input = InputSystem::new(normalized_cursor_bindings());
+        input.set_window_size(Vec2::new(200.0, 100.0));
+        input.apply_event(&InputEvent::CursorMoved(Vec2::new(150.0, 25.0)));
+        assert_eq!(
+            input.actions().get("cursor").axis2_state(),
+            Vec2::new(0.5, -0.5)
+        );
+    }
+
+    #[test]
+    fn window_size_survives_new_bindings() {
+        let mut input = InputSystem::new(InputBindings::default());
+        input.set_window_size(Vec2::new(200.0, 100.0));
+        input.set_bindings(normalized_cursor_bindings());
+        input.apply_event(&InputEvent::CursorMoved(Vec2::new(100.0, 100.0)));
+        assert_eq!(
+            input.actions().get("cursor").axis2_state(),
+            Vec2::new(0.0, 1.0)
+        );
+    }
+}
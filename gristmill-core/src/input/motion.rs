@@ -0,0 +1,572 @@
+use crate::{
+    input::InputActions,
+    math::Vec2,
+    util::{Intern, Symbol},
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+/// The most inputs kept for matching, however recent.
+const MAX_HISTORY: usize = 64;
+
+/// A stick or d-pad direction relative to the way the character faces, so "forward" is toward
+/// the opponent on either side. Numbered like a numpad, as fighting games write motions: 6 is
+/// forward, 4 back, 2 down, 8 up, 5 neutral and 3 down-forward.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    DownBack = 1,
+    Down = 2,
+    DownForward = 3,
+    Back = 4,
+    Neutral = 5,
+    Forward = 6,
+    UpBack = 7,
+    Up = 8,
+    UpForward = 9,
+}
+
+impl Direction {
+    pub fn from_numpad(digit: u8) -> Option<Self> {
+        use Direction::*;
+        Some(match digit {
+            1 => DownBack,
+            2 => Down,
+            3 => DownForward,
+            4 => Back,
+            5 => Neutral,
+            6 => Forward,
+            7 => UpBack,
+            8 => Up,
+            9 => UpForward,
+            _ => return None,
+        })
+    }
+    pub fn numpad(self) -> u8 {
+        self as u8
+    }
+    /// The direction of a stick with y up, ignoring each axis within `deadzone` of 0. Mirrored
+    /// when the character faces left.
+    pub fn from_axis(value: Vec2, deadzone: f32, facing_right: bool) -> Self {
+        let axis = |value: f32| {
+            if value > deadzone {
+                1
+            } else if value < -deadzone {
+                -1
+            } else {
+                0
+            }
+        };
+        let x = if facing_right {
+            axis(value.x)
+        } else {
+            -axis(value.x)
+        };
+        let y = axis(value.y);
+        Direction::from_numpad((5 + x + y * 3) as u8).unwrap()
+    }
+}
+
+/// One step of a `MotionPattern`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MotionStep {
+    Direction(Direction),
+    /// Pressing a button action.
+    Button(Symbol),
+}
+
+/// A sequence of directions and button presses, such as quarter-circle-forward + punch.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MotionPattern {
+    steps: Vec<MotionStep>,
+}
+
+impl MotionPattern {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.steps.push(MotionStep::Direction(direction));
+        self
+    }
+    pub fn button(mut self, action: &str) -> Self {
+        self.steps.push(MotionStep::Button(Symbol::new(action)));
+        self
+    }
+    /// Read numpad notation: each digit is a direction and each name in brackets a button action,
+    /// so quarter-circle-forward + punch is `"236[punch]"`. Returns `None` if the notation is
+    /// invalid or empty.
+    pub fn parse(notation: &str) -> Option<Self> {
+        let mut pattern = MotionPattern::new();
+        let mut chars = notation.chars();
+        while let Some(c) = chars.next() {
+            pattern = match c {
+                '[' => {
+                    let name: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                    if name.is_empty() {
+                        return None;
+                    }
+                    pattern.button(&name)
+                }
+                c if c.is_whitespace() => pattern,
+                c => pattern.direction(Direction::from_numpad(c.to_digit(10)? as u8)?),
+            };
+        }
+        (!pattern.steps.is_empty()).then_some(pattern)
+    }
+    pub fn steps(&self) -> &[MotionStep] {
+        &self.steps
+    }
+}
+
+/// How strictly motions are matched.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MotionLeniency {
+    /// Let other inputs come between a pattern's steps, such as passing through 1 between the 2
+    /// and 3 of a sloppy 236. When off, the steps must be the most recent inputs, in a row.
+    pub allow_extra_inputs: bool,
+    /// How long a completed motion can still be taken with `MotionDetector::take_motion`, so a
+    /// motion finished just before the character can act isn't lost.
+    pub buffer: Duration,
+}
+
+impl Default for MotionLeniency {
+    fn default() -> Self {
+        MotionLeniency {
+            allow_extra_inputs: true,
+            buffer: Duration::from_millis(100),
+        }
+    }
+}
+
+/// A motion that was completed.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MotionEvent {
+    pub name: Symbol,
+    /// When the motion's last input was made.
+    pub time: Instant,
+}
+
+struct Motion {
+    name: Symbol,
+    pattern: MotionPattern,
+    window: Duration,
+    /// The serial of the input that last completed this motion. Inputs up to it can't be reused.
+    consumed: Option<u64>,
+}
+
+#[derive(Copy, Clone)]
+struct MotionInput {
+    step: MotionStep,
+    time: Instant,
+    serial: u64,
+}
+
+/// Detects motion inputs like the special moves of fighting games. Feed it the actions every
+/// frame with `update`; it records each change of direction and each button press that a
+/// registered pattern uses, and reports a motion when its last step is input and all its steps
+/// were input in order within its time window.
+pub struct MotionDetector {
+    direction_action: Symbol,
+    deadzone: f32,
+    facing_right: bool,
+    leniency: MotionLeniency,
+    motions: Vec<Motion>,
+    history: VecDeque<MotionInput>,
+    next_serial: u64,
+    last_direction: Direction,
+    events: Vec<MotionEvent>,
+    pending: HashMap<Symbol, Instant>,
+}
+
+impl MotionDetector {
+    /// Read directions from the Axis2 action `direction_action`, such as "move".
+    pub fn new(direction_action: &str) -> Self {
+        MotionDetector {
+            direction_action: Symbol::new(direction_action),
+            deadzone: 0.5,
+            facing_right: true,
+            leniency: MotionLeniency::default(),
+            motions: Vec::new(),
+            history: VecDeque::new(),
+            next_serial: 0,
+            last_direction: Direction::Neutral,
+            events: Vec::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Watch for `pattern`, reported as `name` when all its steps are input within `window`,
+    /// timed from the first step to the last. Replaces any motion already named `name`.
+    pub fn register_motion(&mut self, name: &str, pattern: MotionPattern, window: Duration) {
+        let name = Symbol::new(name);
+        self.motions.retain(|motion| motion.name != name);
+        self.motions.push(Motion {
+            name,
+            pattern,
+            window,
+            consumed: None,
+        });
+    }
+    pub fn unregister_motion(&mut self, name: &str) {
+        if let Some(name) = Intern::lookup(name) {
+            self.motions.retain(|motion| motion.name != name);
+            self.pending.remove(&name);
+        }
+    }
+
+    pub fn leniency(&self) -> MotionLeniency {
+        self.leniency
+    }
+    pub fn set_leniency(&mut self, leniency: MotionLeniency) {
+        self.leniency = leniency;
+    }
+    /// How far the stick must be pushed on an axis to count as a direction. 0.5 by default.
+    pub fn set_deadzone(&mut self, deadzone: f32) {
+        self.deadzone = deadzone.clamp(0.0, 1.0);
+    }
+    /// Which way the character faces, so forward and back follow it. Facing right by default.
+    pub fn set_facing_right(&mut self, facing_right: bool) {
+        self.facing_right = facing_right;
+    }
+    pub fn facing_right(&self) -> bool {
+        self.facing_right
+    }
+
+    /// Record this frame's input and match it against the registered motions. Inputs are timed
+    /// by their action's timestamp when `InputSystem::set_timestamps` is on, or else `now`.
+    pub fn update(&mut self, actions: &InputActions, now: Instant) {
+        self.events.clear();
+        if let Some(state) = actions.try_get_symbol(self.direction_action) {
+            let direction =
+                Direction::from_axis(state.axis2_state(), self.deadzone, self.facing_right);
+            self.push_direction(direction, state.timestamp().unwrap_or(now));
+        }
+        let mut buttons: Vec<Symbol> = self
+            .motions
+            .iter()
+            .flat_map(|motion| motion.pattern.steps.iter())
+            .filter_map(|step| match step {
+                MotionStep::Button(button) => Some(*button),
+                MotionStep::Direction(_) => None,
+            })
+            .collect();
+        buttons.sort();
+        buttons.dedup();
+        for button in buttons {
+            if let Some(state) = actions
+                .try_get_symbol(button)
+                .filter(|state| state.just_pressed())
+            {
+                self.push_button(button, state.timestamp().unwrap_or(now));
+            }
+        }
+        self.prune(now);
+    }
+    /// Record the stick pointing in `direction`, relative to the character. Only changes of
+    /// direction are recorded, so this can be called every frame.
+    pub fn push_direction(&mut self, direction: Direction, time: Instant) {
+        if direction != self.last_direction {
+            self.last_direction = direction;
+            self.push(MotionStep::Direction(direction), time);
+        }
+    }
+    /// Record a press of the button action `button`.
+    pub fn push_button(&mut self, button: Symbol, time: Instant) {
+        self.push(MotionStep::Button(button), time);
+    }
+
+    /// The motions completed during the last `update`, in the order they were completed.
+    pub fn events(&self) -> &[MotionEvent] {
+        &self.events
+    }
+    /// Whether `name` was completed within the leniency's buffer before `now` and hasn't been
+    /// taken yet. Taking it clears it.
+    pub fn take_motion(&mut self, name: &str, now: Instant) -> bool {
+        let buffer = self.leniency.buffer;
+        match Intern::lookup(name).and_then(|name| self.pending.remove(&name)) {
+            Some(time) => now.saturating_duration_since(time) <= buffer,
+            None => false,
+        }
+    }
+    /// Forget recorded inputs and completed motions, such as when a round restarts.
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.events.clear();
+        self.pending.clear();
+        self.last_direction = Direction::Neutral;
+    }
+
+    fn push(&mut self, step: MotionStep, time: Instant) {
+        let input = MotionInput {
+            step,
+            time,
+            serial: self.next_serial,
+        };
+        self.next_serial += 1;
+        self.history.push_back(input);
+        if self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+        for motion in self.motions.iter_mut() {
+            if Self::matches(motion, &self.history, self.leniency.allow_extra_inputs) {
+                motion.consumed = Some(input.serial);
+                self.events.push(MotionEvent {
+                    name: motion.name,
+                    time,
+                });
+                self.pending.insert(motion.name, time);
+            }
+        }
+    }
+    /// Whether the newest input completes `motion`. Steps are matched newest first, each to the
+    /// latest input that fits, which leaves the most time for the earlier steps.
+    fn matches(motion: &Motion, history: &VecDeque<MotionInput>, allow_extra_inputs: bool) -> bool {
+        let newest = match history.back() {
+            Some(newest) => *newest,
+            None => return false,
+        };
+        let mut steps = motion.pattern.steps.iter().rev().peekable();
+        if steps.peek() != Some(&&newest.step) {
+            return false;
+        }
+        for input in history.iter().rev() {
+            if motion
+                .consumed
+                .is_some_and(|consumed| input.serial <= consumed)
+                || newest.time.saturating_duration_since(input.time) > motion.window
+            {
+                return false;
+            }
+            match steps.peek() {
+                Some(step) if **step == input.step => {
+                    steps.next();
+                    if steps.peek().is_none() {
+                        return true;
+                    }
+                }
+                Some(_) if !allow_extra_inputs => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+    fn prune(&mut self, now: Instant) {
+        let longest = self
+            .motions
+            .iter()
+            .map(|motion| motion.window)
+            .max()
+            .unwrap_or_default();
+        while self
+            .history
+            .front()
+            .is_some_and(|input| now.saturating_duration_since(input.time) > longest)
+        {
+            self.history.pop_front();
+        }
+        let buffer = self.leniency.buffer;
+        self.pending
+            .retain(|_, time| now.saturating_duration_since(*time) <= buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{
+        InputBindings, InputEvent, InputSystem, KeyAxis2Binding, KeyBinding, VirtualKeyCode,
+    };
+
+    type Key = VirtualKeyCode;
+
+    /// Plays keyboard input one frame at a time into a `MotionDetector`, like a game would.
+    struct Player {
+        input: InputSystem,
+        detector: MotionDetector,
+        start: Instant,
+    }
+
+    impl Player {
+        fn new(window: Duration) -> Self {
+            let mut bindings = InputBindings::default();
+            let global = bindings.global();
+            global.add_key_axis2("move", KeyAxis2Binding::new(Key::W, Key::S, Key::A, Key::D));
+            global.add_key("punch", KeyBinding::new(Key::J));
+            let mut detector = MotionDetector::new("move");
+            detector.register_motion(
+                "hadouken",
+                MotionPattern::parse("236[punch]").unwrap(),
+                window,
+            );
+            Player {
+                input: InputSystem::new(bindings),
+                detector,
+                start: Instant::now(),
+            }
+        }
+        fn time(&self, ms: u64) -> Instant {
+            self.start + Duration::from_millis(ms)
+        }
+        /// Press and release keys, then run a frame at `ms`. Returns the motions completed.
+        fn frame(&mut self, ms: u64, press: &[Key], release: &[Key]) -> Vec<Symbol> {
+            for (keys, pressed) in [(press, true), (release, false)] {
+                for key in keys {
+                    self.input
+                        .apply_event(&InputEvent::Key { key: *key, pressed });
+                }
+            }
+            let now = self.time(ms);
+            self.detector.update(self.input.actions(), now);
+            self.input.end_frame();
+            self.detector
+                .events()
+                .iter()
+                .map(|event| event.name)
+                .collect()
+        }
+        /// A quarter circle forward from neutral, one step every `step` ms starting at `start`,
+        /// then punch one step later. Returns the motions completed by the punch.
+        fn quarter_circle_punch(&mut self, start: u64, step: u64) -> Vec<Symbol> {
+            self.frame(start, &[Key::S], &[]);
+            self.frame(start + step, &[Key::D], &[]);
+            self.frame(start + step * 2, &[], &[Key::S]);
+            self.frame(start + step * 3, &[Key::J], &[Key::D])
+        }
+    }
+
+    fn hadouken() -> Vec<Symbol> {
+        vec![Symbol::new("hadouken")]
+    }
+
+    #[test]
+    fn quarter_circle_forward_punch_matches() {
+        let mut player = Player::new(Duration::from_millis(300));
+        assert_eq!(player.quarter_circle_punch(0, 50), hadouken());
+        assert!(player.detector.take_motion("hadouken", player.time(160)));
+        assert!(!player.detector.take_motion("hadouken", player.time(160)));
+    }
+
+    #[test]
+    fn sequence_just_inside_window_matches() {
+        let mut player = Player::new(Duration::from_millis(300));
+        // The first step is at 0 and the punch at 300.
+        assert_eq!(player.quarter_circle_punch(0, 100), hadouken());
+    }
+
+    #[test]
+    fn too_slow_sequence_is_rejected() {
+        let mut player = Player::new(Duration::from_millis(300));
+        assert!(player.quarter_circle_punch(0, 101).is_empty());
+        assert!(!player.detector.take_motion("hadouken", player.time(303)));
+    }
+
+    #[test]
+    fn steps_out_of_order_are_rejected() {
+        let mut player = Player::new(Duration::from_millis(300));
+        // 6, 3, 2 + punch: a reversed quarter circle.
+        player.frame(0, &[Key::D], &[]);
+        player.frame(20, &[Key::S], &[]);
+        assert!(player.frame(40, &[Key::J], &[Key::D]).is_empty());
+    }
+
+    #[test]
+    fn punch_before_directions_is_rejected() {
+        let mut player = Player::new(Duration::from_millis(300));
+        player.frame(0, &[Key::J], &[]);
+        player.frame(20, &[Key::S], &[Key::J]);
+        player.frame(40, &[Key::D], &[]);
+        assert!(player.frame(60, &[], &[Key::S]).is_empty());
+    }
+
+    #[test]
+    fn holding_or_releasing_punch_does_not_repeat_the_motion() {
+        let mut player = Player::new(Duration::from_millis(300));
+        assert_eq!(player.quarter_circle_punch(0, 20), hadouken());
+        // Still held, then released: neither is a new press.
+        assert!(player.frame(80, &[], &[]).is_empty());
+        assert!(player.frame(100, &[], &[Key::J]).is_empty());
+        // A second press reuses the same directions, which were already consumed.
+        assert!(player.frame(120, &[Key::J], &[]).is_empty());
+    }
+
+    #[test]
+    fn motion_can_repeat_with_new_directions() {
+        let mut player = Player::new(Duration::from_millis(300));
+        assert_eq!(player.quarter_circle_punch(0, 20), hadouken());
+        player.frame(80, &[], &[Key::J]);
+        assert_eq!(player.quarter_circle_punch(100, 20), hadouken());
+    }
+
+    #[test]
+    fn facing_left_mirrors_forward() {
+        let mut player = Player::new(Duration::from_millis(300));
+        player.detector.set_facing_right(false);
+        assert!(player.quarter_circle_punch(0, 20).is_empty());
+        player.frame(80, &[], &[Key::J]);
+        player.frame(100, &[Key::S], &[]);
+        player.frame(120, &[Key::A], &[]);
+        player.frame(140, &[], &[Key::S]);
+        assert_eq!(player.frame(160, &[Key::J], &[Key::A]), hadouken());
+    }
+
+    #[test]
+    fn strict_leniency_rejects_extra_inputs() {
+        let mut player = Player::new(Duration::from_millis(300));
+        player.detector.set_leniency(MotionLeniency {
+            allow_extra_inputs: false,
+            ..Default::default()
+        });
+        // 2, 1, 3, 6: passes through down-back on the way.
+        let time = player.time(0);
+        player.detector.push_direction(Direction::Down, time);
+        player.detector.push_direction(Direction::DownBack, time);
+        player.detector.push_direction(Direction::DownForward, time);
+        player.detector.push_direction(Direction::Forward, time);
+        player.detector.push_button(Symbol::new("punch"), time);
+        assert!(player.detector.events().is_empty());
+    }
+
+    #[test]
+    fn buffered_motion_expires() {
+        let mut player = Player::new(Duration::from_millis(300));
+        assert_eq!(player.quarter_circle_punch(0, 20), hadouken());
+        // The default buffer is 100ms after the punch at 60.
+        assert!(!player.detector.take_motion("hadouken", player.time(161)));
+    }
+
+    #[test]
+    fn parse_numpad_notation() {
+        let pattern = MotionPattern::parse("236 [punch]").unwrap();
+        assert_eq!(
+            pattern.steps(),
+            &[
+                MotionStep::Direction(Direction::Down),
+                MotionStep::Direction(Direction::DownForward),
+                MotionStep::Direction(Direction::Forward),
+                MotionStep::Button(Symbol::new("punch")),
+            ]
+        );
+        assert!(MotionPattern::parse("").is_none());
+        assert!(MotionPattern::parse("20").is_none());
+        assert!(MotionPattern::parse("2x").is_none());
+        assert!(MotionPattern::parse("2[]").is_none());
+    }
+
+    #[test]
+    fn axis_directions() {
+        assert_eq!(
+            Direction::from_axis(Vec2::new(0.2, -0.2), 0.5, true),
+            Direction::Neutral
+        );
+        assert_eq!(
+            Direction::from_axis(Vec2::new(1.0, -1.0), 0.5, true),
+            Direction::DownForward
+        );
+        assert_eq!(
+            Direction::from_axis(Vec2::new(1.0, -1.0), 0.5, false),
+            Direction::DownBack
+        );
+    }
+}
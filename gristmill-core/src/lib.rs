@@ -1,6 +1,9 @@
 pub mod asset;
 pub mod geom2d;
 pub mod input;
+pub mod locale;
+pub mod save;
+pub mod util;
 
 pub use glam as math;
 pub use slotmap;
@@ -24,6 +27,54 @@ impl Color {
     pub const fn new_value(value: f32) -> Self {
         Self::new(value, value, value, 1.0)
     }
+
+    pub fn alpha(&self) -> f32 {
+        self.0[3]
+    }
+    pub fn multiply_alpha(self, factor: f32) -> Self {
+        let [r, g, b, a] = self.0;
+        Color([r, g, b, a * factor])
+    }
+
+    // Blend modes. These combine the RGB channels of `self` (the base) and `other`, clamped to
+    // 0..1, and keep the base's alpha.
+    fn blend_rgb(self, other: Color, f: impl Fn(f32, f32) -> f32) -> Self {
+        let [r, g, b, a] = self.0;
+        let [or, og, ob, _] = other.0;
+        Color([
+            f(r, or).clamp(0.0, 1.0),
+            f(g, og).clamp(0.0, 1.0),
+            f(b, ob).clamp(0.0, 1.0),
+            a,
+        ])
+    }
+    /// Darkens: white leaves the base unchanged, black makes it black.
+    pub fn multiply(self, other: Color) -> Self {
+        self.blend_rgb(other, |base, blend| base * blend)
+    }
+    /// Lightens: black leaves the base unchanged, white makes it white.
+    pub fn screen(self, other: Color) -> Self {
+        self.blend_rgb(other, |base, blend| 1.0 - (1.0 - base) * (1.0 - blend))
+    }
+    /// Multiplies dark parts of the base and screens light parts, increasing contrast.
+    pub fn overlay(self, other: Color) -> Self {
+        self.blend_rgb(other, |base, blend| {
+            if base < 0.5 {
+                2.0 * base * blend
+            } else {
+                1.0 - 2.0 * (1.0 - base) * (1.0 - blend)
+            }
+        })
+    }
+    /// Lightens by adding the channels. Named apart from `std::ops::Add`, since it clamps and
+    /// keeps the base's alpha.
+    pub fn add_blend(self, other: Color) -> Self {
+        self.blend_rgb(other, |base, blend| base + blend)
+    }
+    /// Darkens by subtracting the channels.
+    pub fn subtract_blend(self, other: Color) -> Self {
+        self.blend_rgb(other, |base, blend| base - blend)
+    }
 }
 
 impl From<[f32; 4]> for Color {
@@ -56,3 +107,31 @@ macro_rules! new_storage_types {
         pub type $storage_ty = $crate::slotmap::$map_ty<$key_ty, $value_ty>;
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_and_subtract_blends_clamp_and_keep_alpha() {
+        let base = Color::new(0.75, 0.5, 0.25, 0.5);
+        let blend = Color::new(0.5, 0.5, 0.5, 1.0);
+        assert_eq!(base.add_blend(blend).0, [1.0, 1.0, 0.75, 0.5]);
+        assert_eq!(base.subtract_blend(blend).0, [0.25, 0.0, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn multiply_screen_and_overlay_reference_values() {
+        let base = Color::new(0.25, 0.5, 0.75, 0.5);
+        let blend = Color::new(0.5, 0.25, 0.5, 1.0);
+        assert_eq!(base.multiply(blend).0, [0.125, 0.125, 0.375, 0.5]);
+        assert_eq!(base.screen(blend).0, [0.625, 0.625, 0.875, 0.5]);
+        // Red is below 0.5 so it multiplies, green and blue are at or above it so they screen.
+        assert_eq!(base.overlay(blend).0, [0.25, 0.25, 0.75, 0.5]);
+
+        // Each mode's neutral color leaves the base unchanged.
+        assert_eq!(base.multiply(Color::WHITE).0, base.0);
+        assert_eq!(base.screen(Color::BLACK).0, base.0);
+        assert_eq!(base.overlay(Color::new_value(0.5)).0, base.0);
+    }
+}
@@ -0,0 +1,253 @@
+//! Locale-aware formatting of numbers, currency and dates for display.
+//!
+//! This uses a small built-in table rather than full CLDR data. Languages are looked up by tag,
+//! falling back from a region ("de-AT") to its language ("de") and then to English.
+
+use std::sync::RwLock;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+    YearMonthDay,
+}
+
+/// How numbers and dates are written in one language.
+#[derive(Copy, Clone, Debug)]
+pub struct LocaleFormat {
+    pub group_separator: &'static str,
+    pub decimal_mark: char,
+    /// Whether the currency symbol comes before the number ("$5") or after it ("5 €").
+    pub currency_prefix: bool,
+    pub date_order: DateOrder,
+    pub date_separator: char,
+    /// Whether days and months below 10 get a leading zero.
+    pub date_padded: bool,
+}
+
+const fn locale(
+    group_separator: &'static str,
+    decimal_mark: char,
+    currency_prefix: bool,
+    date_order: DateOrder,
+    date_separator: char,
+    date_padded: bool,
+) -> LocaleFormat {
+    LocaleFormat {
+        group_separator,
+        decimal_mark,
+        currency_prefix,
+        date_order,
+        date_separator,
+        date_padded,
+    }
+}
+
+use DateOrder::*;
+const ENGLISH: LocaleFormat = locale(",", '.', true, MonthDayYear, '/', false);
+const LOCALES: &[(&str, LocaleFormat)] = &[
+    ("en", ENGLISH),
+    ("en-gb", locale(",", '.', true, DayMonthYear, '/', true)),
+    ("en-au", locale(",", '.', true, DayMonthYear, '/', true)),
+    ("de", locale(".", ',', false, DayMonthYear, '.', true)),
+    (
+        "fr",
+        locale("\u{202f}", ',', false, DayMonthYear, '/', true),
+    ),
+    ("es", locale(".", ',', false, DayMonthYear, '/', true)),
+    ("it", locale(".", ',', false, DayMonthYear, '/', true)),
+    ("nl", locale(".", ',', true, DayMonthYear, '-', true)),
+    ("pt", locale(".", ',', false, DayMonthYear, '/', true)),
+    ("pt-br", locale(".", ',', true, DayMonthYear, '/', true)),
+    ("ru", locale("\u{a0}", ',', false, DayMonthYear, '.', true)),
+    ("pl", locale("\u{a0}", ',', false, DayMonthYear, '.', true)),
+    ("ja", locale(",", '.', true, YearMonthDay, '/', true)),
+    ("zh", locale(",", '.', true, YearMonthDay, '/', true)),
+    ("ko", locale(",", '.', true, YearMonthDay, '.', true)),
+];
+
+impl LocaleFormat {
+    /// The format for a language tag such as "fr" or "en-GB". Unknown languages use English.
+    pub fn for_language(language: &str) -> LocaleFormat {
+        let language = language.replace('_', "-").to_lowercase();
+        let find = |tag: &str| {
+            LOCALES
+                .iter()
+                .find(|(name, _)| *name == tag)
+                .map(|(_, format)| *format)
+        };
+        find(&language)
+            .or_else(|| language.split('-').next().and_then(find))
+            .unwrap_or(ENGLISH)
+    }
+
+    fn group_digits(&self, digits: &str) -> String {
+        let mut grouped = String::with_capacity(digits.len() * 2);
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index).is_multiple_of(3) {
+                grouped.push_str(self.group_separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+    /// Localize a number already formatted by Rust (with '.' as the decimal mark).
+    fn localize(&self, formatted: &str) -> String {
+        let (sign, unsigned) = match formatted.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", formatted),
+        };
+        let (whole, fraction) = match unsigned.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (unsigned, None),
+        };
+        let mut result = format!("{sign}{}", self.group_digits(whole));
+        if let Some(fraction) = fraction {
+            result.push(self.decimal_mark);
+            result.push_str(fraction);
+        }
+        result
+    }
+
+    /// Format a number with as many decimals as it needs, such as "1,000.5".
+    pub fn format_number(&self, value: f64) -> String {
+        self.localize(&value.to_string())
+    }
+    /// Format a number rounded to a fixed number of decimals.
+    pub fn format_decimal(&self, value: f64, decimals: usize) -> String {
+        self.localize(&format!("{value:.decimals$}"))
+    }
+    /// Format an amount of money given its ISO 4217 code, such as "USD" or "EUR".
+    pub fn format_currency(&self, value: f64, code: &str) -> String {
+        let (symbol, decimals) = currency_symbol(code);
+        let number = self.format_decimal(value.abs(), decimals);
+        let sign = if value < 0.0 { "-" } else { "" };
+        if self.currency_prefix {
+            format!("{sign}{symbol}{number}")
+        } else {
+            format!("{sign}{number}\u{a0}{symbol}")
+        }
+    }
+    /// Format a calendar date. `month` and `day` start at 1.
+    pub fn format_date(&self, year: i32, month: u32, day: u32) -> String {
+        let pad = |value: u32| {
+            if self.date_padded {
+                format!("{value:02}")
+            } else {
+                value.to_string()
+            }
+        };
+        let (month, day, sep) = (pad(month), pad(day), self.date_separator);
+        match self.date_order {
+            DayMonthYear => format!("{day}{sep}{month}{sep}{year}"),
+            MonthDayYear => format!("{month}{sep}{day}{sep}{year}"),
+            YearMonthDay => format!("{year}{sep}{month}{sep}{day}"),
+        }
+    }
+}
+
+/// The symbol and number of decimals for a currency. Unknown codes are written out in full.
+fn currency_symbol(code: &str) -> (&str, usize) {
+    match code {
+        "USD" | "AUD" | "CAD" => ("$", 2),
+        "EUR" => ("€", 2),
+        "GBP" => ("£", 2),
+        "JPY" => ("¥", 0),
+        "CNY" => ("¥", 2),
+        "KRW" => ("₩", 0),
+        "RUB" => ("₽", 2),
+        "BRL" => ("R$", 2),
+        _ => (code, 2),
+    }
+}
+
+static ACTIVE_LANGUAGE: RwLock<String> = RwLock::new(String::new());
+
+/// Set the language used by the formatting functions, such as "en" or "fr-CA".
+pub fn set_language(language: &str) {
+    *ACTIVE_LANGUAGE.write().unwrap() = language.to_owned();
+}
+/// The active language. English until `set_language` is called.
+pub fn language() -> String {
+    let language = ACTIVE_LANGUAGE.read().unwrap();
+    if language.is_empty() {
+        "en".to_owned()
+    } else {
+        language.clone()
+    }
+}
+pub fn active_format() -> LocaleFormat {
+    LocaleFormat::for_language(&language())
+}
+
+pub fn format_number(value: f64) -> String {
+    active_format().format_number(value)
+}
+pub fn format_decimal(value: f64, decimals: usize) -> String {
+    active_format().format_decimal(value, decimals)
+}
+pub fn format_currency(value: f64, code: &str) -> String {
+    active_format().format_currency(value, code)
+}
+pub fn format_date(year: i32, month: u32, day: u32) -> String {
+    active_format().format_date(year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn english_grouping_and_decimals() {
+        let en = LocaleFormat::for_language("en");
+        assert_eq!(en.format_number(0.0), "0");
+        assert_eq!(en.format_number(999.0), "999");
+        assert_eq!(en.format_number(1000.0), "1,000");
+        assert_eq!(en.format_number(1234567.5), "1,234,567.5");
+        assert_eq!(en.format_number(-1234.5), "-1,234.5");
+        assert_eq!(en.format_number(-999.0), "-999");
+        assert_eq!(en.format_decimal(-1234567.891, 2), "-1,234,567.89");
+        assert_eq!(en.format_decimal(100000.0, 0), "100,000");
+    }
+
+    #[test]
+    fn german_grouping_and_decimals() {
+        let de = LocaleFormat::for_language("de");
+        assert_eq!(de.format_number(1234567.5), "1.234.567,5");
+        assert_eq!(de.format_number(-1234.5), "-1.234,5");
+        assert_eq!(de.format_decimal(-0.5, 2), "-0,50");
+        assert_eq!(de.format_decimal(100000.0, 0), "100.000");
+    }
+
+    #[test]
+    fn french_grouping_and_decimals() {
+        let fr = LocaleFormat::for_language("fr");
+        assert_eq!(fr.format_number(1234567.5), "1\u{202f}234\u{202f}567,5");
+        assert_eq!(fr.format_number(-1234.5), "-1\u{202f}234,5");
+        assert_eq!(fr.format_decimal(-999.999, 2), "-1\u{202f}000,00");
+        assert_eq!(fr.format_number(123.0), "123");
+    }
+
+    #[test]
+    fn regions_fall_back_to_language() {
+        assert_eq!(LocaleFormat::for_language("de-AT").decimal_mark, ',');
+        assert_eq!(LocaleFormat::for_language("fr_CA").decimal_mark, ',');
+        assert_eq!(
+            LocaleFormat::for_language("en-GB").format_date(2024, 3, 5),
+            "05/03/2024"
+        );
+        assert_eq!(
+            LocaleFormat::for_language("xx").format_date(2024, 3, 5),
+            "3/5/2024"
+        );
+    }
+
+    #[test]
+    fn negative_currency() {
+        let en = LocaleFormat::for_language("en");
+        let de = LocaleFormat::for_language("de");
+        assert_eq!(en.format_currency(-1234.5, "USD"), "-$1,234.50");
+        assert_eq!(de.format_currency(-1234.5, "EUR"), "-1.234,50\u{a0}€");
+        assert_eq!(en.format_currency(1500.0, "JPY"), "¥1,500");
+    }
+}
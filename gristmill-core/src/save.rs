@@ -0,0 +1,145 @@
+//! Saving slotmap storages. Slotmap keys aren't stable across a save and load, so a save stores
+//! the values as a list. Keys stored inside values must be saved as indices into that list (see
+//! `SavedKeys`), and fixed up after loading (see `LoadedKeys`).
+
+use serde::{Deserialize, Serialize};
+use slotmap::{Key, SecondaryMap, SlotMap};
+
+/// Maps the keys of a storage being saved to their index in the save.
+pub struct SavedKeys<K: Key>(SecondaryMap<K, usize>);
+
+impl<K: Key> SavedKeys<K> {
+    pub fn index(&self, key: K) -> Option<usize> {
+        self.0.get(key).copied()
+    }
+}
+
+/// Maps the indices of a save to the keys the values were loaded as.
+pub struct LoadedKeys<K: Key>(Vec<K>);
+
+impl<K: Key> LoadedKeys<K> {
+    pub fn key(&self, index: usize) -> Option<K> {
+        self.0.get(index).copied()
+    }
+    /// All loaded keys, in the same order as `StorageSave::values`.
+    pub fn keys(&self) -> &[K] {
+        &self.0
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StorageSave<S> {
+    values: Vec<S>,
+}
+
+impl<S> StorageSave<S> {
+    /// Convert every value in `storage` to its saved form.
+    pub fn save<K, V, F>(storage: &SlotMap<K, V>, mut f: F) -> Self
+    where
+        K: Key,
+        F: FnMut(&V, &SavedKeys<K>) -> S,
+    {
+        let keys = SavedKeys(
+            storage
+                .keys()
+                .enumerate()
+                .map(|(index, key)| (key, index))
+                .collect(),
+        );
+        StorageSave {
+            values: storage.values().map(|value| f(value, &keys)).collect(),
+        }
+    }
+
+    pub fn values(&self) -> &[S] {
+        &self.values
+    }
+
+    /// Insert every saved value into `storage`. Keys stored inside values can't be resolved until
+    /// every value has been inserted, so fix them up afterwards with the returned `LoadedKeys`.
+    pub fn load<K, V, F>(&self, storage: &mut SlotMap<K, V>, f: F) -> LoadedKeys<K>
+    where
+        K: Key,
+        F: FnMut(&S) -> V,
+    {
+        LoadedKeys(
+            self.values
+                .iter()
+                .map(f)
+                .map(|value| storage.insert(value))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slotmap::DefaultKey;
+
+    struct Node {
+        name: String,
+        parent: Option<DefaultKey>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct SavedNode {
+        name: String,
+        parent: Option<usize>,
+    }
+
+    #[test]
+    fn save_load_round_trip() {
+        let mut nodes = SlotMap::new();
+        let root = nodes.insert(Node {
+            name: "root".to_owned(),
+            parent: None,
+        });
+        let removed = nodes.insert(Node {
+            name: "removed".to_owned(),
+            parent: Some(root),
+        });
+        nodes.insert(Node {
+            name: "child".to_owned(),
+            parent: Some(root),
+        });
+        nodes.insert(Node {
+            name: "orphan".to_owned(),
+            parent: Some(removed),
+        });
+        nodes.remove(removed);
+
+        let save = StorageSave::save(&nodes, |node, keys| SavedNode {
+            name: node.name.clone(),
+            parent: node.parent.and_then(|parent| keys.index(parent)),
+        });
+        let yaml = serde_yaml::to_string(&save).unwrap();
+        let save: StorageSave<SavedNode> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(save.values().len(), 3);
+
+        // Load into a storage that already has other keys, so the loaded keys differ.
+        let mut loaded = SlotMap::new();
+        loaded.insert(Node {
+            name: "existing".to_owned(),
+            parent: None,
+        });
+        let keys = save.load(&mut loaded, |saved| Node {
+            name: saved.name.clone(),
+            parent: None,
+        });
+        for (saved, &key) in save.values().iter().zip(keys.keys()) {
+            loaded[key].parent = saved.parent.and_then(|index| keys.key(index));
+        }
+
+        let root = keys.key(0).unwrap();
+        assert_eq!(loaded[root].name, "root");
+        let child = &loaded[keys.key(1).unwrap()];
+        assert_eq!((child.name.as_str(), child.parent), ("child", Some(root)));
+        // The key to the removed node was stale, so it was saved as no parent.
+        let orphan = &loaded[keys.key(2).unwrap()];
+        assert_eq!((orphan.name.as_str(), orphan.parent), ("orphan", None));
+        assert_eq!(keys.key(3), None);
+        assert_eq!(loaded.len(), 4);
+    }
+}
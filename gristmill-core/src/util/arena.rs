@@ -0,0 +1,231 @@
+//! A slotmap storage with typed handles.
+//!
+//! Handles are generational: removing an entry bumps its slot's version, so a handle to a removed
+//! entry never resolves again, even after the slot is reused by a later insert. `get` and friends
+//! return `None` for such stale handles instead of another entry's value.
+
+use crate::save::{LoadedKeys, SavedKeys, StorageSave};
+use slotmap::{Key, KeyData, SlotMap};
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+/// A handle to a value in an `Arena<T>`. Handles are `Copy` and only work with arenas of the same
+/// `T`.
+pub struct Handle<T> {
+    data: KeyData,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for Handle<T> {}
+impl<T> Default for Handle<T> {
+    fn default() -> Self {
+        KeyData::default().into()
+    }
+}
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+impl<T> Eq for Handle<T> {}
+impl<T> PartialOrd for Handle<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T> Ord for Handle<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.data.cmp(&other.data)
+    }
+}
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+    }
+}
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({:?})", self.data)
+    }
+}
+impl<T> From<KeyData> for Handle<T> {
+    fn from(data: KeyData) -> Self {
+        Handle {
+            data,
+            _marker: PhantomData,
+        }
+    }
+}
+unsafe impl<T> Key for Handle<T> {
+    fn data(&self) -> KeyData {
+        self.data
+    }
+}
+
+/// Storage for game objects, addressed by `Handle<T>`.
+pub struct Arena<T> {
+    slots: SlotMap<Handle<T>, T>,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Self {
+        Arena {
+            slots: SlotMap::with_key(),
+        }
+    }
+
+    pub fn insert(&mut self, value: T) -> Handle<T> {
+        self.slots.insert(value)
+    }
+    /// Insert a value that needs to know its own handle.
+    pub fn insert_with_handle<F: FnOnce(Handle<T>) -> T>(&mut self, f: F) -> Handle<T> {
+        self.slots.insert_with_key(f)
+    }
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        self.slots.remove(handle)
+    }
+    pub fn clear(&mut self) {
+        self.slots.clear();
+    }
+    pub fn retain<F: FnMut(Handle<T>, &mut T) -> bool>(&mut self, f: F) {
+        self.slots.retain(f);
+    }
+
+    /// Whether `handle` still refers to a value. False once the value has been removed.
+    pub fn is_alive(&self, handle: Handle<T>) -> bool {
+        self.slots.contains_key(handle)
+    }
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        self.slots.get(handle)
+    }
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.slots.get_mut(handle)
+    }
+    /// Borrow several values mutably at once, such as both sides of a collision. Returns `None` if
+    /// any handle is stale, or if the same handle appears twice.
+    pub fn get_disjoint_mut<const N: usize>(
+        &mut self,
+        handles: [Handle<T>; N],
+    ) -> Option<[&mut T; N]> {
+        self.slots.get_disjoint_mut(handles)
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        self.slots.iter()
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle<T>, &mut T)> {
+        self.slots.iter_mut()
+    }
+    pub fn handles(&self) -> impl Iterator<Item = Handle<T>> + '_ {
+        self.slots.keys()
+    }
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.slots.values()
+    }
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.values_mut()
+    }
+
+    pub fn as_slotmap(&self) -> &SlotMap<Handle<T>, T> {
+        &self.slots
+    }
+
+    /// Convert every value to its saved form. Handles stored inside values should be saved as
+    /// indices with `SavedKeys::index`; see the `save` module.
+    pub fn save<S, F>(&self, f: F) -> StorageSave<S>
+    where
+        F: FnMut(&T, &SavedKeys<Handle<T>>) -> S,
+    {
+        StorageSave::save(&self.slots, f)
+    }
+    /// Insert every saved value. Handles from before the save are not valid afterwards; resolve
+    /// saved indices with the returned `LoadedKeys`.
+    pub fn load<S, F>(&mut self, save: &StorageSave<S>, f: F) -> LoadedKeys<Handle<T>>
+    where
+        F: FnMut(&S) -> T,
+    {
+        save.load(&mut self.slots, f)
+    }
+}
+
+impl<T> std::ops::Index<Handle<T>> for Arena<T> {
+    type Output = T;
+    fn index(&self, handle: Handle<T>) -> &T {
+        &self.slots[handle]
+    }
+}
+impl<T> std::ops::IndexMut<Handle<T>> for Arena<T> {
+    fn index_mut(&mut self, handle: Handle<T>) -> &mut T {
+        &mut self.slots[handle]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The slot a handle points to, without its version.
+    fn slot_index<T>(handle: Handle<T>) -> u32 {
+        handle.data().as_ffi() as u32
+    }
+
+    #[test]
+    fn reused_slot_rejects_stale_handle() {
+        let mut arena = Arena::new();
+        let stale = arena.insert("first");
+        assert_eq!(arena.remove(stale), Some("first"));
+        let fresh = arena.insert("second");
+
+        assert_eq!(slot_index(stale), slot_index(fresh));
+        assert_ne!(stale, fresh);
+        assert!(!arena.is_alive(stale));
+        assert_eq!(arena.get(stale), None);
+        assert_eq!(arena.get_mut(stale), None);
+        assert!(arena.get_disjoint_mut([stale, fresh]).is_none());
+        assert_eq!(arena.remove(stale), None);
+        assert_eq!(arena[fresh], "second");
+        assert_eq!(arena.len(), 1);
+    }
+
+    #[test]
+    fn cleared_handles_stay_stale() {
+        let mut arena = Arena::new();
+        let old = arena.insert(1);
+        arena.clear();
+        let new = arena.insert(2);
+        assert_eq!(arena.get(old), None);
+        assert_eq!(arena.get(new), Some(&2));
+    }
+
+    #[test]
+    fn disjoint_borrows() {
+        let mut arena = Arena::new();
+        let a = arena.insert(1);
+        let b = arena.insert(2);
+        let [x, y] = arena.get_disjoint_mut([a, b]).unwrap();
+        std::mem::swap(x, y);
+        assert_eq!((arena[a], arena[b]), (2, 1));
+        assert!(arena.get_disjoint_mut([a, a]).is_none());
+    }
+}
@@ -0,0 +1,159 @@
+/// In-game time, which can be paused, slowed down or sped up separately from real time. Pass the
+/// loop's delta through `tick` and use the result for gameplay, while the UI keeps using the
+/// real delta so menus stay responsive when the game is paused.
+#[derive(Clone, Debug)]
+pub struct GameClock {
+    scale: f64,
+    paused: bool,
+    total: f64,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        GameClock {
+            scale: 1.0,
+            paused: false,
+            total: 0.0,
+        }
+    }
+}
+
+impl GameClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The game time that passes during `real_dt` seconds of real time, without advancing the
+    /// clock.
+    pub fn scaled_dt(&self, real_dt: f64) -> f64 {
+        if self.paused {
+            0.0
+        } else {
+            real_dt * self.scale
+        }
+    }
+    /// Advance the clock by `real_dt` seconds of real time. Returns the scaled delta.
+    pub fn tick(&mut self, real_dt: f64) -> f64 {
+        let dt = self.scaled_dt(real_dt);
+        self.total += dt;
+        dt
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+    /// How fast game time passes relative to real time: 0.5 for slow motion, 2 to fast-forward.
+    /// Negative values are treated as 0.
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = scale.max(0.0);
+    }
+
+    /// The total game time in seconds.
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+    pub fn set_total(&mut self, total: f64) {
+        self.total = total;
+    }
+    /// How far through the current day the clock is, from 0 (midnight) to 1, for days that are
+    /// `day_length` seconds of game time long.
+    pub fn time_of_day(&self, day_length: f64) -> f64 {
+        if day_length <= 0.0 {
+            0.0
+        } else {
+            self.total.rem_euclid(day_length) / day_length
+        }
+    }
+    /// The number of whole days that have passed.
+    pub fn day(&self, day_length: f64) -> i64 {
+        if day_length <= 0.0 {
+            0
+        } else {
+            (self.total / day_length).floor() as i64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_at_real_speed_by_default() {
+        let mut clock = GameClock::new();
+        assert_eq!(clock.tick(0.25), 0.25);
+        assert_eq!(clock.tick(0.5), 0.5);
+        assert_eq!(clock.total(), 0.75);
+    }
+
+    #[test]
+    fn paused_clock_does_not_advance() {
+        let mut clock = GameClock::new();
+        clock.tick(1.0);
+        clock.pause();
+        assert!(clock.is_paused());
+        assert_eq!(clock.tick(0.5), 0.0);
+        assert_eq!(clock.scaled_dt(0.5), 0.0);
+        assert_eq!(clock.total(), 1.0);
+        clock.resume();
+        assert_eq!(clock.tick(0.5), 0.5);
+        assert_eq!(clock.total(), 1.5);
+        clock.set_paused(true);
+        assert_eq!(clock.tick(0.5), 0.0);
+    }
+
+    #[test]
+    fn time_scale_multiplies_delta() {
+        let mut clock = GameClock::new();
+        clock.set_scale(0.5);
+        assert_eq!(clock.tick(0.5), 0.25);
+        clock.set_scale(2.0);
+        assert_eq!(clock.tick(0.5), 1.0);
+        assert_eq!(clock.total(), 1.25);
+        // Pausing wins over the scale, and the scale is kept for when the clock resumes.
+        clock.pause();
+        assert_eq!(clock.tick(0.5), 0.0);
+        clock.resume();
+        assert_eq!(clock.tick(0.25), 0.5);
+    }
+
+    #[test]
+    fn negative_scale_stops_time() {
+        let mut clock = GameClock::new();
+        clock.set_scale(-1.0);
+        assert_eq!(clock.scale(), 0.0);
+        assert_eq!(clock.tick(1.0), 0.0);
+        assert_eq!(clock.total(), 0.0);
+    }
+
+    #[test]
+    fn scaled_dt_does_not_advance() {
+        let mut clock = GameClock::new();
+        clock.set_scale(3.0);
+        assert_eq!(clock.scaled_dt(0.5), 1.5);
+        assert_eq!(clock.total(), 0.0);
+    }
+
+    #[test]
+    fn days() {
+        let mut clock = GameClock::new();
+        clock.set_total(250.0);
+        assert_eq!(clock.day(100.0), 2);
+        assert_eq!(clock.time_of_day(100.0), 0.5);
+        assert_eq!(clock.day(0.0), 0);
+        assert_eq!(clock.time_of_day(0.0), 0.0);
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+trait EventQueue {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn clear(&mut self);
+}
+
+impl<T: 'static> EventQueue for Vec<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+}
+
+/// Typed messages between game systems, such as combat telling audio and UI that something took
+/// damage. Any `'static` type can be an event; each type gets its own queue, so there's no list of
+/// event types to register.
+///
+/// Events stay queued until drained. Call `clear` once per frame, after every system has had a
+/// chance to drain, so that events nobody listens for don't pile up.
+#[derive(Default)]
+pub struct EventBus {
+    queues: HashMap<TypeId, Box<dyn EventQueue>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn queue<T: 'static>(&self) -> Option<&Vec<T>> {
+        self.queues
+            .get(&TypeId::of::<T>())
+            .and_then(|queue| queue.as_any().downcast_ref())
+    }
+    fn queue_mut<T: 'static>(&mut self) -> Option<&mut Vec<T>> {
+        self.queues
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|queue| queue.as_any_mut().downcast_mut())
+    }
+
+    pub fn emit<T: 'static>(&mut self, event: T) {
+        self.queues
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Vec::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<Vec<T>>()
+            .expect("event queue has the wrong type")
+            .push(event);
+    }
+    /// Remove and return the queued events of type `T`, oldest first.
+    pub fn drain<T: 'static>(&mut self) -> Vec<T> {
+        self.queue_mut().map(std::mem::take).unwrap_or_default()
+    }
+    /// The queued events of type `T`, without removing them.
+    pub fn peek<T: 'static>(&self) -> &[T] {
+        self.queue().map_or(&[], Vec::as_slice)
+    }
+    pub fn len<T: 'static>(&self) -> usize {
+        self.peek::<T>().len()
+    }
+    pub fn is_empty<T: 'static>(&self) -> bool {
+        self.peek::<T>().is_empty()
+    }
+    /// Drop the queued events of every type.
+    pub fn clear(&mut self) {
+        for queue in self.queues.values_mut() {
+            queue.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Debug)]
+    struct Damage(u32);
+    #[derive(PartialEq, Debug)]
+    struct Heal(u32);
+
+    #[test]
+    fn types_have_separate_queues() {
+        let mut bus = EventBus::new();
+        bus.emit(Damage(5));
+        bus.emit(Heal(3));
+        bus.emit(Damage(7));
+        assert_eq!(bus.len::<Damage>(), 2);
+        assert_eq!(bus.len::<Heal>(), 1);
+        assert!(bus.is_empty::<u32>());
+
+        assert_eq!(bus.drain::<Heal>(), vec![Heal(3)]);
+        assert_eq!(bus.peek::<Damage>(), &[Damage(5), Damage(7)]);
+    }
+
+    #[test]
+    fn events_drain_oldest_first() {
+        let mut bus = EventBus::new();
+        for amount in 0..5 {
+            bus.emit(Damage(amount));
+        }
+        assert_eq!(
+            bus.drain::<Damage>(),
+            (0..5).map(Damage).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn drain_after_drain_is_empty() {
+        let mut bus = EventBus::new();
+        assert!(bus.drain::<Damage>().is_empty());
+        bus.emit(Damage(1));
+        assert_eq!(bus.drain::<Damage>(), vec![Damage(1)]);
+        assert!(bus.drain::<Damage>().is_empty());
+        assert!(bus.is_empty::<Damage>());
+        // The queue still works after being drained.
+        bus.emit(Damage(2));
+        assert_eq!(bus.drain::<Damage>(), vec![Damage(2)]);
+    }
+
+    #[test]
+    fn peek_keeps_events() {
+        let mut bus = EventBus::new();
+        bus.emit(Damage(1));
+        assert_eq!(bus.peek::<Damage>(), &[Damage(1)]);
+        assert_eq!(bus.peek::<Damage>(), &[Damage(1)]);
+        assert_eq!(bus.drain::<Damage>(), vec![Damage(1)]);
+    }
+
+    #[test]
+    fn clear_empties_every_queue() {
+        let mut bus = EventBus::new();
+        bus.emit(Damage(1));
+        bus.emit(Heal(1));
+        bus.clear();
+        assert!(bus.is_empty::<Damage>());
+        assert!(bus.is_empty::<Heal>());
+        bus.emit(Heal(2));
+        assert_eq!(bus.drain::<Heal>(), vec![Heal(2)]);
+    }
+}
@@ -0,0 +1,422 @@
+use crate::math::Vec2;
+use serde::{Deserialize, Serialize};
+use std::{fmt, ops};
+
+/// A signed fixed-point number with 16 integer and 16 fractional bits, for simulations that must
+/// give bit-identical results on every machine, such as lockstep multiplayer. Floating-point
+/// results can differ between compilers and CPUs; integer math can't.
+///
+/// Values range from -32768 to just under 32768, in steps of 1/65536 (about 0.000015).
+/// Arithmetic wraps on overflow in every build, like `i32::wrapping_add`, so an overflow gives the
+/// same wrong answer everywhere instead of a panic in debug builds only. Multiplication and
+/// division round toward negative infinity. Dividing by zero panics.
+///
+/// Convert from floats only when loading data or reading input, and back to floats only for
+/// rendering, so no float math ends up in the simulation.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Fixed(i32);
+
+impl Fixed {
+    pub const FRACTION_BITS: u32 = 16;
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(1 << Self::FRACTION_BITS);
+    pub const HALF: Fixed = Fixed(1 << (Self::FRACTION_BITS - 1));
+    pub const MIN: Fixed = Fixed(i32::MIN);
+    pub const MAX: Fixed = Fixed(i32::MAX);
+    /// The smallest positive value.
+    pub const EPSILON: Fixed = Fixed(1);
+
+    pub const fn from_bits(bits: i32) -> Self {
+        Fixed(bits)
+    }
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+    /// Wraps if `value` is outside -32768..32768.
+    pub const fn from_int(value: i32) -> Self {
+        Fixed(value.wrapping_shl(Self::FRACTION_BITS))
+    }
+    /// `numerator / denominator`, such as `Fixed::from_ratio(1, 3)`, without going through a
+    /// float.
+    pub fn from_ratio(numerator: i32, denominator: i32) -> Self {
+        Fixed::from_int(numerator) / Fixed::from_int(denominator)
+    }
+    /// The nearest fixed-point value, with halves rounded away from zero. Saturates at `MIN` and
+    /// `MAX`, and NaN gives zero. The result is the same on every platform, so this is safe for
+    /// loading data.
+    pub fn from_f32(value: f32) -> Self {
+        Fixed((value as f64 * Self::ONE.0 as f64).round() as i32)
+    }
+    /// For rendering. Exact, since every fixed-point value fits in an `f64`; the `f32` may round.
+    pub fn to_f32(self) -> f32 {
+        self.to_f64() as f32
+    }
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::ONE.0 as f64
+    }
+
+    /// Rounded toward negative infinity.
+    pub const fn to_int(self) -> i32 {
+        self.0 >> Self::FRACTION_BITS
+    }
+    pub const fn floor(self) -> Self {
+        Fixed(self.0 & !(Self::ONE.0 - 1))
+    }
+    pub const fn ceil(self) -> Self {
+        Fixed(self.0.wrapping_add(Self::ONE.0 - 1)).floor()
+    }
+    /// Halves round up.
+    pub const fn round(self) -> Self {
+        Fixed(self.0.wrapping_add(Self::HALF.0)).floor()
+    }
+    pub const fn fract(self) -> Self {
+        Fixed(self.0 & (Self::ONE.0 - 1))
+    }
+    pub const fn abs(self) -> Self {
+        Fixed(self.0.wrapping_abs())
+    }
+    pub const fn signum(self) -> Self {
+        Fixed::from_int(self.0.signum())
+    }
+    pub fn min(self, other: Fixed) -> Self {
+        Ord::min(self, other)
+    }
+    pub fn max(self, other: Fixed) -> Self {
+        Ord::max(self, other)
+    }
+    pub fn clamp(self, min: Fixed, max: Fixed) -> Self {
+        Ord::clamp(self, min, max)
+    }
+    /// The square root, rounded down. Negative values give 0.
+    pub fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Fixed::ZERO;
+        }
+        Fixed(isqrt((self.0 as u64) << Self::FRACTION_BITS) as i32)
+    }
+    /// `self` at `t` = 0, `other` at `t` = 1.
+    pub fn lerp(self, other: Fixed, t: Fixed) -> Self {
+        self + (other - self) * t
+    }
+}
+
+/// The integer square root of `value`, rounded down.
+fn isqrt(value: u64) -> u64 {
+    let mut remainder = value;
+    let mut root = 0u64;
+    let mut bit = 1u64 << 62;
+    while bit > value {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if remainder >= root + bit {
+            remainder -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+impl fmt::Debug for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fixed({})", self.to_f64())
+    }
+}
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f64(), f)
+    }
+}
+
+impl From<i32> for Fixed {
+    fn from(value: i32) -> Self {
+        Fixed::from_int(value)
+    }
+}
+
+impl ops::Add for Fixed {
+    type Output = Fixed;
+    fn add(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.wrapping_add(rhs.0))
+    }
+}
+impl ops::Sub for Fixed {
+    type Output = Fixed;
+    fn sub(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.wrapping_sub(rhs.0))
+    }
+}
+impl ops::Mul for Fixed {
+    type Output = Fixed;
+    fn mul(self, rhs: Fixed) -> Fixed {
+        Fixed(((self.0 as i64 * rhs.0 as i64) >> Self::FRACTION_BITS) as i32)
+    }
+}
+impl ops::Div for Fixed {
+    type Output = Fixed;
+    fn div(self, rhs: Fixed) -> Fixed {
+        let (numerator, denominator) = ((self.0 as i64) << Self::FRACTION_BITS, rhs.0 as i64);
+        let mut quotient = numerator / denominator;
+        if numerator % denominator != 0 && (numerator < 0) != (denominator < 0) {
+            quotient -= 1;
+        }
+        Fixed(quotient as i32)
+    }
+}
+impl ops::Neg for Fixed {
+    type Output = Fixed;
+    fn neg(self) -> Fixed {
+        Fixed(self.0.wrapping_neg())
+    }
+}
+impl ops::AddAssign for Fixed {
+    fn add_assign(&mut self, rhs: Fixed) {
+        *self = *self + rhs;
+    }
+}
+impl ops::SubAssign for Fixed {
+    fn sub_assign(&mut self, rhs: Fixed) {
+        *self = *self - rhs;
+    }
+}
+impl ops::MulAssign for Fixed {
+    fn mul_assign(&mut self, rhs: Fixed) {
+        *self = *self * rhs;
+    }
+}
+impl ops::DivAssign for Fixed {
+    fn div_assign(&mut self, rhs: Fixed) {
+        *self = *self / rhs;
+    }
+}
+
+/// A 2D vector of `Fixed` values, for deterministic positions and velocities. Convert to `Vec2`
+/// with `to_vec2` for rendering.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug, Serialize, Deserialize)]
+pub struct FVec2 {
+    pub x: Fixed,
+    pub y: Fixed,
+}
+
+impl FVec2 {
+    pub const ZERO: FVec2 = FVec2::new(Fixed::ZERO, Fixed::ZERO);
+    pub const ONE: FVec2 = FVec2::new(Fixed::ONE, Fixed::ONE);
+    pub const X: FVec2 = FVec2::new(Fixed::ONE, Fixed::ZERO);
+    pub const Y: FVec2 = FVec2::new(Fixed::ZERO, Fixed::ONE);
+
+    pub const fn new(x: Fixed, y: Fixed) -> Self {
+        FVec2 { x, y }
+    }
+    pub const fn from_ints(x: i32, y: i32) -> Self {
+        FVec2::new(Fixed::from_int(x), Fixed::from_int(y))
+    }
+    pub const fn splat(value: Fixed) -> Self {
+        FVec2::new(value, value)
+    }
+    /// See `Fixed::from_f32`.
+    pub fn from_vec2(value: Vec2) -> Self {
+        FVec2::new(Fixed::from_f32(value.x), Fixed::from_f32(value.y))
+    }
+    pub fn to_vec2(self) -> Vec2 {
+        Vec2::new(self.x.to_f32(), self.y.to_f32())
+    }
+
+    pub fn dot(self, other: FVec2) -> Fixed {
+        self.x * other.x + self.y * other.y
+    }
+    /// The z component of the 3D cross product, positive if `other` is counterclockwise from
+    /// `self` in a y-up space.
+    pub fn perp_dot(self, other: FVec2) -> Fixed {
+        self.x * other.y - self.y * other.x
+    }
+    /// Overflows for vectors longer than about 181, since it's the squared length.
+    pub fn length_squared(self) -> Fixed {
+        self.dot(self)
+    }
+    /// Works for any vector whose components are in range, unlike `length_squared`. Lengths
+    /// past `Fixed::MAX`, such as that of (30000, 30000), saturate at `Fixed::MAX`.
+    pub fn length(self) -> Fixed {
+        let x = self.x.to_bits().unsigned_abs() as u64;
+        let y = self.y.to_bits().unsigned_abs() as u64;
+        Fixed(isqrt(x * x + y * y).min(i32::MAX as u64) as i32)
+    }
+    pub fn distance(self, other: FVec2) -> Fixed {
+        (other - self).length()
+    }
+    /// This vector scaled to length 1, or zero if it has no length.
+    pub fn normalize_or_zero(self) -> Self {
+        let length = self.length();
+        if length == Fixed::ZERO {
+            FVec2::ZERO
+        } else {
+            self / length
+        }
+    }
+    pub fn abs(self) -> Self {
+        FVec2::new(self.x.abs(), self.y.abs())
+    }
+    pub fn min(self, other: FVec2) -> Self {
+        FVec2::new(self.x.min(other.x), self.y.min(other.y))
+    }
+    pub fn max(self, other: FVec2) -> Self {
+        FVec2::new(self.x.max(other.x), self.y.max(other.y))
+    }
+    pub fn lerp(self, other: FVec2, t: Fixed) -> Self {
+        FVec2::new(self.x.lerp(other.x, t), self.y.lerp(other.y, t))
+    }
+}
+
+impl ops::Add for FVec2 {
+    type Output = FVec2;
+    fn add(self, rhs: FVec2) -> FVec2 {
+        FVec2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+impl ops::Sub for FVec2 {
+    type Output = FVec2;
+    fn sub(self, rhs: FVec2) -> FVec2 {
+        FVec2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+impl ops::Mul<Fixed> for FVec2 {
+    type Output = FVec2;
+    fn mul(self, rhs: Fixed) -> FVec2 {
+        FVec2::new(self.x * rhs, self.y * rhs)
+    }
+}
+impl ops::Div<Fixed> for FVec2 {
+    type Output = FVec2;
+    fn div(self, rhs: Fixed) -> FVec2 {
+        FVec2::new(self.x / rhs, self.y / rhs)
+    }
+}
+impl ops::Neg for FVec2 {
+    type Output = FVec2;
+    fn neg(self) -> FVec2 {
+        FVec2::new(-self.x, -self.y)
+    }
+}
+impl ops::AddAssign for FVec2 {
+    fn add_assign(&mut self, rhs: FVec2) {
+        *self = *self + rhs;
+    }
+}
+impl ops::SubAssign for FVec2 {
+    fn sub_assign(&mut self, rhs: FVec2) {
+        *self = *self - rhs;
+    }
+}
+impl ops::MulAssign<Fixed> for FVec2 {
+    fn mul_assign(&mut self, rhs: Fixed) {
+        *self = *self * rhs;
+    }
+}
+impl ops::DivAssign<Fixed> for FVec2 {
+    fn div_assign(&mut self, rhs: Fixed) {
+        *self = *self / rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(bits: i32) -> Fixed {
+        Fixed::from_bits(bits)
+    }
+
+    #[test]
+    fn mul_rounds_toward_negative_infinity() {
+        assert_eq!(Fixed::from_int(3) * Fixed::HALF, Fixed::from_ratio(3, 2));
+        assert_eq!(Fixed::EPSILON * Fixed::HALF, Fixed::ZERO);
+        assert_eq!(-Fixed::EPSILON * Fixed::HALF, -Fixed::EPSILON);
+        assert_eq!(bits(3) * Fixed::HALF, bits(1));
+        assert_eq!(bits(-3) * Fixed::HALF, bits(-2));
+        assert_eq!(
+            Fixed::from_int(-2) * Fixed::from_int(3),
+            Fixed::from_int(-6)
+        );
+    }
+
+    #[test]
+    fn div_rounds_toward_negative_infinity() {
+        assert_eq!(
+            Fixed::from_int(7) / Fixed::from_int(2),
+            Fixed::from_f32(3.5)
+        );
+        assert_eq!(Fixed::ONE / Fixed::from_int(3), bits(21845));
+        assert_eq!(-Fixed::ONE / Fixed::from_int(3), bits(-21846));
+        assert_eq!(Fixed::ONE / Fixed::from_int(-3), bits(-21846));
+        assert_eq!(-Fixed::ONE / Fixed::from_int(-3), bits(21845));
+        assert_eq!(Fixed::EPSILON / Fixed::from_int(2), Fixed::ZERO);
+        assert_eq!(-Fixed::EPSILON / Fixed::from_int(2), -Fixed::EPSILON);
+        assert_eq!(Fixed::from_ratio(-1, 3), bits(-21846));
+    }
+
+    #[test]
+    #[should_panic]
+    fn div_by_zero_panics() {
+        let _ = Fixed::ONE / Fixed::ZERO;
+    }
+
+    #[test]
+    fn rounding_negative_values() {
+        let value = Fixed::from_f32(-2.5);
+        assert_eq!(value.floor(), Fixed::from_int(-3));
+        assert_eq!(value.ceil(), Fixed::from_int(-2));
+        assert_eq!(value.round(), Fixed::from_int(-2));
+        assert_eq!(value.to_int(), -3);
+        assert_eq!(value.fract(), Fixed::HALF);
+        assert_eq!(Fixed::from_f32(2.5).round(), Fixed::from_int(3));
+    }
+
+    #[test]
+    fn float_conversion_saturates_at_the_limits() {
+        assert_eq!(Fixed::from_f32(1.0e9), Fixed::MAX);
+        assert_eq!(Fixed::from_f32(-1.0e9), Fixed::MIN);
+        assert_eq!(Fixed::from_f32(f32::INFINITY), Fixed::MAX);
+        assert_eq!(Fixed::from_f32(f32::NEG_INFINITY), Fixed::MIN);
+        assert_eq!(Fixed::from_f32(f32::NAN), Fixed::ZERO);
+        assert_eq!(Fixed::from_f32(-32768.0), Fixed::MIN);
+        assert_eq!(Fixed::MIN.to_f32(), -32768.0);
+        assert_eq!(Fixed::MAX.to_f64(), 32768.0 - 1.0 / 65536.0);
+    }
+
+    #[test]
+    fn float_conversion_rounds_to_nearest() {
+        assert_eq!(Fixed::from_f32(1.5).to_f32(), 1.5);
+        assert_eq!(Fixed::from_f32(-0.25).to_f32(), -0.25);
+        assert_eq!(Fixed::from_f32(0.1), bits(6554));
+        assert_eq!(Fixed::from_f32(-0.1), bits(-6554));
+        assert_eq!(Fixed::from_f32(0.4 / 65536.0), Fixed::ZERO);
+        assert_eq!(Fixed::from_f32(0.6 / 65536.0), Fixed::EPSILON);
+        assert_eq!(Fixed::EPSILON.to_f64(), 1.0 / 65536.0);
+    }
+
+    #[test]
+    fn arithmetic_wraps_past_the_limits() {
+        assert_eq!(Fixed::MAX + Fixed::EPSILON, Fixed::MIN);
+        assert_eq!(Fixed::MIN - Fixed::EPSILON, Fixed::MAX);
+        assert_eq!(-Fixed::MIN, Fixed::MIN);
+        assert_eq!(Fixed::from_int(32768), Fixed::MIN);
+        assert_eq!(Fixed::MAX * Fixed::ONE, Fixed::MAX);
+        assert_eq!(Fixed::MIN * Fixed::ONE, Fixed::MIN);
+    }
+
+    #[test]
+    fn sqrt_and_length() {
+        assert_eq!(Fixed::from_int(16).sqrt(), Fixed::from_int(4));
+        assert_eq!(Fixed::from_f32(2.25).sqrt(), Fixed::from_f32(1.5));
+        assert_eq!(Fixed::from_int(-4).sqrt(), Fixed::ZERO);
+        assert_eq!(FVec2::from_ints(3, -4).length(), Fixed::from_int(5));
+        // Too long for length_squared, which would overflow.
+        assert_eq!(FVec2::from_ints(30000, 0).length(), Fixed::from_int(30000));
+        // Longer than the largest value, which would wrap to a negative length.
+        assert_eq!(FVec2::from_ints(30000, 30000).length(), Fixed::MAX);
+        assert_eq!(FVec2::new(Fixed::MIN, Fixed::MIN).length(), Fixed::MAX);
+    }
+}
@@ -0,0 +1,80 @@
+/// Reuses `Vec`s that are filled and emptied every frame, such as draw batches, so they keep
+/// their capacity instead of growing from nothing each time.
+///
+/// `take` a `Vec` at the start of the work and `give` it back when done. Given-back `Vec`s are
+/// cleared but keep their allocation, so after the first few frames no allocation happens at all.
+pub struct FramePool<T> {
+    free: Vec<Vec<T>>,
+}
+
+impl<T> Default for FramePool<T> {
+    fn default() -> Self {
+        FramePool { free: Vec::new() }
+    }
+}
+
+impl<T> FramePool<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An empty `Vec`, with the capacity of one given back earlier if there is one.
+    pub fn take(&mut self) -> Vec<T> {
+        self.free.pop().unwrap_or_default()
+    }
+    /// Return a `Vec` to the pool. Its contents are dropped, and its capacity kept for the next
+    /// `take`.
+    pub fn give(&mut self, mut vec: Vec<T>) {
+        if vec.capacity() > 0 {
+            vec.clear();
+            self.free.push(vec);
+        }
+    }
+    /// The number of `Vec`s waiting to be taken.
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+    /// The total capacity held by the pool, in elements.
+    pub fn capacity(&self) -> usize {
+        self.free.iter().map(Vec::capacity).sum()
+    }
+    /// Free every pooled `Vec`, such as after a loading spike that grew them far beyond what a
+    /// normal frame needs.
+    pub fn clear(&mut self) {
+        self.free.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recycled_vec_keeps_capacity() {
+        let mut pool = FramePool::new();
+        let mut batch = pool.take();
+        batch.extend(0..100u32);
+        let capacity = batch.capacity();
+        pool.give(batch);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.capacity(), capacity);
+
+        let batch = pool.take();
+        assert!(batch.is_empty());
+        assert_eq!(batch.capacity(), capacity);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn unallocated_vecs_are_not_pooled() {
+        let mut pool = FramePool::<u32>::new();
+        pool.give(Vec::new());
+        assert!(pool.is_empty());
+        pool.give(Vec::with_capacity(8));
+        pool.clear();
+        assert_eq!(pool.capacity(), 0);
+    }
+}
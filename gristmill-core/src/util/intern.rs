@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{OnceLock, RwLock},
+};
+
+static INTERN: OnceLock<RwLock<Intern>> = OnceLock::new();
+
+/// The global string interner behind `Symbol`.
+///
+/// Interned strings live until the program exits: they are leaked on first use and never freed,
+/// so a symbol stays valid forever and `Symbol::as_str` can return a `&'static str`. Intern names
+/// from code and data files, not arbitrary user input, or the interner will grow without bound.
+#[derive(Default)]
+pub struct Intern {
+    symbols: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+impl Intern {
+    fn global() -> &'static RwLock<Intern> {
+        INTERN.get_or_init(Default::default)
+    }
+
+    /// The symbol for `string`, interning it if it hasn't been already.
+    pub fn intern(string: &str) -> Symbol {
+        if let Some(symbol) = Intern::lookup(string) {
+            return symbol;
+        }
+        let mut intern = Intern::global().write().unwrap();
+        // Another thread may have interned it between the locks.
+        if let Some(symbol) = intern.symbols.get(string) {
+            return *symbol;
+        }
+        let string: &'static str = Box::leak(string.to_owned().into_boxed_str());
+        let symbol = Symbol(intern.strings.len() as u32);
+        intern.strings.push(string);
+        intern.symbols.insert(string, symbol);
+        symbol
+    }
+    /// The symbol for `string` if it has been interned, without interning it.
+    pub fn lookup(string: &str) -> Option<Symbol> {
+        Intern::global()
+            .read()
+            .unwrap()
+            .symbols
+            .get(string)
+            .copied()
+    }
+    /// How many strings have been interned.
+    pub fn len() -> usize {
+        Intern::global().read().unwrap().strings.len()
+    }
+}
+
+/// An interned string. Copying, comparing and hashing a symbol only touches an integer, so it
+/// makes a cheap map key for names that are looked up every frame.
+///
+/// Two symbols are equal exactly when their strings are. Ordering is by when the strings were
+/// interned, not alphabetical. Serializes as its string.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn new(string: &str) -> Self {
+        Intern::intern(string)
+    }
+    pub fn as_str(self) -> &'static str {
+        Intern::global().read().unwrap().strings[self.0 as usize]
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(string: &str) -> Self {
+        Symbol::new(string)
+    }
+}
+impl From<&String> for Symbol {
+    fn from(string: &String) -> Self {
+        Symbol::new(string)
+    }
+}
+impl From<String> for Symbol {
+    fn from(string: String) -> Self {
+        Symbol::new(&string)
+    }
+}
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Symbol({:?})", self.as_str())
+    }
+}
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        Ok(Symbol::new(&string))
+    }
+}
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The interner is shared by every test, so each test uses its own strings.
+
+    #[test]
+    fn same_string_same_symbol() {
+        let jump = Symbol::new("intern_test_jump");
+        assert_eq!(Symbol::from(String::from("intern_test_jump")), jump);
+        assert_ne!(Symbol::new("intern_test_crouch"), jump);
+        assert_eq!(jump.as_str(), "intern_test_jump");
+        assert_eq!(jump, "intern_test_jump");
+    }
+
+    #[test]
+    fn lookup_does_not_intern() {
+        assert_eq!(Intern::lookup("intern_test_unknown"), None);
+        assert_eq!(Intern::lookup("intern_test_unknown"), None);
+        let symbol = Intern::intern("intern_test_known");
+        assert_eq!(Intern::lookup("intern_test_known"), Some(symbol));
+    }
+
+    #[test]
+    fn serializes_as_its_string() {
+        let symbol = Symbol::new("intern_test_fire");
+        let yaml = serde_yaml::to_string(&symbol).unwrap();
+        assert_eq!(yaml.trim(), "intern_test_fire");
+        assert_eq!(serde_yaml::from_str::<Symbol>(&yaml).unwrap(), symbol);
+    }
+}
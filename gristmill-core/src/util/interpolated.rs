@@ -0,0 +1,88 @@
+use super::Lerp;
+
+/// The last two simulation values of something drawn every frame, such as an entity's position
+/// under a fixed timestep. `push` the new value after each step, and draw
+/// `render_value(alpha)`, where `alpha` is how far the time since the last step is through the
+/// next one (the accumulator divided by the step length).
+///
+/// Rendering lags the simulation by up to one step in exchange for smooth motion.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Interpolated<T> {
+    previous: T,
+    current: T,
+}
+
+impl<T: Lerp> Interpolated<T> {
+    pub fn new(value: T) -> Self {
+        Interpolated {
+            previous: value,
+            current: value,
+        }
+    }
+
+    /// Record the value after a simulation step.
+    pub fn push(&mut self, value: T) {
+        self.previous = self.current;
+        self.current = value;
+    }
+    /// Set the value without interpolating from the old one, such as after a teleport.
+    pub fn reset(&mut self, value: T) {
+        self.previous = value;
+        self.current = value;
+    }
+
+    pub fn previous(&self) -> T {
+        self.previous
+    }
+    pub fn current(&self) -> T {
+        self.current
+    }
+    /// The value to draw, `alpha` of the way from the previous step to the current one. `alpha`
+    /// is clamped to 0..1.
+    pub fn render_value(&self, alpha: f32) -> T {
+        self.previous.lerp(self.current, alpha.clamp(0.0, 1.0))
+    }
+    /// The value `alpha` steps past the current one, continuing the last step's motion, for
+    /// remote entities whose next update hasn't arrived yet. Extrapolating far overshoots, so
+    /// callers should cap `alpha`.
+    pub fn extrapolate(&self, alpha: f32) -> T {
+        self.previous.lerp(self.current, 1.0 + alpha.max(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Angle;
+    use std::f32::consts::TAU;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn alpha_ends_are_previous_and_current() {
+        let mut value = Interpolated::new(0.0f32);
+        value.push(2.0);
+        value.push(6.0);
+        assert_eq!(value.render_value(0.0), 2.0);
+        assert_eq!(value.render_value(1.0), 6.0);
+        assert_eq!(value.render_value(0.25), 3.0);
+        assert_eq!(value.render_value(-1.0), 2.0);
+        assert_eq!(value.render_value(2.0), 6.0);
+        assert_eq!(value.extrapolate(0.5), 8.0);
+
+        value.reset(10.0);
+        assert_eq!(value.render_value(0.5), 10.0);
+    }
+
+    #[test]
+    fn angle_interpolates_across_the_wrap() {
+        let mut angle = Interpolated::new(Angle(TAU - 0.1));
+        angle.push(Angle(0.1));
+        // Halfway is the wrap point, not half a turn the other way.
+        assert_close(angle.render_value(0.5).0, TAU);
+        // The current angle, unwrapped to continue from the previous one.
+        assert_close(angle.render_value(1.0).0, TAU + 0.1);
+    }
+}
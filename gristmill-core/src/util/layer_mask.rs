@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize, Serializer};
+use std::{fmt, ops, sync::RwLock};
+
+static LAYER_NAMES: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// A set of up to 32 layers, for deciding what collides with what or what a camera draws.
+/// Layers can be given names with `register_layer`, so data files can refer to them by name.
+///
+/// Deserializes from a number, a layer name, a list of layer names, or "all"/"none". Serializes
+/// as a list of names when every set layer has one, otherwise as a number.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct LayerMask(pub u32);
+
+impl LayerMask {
+    pub const NONE: LayerMask = LayerMask(0);
+    pub const ALL: LayerMask = LayerMask(u32::MAX);
+
+    /// The mask containing only layer `index`. Returns `NONE` if `index` is 32 or more.
+    pub const fn layer(index: u32) -> Self {
+        if index < 32 {
+            LayerMask(1 << index)
+        } else {
+            LayerMask::NONE
+        }
+    }
+    /// The layer registered as `name`.
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "all" => return Some(LayerMask::ALL),
+            "none" => return Some(LayerMask::NONE),
+            _ => (),
+        }
+        let names = LAYER_NAMES.read().unwrap();
+        let index = names.iter().position(|layer| layer == name)?;
+        Some(LayerMask::layer(index as u32))
+    }
+    /// The names of the layers in this mask, skipping layers that have no name.
+    pub fn names(self) -> Vec<String> {
+        let names = LAYER_NAMES.read().unwrap();
+        names
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.contains(LayerMask::layer(*index as u32)))
+            .map(|(_, name)| name.clone())
+            .collect()
+    }
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+    /// Whether every layer in `other` is also in this mask. Always true if `other` is empty.
+    pub fn contains(self, other: LayerMask) -> bool {
+        self.0 & other.0 == other.0
+    }
+    /// Whether the masks share at least one layer. Always false if either is empty.
+    pub fn intersects(self, other: LayerMask) -> bool {
+        self.0 & other.0 != 0
+    }
+    pub fn set(&mut self, other: LayerMask) {
+        self.0 |= other.0;
+    }
+    pub fn clear(&mut self, other: LayerMask) {
+        self.0 &= !other.0;
+    }
+    pub fn toggle(&mut self, other: LayerMask) {
+        self.0 ^= other.0;
+    }
+    pub fn with(self, other: LayerMask) -> Self {
+        LayerMask(self.0 | other.0)
+    }
+    pub fn without(self, other: LayerMask) -> Self {
+        LayerMask(self.0 & !other.0)
+    }
+}
+
+/// Name the next unused layer, and return its mask. Registering a name twice returns the same
+/// layer. Returns `NONE` and logs an error if all 32 layers are taken.
+pub fn register_layer(name: &str) -> LayerMask {
+    if let Some(mask) = LayerMask::named(name) {
+        return mask;
+    }
+    let mut names = LAYER_NAMES.write().unwrap();
+    if names.len() >= 32 {
+        log::error!("Can't register layer \"{name}\": all 32 layers are taken.");
+        return LayerMask::NONE;
+    }
+    names.push(name.to_owned());
+    LayerMask::layer(names.len() as u32 - 1)
+}
+
+impl fmt::Debug for LayerMask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LayerMask({:#034b})", self.0)
+    }
+}
+
+impl ops::BitOr for LayerMask {
+    type Output = LayerMask;
+    fn bitor(self, rhs: LayerMask) -> LayerMask {
+        LayerMask(self.0 | rhs.0)
+    }
+}
+impl ops::BitOrAssign for LayerMask {
+    fn bitor_assign(&mut self, rhs: LayerMask) {
+        self.0 |= rhs.0;
+    }
+}
+impl ops::BitAnd for LayerMask {
+    type Output = LayerMask;
+    fn bitand(self, rhs: LayerMask) -> LayerMask {
+        LayerMask(self.0 & rhs.0)
+    }
+}
+impl ops::BitAndAssign for LayerMask {
+    fn bitand_assign(&mut self, rhs: LayerMask) {
+        self.0 &= rhs.0;
+    }
+}
+impl ops::Not for LayerMask {
+    type Output = LayerMask;
+    fn not(self) -> LayerMask {
+        LayerMask(!self.0)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LayerMaskDe {
+    Bits(u32),
+    Name(String),
+    Names(Vec<String>),
+}
+
+impl TryFrom<LayerMaskDe> for LayerMask {
+    type Error = String;
+    fn try_from(value: LayerMaskDe) -> Result<Self, Self::Error> {
+        let named = |name: &str| LayerMask::named(name).ok_or(format!("unknown layer \"{name}\""));
+        match value {
+            LayerMaskDe::Bits(bits) => Ok(LayerMask(bits)),
+            LayerMaskDe::Name(name) => named(&name),
+            LayerMaskDe::Names(names) => names
+                .iter()
+                .try_fold(LayerMask::NONE, |mask, name| Ok(mask | named(name)?)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LayerMask {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        LayerMaskDe::deserialize(deserializer)?
+            .try_into()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for LayerMask {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let names = self.names();
+        let named = names.iter().fold(LayerMask::NONE, |mask, name| {
+            mask | LayerMask::named(name).unwrap()
+        });
+        if named == *self {
+            names.serialize(serializer)
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HIGHEST: LayerMask = LayerMask::layer(31);
+
+    #[test]
+    fn layer_bits() {
+        assert_eq!(LayerMask::layer(0).bits(), 1);
+        assert_eq!(HIGHEST.bits(), 1 << 31);
+        assert_eq!(LayerMask::layer(32), LayerMask::NONE);
+        assert_eq!(LayerMask::layer(u32::MAX), LayerMask::NONE);
+    }
+
+    #[test]
+    fn set_and_clear() {
+        let mut mask = LayerMask::NONE;
+        mask.set(HIGHEST);
+        mask.set(LayerMask::layer(0));
+        assert_eq!(mask.bits(), (1 << 31) | 1);
+        assert!(mask.contains(HIGHEST));
+        mask.clear(HIGHEST);
+        assert_eq!(mask, LayerMask::layer(0));
+        // Clearing a layer that isn't set does nothing.
+        mask.clear(HIGHEST);
+        assert_eq!(mask, LayerMask::layer(0));
+        mask.toggle(HIGHEST | LayerMask::layer(0));
+        assert_eq!(mask, HIGHEST);
+        assert_eq!(LayerMask::ALL.without(HIGHEST).bits(), u32::MAX >> 1);
+        assert_eq!(!HIGHEST, LayerMask::ALL.without(HIGHEST));
+    }
+
+    #[test]
+    fn intersects() {
+        let a = LayerMask::layer(2) | HIGHEST;
+        assert!(a.intersects(HIGHEST));
+        assert!(a.intersects(LayerMask::ALL));
+        assert!(!a.intersects(LayerMask::layer(30)));
+        assert!(!a.intersects(LayerMask::NONE));
+        assert!(!LayerMask::NONE.intersects(LayerMask::NONE));
+        assert!(!a.without(HIGHEST).intersects(HIGHEST));
+    }
+
+    #[test]
+    fn contains() {
+        let a = LayerMask::layer(2) | HIGHEST;
+        assert!(a.contains(HIGHEST));
+        assert!(a.contains(a));
+        assert!(a.contains(LayerMask::NONE));
+        assert!(!a.contains(HIGHEST | LayerMask::layer(3)));
+        assert!(LayerMask::ALL.contains(a));
+    }
+
+    #[test]
+    fn deserialize_bits() {
+        let mask: LayerMask = serde_yaml::from_str("2147483649").unwrap();
+        assert_eq!(mask, HIGHEST | LayerMask::layer(0));
+        assert_eq!(
+            serde_yaml::from_str::<LayerMask>("all").unwrap(),
+            LayerMask::ALL
+        );
+        assert_eq!(
+            serde_yaml::from_str::<LayerMask>("none").unwrap(),
+            LayerMask::NONE
+        );
+        assert!(serde_yaml::from_str::<LayerMask>("unregistered_layer_name").is_err());
+    }
+}
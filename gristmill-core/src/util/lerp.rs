@@ -0,0 +1,162 @@
+use crate::{
+    geom2d::Rect,
+    math::{Vec2, Vec3},
+    Color,
+};
+use std::f32::consts::{PI, TAU};
+
+/// Values that can be blended, for tweens, smoothing and interpolation between simulation steps.
+pub trait Lerp: Copy {
+    /// `self` at `t` = 0, `other` at `t` = 1. `t` isn't clamped, so values outside 0..1
+    /// extrapolate.
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+impl Lerp for f64 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t as f64
+    }
+}
+impl Lerp for Vec2 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Vec2::lerp(self, other, t)
+    }
+}
+impl Lerp for Vec3 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Vec3::lerp(self, other, t)
+    }
+}
+/// Blends each channel, including alpha, without any color space conversion.
+impl Lerp for Color {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        let (a, b): ([f32; 4], [f32; 4]) = (self.into(), other.into());
+        Color::from([0, 1, 2, 3].map(|i| a[i].lerp(b[i], t)))
+    }
+}
+impl Lerp for Rect {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Rect {
+            position: Vec2::lerp(self.position, other.position, t),
+            size: Vec2::lerp(self.size, other.size, t),
+        }
+    }
+}
+
+/// A rotation in radians that lerps the short way around, so going from just below 2π to just
+/// above 0 turns a little forward instead of almost a full turn back. Angles exactly half a turn
+/// apart turn forward. The result isn't wrapped, so it may fall outside 0..2π.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Angle(pub f32);
+
+impl Angle {
+    /// The signed difference from `self` to `other`, in -π..π.
+    pub fn delta(self, other: Angle) -> f32 {
+        let delta = (other.0 - self.0).rem_euclid(TAU);
+        if delta > PI {
+            delta - TAU
+        } else {
+            delta
+        }
+    }
+}
+
+impl Lerp for Angle {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Angle(self.0 + self.delta(other) * t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn endpoints() {
+        assert_eq!(2.0f32.lerp(6.0, 0.0), 2.0);
+        assert_eq!(2.0f32.lerp(6.0, 1.0), 6.0);
+        assert_eq!(2.0f32.lerp(6.0, 0.25), 3.0);
+        assert_eq!(2.0f64.lerp(6.0, 1.0), 6.0);
+        let (a, b) = (Vec2::new(1.0, -1.0), Vec2::new(3.0, 5.0));
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        let (a, b) = (
+            Rect::new(0.0, 0.0, 10.0, 10.0),
+            Rect::new(10.0, 20.0, 30.0, 50.0),
+        );
+        assert_eq!(Lerp::lerp(a, b, 0.0), a);
+        assert_eq!(Lerp::lerp(a, b, 1.0), b);
+        assert_eq!(Lerp::lerp(a, b, 0.5), Rect::new(5.0, 10.0, 20.0, 30.0));
+    }
+
+    #[test]
+    fn color_blends_every_channel() {
+        let a = Color::from([0.0, 0.2, 1.0, 0.0]);
+        let b = Color::from([1.0, 0.6, 0.0, 1.0]);
+        let channels = |t: f32| -> [f32; 4] { a.lerp(b, t).into() };
+        assert_eq!(channels(0.0), [0.0, 0.2, 1.0, 0.0]);
+        assert_eq!(channels(1.0), [1.0, 0.6, 0.0, 1.0]);
+        let mid = channels(0.5);
+        for (channel, expected) in mid.into_iter().zip([0.5, 0.4, 0.5, 0.5]) {
+            assert_close(channel, expected);
+        }
+    }
+
+    #[test]
+    fn t_outside_range_extrapolates() {
+        assert_eq!(2.0f32.lerp(6.0, -0.5), 0.0);
+        assert_eq!(2.0f32.lerp(6.0, 1.5), 8.0);
+        assert_eq!(
+            Vec2::ZERO.lerp(Vec2::new(2.0, -4.0), 2.0),
+            Vec2::new(4.0, -8.0)
+        );
+        assert_close(Angle(0.0).lerp(Angle(1.0), 2.0).0, 2.0);
+        assert_close(Angle(0.0).lerp(Angle(1.0), -1.0).0, -1.0);
+    }
+
+    #[test]
+    fn angle_endpoints() {
+        let (a, b) = (Angle(0.5), Angle(2.0));
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_close(a.lerp(b, 1.0).0, 2.0);
+        assert_close(a.lerp(b, 0.5).0, 1.25);
+    }
+
+    #[test]
+    fn angle_wraps_across_pi() {
+        // From just below π to just above -π is a small step forward through π.
+        let (a, b) = (Angle(PI - 0.1), Angle(-PI + 0.1));
+        assert_close(a.delta(b), 0.2);
+        assert_close(a.lerp(b, 0.5).0, PI);
+        assert_close(a.lerp(b, 1.0).0, PI + 0.1);
+        // And back the other way.
+        assert_close(b.delta(a), -0.2);
+        assert_close(b.lerp(a, 0.5).0, -PI);
+    }
+
+    #[test]
+    fn angle_wraps_across_zero() {
+        let (a, b) = (Angle(TAU - 0.1), Angle(0.1));
+        assert_close(a.delta(b), 0.2);
+        assert_close(a.lerp(b, 0.5).0, TAU);
+        assert_close(b.delta(a), -0.2);
+        // Whole turns make no difference.
+        assert_close(Angle(0.0).delta(Angle(3.0 * TAU + 0.5)), 0.5);
+    }
+
+    #[test]
+    fn half_turn_goes_forward() {
+        assert_close(Angle(0.0).delta(Angle(PI)), PI);
+        assert_close(Angle(PI).delta(Angle(0.0)), PI);
+        assert_close(Angle(-PI / 2.0).lerp(Angle(PI / 2.0), 0.5).0, 0.0);
+    }
+}
@@ -0,0 +1,19 @@
+mod arena;
+mod clock;
+mod event_bus;
+mod fixed;
+mod frame_pool;
+mod intern;
+mod interpolated;
+mod layer_mask;
+mod lerp;
+
+pub use arena::*;
+pub use clock::*;
+pub use event_bus::*;
+pub use fixed::*;
+pub use frame_pool::*;
+pub use intern::*;
+pub use interpolated::*;
+pub use layer_mask::*;
+pub use lerp::*;
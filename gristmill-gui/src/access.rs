@@ -0,0 +1,199 @@
+//! Accessibility information for screen readers, through [AccessKit](https://accesskit.dev).
+//!
+//! Widgets declare an `accesskit::Role` with `Widget::access_role`, which is recorded when the
+//! widget is created with `Gui::create_widget` or unpacked. The standard widgets map to roles as
+//! follows:
+//!
+//! | Widget           | `accesskit::Role` |
+//! |------------------|-------------------|
+//! | `Text`           | `StaticText`      |
+//! | `Marquee`        | `StaticText`      |
+//! | `Button`         | `Button`          |
+//! | `Image`          | `Image`           |
+//! | `FloatingWindow` | `Window`          |
+//! | `ListView`       | `List`            |
+//! | `RadialMenu`     | `Menu`            |
+//!
+//! Other nodes have no role and are skipped, with their children moved up to the nearest node
+//! that has one. The GUI root is the `Window` node at the top of the tree. A node's accessible
+//! name is its label from `Gui::set_access_label`, or else (for leaf roles like buttons) all of
+//! the text inside it. `FloatingWindow` is labeled with its title.
+//!
+//! Nodes also report their state: `NodeFlags::focusable`, `NodeFlags::disabled` (which buttons
+//! set while they aren't enabled with `Button::interact`), and a checked state from
+//! `Gui::set_access_checked` for toggles. Focus follows `Gui::focused`.
+//!
+//! Create an `accesskit_winit::Adapter` for the window, pass it window events, and give it
+//! `Gui::access_update` once per frame after `update`.
+
+use crate::{Gui, GuiNodeId, NodeDraw};
+pub use accesskit;
+use accesskit::{CheckedState, DefaultActionVerb, Node, NodeId, Role, Tree, TreeUpdate};
+pub use accesskit_winit;
+use gristmill_core::slotmap::Key;
+use std::{num::NonZeroU128, sync::Arc};
+
+/// Leaf roles are announced by name only; the nodes inside them aren't part of the tree.
+pub fn is_leaf_role(role: Role) -> bool {
+    matches!(
+        role,
+        Role::StaticText | Role::Button | Role::CheckBox | Role::Image | Role::MenuItem
+    )
+}
+
+/// The AccessKit id of a GUI node. Ids are never zero, and a node keeps its id until it's removed.
+pub fn access_node_id(node: GuiNodeId) -> NodeId {
+    NodeId(NonZeroU128::new(u128::from(node.data().as_ffi()) + 1).unwrap())
+}
+
+impl Gui {
+    pub fn set_access_role(&mut self, node: GuiNodeId, role: Option<Role>) {
+        if let Some(role) = role {
+            self.access_roles.insert(node, role);
+        } else {
+            self.access_roles.remove(node);
+        }
+    }
+    pub fn access_role(&self, node: GuiNodeId) -> Option<Role> {
+        self.access_roles.get(node).copied()
+    }
+    /// Announce `node` with this name instead of the text inside it.
+    pub fn set_access_label<S: Into<String>>(&mut self, node: GuiNodeId, label: Option<S>) {
+        if let Some(label) = label {
+            self.access_labels.insert(node, label.into());
+        } else {
+            self.access_labels.remove(node);
+        }
+    }
+    /// Announce `node` as checked or unchecked, for toggle buttons and check boxes. Give such a
+    /// node the `CheckBox` role with `set_access_role`. `None` means it can't be checked.
+    pub fn set_access_checked(&mut self, node: GuiNodeId, checked: Option<bool>) {
+        if let Some(checked) = checked {
+            self.access_checked.insert(node, checked);
+        } else {
+            self.access_checked.remove(node);
+        }
+    }
+
+    fn access_name(&self, node: GuiNodeId, role: Role) -> Option<Box<str>> {
+        if let Some(label) = self.access_labels.get(node) {
+            return Some(label.as_str().into());
+        }
+        if !is_leaf_role(role) {
+            return None;
+        }
+        let mut parts = Vec::new();
+        self.collect_text(node, &mut parts);
+        Some(parts.join(" ").into())
+    }
+    fn collect_text(&self, node: GuiNodeId, parts: &mut Vec<String>) {
+        if let Some(NodeDraw::Text(section)) = self.nodes.get(node).map(|node| &node.draw) {
+            let text: String = section.text.iter().map(|text| text.text.as_str()).collect();
+            if !text.is_empty() {
+                parts.push(text);
+            }
+        }
+        for child in self.node_children.get(node).into_iter().flatten() {
+            self.collect_text(*child, parts);
+        }
+    }
+    fn access_node(&self, node: GuiNodeId, role: Role, children: Vec<NodeId>) -> Node {
+        let flags = &self.nodes[node].flags;
+        let checked_state = self.access_checked.get(node).map(|checked| {
+            if *checked {
+                CheckedState::True
+            } else {
+                CheckedState::False
+            }
+        });
+        let clickable = matches!(role, Role::Button | Role::CheckBox | Role::MenuItem);
+        Node {
+            role,
+            children,
+            name: self.access_name(node, role),
+            focusable: flags.focusable,
+            disabled: flags.disabled,
+            checked_state,
+            default_action_verb: (clickable && !flags.disabled).then_some(DefaultActionVerb::Click),
+            ..Default::default()
+        }
+    }
+    /// Add `node` and the nodes under it to `update`, and the ids of the top ones to `parent`.
+    fn collect_access(&self, node: GuiNodeId, parent: &mut Vec<NodeId>, update: &mut TreeUpdate) {
+        if !self.nodes.get(node).map_or(false, |node| node.visible) {
+            return;
+        }
+        let children = self.node_children.get(node).into_iter().flatten();
+        if let Some(role) = self.access_role(node) {
+            parent.push(access_node_id(node));
+            let mut own_children = Vec::new();
+            if !is_leaf_role(role) {
+                for child in children {
+                    self.collect_access(*child, &mut own_children, update);
+                }
+            }
+            let access_node = self.access_node(node, role, own_children);
+            update
+                .nodes
+                .push((access_node_id(node), Arc::new(access_node)));
+        } else {
+            for child in children {
+                self.collect_access(*child, parent, update);
+            }
+        }
+    }
+    /// The whole accessibility tree, built from the current layout, for
+    /// `accesskit_winit::Adapter::update`. Call this after `update`.
+    pub fn access_update(&self) -> TreeUpdate {
+        let root = access_node_id(self.root);
+        let mut update = TreeUpdate {
+            nodes: Vec::new(),
+            tree: Some(Tree::new(root)),
+            focus: None,
+        };
+        let mut children = Vec::new();
+        for child in self.node_children.get(self.root).into_iter().flatten() {
+            self.collect_access(*child, &mut children, &mut update);
+        }
+        let root_node = Node {
+            role: Role::Window,
+            children,
+            ..Default::default()
+        };
+        update.nodes.push((root, Arc::new(root_node)));
+        // Focus must be on a node in the tree; without a focused widget it's on the window.
+        let focus = self
+            .focus
+            .map(access_node_id)
+            .filter(|focus| update.nodes.iter().any(|(id, _)| id == focus));
+        update.focus = Some(focus.unwrap_or(root));
+        update
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GuiNodeStorage;
+
+    #[test]
+    fn node_ids_are_unique_and_stable() {
+        let mut nodes = GuiNodeStorage::default();
+        let a = nodes.insert(Default::default());
+        let b = nodes.insert(Default::default());
+        assert_ne!(access_node_id(a), access_node_id(b));
+        assert_eq!(access_node_id(a), access_node_id(a));
+        // A reused slot gets a new id, so a screen reader doesn't confuse it with the old node.
+        nodes.remove(a);
+        let c = nodes.insert(Default::default());
+        assert_ne!(access_node_id(a), access_node_id(c));
+    }
+
+    #[test]
+    fn leaf_roles() {
+        assert!(is_leaf_role(Role::Button));
+        assert!(is_leaf_role(Role::StaticText));
+        assert!(!is_leaf_role(Role::Window));
+        assert!(!is_leaf_role(Role::List));
+    }
+}
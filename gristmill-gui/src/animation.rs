@@ -0,0 +1,149 @@
+use crate::GuiNodeId;
+use gristmill_core::{geom2d::EdgeRect, math::IVec2};
+use std::time::{Duration, Instant};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Map linear progress `t` in 0..=1 to eased progress.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// The parts of a node's layout that can be animated.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct LayoutTarget {
+    pub size: IVec2,
+    pub margin: EdgeRect,
+}
+
+impl LayoutTarget {
+    fn lerp(self, other: LayoutTarget, t: f32) -> LayoutTarget {
+        let lerp_i32 = |a: i32, b: i32| a + ((b - a) as f32 * t).round() as i32;
+        LayoutTarget {
+            size: IVec2::new(
+                lerp_i32(self.size.x, other.size.x),
+                lerp_i32(self.size.y, other.size.y),
+            ),
+            margin: EdgeRect::new(
+                lerp_i32(self.margin.top, other.margin.top),
+                lerp_i32(self.margin.right, other.margin.right),
+                lerp_i32(self.margin.bottom, other.margin.bottom),
+                lerp_i32(self.margin.left, other.margin.left),
+            ),
+        }
+    }
+}
+
+pub(crate) struct LayoutAnimation {
+    pub node: GuiNodeId,
+    from: LayoutTarget,
+    to: LayoutTarget,
+    start: Instant,
+    duration: Duration,
+    easing: Easing,
+}
+
+impl LayoutAnimation {
+    pub fn new(
+        node: GuiNodeId,
+        from: LayoutTarget,
+        to: LayoutTarget,
+        duration: Duration,
+        easing: Easing,
+    ) -> Self {
+        LayoutAnimation {
+            node,
+            from,
+            to,
+            start: Instant::now(),
+            duration,
+            easing,
+        }
+    }
+    /// Returns the current value and whether the animation has finished.
+    pub fn sample(&self, now: Instant) -> (LayoutTarget, bool) {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= self.duration {
+            return (self.to, true);
+        }
+        let t = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        (self.from.lerp(self.to, self.easing.apply(t)), false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASINGS: [Easing; 4] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+    ];
+
+    #[test]
+    fn easing_endpoints() {
+        for easing in EASINGS {
+            assert_eq!(easing.apply(0.0), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(1.0), 1.0, "{easing:?}");
+            assert_eq!(easing.apply(-1.0), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(2.0), 1.0, "{easing:?}");
+        }
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+    }
+
+    #[test]
+    fn sample_reaches_target() {
+        let from = LayoutTarget {
+            size: IVec2::new(100, 20),
+            margin: EdgeRect::new(0, 0, 0, 0),
+        };
+        let to = LayoutTarget {
+            size: IVec2::new(300, 60),
+            margin: EdgeRect::new(8, -4, 8, 4),
+        };
+        let duration = Duration::from_millis(200);
+        for easing in EASINGS {
+            let animation = LayoutAnimation::new(GuiNodeId::default(), from, to, duration, easing);
+            let start = animation.start;
+            assert_eq!(animation.sample(start), (from, false), "{easing:?}");
+            assert_eq!(animation.sample(start + duration), (to, true), "{easing:?}");
+            assert_eq!(
+                animation.sample(start + duration * 2),
+                (to, true),
+                "{easing:?}"
+            );
+        }
+        let linear = LayoutAnimation::new(GuiNodeId::default(), from, to, duration, Easing::Linear);
+        let (halfway, finished) = linear.sample(linear.start + duration / 2);
+        assert!(!finished);
+        assert_eq!(halfway.size, IVec2::new(200, 40));
+        assert_eq!(halfway.margin, EdgeRect::new(4, -2, 4, 2));
+        // Eased progress of 1 lands exactly on the target, with no rounding error.
+        assert_eq!(from.lerp(to, 1.0), to);
+    }
+}
@@ -0,0 +1,138 @@
+//! One-way bindings from game state to text nodes.
+
+use crate::{Gui, GuiNodeId, GuiNodeStorage, NodeDraw, OwnedText};
+
+pub(crate) struct TextBinding {
+    node: GuiNodeId,
+    source: Box<dyn FnMut() -> String>,
+    last: Option<String>,
+}
+
+impl TextBinding {
+    /// Returns false once the node has been removed.
+    fn update(&mut self, nodes: &mut GuiNodeStorage) -> bool {
+        let section = match nodes.get_mut(self.node).map(|node| &mut node.draw) {
+            Some(NodeDraw::Text(section)) => section,
+            Some(_) => return true,
+            None => return false,
+        };
+        let text = (self.source)();
+        if self.last.as_ref() == Some(&text) {
+            return true;
+        }
+        // Keep the font, size and color the text already has.
+        if let Some(first) = section.text.first_mut() {
+            first.text.clone_from(&text);
+            section.text.truncate(1);
+        } else {
+            section.text.push(OwnedText::new(text.clone()));
+        }
+        self.last = Some(text);
+        true
+    }
+}
+
+impl Gui {
+    /// Set the text of `node` from `source` at the start of every `update`, such as
+    /// `gui.bind_text(label, move || format!("HP: {}", hp.get()))`. The text is only replaced when
+    /// the string changes, and keeps the style of its first section. Replaces any binding the node
+    /// already has, and is dropped when the node is removed.
+    pub fn bind_text<F>(&mut self, node: GuiNodeId, source: F)
+    where
+        F: FnMut() -> String + 'static,
+    {
+        self.unbind_text(node);
+        self.text_bindings.push(TextBinding {
+            node,
+            source: Box::new(source),
+            last: None,
+        });
+    }
+    pub fn unbind_text(&mut self, node: GuiNodeId) {
+        self.text_bindings.retain(|binding| binding.node != node);
+    }
+    pub fn is_text_bound(&self, node: GuiNodeId) -> bool {
+        self.text_bindings
+            .iter()
+            .any(|binding| binding.node == node)
+    }
+
+    pub(crate) fn update_bindings(&mut self) {
+        let nodes = &mut self.nodes;
+        self.text_bindings
+            .retain_mut(|binding| binding.update(nodes));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GuiNode, OwnedSection};
+    use std::{cell::Cell, rc::Rc};
+
+    fn text_of(nodes: &GuiNodeStorage, node: GuiNodeId) -> Vec<(String, f32)> {
+        match &nodes[node].draw {
+            NodeDraw::Text(section) => section
+                .text
+                .iter()
+                .map(|text| (text.text.clone(), text.scale.x))
+                .collect(),
+            _ => panic!("not a text node"),
+        }
+    }
+
+    #[test]
+    fn bound_text_follows_source() {
+        let mut nodes = GuiNodeStorage::default();
+        let section = OwnedSection::default()
+            .add_text(OwnedText::new("HP: ?").with_scale(20.0))
+            .add_text(OwnedText::new(" (old)"));
+        let node = nodes.insert(GuiNode::with_draw(NodeDraw::Text(section)));
+        let hp = Rc::new(Cell::new(3));
+        let mut binding = TextBinding {
+            node,
+            source: Box::new({
+                let hp = hp.clone();
+                move || format!("HP: {}", hp.get())
+            }),
+            last: None,
+        };
+
+        assert!(binding.update(&mut nodes));
+        // Replaces all the text, keeping the first section's style.
+        assert_eq!(text_of(&nodes, node), vec![("HP: 3".to_owned(), 20.0)]);
+        hp.set(2);
+        assert!(binding.update(&mut nodes));
+        assert_eq!(text_of(&nodes, node), vec![("HP: 2".to_owned(), 20.0)]);
+
+        // An unchanged string leaves the text alone, even if something else changed it.
+        if let NodeDraw::Text(section) = &mut nodes[node].draw {
+            section.text[0].text = "edited".to_owned();
+        }
+        assert!(binding.update(&mut nodes));
+        assert_eq!(text_of(&nodes, node), vec![("edited".to_owned(), 20.0)]);
+
+        nodes.remove(node);
+        assert!(!binding.update(&mut nodes));
+    }
+
+    #[test]
+    fn binding_to_empty_text_adds_a_section() {
+        let mut nodes = GuiNodeStorage::default();
+        let node = nodes.insert(GuiNode::with_draw(NodeDraw::Text(OwnedSection::default())));
+        let other = nodes.insert(GuiNode::default());
+        let mut binding = TextBinding {
+            node,
+            source: Box::new(|| "Ready".to_owned()),
+            last: None,
+        };
+        assert!(binding.update(&mut nodes));
+        let texts = text_of(&nodes, node);
+        assert_eq!(texts.len(), 1);
+        assert_eq!(texts[0].0, "Ready");
+        // A node that doesn't draw text keeps its binding, in case it becomes text later.
+        binding.node = other;
+        assert!(binding.update(&mut nodes));
+        assert!(matches!(nodes[other].draw, NodeDraw::None));
+    }
+}
@@ -0,0 +1,250 @@
+//! Layouts that change with the shape of the screen, like CSS media queries.
+//!
+//! Give a node alternative layouts with `Gui::add_breakpoint`. Every `update`, the node takes the
+//! layout of the first breakpoint that matches the screen, or the layout it had before any
+//! breakpoints were added if none match. A layout is only applied when the matching breakpoint
+//! changes, so changes made to the node's layout in between are kept until then.
+
+use crate::{Gui, GuiNodeId, NodeLayout};
+use gristmill_core::math::IVec2;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Orientation {
+    /// At least as wide as tall.
+    Landscape,
+    Portrait,
+}
+
+impl Orientation {
+    pub fn of(size: IVec2) -> Self {
+        if size.x >= size.y {
+            Orientation::Landscape
+        } else {
+            Orientation::Portrait
+        }
+    }
+}
+
+/// A condition on the screen size, in GUI units. Every limit that is set must hold for the
+/// breakpoint to match; the default matches any screen.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Breakpoint {
+    pub min_width: Option<i32>,
+    pub max_width: Option<i32>,
+    pub min_height: Option<i32>,
+    pub max_height: Option<i32>,
+    /// Width divided by height, so 16:9 is about 1.78 and ultrawide 21:9 about 2.33.
+    pub min_aspect: Option<f32>,
+    pub max_aspect: Option<f32>,
+    pub orientation: Option<Orientation>,
+}
+
+impl Breakpoint {
+    pub fn portrait() -> Self {
+        Breakpoint {
+            orientation: Some(Orientation::Portrait),
+            ..Default::default()
+        }
+    }
+    pub fn landscape() -> Self {
+        Breakpoint {
+            orientation: Some(Orientation::Landscape),
+            ..Default::default()
+        }
+    }
+    pub fn max_width(width: i32) -> Self {
+        Breakpoint {
+            max_width: Some(width),
+            ..Default::default()
+        }
+    }
+    pub fn min_aspect(aspect: f32) -> Self {
+        Breakpoint {
+            min_aspect: Some(aspect),
+            ..Default::default()
+        }
+    }
+
+    pub fn matches(&self, size: IVec2) -> bool {
+        let aspect = size.x as f32 / size.y.max(1) as f32;
+        self.min_width.map_or(true, |min| size.x >= min)
+            && self.max_width.map_or(true, |max| size.x <= max)
+            && self.min_height.map_or(true, |min| size.y >= min)
+            && self.max_height.map_or(true, |max| size.y <= max)
+            && self.min_aspect.map_or(true, |min| aspect >= min)
+            && self.max_aspect.map_or(true, |max| aspect <= max)
+            && self
+                .orientation
+                .map_or(true, |orientation| Orientation::of(size) == orientation)
+    }
+}
+
+/// The index of the first breakpoint in `breakpoints` that matches `size`.
+pub fn select_breakpoint<'a, I>(breakpoints: I, size: IVec2) -> Option<usize>
+where
+    I: IntoIterator<Item = &'a Breakpoint>,
+{
+    breakpoints
+        .into_iter()
+        .position(|breakpoint| breakpoint.matches(size))
+}
+
+pub(crate) struct NodeBreakpoints {
+    variants: Vec<(Breakpoint, NodeLayout)>,
+    /// The layout from before any breakpoints were added, used when none match.
+    fallback: NodeLayout,
+    active: Option<usize>,
+    /// Apply the matching layout at the next update, even if the same breakpoint still matches.
+    dirty: bool,
+}
+
+impl NodeBreakpoints {
+    /// The layout to switch to on a screen of `size`, if the matching breakpoint changed.
+    fn update(&mut self, size: IVec2) -> Option<&NodeLayout> {
+        let active = select_breakpoint(self.variants.iter().map(|(b, _)| b), size);
+        if active == self.active && !self.dirty {
+            return None;
+        }
+        self.active = active;
+        self.dirty = false;
+        Some(match active {
+            Some(index) => &self.variants[index].1,
+            None => &self.fallback,
+        })
+    }
+}
+
+impl Gui {
+    /// Give `node` the layout `layout` while the screen matches `breakpoint`. Breakpoints are
+    /// checked in the order they were added, and the first match wins. The layout is applied at
+    /// the next `update`.
+    pub fn add_breakpoint(&mut self, node: GuiNodeId, breakpoint: Breakpoint, layout: NodeLayout) {
+        if !self.breakpoints.contains_key(node) {
+            let fallback = match self.nodes.get(node) {
+                Some(node) => node.layout.clone(),
+                None => return,
+            };
+            self.breakpoints.insert(
+                node,
+                NodeBreakpoints {
+                    variants: Vec::new(),
+                    fallback,
+                    active: None,
+                    dirty: true,
+                },
+            );
+        }
+        let breakpoints = &mut self.breakpoints[node];
+        breakpoints.variants.push((breakpoint, layout));
+        breakpoints.dirty = true;
+    }
+    /// Remove the node's breakpoints and give it back the layout it had before them.
+    pub fn clear_breakpoints(&mut self, node: GuiNodeId) {
+        if let (Some(breakpoints), Some(node)) =
+            (self.breakpoints.remove(node), self.nodes.get_mut(node))
+        {
+            Self::apply_layout(&mut node.layout, &breakpoints.fallback);
+        }
+    }
+    /// The index of the breakpoint `node`'s layout currently comes from, or `None` if it has its
+    /// fallback layout.
+    pub fn active_breakpoint(&self, node: GuiNodeId) -> Option<usize> {
+        self.breakpoints
+            .get(node)
+            .and_then(|breakpoints| breakpoints.active)
+    }
+    /// The size of the screen in GUI units, which breakpoints are matched against.
+    pub fn screen_size(&self) -> IVec2 {
+        (self.viewport_size.as_vec2() / self.scale()).as_ivec2()
+    }
+
+    fn apply_layout(layout: &mut NodeLayout, variant: &NodeLayout) {
+        let content_size = layout.content_size;
+        *layout = variant.clone();
+        layout.content_size = content_size;
+    }
+
+    pub(crate) fn update_breakpoints(&mut self) {
+        let size = self.screen_size();
+        let nodes = &mut self.nodes;
+        self.breakpoints.retain(|node, breakpoints| {
+            let node = match nodes.get_mut(node) {
+                Some(node) => node,
+                None => return false,
+            };
+            if let Some(layout) = breakpoints.update(size) {
+                Self::apply_layout(&mut node.layout, layout);
+            }
+            true
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_are_inclusive() {
+        let breakpoint = Breakpoint {
+            min_width: Some(600),
+            max_width: Some(1200),
+            min_height: Some(400),
+            ..Default::default()
+        };
+        assert!(breakpoint.matches(IVec2::new(600, 400)));
+        assert!(breakpoint.matches(IVec2::new(1200, 900)));
+        assert!(!breakpoint.matches(IVec2::new(599, 400)));
+        assert!(!breakpoint.matches(IVec2::new(1201, 400)));
+        assert!(!breakpoint.matches(IVec2::new(800, 399)));
+        assert!(Breakpoint::default().matches(IVec2::ZERO));
+    }
+
+    #[test]
+    fn aspect_and_orientation() {
+        let ultrawide = Breakpoint::min_aspect(2.0);
+        assert!(ultrawide.matches(IVec2::new(2560, 1080)));
+        assert!(ultrawide.matches(IVec2::new(2000, 1000)));
+        assert!(!ultrawide.matches(IVec2::new(1920, 1080)));
+        // A zero height doesn't divide by zero.
+        assert!(ultrawide.matches(IVec2::new(100, 0)));
+
+        assert!(Breakpoint::landscape().matches(IVec2::new(500, 500)));
+        assert!(!Breakpoint::portrait().matches(IVec2::new(500, 500)));
+        assert!(Breakpoint::portrait().matches(IVec2::new(500, 501)));
+        assert_eq!(
+            select_breakpoint(
+                &[Breakpoint::max_width(400), Breakpoint::portrait()],
+                IVec2::new(300, 600)
+            ),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn aspect_change_switches_layout() {
+        let layout = |child_spacing| NodeLayout {
+            child_spacing,
+            ..Default::default()
+        };
+        let mut breakpoints = NodeBreakpoints {
+            variants: vec![
+                (Breakpoint::min_aspect(2.0), layout(1)),
+                (Breakpoint::portrait(), layout(2)),
+            ],
+            fallback: layout(0),
+            active: None,
+            dirty: true,
+        };
+        let spacing = |layout: Option<&NodeLayout>| layout.map(|layout| layout.child_spacing);
+        let widescreen = IVec2::new(1920, 1080);
+        assert_eq!(spacing(breakpoints.update(widescreen)), Some(0));
+        // Nothing changes until a different breakpoint matches.
+        assert_eq!(spacing(breakpoints.update(widescreen)), None);
+        assert_eq!(spacing(breakpoints.update(IVec2::new(2560, 1080))), Some(1));
+        assert_eq!(breakpoints.active, Some(0));
+        assert_eq!(spacing(breakpoints.update(IVec2::new(1080, 1920))), Some(2));
+        assert_eq!(spacing(breakpoints.update(widescreen)), Some(0));
+        assert_eq!(breakpoints.active, None);
+    }
+}
@@ -0,0 +1,234 @@
+//! Right-click menus.
+//!
+//! Give a node a list of items with `Gui::set_context_menu`. Pressing the "secondary" action over
+//! it (or any of its descendants that has no menu of its own) opens the menu at the pointer,
+//! flipped to the other side of the pointer if it would go past the edge of the screen. Choosing
+//! an item closes the menu and produces a `ContextMenuEvent`; pressing anywhere else closes it
+//! without one.
+//!
+//! The menu's look comes from the "context_menu" style class: `width`, `item_height`, `padding`,
+//! `texture`, `color`, `hover_color`, and `shadow_offset`, `shadow_color` and `shadow_blur`. Item
+//! labels use the "text" class.
+
+use crate::{
+    widget::{Text, WidgetNodeExt, WidgetStyle},
+    Anchor, Gui, GuiNode, GuiNodeExt, GuiNodeId, NodeDraw, NodeFlags, NodeLayout,
+};
+use gristmill_core::{geom2d::EdgeRect, input::InputActions, math::IVec2, Color};
+use winit::window::CursorIcon;
+
+/// An item chosen from a context menu.
+#[derive(Clone, Debug)]
+pub struct ContextMenuEvent {
+    /// The node the menu was opened on.
+    pub target: GuiNodeId,
+    pub index: usize,
+    pub item: String,
+}
+
+pub(crate) struct OpenContextMenu {
+    target: GuiNodeId,
+    node: GuiNodeId,
+    items: Vec<GuiNodeId>,
+    color: Color,
+    hover_color: Color,
+}
+
+/// Where to put a menu of `size` opened at `pointer`, so that it stays inside `bounds`. The menu
+/// opens down and to the right of the pointer, and flips to the other side on each axis where it
+/// doesn't fit.
+pub fn context_menu_position(pointer: IVec2, size: IVec2, bounds: IVec2) -> IVec2 {
+    let flip = |pointer: i32, size: i32, bound: i32| {
+        if pointer + size > bound && pointer - size >= 0 {
+            pointer - size
+        } else {
+            pointer.min(bound - size).max(0)
+        }
+    };
+    IVec2::new(
+        flip(pointer.x, size.x, bounds.x),
+        flip(pointer.y, size.y, bounds.y),
+    )
+}
+
+impl Gui {
+    /// Show `items` when `node` is right-clicked. An empty list removes the menu.
+    pub fn set_context_menu<I, S>(&mut self, node: GuiNodeId, items: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let items: Vec<String> = items.into_iter().map(Into::into).collect();
+        if items.is_empty() {
+            self.context_menus.remove(node);
+        } else {
+            self.context_menus.insert(node, items);
+        }
+    }
+    pub fn context_menu(&self, node: GuiNodeId) -> Option<&[String]> {
+        self.context_menus.get(node).map(Vec::as_slice)
+    }
+    pub fn is_context_menu_open(&self) -> bool {
+        self.open_context_menu.is_some()
+    }
+    /// The open menu's node, to check its rect or position it manually.
+    pub fn context_menu_node(&self) -> Option<GuiNodeId> {
+        self.open_context_menu.as_ref().map(|menu| menu.node)
+    }
+    /// The item chosen since this was last called, if any.
+    pub fn take_context_menu_event(&mut self) -> Option<ContextMenuEvent> {
+        self.context_menu_event.take()
+    }
+
+    /// Open the context menu of `target` at `pointer`, in GUI units. Does nothing if `target` has
+    /// no menu.
+    pub fn open_context_menu(&mut self, target: GuiNodeId, pointer: IVec2) {
+        self.close_context_menu();
+        let items = match self.context_menus.get(target) {
+            Some(items) => items.clone(),
+            None => return,
+        };
+        let mut style = self.styles.query(std::iter::once("context_menu"));
+        let width = style.widget_value("width", 160);
+        let item_height = style.widget_value("item_height", 24);
+        let padding = style.widget_value("padding", 4);
+        let texture = style.widget_value("texture", None);
+        let background = style.widget_value("color", Color::new_value(0.95));
+        let color = Color::new(0.0, 0.0, 0.0, 0.0);
+        let hover_color = style.widget_value("hover_color", Color::new(0.3, 0.6, 0.9, 0.4));
+
+        let size = IVec2::new(width, item_height * items.len() as i32 + padding * 2);
+        let bounds = (self.viewport_size.as_vec2() / self.scale()).as_ivec2();
+        let position = context_menu_position(pointer, size, bounds);
+        let root = self.root;
+        let node = root.add_child(
+            self,
+            GuiNode {
+                flags: NodeFlags {
+                    pointer_opaque: true,
+                    ..Default::default()
+                },
+                layout: NodeLayout {
+                    size,
+                    margin: EdgeRect::new(position.y, 0, 0, position.x),
+                    child_layout: "vbox".to_owned(),
+                    ..Default::default()
+                },
+                draw: NodeDraw::Rect(texture, background),
+                shadow: style.widget_shadow(),
+                ..Default::default()
+            },
+        );
+        let item_nodes = items
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let item_node = node.add_child(
+                    self,
+                    GuiNode {
+                        flags: NodeFlags {
+                            pointer_opaque: true,
+                            ..Default::default()
+                        },
+                        layout: NodeLayout {
+                            size: IVec2::new(0, item_height),
+                            margin: if index == 0 {
+                                EdgeRect::new(padding, 0, 0, 0)
+                            } else {
+                                EdgeRect::ZERO
+                            },
+                            ..Default::default()
+                        },
+                        draw: NodeDraw::Rect(None, color),
+                        cursor: Some(CursorIcon::Hand),
+                        ..Default::default()
+                    },
+                );
+                let label = self.create_widget::<Text>(item_node);
+                label.set_text_align(self, (Anchor::Begin, Anchor::Middle), false);
+                label.set_text_string(self, item);
+                label.set_layout_margin(self, EdgeRect::new(0, padding * 2, 0, padding * 2));
+                item_node
+            })
+            .collect();
+        self.open_context_menu = Some(OpenContextMenu {
+            target,
+            node,
+            items: item_nodes,
+            color,
+            hover_color,
+        });
+    }
+    pub fn close_context_menu(&mut self) {
+        if let Some(menu) = self.open_context_menu.take() {
+            self.remove_node(menu.node);
+        }
+    }
+
+    pub(crate) fn update_context_menu(
+        &mut self,
+        input: &InputActions,
+        pointer: Option<IVec2>,
+        pointer_over: Option<GuiNodeId>,
+    ) {
+        let primary = input.try_get("primary").copied().unwrap_or_default();
+        let secondary = input.try_get("secondary").copied().unwrap_or_default();
+        if let Some(menu) = self.open_context_menu.as_ref() {
+            // Items contain their label, which the pointer may be over instead.
+            let hovered = pointer_over.and_then(|over| {
+                menu.items.iter().position(|item| {
+                    *item == over
+                        || self
+                            .node_children
+                            .get(*item)
+                            .map_or(false, |children| children.contains(&over))
+                })
+            });
+            for (index, item) in menu.items.iter().enumerate() {
+                if let Some(node) = self.nodes.get_mut(*item) {
+                    let color = if hovered == Some(index) {
+                        menu.hover_color
+                    } else {
+                        menu.color
+                    };
+                    node.draw = NodeDraw::Rect(None, color);
+                }
+            }
+            if primary.just_pressed() || secondary.just_pressed() {
+                if let Some(index) = hovered.filter(|_| primary.just_pressed()) {
+                    let target = menu.target;
+                    let item = self
+                        .context_menus
+                        .get(target)
+                        .and_then(|items| items.get(index));
+                    self.context_menu_event = item.map(|item| ContextMenuEvent {
+                        target,
+                        index,
+                        item: item.clone(),
+                    });
+                }
+                let over_menu = pointer_over == Some(menu.node) || hovered.is_some();
+                if !over_menu || primary.just_pressed() {
+                    self.close_context_menu();
+                }
+            }
+        }
+
+        if !secondary.just_pressed() || self.open_context_menu.is_some() {
+            return;
+        }
+        let (pointer, pointer_over) = match (pointer, pointer_over) {
+            (Some(pointer), Some(pointer_over)) => (pointer, pointer_over),
+            _ => return,
+        };
+        let mut path = Vec::new();
+        self.find_path(self.root, pointer_over, &mut path);
+        if let Some(target) = path
+            .into_iter()
+            .rev()
+            .find(|node| self.context_menus.contains_key(*node))
+        {
+            self.open_context_menu(target, pointer);
+        }
+    }
+}
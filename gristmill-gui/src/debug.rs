@@ -0,0 +1,115 @@
+use crate::{
+    widget::{Text, WidgetNodeExt},
+    Anchor, Corner, Gui, GuiNode, GuiNodeExt, GuiNodeId, NodeDraw, NodeLayout, OwnedText,
+};
+use gristmill_core::{geom2d::EdgeRect, input::InputActions, math::IVec2, Color};
+use std::time::{Duration, Instant};
+
+/// An on-screen display of frame rate and draw statistics. Create it after the rest of the GUI so
+/// that it draws on top.
+///
+/// The overlay toggles when the `toggle_debug_overlay` action is pressed, if the bindings have one.
+pub struct DebugOverlay {
+    panel: GuiNodeId,
+    text: Text,
+    visible: bool,
+    sample_start: Instant,
+    last_frame: Instant,
+    frames: u32,
+    longest_frame: Duration,
+}
+
+impl DebugOverlay {
+    const FONT_SIZE: f32 = 16.0;
+    const SAMPLE_PERIOD: Duration = Duration::from_millis(500);
+
+    pub fn new(gui: &mut Gui, corner: Corner) -> Self {
+        let anchors = match corner {
+            Corner::TopLeft => (Anchor::Begin, Anchor::Begin),
+            Corner::TopRight => (Anchor::End, Anchor::Begin),
+            Corner::BottomLeft => (Anchor::Begin, Anchor::End),
+            Corner::BottomRight => (Anchor::End, Anchor::End),
+        };
+        let panel = gui.root().add_child(
+            gui,
+            GuiNode::new(
+                NodeLayout {
+                    size: IVec2::new(200, 84),
+                    margin: EdgeRect::splat(8),
+                    anchors,
+                    ..Default::default()
+                },
+                NodeDraw::Rect(None, Color::new(0.0, 0.0, 0.0, 0.6)),
+            ),
+        );
+        let text: Text = gui.create_widget(panel);
+        text.set_text_align(gui, (Anchor::Begin, Anchor::Begin), true);
+        if let Some(node) = text.node_data(gui) {
+            node.layout.margin = EdgeRect::splat(6);
+        }
+        let now = Instant::now();
+        DebugOverlay {
+            panel,
+            text,
+            visible: true,
+            sample_start: now,
+            last_frame: now,
+            frames: 0,
+            longest_frame: Duration::ZERO,
+        }
+    }
+
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+    pub fn set_visible(&mut self, gui: &mut Gui, visible: bool) {
+        self.visible = visible;
+        if let Some(node) = gui.nodes_mut().get_mut(self.panel) {
+            node.flags.visible = visible;
+        }
+    }
+    pub fn toggle(&mut self, gui: &mut Gui) {
+        self.set_visible(gui, !self.visible);
+    }
+
+    /// Call once per frame. The displayed numbers are averaged and refreshed twice a second so
+    /// they stay readable.
+    pub fn update(&mut self, gui: &mut Gui, input: &InputActions) {
+        if input
+            .try_get("toggle_debug_overlay")
+            .map_or(false, |action| action.just_pressed())
+        {
+            self.toggle(gui);
+        }
+
+        let now = Instant::now();
+        self.frames += 1;
+        self.longest_frame = self.longest_frame.max(now - self.last_frame);
+        self.last_frame = now;
+        let elapsed = now - self.sample_start;
+        if elapsed < Self::SAMPLE_PERIOD {
+            return;
+        }
+
+        let fps = self.frames as f64 / elapsed.as_secs_f64();
+        let frame_ms = elapsed.as_secs_f64() * 1000.0 / self.frames as f64;
+        let metrics = gui.rect_renderer().metrics();
+        let text = format!(
+            "FPS: {:.0}\nFrame: {:.2} ms (max {:.2} ms)\nDraw calls: {}\nRects: {}",
+            fps,
+            frame_ms,
+            self.longest_frame.as_secs_f64() * 1000.0,
+            metrics.draw_calls,
+            metrics.rects,
+        );
+        self.text.set_text(
+            gui,
+            vec![OwnedText::new(text)
+                .with_scale(Self::FONT_SIZE)
+                .with_color(<[f32; 4]>::from(Color::WHITE))],
+        );
+        self.sample_start = now;
+        self.frames = 0;
+        self.longest_frame = Duration::ZERO;
+    }
+}
@@ -1,17 +1,102 @@
-use gristmill_core::geom2d::IRect;
+use gristmill_core::{
+    geom2d::IRect,
+    math::{IVec2, Vec2},
+};
+use std::f32::consts::TAU;
 
 use crate::NodeLayout;
 
 pub trait GuiLayout {
-    fn begin_layout(&mut self, rect: IRect, spacing: i32);
+    /// `child_sizes` are the sizes (including margins) of all children about to be laid out.
+    fn begin_layout(&mut self, rect: IRect, layout: &NodeLayout, child_sizes: &[IVec2]);
+    /// Like `begin_layout`, with each child's `grow` weight. Layouts that share free space
+    /// between growing children override this; by default the weights are ignored.
+    fn begin_layout_grow(
+        &mut self,
+        rect: IRect,
+        layout: &NodeLayout,
+        child_sizes: &[IVec2],
+        _child_grow: &[f32],
+    ) {
+        self.begin_layout(rect, layout, child_sizes);
+    }
     fn layout_child(&mut self, layout: &NodeLayout) -> IRect;
+    /// The size needed to fit children of `child_sizes` (including margins), used by nodes with
+    /// `fit_content`. By default this is the largest child on each axis.
+    fn measure(&self, _layout: &NodeLayout, child_sizes: &[IVec2]) -> IVec2 {
+        child_sizes.iter().copied().fold(IVec2::ZERO, IVec2::max)
+    }
+}
+
+/// The total length of `lengths` laid end to end with `spacing` between them.
+fn packed_length(lengths: impl Iterator<Item = i32>, spacing: i32) -> i32 {
+    let (total, count) = lengths.fold((0, 0), |(total, count), length| (total + length, count + 1));
+    total + spacing * (count - 1).max(0)
+}
+
+/// Shares the space left over in a box layout between children with a `grow` weight.
+#[derive(Default)]
+struct GrowSpace {
+    free: i32,
+    total_grow: f32,
+    used_grow: f32,
+}
+
+impl GrowSpace {
+    fn new(free: i32, child_grow: &[f32]) -> Self {
+        GrowSpace {
+            free: free.max(0),
+            total_grow: child_grow.iter().map(|grow| grow.max(0.0)).sum(),
+            used_grow: 0.0,
+        }
+    }
+    fn is_growing(&self) -> bool {
+        self.total_grow > 0.0
+    }
+    /// The extra length for the next child. Rounds so that the extras add up to exactly the free
+    /// space.
+    fn next(&mut self, grow: f32) -> i32 {
+        if !self.is_growing() || grow <= 0.0 {
+            return 0;
+        }
+        let share = |used: f32| (self.free as f32 * used / self.total_grow).round() as i32;
+        let before = share(self.used_grow);
+        self.used_grow += grow;
+        share(self.used_grow) - before
+    }
+}
+
+/// The first child's offset, the gap between children, and the space for growing children, for
+/// a box layout along an axis of length `available`.
+fn box_gaps(
+    layout: &NodeLayout,
+    available: i32,
+    lengths: impl Iterator<Item = i32>,
+    count: usize,
+    child_grow: &[f32],
+) -> (i32, i32, GrowSpace) {
+    let total: i32 = lengths.sum();
+    let grow = GrowSpace::new(
+        available - total - layout.child_spacing * (count as i32 - 1).max(0),
+        child_grow,
+    );
+    if grow.is_growing() {
+        // Growing children take all the free space, so they're packed.
+        (0, layout.child_spacing, grow)
+    } else {
+        let (offset, spacing) =
+            layout
+                .child_distribution
+                .gaps(available, total, count, layout.child_spacing);
+        (offset, spacing, GrowSpace::default())
+    }
 }
 
 #[derive(Default)]
 pub struct Anchor(IRect);
 
 impl GuiLayout for Anchor {
-    fn begin_layout(&mut self, rect: IRect, _spacing: i32) {
+    fn begin_layout(&mut self, rect: IRect, _layout: &NodeLayout, _child_sizes: &[IVec2]) {
         self.0 = rect;
     }
     fn layout_child(&mut self, layout: &NodeLayout) -> IRect {
@@ -26,21 +111,45 @@ pub struct HBox {
     rect: IRect,
     spacing: i32,
     x: i32,
+    grow: GrowSpace,
 }
 
 impl GuiLayout for HBox {
-    fn begin_layout(&mut self, rect: IRect, spacing: i32) {
+    fn begin_layout(&mut self, rect: IRect, layout: &NodeLayout, child_sizes: &[IVec2]) {
+        self.begin_layout_grow(rect, layout, child_sizes, &[]);
+    }
+    fn begin_layout_grow(
+        &mut self,
+        rect: IRect,
+        layout: &NodeLayout,
+        child_sizes: &[IVec2],
+        child_grow: &[f32],
+    ) {
+        let (offset, spacing, grow) = box_gaps(
+            layout,
+            rect.width(),
+            child_sizes.iter().map(|size| size.x),
+            child_sizes.len(),
+            child_grow,
+        );
         self.rect = rect;
         self.spacing = spacing;
-        self.x = rect.position.x;
+        self.x = rect.position.x + offset;
+        self.grow = grow;
     }
     fn layout_child(&mut self, layout: &NodeLayout) -> IRect {
-        let width = layout.width();
+        let width = layout.width() + self.grow.next(layout.grow);
         let (y, height) = layout.vertical(self.rect.y(), self.rect.height());
         let child_rect = IRect::new(self.x, y, width, height);
         self.x += width + self.spacing;
         child_rect
     }
+    fn measure(&self, layout: &NodeLayout, child_sizes: &[IVec2]) -> IVec2 {
+        IVec2::new(
+            packed_length(child_sizes.iter().map(|size| size.x), layout.child_spacing),
+            child_sizes.iter().map(|size| size.y).max().unwrap_or(0),
+        )
+    }
 }
 
 #[derive(Default)]
@@ -48,19 +157,151 @@ pub struct VBox {
     rect: IRect,
     spacing: i32,
     y: i32,
+    grow: GrowSpace,
 }
 
 impl GuiLayout for VBox {
-    fn begin_layout(&mut self, rect: IRect, spacing: i32) {
+    fn begin_layout(&mut self, rect: IRect, layout: &NodeLayout, child_sizes: &[IVec2]) {
+        self.begin_layout_grow(rect, layout, child_sizes, &[]);
+    }
+    fn begin_layout_grow(
+        &mut self,
+        rect: IRect,
+        layout: &NodeLayout,
+        child_sizes: &[IVec2],
+        child_grow: &[f32],
+    ) {
+        let (offset, spacing, grow) = box_gaps(
+            layout,
+            rect.height(),
+            child_sizes.iter().map(|size| size.y),
+            child_sizes.len(),
+            child_grow,
+        );
         self.rect = rect;
         self.spacing = spacing;
-        self.y = rect.position.y;
+        self.y = rect.position.y + offset;
+        self.grow = grow;
     }
     fn layout_child(&mut self, layout: &NodeLayout) -> IRect {
-        let height = layout.height();
+        let height = layout.height() + self.grow.next(layout.grow);
         let (x, width) = layout.horizontal(self.rect.x(), self.rect.width());
         let child_rect = IRect::new(x, self.y, width, height);
         self.y += height + self.spacing;
         child_rect
     }
+    fn measure(&self, layout: &NodeLayout, child_sizes: &[IVec2]) -> IVec2 {
+        IVec2::new(
+            child_sizes.iter().map(|size| size.x).max().unwrap_or(0),
+            packed_length(child_sizes.iter().map(|size| size.y), layout.child_spacing),
+        )
+    }
+}
+
+/// Places children evenly around a circle that fits inside the rect, starting at the top and
+/// going clockwise.
+#[derive(Default)]
+pub struct Radial {
+    center: IVec2,
+    radius: f32,
+    count: usize,
+    index: usize,
+}
+
+impl Radial {
+    /// The direction (with +y down) of child `index` out of `count`.
+    pub fn direction(index: usize, count: usize) -> Vec2 {
+        let angle = index as f32 * TAU / count.max(1) as f32;
+        Vec2::new(angle.sin(), -angle.cos())
+    }
+    /// The index of the child whose sector contains `offset`, a direction from the center. Each
+    /// child's sector is centered on its direction, and boundaries belong to the next child
+    /// clockwise.
+    pub fn sector(offset: Vec2, count: usize) -> Option<usize> {
+        if count == 0 || offset == Vec2::ZERO {
+            return None;
+        }
+        let angle = offset.x.atan2(-offset.y).rem_euclid(TAU);
+        let sector_angle = TAU / count as f32;
+        Some(((angle + sector_angle / 2.0) / sector_angle) as usize % count)
+    }
+}
+
+impl GuiLayout for Radial {
+    fn begin_layout(&mut self, rect: IRect, _layout: &NodeLayout, child_sizes: &[IVec2]) {
+        let largest_child = child_sizes
+            .iter()
+            .map(|size| size.max_element())
+            .max()
+            .unwrap_or(0);
+        self.center = rect.center();
+        self.radius = (rect.size.min_element() - largest_child).max(0) as f32 / 2.0;
+        self.count = child_sizes.len();
+        self.index = 0;
+    }
+    fn layout_child(&mut self, layout: &NodeLayout) -> IRect {
+        let size = IVec2::new(layout.width(), layout.height());
+        let offset = Self::direction(self.index, self.count) * self.radius;
+        self.index += 1;
+        IRect {
+            position: self.center + offset.round().as_ivec2() - size / 2,
+            size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The direction `degrees` clockwise from straight up.
+    fn at(degrees: f32) -> Vec2 {
+        let angle = degrees.to_radians();
+        Vec2::new(angle.sin(), -angle.cos())
+    }
+
+    #[test]
+    fn one_sector_takes_everything() {
+        for degrees in [0.0, 90.0, 179.9, 180.0, 270.0, 359.9] {
+            assert_eq!(Radial::sector(at(degrees), 1), Some(0));
+        }
+    }
+
+    #[test]
+    fn four_sectors() {
+        assert_eq!(Radial::sector(Vec2::new(0.0, -1.0), 4), Some(0));
+        assert_eq!(Radial::sector(Vec2::new(1.0, 0.0), 4), Some(1));
+        assert_eq!(Radial::sector(Vec2::new(0.0, 1.0), 4), Some(2));
+        assert_eq!(Radial::sector(Vec2::new(-1.0, 0.0), 4), Some(3));
+        // Diagonals are exact boundaries, which belong to the next child clockwise.
+        assert_eq!(Radial::sector(Vec2::new(1.0, -1.0), 4), Some(1));
+        assert_eq!(Radial::sector(Vec2::new(1.0, 1.0), 4), Some(2));
+        assert_eq!(Radial::sector(Vec2::new(-1.0, 1.0), 4), Some(3));
+        assert_eq!(Radial::sector(Vec2::new(-1.0, -1.0), 4), Some(0));
+        // Just before the first boundary, and just before straight up.
+        assert_eq!(Radial::sector(at(44.9), 4), Some(0));
+        assert_eq!(Radial::sector(at(359.9), 4), Some(0));
+    }
+
+    #[test]
+    fn eight_sectors() {
+        assert_eq!(Radial::sector(at(0.0), 8), Some(0));
+        for index in 0..8 {
+            let boundary = index as f32 * 45.0 + 22.5;
+            assert_eq!(Radial::sector(at(boundary - 0.1), 8), Some(index));
+            assert_eq!(Radial::sector(at(boundary + 0.1), 8), Some((index + 1) % 8));
+        }
+    }
+
+    #[test]
+    fn sector_of_each_direction_is_its_index() {
+        for count in [1, 3, 4, 8, 12] {
+            for index in 0..count {
+                let direction = Radial::direction(index, count);
+                assert_eq!(Radial::sector(direction, count), Some(index));
+            }
+        }
+        assert_eq!(Radial::sector(Vec2::ZERO, 4), None);
+        assert_eq!(Radial::sector(at(0.0), 0), None);
+    }
 }
@@ -1,29 +1,72 @@
+#[cfg(feature = "accessibility")]
+pub mod access;
+pub mod animation;
+mod binding;
+pub mod breakpoint;
+pub mod context_menu;
+#[cfg(feature = "debug-overlay")]
+pub mod debug;
 pub mod layout;
 mod render;
+pub mod settings;
+pub mod text_span;
+pub mod toast;
 pub mod unpack;
 pub mod widget;
+pub mod world_marker;
 
-pub use glyph_brush::{OwnedSection, OwnedText};
+pub use glyph_brush::{FontId, OwnedSection, OwnedText};
 use layout::GuiLayout;
 use std::{
     collections::HashMap,
     rc::{Rc, Weak},
+    time::{Duration, Instant},
 };
 
 use crate::{
+    animation::{Easing, LayoutAnimation, LayoutTarget},
+    binding::TextBinding,
+    context_menu::{ContextMenuEvent, OpenContextMenu},
     render::GuiRenderer,
+    text_span::{SpanId, TextSpanEvent},
+    toast::Toast,
     unpack::Unpacker,
-    widget::{Widget, WidgetBehavior, WidgetInput, WidgetStyles},
+    widget::{
+        StyleValues, Widget, WidgetBehavior, WidgetInput, WidgetNode, WidgetRestyle, WidgetStyles,
+    },
+    world_marker::WorldMarker,
 };
 use gristmill_core::{
-    asset::AssetResult, geom2d::*, input::InputActions, math::IVec2, new_storage_types,
-    slotmap::SecondaryMap, Color,
+    asset::AssetResult,
+    geom2d::*,
+    input::InputActions,
+    math::{IVec2, Vec2},
+    new_storage_types,
+    slotmap::SecondaryMap,
+    Color,
 };
-use gristmill_render::{texture_rect::TextureRectRenderer, RenderContext, Renderable, Texture};
+use gristmill_render::{
+    texture_rect::{DropShadow, GradientDirection, TextureRectRenderer},
+    RenderContext, Renderable, Texture,
+};
+use winit::window::CursorIcon;
 
 pub struct NodeFlags {
     pub visible: bool,
     pub pointer_opaque: bool,
+    /// Clip the drawing and pointer detection of all descendants to this node's rect. Nested
+    /// clipping nodes clip to the intersection of their rects.
+    pub clip_children: bool,
+    /// The node can receive focus from `Gui::navigate`.
+    pub focusable: bool,
+    /// The node is shown as unavailable, and screen readers announce it that way. Buttons set
+    /// this while they aren't enabled with `Button::interact`.
+    pub disabled: bool,
+    /// Bring the node in front of its siblings when it or a descendant is pressed.
+    pub raise_on_press: bool,
+    /// Hit test the runs of the node's text. The node takes the pointer like a pointer opaque
+    /// one; see the `text_span` module.
+    pub text_spans: bool,
 }
 
 impl Default for NodeFlags {
@@ -31,11 +74,35 @@ impl Default for NodeFlags {
         NodeFlags {
             visible: true,
             pointer_opaque: false,
+            clip_children: false,
+            focusable: false,
+            disabled: false,
+            raise_on_press: false,
+            text_spans: false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    fn vector(self) -> IVec2 {
+        match self {
+            Direction::Up => IVec2::NEG_Y,
+            Direction::Down => IVec2::Y,
+            Direction::Left => IVec2::NEG_X,
+            Direction::Right => IVec2::X,
         }
     }
 }
 
-#[derive(Default)]
+#[derive(Copy, Clone, Default)]
 pub enum Anchor {
     #[default]
     Begin,
@@ -43,6 +110,14 @@ pub enum Anchor {
     End,
 }
 
+impl<'de> serde::Deserialize<'de> for Anchor {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|_| serde::de::Error::custom(format!("unknown anchor {s:?}")))
+    }
+}
+
 impl std::str::FromStr for Anchor {
     type Err = ();
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -55,24 +130,123 @@ impl std::str::FromStr for Anchor {
     }
 }
 
-#[derive(Default)]
+/// A corner of a node, such as the one a badge is attached to.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl std::str::FromStr for Corner {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top_left" | "TopLeft" => Ok(Corner::TopLeft),
+            "top_right" | "TopRight" => Ok(Corner::TopRight),
+            "bottom_left" | "BottomLeft" => Ok(Corner::BottomLeft),
+            "bottom_right" | "BottomRight" => Ok(Corner::BottomRight),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Corner {
+    fn point(self, rect: IRect) -> IVec2 {
+        let (left, top) = (rect.x(), rect.y());
+        let (right, bottom) = (left + rect.width(), top + rect.height());
+        match self {
+            Corner::TopLeft => IVec2::new(left, top),
+            Corner::TopRight => IVec2::new(right, top),
+            Corner::BottomLeft => IVec2::new(left, bottom),
+            Corner::BottomRight => IVec2::new(right, bottom),
+        }
+    }
+}
+
+/// How hbox and vbox layouts spread their children along the main axis.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Distribution {
+    /// Children are packed at the start, separated by `child_spacing`.
+    #[default]
+    Packed,
+    /// The first and last children touch the container edges, with equal gaps between.
+    SpaceBetween,
+    /// Each child has equal space on both sides, so edge gaps are half the inner gaps.
+    SpaceAround,
+    /// Gaps between children and at the edges are all equal.
+    SpaceEvenly,
+}
+
+impl Distribution {
+    /// Returns the offset of the first child and the gap between children.
+    pub fn gaps(self, available: i32, total_size: i32, count: usize, spacing: i32) -> (i32, i32) {
+        let free = (available - total_size).max(0);
+        let count = count as i32;
+        match self {
+            Distribution::Packed => (0, spacing),
+            Distribution::SpaceBetween if count > 1 => (0, free / (count - 1)),
+            Distribution::SpaceAround if count > 0 => {
+                let gap = free / count;
+                (gap / 2, gap)
+            }
+            Distribution::SpaceEvenly => {
+                let gap = free / (count + 1);
+                (gap, gap)
+            }
+            Distribution::SpaceBetween | Distribution::SpaceAround => (0, 0),
+        }
+    }
+}
+
+impl std::str::FromStr for Distribution {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "packed" | "Packed" => Ok(Distribution::Packed),
+            "space_between" | "space-between" | "SpaceBetween" => Ok(Distribution::SpaceBetween),
+            "space_around" | "space-around" | "SpaceAround" => Ok(Distribution::SpaceAround),
+            "space_evenly" | "space-evenly" | "SpaceEvenly" => Ok(Distribution::SpaceEvenly),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
 pub struct NodeLayout {
     pub size: IVec2,
     pub margin: EdgeRect,
     pub anchors: (Anchor, Anchor),
     pub child_layout: String,
     pub child_spacing: i32,
+    pub child_distribution: Distribution,
+    /// Axes with a size of 0 are sized to fit the children, as measured by the child layout,
+    /// instead of filling the container.
+    pub fit_content: bool,
+    /// In an hbox or vbox, how much of the container's leftover space this node takes, relative
+    /// to its siblings' weights. 0 keeps the node at its size.
+    pub grow: f32,
+    content_size: IVec2,
 }
 
 impl NodeLayout {
+    /// The node's size, with axes sized by `fit_content` filled in from the last layout.
+    pub fn resolved_size(&self) -> IVec2 {
+        if self.fit_content {
+            IVec2::select(self.size.cmpeq(IVec2::ZERO), self.content_size, self.size)
+        } else {
+            self.size
+        }
+    }
     pub fn width(&self) -> i32 {
-        self.size.x + self.margin.left + self.margin.right
+        self.resolved_size().x + self.margin.left + self.margin.right
     }
     pub fn height(&self) -> i32 {
-        self.size.y + self.margin.top + self.margin.bottom
+        self.resolved_size().y + self.margin.top + self.margin.bottom
     }
     pub fn horizontal(&self, container_x: i32, container_width: i32) -> (i32, i32) {
-        if self.size.x == 0 {
+        if self.resolved_size().x == 0 {
             (container_x, container_width)
         } else {
             let width = self.width();
@@ -86,7 +260,7 @@ impl NodeLayout {
         }
     }
     pub fn vertical(&self, container_y: i32, container_height: i32) -> (i32, i32) {
-        if self.size.y == 0 {
+        if self.resolved_size().y == 0 {
             (container_y, container_height)
         } else {
             let height = self.height();
@@ -101,10 +275,103 @@ impl NodeLayout {
     }
 }
 
+/// How an image's texture is fitted into its node rect.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum ScaleMode {
+    /// Stretch the texture to fill the rect.
+    #[default]
+    Stretch,
+    /// Scale the texture to fit inside the rect, keeping its aspect ratio.
+    Fit,
+    /// Scale the texture to cover the rect, keeping its aspect ratio and cropping the overflow.
+    Fill,
+    /// Draw the texture at its native size in the middle of the rect, cropped if it doesn't fit.
+    Center,
+    /// Repeat the texture at its native size, starting from the top-left corner.
+    Tile,
+}
+
+impl ScaleMode {
+    /// Returns the rect to draw and the UV rect to sample for a texture of `texture_size`.
+    pub fn draw_rect(self, rect: Rect, texture_size: Vec2) -> (Rect, Rect) {
+        if texture_size.x <= 0.0 || texture_size.y <= 0.0 {
+            return (rect, Rect::ONE);
+        }
+        let centered = |size: Vec2| Rect {
+            position: rect.position + (rect.size - size) / 2.0,
+            size,
+        };
+        let centered_uv = |size: Vec2| Rect {
+            position: (Vec2::ONE - size) / 2.0,
+            size,
+        };
+        match self {
+            ScaleMode::Stretch => (rect, Rect::ONE),
+            ScaleMode::Fit => {
+                let scale = (rect.size / texture_size).min_element();
+                (centered(texture_size * scale), Rect::ONE)
+            }
+            ScaleMode::Fill => {
+                let scale = (rect.size / texture_size).max_element();
+                (rect, centered_uv(rect.size / scale / texture_size))
+            }
+            ScaleMode::Center => {
+                let size = texture_size.min(rect.size);
+                (centered(size), centered_uv(size / texture_size))
+            }
+            ScaleMode::Tile => (rect, Rect::from_size(rect.size / texture_size)),
+        }
+    }
+}
+
+impl std::str::FromStr for ScaleMode {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stretch" | "Stretch" => Ok(ScaleMode::Stretch),
+            "fit" | "Fit" => Ok(ScaleMode::Fit),
+            "fill" | "Fill" => Ok(ScaleMode::Fill),
+            "center" | "Center" => Ok(ScaleMode::Center),
+            "tile" | "Tile" => Ok(ScaleMode::Tile),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub enum NodeDraw {
     None,
     Rect(Option<Texture>, Color),
+    /// Like `Rect`, with corners rounded to the given radius in pixels.
+    RoundedRect(Option<Texture>, Color, f32),
+    /// A textured rect fitted to the node according to `scale_mode`.
+    Image {
+        texture: Option<Texture>,
+        color: Color,
+        corner_radius: f32,
+        scale_mode: ScaleMode,
+        /// Desaturate the texture before `color` tints it, such as for disabled icons.
+        grayscale: bool,
+        /// The texture has premultiplied alpha. See `TextureRect::premultiplied`.
+        premultiplied: bool,
+        /// The part of the texture to draw, in UV coordinates, such as one frame of a sprite
+        /// sheet. `None` draws the whole texture. `ScaleMode::Tile` can't repeat a frame.
+        frame: Option<Rect>,
+    },
+    /// A texture split into a 3x3 grid by `border` (in texture pixels). Corners keep their size,
+    /// while edges and the center stretch, or repeat at their native size if `tile` is set.
+    NineSlice {
+        texture: Option<Texture>,
+        color: Color,
+        border: EdgeRect,
+        tile: bool,
+    },
+    Gradient {
+        from: Color,
+        to: Color,
+        direction: GradientDirection,
+        corner_radius: f32,
+    },
     Text(OwnedSection),
 }
 
@@ -116,17 +383,42 @@ impl Default for NodeDraw {
 
 new_storage_types!(pub type GuiNodeStorage = <GuiNodeId, GuiNode>);
 
-#[derive(Default)]
 pub struct GuiNode {
     pub flags: NodeFlags,
     pub layout: NodeLayout,
     pub draw: NodeDraw,
     pub offset: IRect,
+    pub cursor: Option<CursorIcon>,
+    /// Drawn beneath the node, in GUI units. Text nodes don't cast shadows.
+    pub shadow: Option<DropShadow>,
+    /// Multiplies the alpha of this node and all of its descendants.
+    pub opacity: f32,
     visible: bool,
     rect: IRect,
+    clip: Option<IRect>,
+    effective_opacity: f32,
     z: u16,
 }
 
+impl Default for GuiNode {
+    fn default() -> Self {
+        GuiNode {
+            flags: NodeFlags::default(),
+            layout: NodeLayout::default(),
+            draw: NodeDraw::default(),
+            offset: IRect::default(),
+            cursor: None,
+            shadow: None,
+            opacity: 1.0,
+            visible: false,
+            rect: IRect::default(),
+            clip: None,
+            effective_opacity: 1.0,
+            z: 0,
+        }
+    }
+}
+
 impl GuiNode {
     pub fn new(layout: NodeLayout, draw: NodeDraw) -> GuiNode {
         GuiNode {
@@ -142,11 +434,35 @@ impl GuiNode {
         }
     }
 
-    fn draw_rect(&self) -> (IRect, u16) {
-        (self.rect.add_components(self.offset), self.z)
+    /// The area the node's children are clipped to: its own clip, narrowed to its rect if it
+    /// has `clip_children`.
+    fn child_clip(&self) -> Option<IRect> {
+        if self.flags.clip_children {
+            Some(
+                self.clip
+                    .map_or(self.rect, |clip| clip.intersection(self.rect)),
+            )
+        } else {
+            self.clip
+        }
+    }
+    fn draw_rect(&self, scale: f32) -> (IRect, u16) {
+        (self.rect.add_components(self.offset).scale(scale), self.z)
     }
 }
 
+fn combined_scale(ui_scale: f32, viewport_size: IVec2, reference_resolution: Option<IVec2>) -> f32 {
+    let auto_scale = reference_resolution.map_or(1.0, |resolution| {
+        let ratio = viewport_size.as_vec2() / resolution.max(IVec2::ONE).as_vec2();
+        ratio.min_element()
+    });
+    ui_scale * auto_scale
+}
+/// The root node's rect in GUI units: the viewport shrunk by `scale`, inside the safe area.
+fn root_rect(viewport_size: IVec2, scale: f32, safe_area: EdgeRect) -> IRect {
+    IRect::from_size((viewport_size.as_vec2() / scale).as_ivec2()).inset(safe_area)
+}
+
 pub trait GuiNodeExt {
     fn add_child(&self, gui: &mut Gui, child: GuiNode) -> GuiNodeId;
 }
@@ -163,6 +479,19 @@ impl GuiNodeExt for GuiNodeId {
     }
 }
 
+struct Badge {
+    node: GuiNodeId,
+    corner: Corner,
+    offset: IVec2,
+}
+
+struct StyledWidget {
+    node: GuiNodeId,
+    classes: Vec<String>,
+    extra: StyleValues,
+    restyle: Rc<dyn WidgetRestyle>,
+}
+
 pub struct Gui {
     renderer: GuiRenderer,
     styles: WidgetStyles,
@@ -171,9 +500,38 @@ pub struct Gui {
 
     nodes: GuiNodeStorage,
     node_children: SecondaryMap<GuiNodeId, Vec<GuiNodeId>>,
+    node_badges: SecondaryMap<GuiNodeId, Vec<Badge>>,
     root: GuiNodeId,
     behaviors: Vec<Weak<dyn WidgetBehavior>>,
+    text_bindings: Vec<TextBinding>,
+    styled_widgets: Vec<StyledWidget>,
     unpacker: Unpacker,
+    cursor: CursorIcon,
+    focus: Option<GuiNodeId>,
+    navigation_wrap: bool,
+    viewport_size: IVec2,
+    ui_scale: f32,
+    reference_resolution: Option<IVec2>,
+    safe_area: EdgeRect,
+    safe_area_fraction: f32,
+    animations: Vec<LayoutAnimation>,
+    pointer_opacity_threshold: f32,
+    context_menus: SecondaryMap<GuiNodeId, Vec<String>>,
+    open_context_menu: Option<OpenContextMenu>,
+    context_menu_event: Option<ContextMenuEvent>,
+    toast_container: Option<GuiNodeId>,
+    toasts: Vec<Toast>,
+    pointer_span: Option<(GuiNodeId, SpanId)>,
+    text_span_event: Option<TextSpanEvent>,
+    world_camera: Option<CameraTransform>,
+    world_markers: Vec<WorldMarker>,
+    breakpoints: SecondaryMap<GuiNodeId, breakpoint::NodeBreakpoints>,
+    #[cfg(feature = "accessibility")]
+    access_roles: SecondaryMap<GuiNodeId, accesskit::Role>,
+    #[cfg(feature = "accessibility")]
+    access_labels: SecondaryMap<GuiNodeId, String>,
+    #[cfg(feature = "accessibility")]
+    access_checked: SecondaryMap<GuiNodeId, bool>,
 }
 
 impl Gui {
@@ -181,25 +539,58 @@ impl Gui {
         let mut layouts: HashMap<String, Box<dyn GuiLayout>> = HashMap::new();
         layouts.insert("hbox".to_owned(), Box::<layout::HBox>::default());
         layouts.insert("vbox".to_owned(), Box::<layout::VBox>::default());
+        layouts.insert("radial".to_owned(), Box::<layout::Radial>::default());
         layouts
     }
 
-    pub fn new(context: &mut RenderContext, styles: WidgetStyles) -> Self {
+    pub fn new(context: &mut RenderContext, mut styles: WidgetStyles) -> Self {
+        let mut renderer = GuiRenderer::new(context);
+        Self::load_style_fonts(&mut renderer, &mut styles);
         let mut nodes = GuiNodeStorage::default();
+        let viewport_size = context.viewport().as_irect().size;
         let root = nodes.insert(GuiNode {
-            rect: context.viewport().as_irect(),
+            rect: IRect::from_size(viewport_size),
             ..Default::default()
         });
         Gui {
-            renderer: GuiRenderer::new(context),
+            renderer,
             styles,
             layouts: Self::default_layouts(),
             default_layout: Box::<layout::Anchor>::default(),
             nodes,
             node_children: SecondaryMap::new(),
+            node_badges: SecondaryMap::new(),
             root,
             behaviors: Vec::new(),
+            text_bindings: Vec::new(),
+            styled_widgets: Vec::new(),
             unpacker: Unpacker::with_standard_widgets(),
+            cursor: CursorIcon::Default,
+            focus: None,
+            navigation_wrap: true,
+            viewport_size,
+            ui_scale: 1.0,
+            reference_resolution: None,
+            safe_area: EdgeRect::ZERO,
+            safe_area_fraction: 0.0,
+            animations: Vec::new(),
+            pointer_opacity_threshold: 0.05,
+            context_menus: SecondaryMap::new(),
+            open_context_menu: None,
+            context_menu_event: None,
+            toast_container: None,
+            toasts: Vec::new(),
+            pointer_span: None,
+            text_span_event: None,
+            world_camera: None,
+            world_markers: Vec::new(),
+            breakpoints: SecondaryMap::new(),
+            #[cfg(feature = "accessibility")]
+            access_roles: SecondaryMap::new(),
+            #[cfg(feature = "accessibility")]
+            access_labels: SecondaryMap::new(),
+            #[cfg(feature = "accessibility")]
+            access_checked: SecondaryMap::new(),
         }
     }
     pub fn load_styles(context: &mut RenderContext) -> AssetResult<Self> {
@@ -210,10 +601,128 @@ impl Gui {
     pub fn rect_renderer(&mut self) -> &mut TextureRectRenderer {
         self.renderer.rect_renderer()
     }
+    /// Outline every laid out node, including ones that draw nothing or are fully transparent,
+    /// and label it with its z. Outline colors come from `TextureRect::wireframe_color`, so nodes
+    /// on different layers stand apart. For debugging layout; off by default.
+    pub fn set_wireframe(&mut self, enabled: bool) {
+        self.renderer.set_wireframe(enabled);
+    }
+    pub fn wireframe(&self) -> bool {
+        self.renderer.wireframe()
+    }
+    /// The size of `text` laid out on a single line, in unscaled GUI units.
+    pub fn measure_text(&mut self, text: &[OwnedText]) -> Vec2 {
+        self.renderer.measure_text(text)
+    }
+    fn load_style_fonts(renderer: &mut GuiRenderer, styles: &mut WidgetStyles) {
+        for (name, data) in styles.fonts() {
+            renderer.load_font(name, data.to_vec());
+        }
+        styles.resolve_fonts(|name| renderer.font_id(name));
+    }
+    /// Load a font for styles to refer to by `name`. Loading a name twice keeps the first font.
+    /// Returns `None` if `data` isn't a valid font.
+    ///
+    /// Styles that already used the name are resolved now, but widgets created before this need
+    /// `set_styles` to pick it up.
+    pub fn load_font(&mut self, name: &str, data: Vec<u8>) -> Option<FontId> {
+        let id = self.renderer.load_font(name, data)?;
+        let renderer = &self.renderer;
+        self.styles.resolve_fonts(|name| renderer.font_id(name));
+        Some(id)
+    }
+    /// The font loaded under `name`. "default" is the built-in font.
+    pub fn font_id(&self, name: &str) -> Option<FontId> {
+        self.renderer.font_id(name)
+    }
     pub fn styles(&self) -> &WidgetStyles {
         &self.styles
     }
+    /// Replace the styles, and re-apply them to every existing widget that supports restyling.
+    pub fn set_styles(&mut self, mut styles: WidgetStyles) {
+        Self::load_style_fonts(&mut self.renderer, &mut styles);
+        self.styles = styles;
+        let nodes = &mut self.nodes;
+        let styles = &self.styles;
+        self.styled_widgets.retain(|widget| {
+            if !nodes.contains_key(widget.node) {
+                return false;
+            }
+            let mut style = styles.query(widget.classes.iter().map(String::as_str));
+            style.extend(widget.extra.clone());
+            widget.restyle.restyle(nodes, style);
+            true
+        });
+    }
 
+    /// Multiply the size of everything in the GUI, including text. Layout happens in unscaled
+    /// units and each edge is rounded to a whole pixel afterwards, so edges stay crisp at any scale.
+    ///
+    /// The viewport is measured in physical pixels, so on high-DPI displays pass the window's
+    /// scale factor multiplied by the user's preferred scale.
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        self.ui_scale = scale.max(0.1);
+    }
+    pub fn ui_scale(&self) -> f32 {
+        self.ui_scale
+    }
+    /// Additionally scale the GUI by how much larger or smaller the viewport is than this
+    /// resolution, keeping the aspect ratio.
+    pub fn set_reference_resolution(&mut self, resolution: Option<IVec2>) {
+        self.reference_resolution = resolution;
+    }
+    /// Keep the root node, and so all anchored UI, this far from the edges of the viewport, in
+    /// GUI units. For displays whose edges can't be seen or used, like TVs with overscan or
+    /// notched screens. Winit doesn't report platform insets, so these must come from the game,
+    /// such as from an options menu where the player adjusts them.
+    pub fn set_safe_area(&mut self, insets: EdgeRect) {
+        self.safe_area = insets;
+    }
+    /// Additionally keep the root node inside this fraction of the viewport on each side, such
+    /// as 0.05 for the common title-safe area on TVs. The larger of this and `set_safe_area` is
+    /// used for each edge.
+    pub fn set_safe_area_fraction(&mut self, fraction: f32) {
+        self.safe_area_fraction = fraction.clamp(0.0, 0.5);
+    }
+    /// The insets the root node currently has, in GUI units.
+    pub fn safe_area(&self) -> EdgeRect {
+        let size = self.viewport_size.as_vec2() / self.scale();
+        let inset = (size * self.safe_area_fraction).ceil().as_ivec2();
+        EdgeRect::new(
+            self.safe_area.top.max(inset.y),
+            self.safe_area.right.max(inset.x),
+            self.safe_area.bottom.max(inset.y),
+            self.safe_area.left.max(inset.x),
+        )
+    }
+    /// The combined scale factor from `set_ui_scale` and `set_reference_resolution`.
+    pub fn scale(&self) -> f32 {
+        combined_scale(self.ui_scale, self.viewport_size, self.reference_resolution)
+    }
+
+    /// Measure pass: compute the content size of every `fit_content` node, children first.
+    /// Returns the size of `node` including its margin.
+    fn measure(&mut self, node: GuiNodeId) -> IVec2 {
+        let child_count = self.node_children.get(node).map_or(0, Vec::len);
+        let mut child_sizes = Vec::with_capacity(child_count);
+        for index in 0..child_count {
+            let child = self.node_children[node][index];
+            child_sizes.push(self.measure(child));
+        }
+        let node_data = if let Some(data) = self.nodes.get_mut(node) {
+            data
+        } else {
+            return IVec2::ZERO;
+        };
+        if node_data.layout.fit_content {
+            let child_layout = self
+                .layouts
+                .get(&node_data.layout.child_layout)
+                .unwrap_or(&self.default_layout);
+            node_data.layout.content_size = child_layout.measure(&node_data.layout, &child_sizes);
+        }
+        IVec2::new(node_data.layout.width(), node_data.layout.height())
+    }
     fn layout(&mut self, node: GuiNodeId) {
         let node_data = if let Some(data) = self.nodes.get(node) {
             data
@@ -224,31 +733,74 @@ impl Gui {
             return;
         }
         let node_rect = node_data.rect;
+        let node_clip = node_data.clip;
+        let child_clip = node_data.child_clip();
+        let opacity = node_data.effective_opacity;
         let mut z = node_data.z;
-        let children = if let Some(children) = self.node_children.get_mut(node) {
-            children
-        } else {
-            return;
-        };
-        let child_layout = self
-            .layouts
-            .get_mut(&node_data.layout.child_layout)
-            .unwrap_or(&mut self.default_layout);
-        child_layout.begin_layout(node_rect, node_data.layout.child_spacing);
-        children.retain_mut(|child| {
-            let child_data = if let Some(data) = self.nodes.get_mut(*child) {
-                data
-            } else {
-                return false;
-            };
-            child_data.visible = child_data.flags.visible;
-            let rect = child_layout.layout_child(&child_data.layout);
-            child_data.rect = rect.inset(child_data.layout.margin);
-            z += 1;
-            child_data.z = z;
-            true
-        });
-        for child in children.clone() {
+        let mut layout_next = Vec::new();
+        if let Some(children) = self.node_children.get_mut(node) {
+            let child_sizes: Vec<IVec2> = children
+                .iter()
+                .filter_map(|child| self.nodes.get(*child))
+                .map(|child| IVec2::new(child.layout.width(), child.layout.height()))
+                .collect();
+            let child_grow: Vec<f32> = children
+                .iter()
+                .filter_map(|child| self.nodes.get(*child))
+                .map(|child| child.layout.grow)
+                .collect();
+            let child_layout = self
+                .layouts
+                .get_mut(&node_data.layout.child_layout)
+                .unwrap_or(&mut self.default_layout);
+            child_layout.begin_layout_grow(node_rect, &node_data.layout, &child_sizes, &child_grow);
+            children.retain_mut(|child| {
+                let child_data = if let Some(data) = self.nodes.get_mut(*child) {
+                    data
+                } else {
+                    return false;
+                };
+                child_data.visible = child_data.flags.visible;
+                let rect = child_layout.layout_child(&child_data.layout);
+                child_data.rect = rect.inset(child_data.layout.margin);
+                child_data.clip = child_clip;
+                child_data.effective_opacity = opacity * child_data.opacity;
+                // Leave a z between the node and its previous sibling for its shadow.
+                if child_data.shadow.is_some() {
+                    z += 1;
+                }
+                z += 1;
+                child_data.z = z;
+                true
+            });
+            layout_next.extend_from_slice(children);
+        }
+        // Badges are positioned after the children so they draw on top. They can hang outside of
+        // the parent, so they aren't clipped by it.
+        if let Some(badges) = self.node_badges.get_mut(node) {
+            badges.retain(|badge| {
+                let badge_data = if let Some(data) = self.nodes.get_mut(badge.node) {
+                    data
+                } else {
+                    return false;
+                };
+                badge_data.visible = badge_data.flags.visible;
+                let layout = &badge_data.layout;
+                let size = IVec2::new(layout.width(), layout.height());
+                let position = badge.corner.point(node_rect) - size / 2 + badge.offset;
+                badge_data.rect = IRect { position, size }.inset(layout.margin);
+                badge_data.clip = node_clip;
+                badge_data.effective_opacity = opacity * badge_data.opacity;
+                if badge_data.shadow.is_some() {
+                    z += 1;
+                }
+                z += 1;
+                badge_data.z = z;
+                layout_next.push(badge.node);
+                true
+            });
+        }
+        for child in layout_next {
             self.layout(child);
         }
     }
@@ -257,6 +809,18 @@ impl Gui {
         if !node_data.visible {
             return None;
         }
+        if let Some(clip) = node_data.clip {
+            if !clip.contains(pointer) {
+                return None;
+            }
+        }
+        if let Some(badges) = self.node_badges.get(node) {
+            for badge in badges.iter().rev() {
+                if let Some(pointer_over) = self.find_pointer_over(badge.node, pointer) {
+                    return Some(pointer_over);
+                }
+            }
+        }
         if let Some(children) = self.node_children.get(node) {
             for child in children.iter().rev() {
                 if let Some(pointer_over) = self.find_pointer_over(*child, pointer) {
@@ -264,7 +828,10 @@ impl Gui {
                 }
             }
         }
-        if node_data.flags.pointer_opaque && node_data.rect.contains(pointer) {
+        if (node_data.flags.pointer_opaque || node_data.flags.text_spans)
+            && node_data.effective_opacity >= self.pointer_opacity_threshold
+            && node_data.rect.contains(pointer)
+        {
             Some(node)
         } else {
             None
@@ -272,26 +839,79 @@ impl Gui {
     }
 
     pub fn update(&mut self, input: &InputActions) {
+        // Pick layouts for the screen size, before animations move them.
+        self.update_breakpoints();
+        // Advance layout animations.
+        let now = Instant::now();
+        let nodes = &mut self.nodes;
+        self.animations.retain(|animation| {
+            if let Some(node) = nodes.get_mut(animation.node) {
+                let (value, finished) = animation.sample(now);
+                node.layout.size = value.size;
+                node.layout.margin = value.margin;
+                !finished
+            } else {
+                false
+            }
+        });
+
+        self.update_toasts(now);
+        // Update bound text before it's measured.
+        self.update_bindings();
+        self.update_world_markers();
+
         // Layout all nodes.
         for node in self.nodes.values_mut() {
             node.visible = false;
         }
-        self.nodes
+        let root_node = self
+            .nodes
             .get_mut(self.root)
-            .expect("root node has been removed")
-            .visible = true;
+            .expect("root node has been removed");
+        root_node.visible = true;
+        root_node.effective_opacity = root_node.opacity;
+        self.measure(self.root);
         self.layout(self.root);
 
         // Find the node the pointer is over.
         let pointer_state = input.get("primary");
-        let pointer_over = pointer_state
-            .pointer()
-            .and_then(|p| self.find_pointer_over(self.root, p.as_ivec2()));
+        let scale = self.scale();
+        let pointer = pointer_state.pointer().map(|p| (p / scale).as_ivec2());
+        let pointer_over = pointer.and_then(|p| self.find_pointer_over(self.root, p));
+        self.cursor = pointer_over
+            .and_then(|node| self.nodes.get(node))
+            .and_then(|node| node.cursor)
+            .unwrap_or_default();
+        self.update_context_menu(input, pointer, pointer_over);
+        self.update_text_spans(pointer, pointer_over, pointer_state.just_pressed());
+        if let Some(pointer_over) = pointer_over.filter(|_| pointer_state.just_pressed()) {
+            let mut path = Vec::new();
+            self.find_path(self.root, pointer_over, &mut path);
+            for node in path {
+                if self
+                    .nodes
+                    .get(node)
+                    .map_or(false, |n| n.flags.raise_on_press)
+                {
+                    self.bring_to_front(node);
+                }
+            }
+        }
+
+        // Drop focus from nodes that can't be focused anymore.
+        if let Some(focus) = self.focus {
+            if !self.is_focusable(focus) {
+                self.focus = None;
+            }
+        }
 
         // Update widget behaviors.
         let input = WidgetInput {
             state: pointer_state,
+            pointer,
             pointer_over,
+            focused: self.focus,
+            confirm: input.try_get("confirm").copied().unwrap_or_default(),
         };
         self.behaviors.retain_mut(|behavior| {
             if let Some(behavior) = behavior.upgrade() {
@@ -303,6 +923,192 @@ impl Gui {
         });
     }
 
+    fn is_focusable(&self, node: GuiNodeId) -> bool {
+        self.nodes
+            .get(node)
+            .map(|node| node.visible && node.flags.focusable)
+            .unwrap_or(false)
+    }
+    pub fn focused(&self) -> Option<GuiNodeId> {
+        self.focus
+    }
+    pub fn set_focus(&mut self, node: Option<GuiNodeId>) {
+        self.focus = node;
+    }
+    /// Pointer-opaque nodes whose opacity (including their ancestors') is below this value let the
+    /// pointer pass through, so fading out nodes stop blocking clicks. Defaults to 0.05.
+    pub fn set_pointer_opacity_threshold(&mut self, threshold: f32) {
+        self.pointer_opacity_threshold = threshold;
+    }
+    /// Whether `navigate` wraps around to the far side when there is no node in that direction.
+    pub fn set_navigation_wrap(&mut self, wrap: bool) {
+        self.navigation_wrap = wrap;
+    }
+    /// Move focus to the nearest focusable node in the given direction, measured between the
+    /// centers of the laid out rects. If nothing is focused, the top-left focusable node is focused.
+    pub fn navigate(&mut self, direction: Direction) -> Option<GuiNodeId> {
+        let candidates: Vec<(GuiNodeId, IVec2)> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.visible && node.flags.focusable)
+            .map(|(id, node)| (id, node.rect.center()))
+            .collect();
+        let from = self
+            .focus
+            .and_then(|focus| candidates.iter().find(|(id, _)| *id == focus));
+        let next = if let Some(&(from_id, from)) = from {
+            let axis = direction.vector();
+            // Prefer nodes that are close along the direction and aligned with the focused node.
+            let find_best = |forward: bool| {
+                candidates
+                    .iter()
+                    .filter(|(id, _)| *id != from_id)
+                    .filter_map(|(id, center)| {
+                        let offset = *center - from;
+                        let distance = offset.dot(axis);
+                        let misalignment = offset.perp_dot(axis).abs();
+                        if distance != 0 && (distance > 0) == forward {
+                            Some((distance + misalignment * 2, *id))
+                        } else {
+                            None
+                        }
+                    })
+                    .min_by_key(|(score, _)| *score)
+                    .map(|(_, id)| id)
+            };
+            find_best(true).or_else(|| {
+                if self.navigation_wrap {
+                    find_best(false)
+                } else {
+                    None
+                }
+            })
+        } else {
+            candidates
+                .iter()
+                .min_by_key(|(_, center)| (center.y, center.x))
+                .map(|(id, _)| *id)
+        };
+        if next.is_some() {
+            self.focus = next;
+        }
+        self.focus
+    }
+
+    /// Smoothly change a node's layout size and margin to `target`, starting from their current
+    /// values. Replaces any animation already running on the node.
+    pub fn animate_to(
+        &mut self,
+        node: GuiNodeId,
+        target: LayoutTarget,
+        duration: Duration,
+        easing: Easing,
+    ) {
+        let from = if let Some(node_data) = self.nodes.get(node) {
+            LayoutTarget {
+                size: node_data.layout.size,
+                margin: node_data.layout.margin,
+            }
+        } else {
+            return;
+        };
+        self.animations.retain(|animation| animation.node != node);
+        self.animations
+            .push(LayoutAnimation::new(node, from, target, duration, easing));
+    }
+    pub fn is_animating(&self, node: GuiNodeId) -> bool {
+        self.animations
+            .iter()
+            .any(|animation| animation.node == node)
+    }
+
+    /// Add a node that is centered on a corner of `parent` (plus `offset`) instead of taking part
+    /// in the parent's child layout, such as a notification dot on a button. The badge is sized
+    /// by its own layout, doesn't affect the parent's layout, and draws above the parent's
+    /// children. It is removed along with the parent.
+    pub fn attach_badge(
+        &mut self,
+        parent: GuiNodeId,
+        node: GuiNode,
+        corner: Corner,
+        offset: IVec2,
+    ) -> GuiNodeId {
+        let badges = self
+            .node_badges
+            .entry(parent)
+            .expect("node has been removed")
+            .or_default();
+        let node = self.nodes.insert(node);
+        badges.push(Badge {
+            node,
+            corner,
+            offset,
+        });
+        node
+    }
+
+    /// Fill `path` with the nodes from `from` down to `target`, inclusive. Returns false if
+    /// `target` isn't a descendant of `from`.
+    fn find_path(&self, from: GuiNodeId, target: GuiNodeId, path: &mut Vec<GuiNodeId>) -> bool {
+        path.push(from);
+        if from == target {
+            return true;
+        }
+        let children = self.node_children.get(from).into_iter().flatten();
+        let badges = self
+            .node_badges
+            .get(from)
+            .into_iter()
+            .flatten()
+            .map(|badge| &badge.node);
+        for child in children.chain(badges) {
+            if self.find_path(*child, target, path) {
+                return true;
+            }
+        }
+        path.pop();
+        false
+    }
+    /// Move a node to the end of its parent's children, so it draws above its siblings.
+    pub fn bring_to_front(&mut self, node: GuiNodeId) {
+        for children in self.node_children.values_mut() {
+            if let Some(index) = children.iter().position(|child| *child == node) {
+                let child = children.remove(index);
+                children.push(child);
+                return;
+            }
+        }
+    }
+
+    /// Remove a node and all of its descendants.
+    pub fn remove_node(&mut self, node: GuiNodeId) {
+        if node == self.root {
+            log::error!("The root node can't be removed.");
+            return;
+        }
+        if let Some(children) = self.node_children.remove(node) {
+            for child in children {
+                self.remove_node(child);
+            }
+        }
+        if let Some(badges) = self.node_badges.remove(node) {
+            for badge in badges {
+                self.remove_node(badge.node);
+            }
+        }
+        self.nodes.remove(node);
+        self.context_menus.remove(node);
+        #[cfg(feature = "accessibility")]
+        {
+            self.access_roles.remove(node);
+            self.access_labels.remove(node);
+            self.access_checked.remove(node);
+        }
+        if self.focus == Some(node) {
+            self.focus = None;
+        }
+    }
+
     pub fn nodes(&self) -> &GuiNodeStorage {
         &self.nodes
     }
@@ -310,6 +1116,10 @@ impl Gui {
         &mut self.nodes
     }
 
+    pub fn desired_cursor(&self) -> CursorIcon {
+        self.cursor
+    }
+
     pub fn root(&self) -> GuiNodeId {
         self.root
     }
@@ -326,20 +1136,168 @@ impl Gui {
         behavior
     }
 
-    pub fn create_widget<W: Widget>(&mut self, parent: GuiNodeId) -> W {
+    pub fn create_widget<W: Widget + WidgetNode>(&mut self, parent: GuiNodeId) -> W {
         let style = self.styles.query(std::iter::once(W::class_name()));
-        W::new(self, parent, style)
+        let widget = W::new(self, parent, style);
+        self.register_styled(
+            &widget,
+            vec![W::class_name().to_owned()],
+            StyleValues::new(),
+        );
+        widget
+    }
+    pub(crate) fn register_styled<W: Widget + WidgetNode>(
+        &mut self,
+        widget: &W,
+        classes: Vec<String>,
+        extra: StyleValues,
+    ) {
+        #[cfg(feature = "accessibility")]
+        if let Some(role) = W::access_role() {
+            self.access_roles.insert(widget.node(), role);
+        }
+        if let Some(restyle) = widget.restyle_handle() {
+            self.styled_widgets.push(StyledWidget {
+                node: widget.node(),
+                classes,
+                extra,
+                restyle,
+            });
+        }
     }
 }
 
 impl Renderable for Gui {
     fn pre_render(&mut self, context: &mut RenderContext) {
+        self.viewport_size = context.viewport().as_irect().size;
+        let scale = self.scale();
+        let safe_area = self.safe_area();
         if let Some(root_node) = self.nodes.get_mut(self.root) {
-            root_node.rect = context.viewport().as_irect();
+            root_node.rect = root_rect(self.viewport_size, scale, safe_area);
         }
-        self.renderer.process(context, &self.nodes);
+        self.renderer.process(context, &self.nodes, scale);
     }
     fn render(&mut self, context: &mut RenderContext) {
         self.renderer.draw_all(context);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::GuiLayout;
+
+    /// Lay out one child of `parent` with the anchor layout, as `Gui::layout` does.
+    fn layout_child(parent: IRect, layout: &NodeLayout) -> IRect {
+        let mut anchor = layout::Anchor::default();
+        let size = IVec2::new(layout.width(), layout.height());
+        anchor.begin_layout(parent, &NodeLayout::default(), &[size]);
+        anchor.layout_child(layout).inset(layout.margin)
+    }
+
+    #[test]
+    fn double_scale_doubles_drawn_layout() {
+        let viewport = IVec2::new(1280, 720);
+        let root = root_rect(viewport, 2.0, EdgeRect::ZERO);
+        assert_eq!(root, IRect::new(0, 0, 640, 360));
+        let layout = NodeLayout {
+            size: IVec2::new(100, 30),
+            margin: EdgeRect::new(0, 10, 10, 0),
+            anchors: (Anchor::End, Anchor::End),
+            ..Default::default()
+        };
+        let node = GuiNode {
+            rect: layout_child(root, &layout),
+            ..GuiNode::new(layout, NodeDraw::None)
+        };
+        assert_eq!(node.rect, IRect::new(530, 320, 100, 30));
+        // The same corner of the screen, at twice the size.
+        assert_eq!(node.draw_rect(2.0).0, IRect::new(1060, 640, 200, 60));
+        assert_eq!(node.draw_rect(1.0).0, node.rect);
+    }
+
+    #[test]
+    fn fractional_scale_keeps_edges_touching() {
+        let left = GuiNode {
+            rect: IRect::new(0, 0, 33, 10),
+            ..Default::default()
+        };
+        let right = GuiNode {
+            rect: IRect::new(33, 0, 33, 10),
+            ..Default::default()
+        };
+        let (left, _) = left.draw_rect(1.5);
+        let (right, _) = right.draw_rect(1.5);
+        assert_eq!(left.position.x + left.size.x, right.position.x);
+        assert_eq!(right.position.x + right.size.x, 99);
+    }
+
+    #[test]
+    fn overflowing_child_is_clipped() {
+        let clipping = |rect, clip| GuiNode {
+            flags: NodeFlags {
+                clip_children: true,
+                ..Default::default()
+            },
+            rect,
+            clip,
+            ..Default::default()
+        };
+        let parent = clipping(IRect::new(0, 0, 100, 100), None);
+        let child = GuiNode {
+            rect: IRect::new(50, 50, 100, 100),
+            clip: parent.child_clip(),
+            ..Default::default()
+        };
+        assert_eq!(child.clip, Some(IRect::new(0, 0, 100, 100)));
+        assert_eq!(
+            child.clip.unwrap().intersection(child.rect),
+            IRect::new(50, 50, 50, 50)
+        );
+        // Without the flag, children only inherit the parent's own clip.
+        assert_eq!(child.child_clip(), child.clip);
+        assert_eq!(GuiNode::default().child_clip(), None);
+
+        // Nested clipping nodes clip to the overlap of both.
+        let nested = clipping(IRect::new(60, -20, 100, 100), parent.child_clip());
+        assert_eq!(nested.child_clip(), Some(IRect::new(60, 0, 40, 80)));
+        // The clip is scaled with the rest of the node when drawn.
+        assert_eq!(
+            nested.child_clip().map(|clip| clip.scale(2.0)),
+            Some(IRect::new(120, 0, 80, 160))
+        );
+    }
+
+    #[test]
+    fn reference_resolution_scales_with_viewport() {
+        let reference = Some(IVec2::new(1280, 720));
+        assert_eq!(combined_scale(1.0, IVec2::new(2560, 1440), reference), 2.0);
+        // Keeps the aspect ratio, fitting the narrower axis.
+        assert_eq!(combined_scale(1.0, IVec2::new(2560, 720), reference), 1.0);
+        assert_eq!(combined_scale(1.5, IVec2::new(640, 360), reference), 0.75);
+        assert_eq!(combined_scale(2.0, IVec2::new(640, 360), None), 2.0);
+    }
+
+    #[test]
+    fn anchor_names_and_aliases() {
+        for (name, expected) in [
+            ("begin", 0),
+            ("Left", 0),
+            ("top", 0),
+            ("middle", 1),
+            ("center", 1),
+            ("End", 2),
+            ("right", 2),
+            ("bottom", 2),
+        ] {
+            let index = match name.parse::<Anchor>() {
+                Ok(Anchor::Begin) => 0,
+                Ok(Anchor::Middle) => 1,
+                Ok(Anchor::End) => 2,
+                Err(()) => panic!("{name:?} didn't parse"),
+            };
+            assert_eq!(index, expected, "{name:?}");
+        }
+        assert!("sideways".parse::<Anchor>().is_err());
+    }
+}
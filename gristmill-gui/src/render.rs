@@ -1,17 +1,20 @@
-use crate::{GuiNodeStorage, NodeDraw};
+use crate::{GuiNode, GuiNodeStorage, NodeDraw, ScaleMode};
 use glyph_brush::*;
+use gristmill_core::Color;
 use gristmill_core::{
-    geom2d::{IRect, Rect},
-    math::IVec2,
+    geom2d::{EdgeRect, IRect, Rect},
+    math::{IVec2, Vec2},
 };
 use gristmill_render::{
-    texture_rect::{TextureRect, TextureRectRenderer},
-    RenderContext, Texture,
+    texture_rect::{DropShadow, TextureRect, TextureRectRenderer},
+    RenderContext, Texture, TextureAddressMode,
+};
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    sync::Arc,
 };
-use std::sync::Arc;
 use vulkano::{
-    buffer::{BufferUsage, CpuAccessibleBuffer},
-    command_buffer::CopyBufferToImageInfo,
     format::Format,
     image::{
         view::{ImageView, ImageViewCreateInfo},
@@ -20,6 +23,106 @@ use vulkano::{
     sampler::{ComponentMapping, ComponentSwizzle},
 };
 
+// One segment of a nine-slice along one axis: (position, length, texture position, texture length).
+type SliceSegment = (f32, f32, f32, f32);
+
+fn slice_segments(
+    position: f32,
+    length: f32,
+    texture_length: f32,
+    (begin, end): (f32, f32),
+    scale: f32,
+) -> [SliceSegment; 3] {
+    // Shrink the borders if the rect is too small to fit both.
+    let fit = (length / ((begin + end) * scale)).min(1.0);
+    let (dest_begin, dest_end) = (begin * scale * fit, end * scale * fit);
+    [
+        (position, dest_begin, 0.0, begin),
+        (
+            position + dest_begin,
+            length - dest_begin - dest_end,
+            begin,
+            texture_length - begin - end,
+        ),
+        (
+            position + length - dest_end,
+            dest_end,
+            texture_length - end,
+            end,
+        ),
+    ]
+}
+
+fn tile_segment(segment: SliceSegment, tile: bool, scale: f32) -> Vec<SliceSegment> {
+    let (position, length, texture_position, texture_length) = segment;
+    let tile_length = texture_length * scale;
+    if !tile || tile_length < 1.0 {
+        return vec![segment];
+    }
+    let mut tiles = Vec::new();
+    let mut offset = 0.0;
+    while offset < length {
+        let part = (length - offset).min(tile_length);
+        tiles.push((
+            position + offset,
+            part,
+            texture_position,
+            texture_length * part / tile_length,
+        ));
+        offset += tile_length;
+    }
+    tiles
+}
+
+/// The rects and UV rects of the quads that draw a nine-slice image into `rect`.
+fn nine_slice_quads(
+    texture_size: Vec2,
+    border: EdgeRect,
+    rect: Rect,
+    scale: f32,
+    tile: bool,
+) -> Vec<(Rect, Rect)> {
+    if texture_size.x <= 0.0 || texture_size.y <= 0.0 {
+        return vec![(rect, Rect::ONE)];
+    }
+    let columns = slice_segments(
+        rect.x(),
+        rect.width(),
+        texture_size.x,
+        (border.left as f32, border.right as f32),
+        scale,
+    );
+    let rows = slice_segments(
+        rect.y(),
+        rect.height(),
+        texture_size.y,
+        (border.top as f32, border.bottom as f32),
+        scale,
+    );
+    let mut quads = Vec::new();
+    for (row_index, row) in rows.into_iter().enumerate() {
+        for (column_index, column) in columns.into_iter().enumerate() {
+            if column.1 <= 0.0 || row.1 <= 0.0 || column.3 <= 0.0 || row.3 <= 0.0 {
+                continue;
+            }
+            for x in tile_segment(column, tile && column_index == 1, scale) {
+                for y in tile_segment(row, tile && row_index == 1, scale) {
+                    quads.push((
+                        Rect::new(x.0, y.0, x.1, y.1),
+                        Rect::new(
+                            x.2 / texture_size.x,
+                            y.2 / texture_size.y,
+                            x.3 / texture_size.x,
+                            y.3 / texture_size.y,
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+    quads
+}
+
 fn text_screen_position(rect: IRect, layout: Layout<BuiltInLineBreaker>) -> IVec2 {
     let (h_align, v_align) = match layout {
         Layout::SingleLine {
@@ -42,11 +145,55 @@ fn text_screen_position(rect: IRect, layout: Layout<BuiltInLineBreaker>) -> IVec
     IVec2::new(x, y)
 }
 
+#[derive(Clone, PartialEq)]
+struct GlyphExtra {
+    color: [f32; 4],
+    z: f32,
+    clip: Option<IRect>,
+}
+
+impl Hash for GlyphExtra {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.color.map(f32::to_bits).hash(state);
+        self.z.to_bits().hash(state);
+        self.clip
+            .map(|clip| [clip.x(), clip.y(), clip.width(), clip.height()])
+            .hash(state);
+    }
+}
+
+/// `GuiRenderer::text_span_at`, laying out the text with `fonts`.
+fn section_span_at<F: ab_glyph::Font>(
+    fonts: &[F],
+    section: &OwnedSection,
+    rect: IRect,
+    point: Vec2,
+) -> Option<usize> {
+    let geometry = SectionGeometry {
+        screen_position: text_screen_position(rect, section.layout).as_vec2().into(),
+        bounds: rect.size.as_vec2().into(),
+    };
+    section
+        .layout
+        .calculate_glyphs(fonts, &geometry, &section.text)
+        .into_iter()
+        .find(|glyph| {
+            let bounds = fonts[glyph.font_id.0].glyph_bounds(&glyph.glyph);
+            point.x >= bounds.min.x
+                && point.x < bounds.max.x
+                && point.y >= bounds.min.y
+                && point.y < bounds.max.y
+        })
+        .map(|glyph| glyph.section_index)
+}
+
 pub struct GuiRenderer {
     rect_renderer: TextureRectRenderer,
-    glyph_brush: GlyphBrush<TextureRect>,
+    glyph_brush: GlyphBrush<TextureRect, GlyphExtra>,
     glyph_texture: Texture,
     glyph_draw: Vec<TextureRect>,
+    fonts: HashMap<String, FontId>,
+    wireframe: bool,
 }
 
 impl GuiRenderer {
@@ -63,14 +210,75 @@ impl GuiRenderer {
             glyph_brush,
             glyph_texture,
             glyph_draw: Vec::new(),
+            fonts: HashMap::new(),
+            wireframe: false,
         }
     }
 
     pub fn rect_renderer(&mut self) -> &mut TextureRectRenderer {
         &mut self.rect_renderer
     }
+    /// Add a font under `name`. A name that is already loaded keeps its existing font.
+    pub fn load_font(&mut self, name: &str, data: Vec<u8>) -> Option<FontId> {
+        if let Some(id) = self.fonts.get(name) {
+            return Some(*id);
+        }
+        match ab_glyph::FontArc::try_from_vec(data) {
+            Ok(font) => {
+                let id = self.glyph_brush.add_font(font);
+                self.fonts.insert(name.to_owned(), id);
+                Some(id)
+            }
+            Err(error) => {
+                log::warn!("Failed to load font \"{name}\": {error}");
+                None
+            }
+        }
+    }
+    pub fn font_id(&self, name: &str) -> Option<FontId> {
+        if name == "default" {
+            Some(FontId(0))
+        } else {
+            self.fonts.get(name).copied()
+        }
+    }
+    pub fn wireframe(&self) -> bool {
+        self.wireframe
+    }
+    pub fn set_wireframe(&mut self, enabled: bool) {
+        self.wireframe = enabled;
+    }
 
-    fn glyph_vertex(glyph_texture: &Texture, glyph: GlyphVertex) -> TextureRect {
+    /// The size of `text` laid out on a single line, in unscaled GUI units.
+    pub fn measure_text(&mut self, text: &[OwnedText]) -> Vec2 {
+        let section = Section::default().with_text(
+            text.iter()
+                .map(|text| Text {
+                    text: &text.text,
+                    scale: text.scale,
+                    font_id: text.font_id,
+                    extra: GlyphExtra {
+                        color: text.extra.color,
+                        z: 0.0,
+                        clip: None,
+                    },
+                })
+                .collect(),
+        );
+        self.glyph_brush
+            .glyph_bounds(section)
+            .map_or(Vec2::ZERO, |bounds| {
+                Vec2::new(bounds.width(), bounds.height())
+            })
+    }
+
+    /// The index of the run in `section` that has a glyph under `point`, with the section laid
+    /// out in `rect`. Everything is in unscaled GUI units.
+    pub fn text_span_at(&self, section: &OwnedSection, rect: IRect, point: Vec2) -> Option<usize> {
+        section_span_at(self.glyph_brush.fonts(), section, rect, point)
+    }
+
+    fn glyph_vertex(glyph_texture: &Texture, glyph: GlyphVertex<GlyphExtra>) -> TextureRect {
         fn convert_rect(rect: ab_glyph::Rect) -> Rect {
             [rect.min.x, rect.min.y, rect.width(), rect.height()].into()
         }
@@ -78,8 +286,14 @@ impl GuiRenderer {
             texture: Some(glyph_texture.clone()),
             rect: convert_rect(glyph.pixel_coords),
             uv_rect: convert_rect(glyph.tex_coords),
-            color: gristmill_core::Color::from(glyph.extra.color),
+            color: Color::from(glyph.extra.color),
+            gradient: None,
+            corner_radius: 0.0,
+            grayscale: false,
+            premultiplied: false,
+            clip: glyph.extra.clip,
             z: glyph.extra.z as u16,
+            opaque: false,
         }
     }
     fn create_glyph_texture(context: &mut RenderContext, dimensions: (u32, u32)) -> Texture {
@@ -117,49 +331,244 @@ impl GuiRenderer {
         region: Rectangle<u32>,
         tex_data: &[u8],
     ) {
-        let transfer_buffer = CpuAccessibleBuffer::from_iter(
-            context.allocator(),
-            BufferUsage {
-                transfer_src: true,
-                ..BufferUsage::empty()
-            },
-            false,
-            tex_data.iter().cloned(),
-        )
-        .unwrap();
-        let mut copy_info =
-            CopyBufferToImageInfo::buffer_image(transfer_buffer, glyph_texture.image());
-        copy_info.regions[0].image_offset = [region.min[0], region.min[1], 0];
-        copy_info.regions[0].image_extent = [region.width(), region.height(), 1];
-        context.builder().copy_buffer_to_image(copy_info).unwrap();
+        let region = IRect::new(
+            region.min[0] as i32,
+            region.min[1] as i32,
+            region.width() as i32,
+            region.height() as i32,
+        );
+        glyph_texture
+            .update_region(context, region, tex_data)
+            .unwrap();
     }
 
-    pub fn process(&mut self, context: &mut RenderContext, nodes: &GuiNodeStorage) {
+    fn queue_rect(&mut self, shadow: Option<DropShadow>, rect: TextureRect) {
+        if let Some(shadow) = shadow {
+            self.rect_renderer.queue_with_shadow(rect, &shadow);
+        } else {
+            self.rect_renderer.queue(rect);
+        }
+    }
+
+    /// Outline the node's rect and label it with its z.
+    fn queue_wireframe(&mut self, node: &GuiNode, scale: f32) {
+        let (rect, z) = node.draw_rect(scale);
+        let color = TextureRect::wireframe_color(z);
+        self.rect_renderer
+            .queue_all(TextureRect::outline(rect.as_rect(), 1.0, color));
+        let label = z.to_string();
+        let section = Section::default()
+            .with_screen_position(rect.position.as_vec2() + Vec2::splat(2.0))
+            .with_text(vec![Text {
+                text: &label,
+                scale: ab_glyph::PxScale::from(10.0 * scale),
+                font_id: FontId(0),
+                extra: GlyphExtra {
+                    color: color.into(),
+                    z: u16::MAX as f32,
+                    clip: None,
+                },
+            }]);
+        self.glyph_brush.queue(section);
+    }
+
+    pub fn process(&mut self, context: &mut RenderContext, nodes: &GuiNodeStorage, scale: f32) {
         for (_, node) in nodes.iter() {
             if !node.visible {
                 continue;
             }
+            if self.wireframe {
+                self.queue_wireframe(node, scale);
+            }
+            if node.effective_opacity <= 0.0 {
+                continue;
+            }
+            let opacity = node.effective_opacity;
+            let clip = node.clip.map(|clip| clip.scale(scale));
+            let shadow = node.shadow.map(|shadow| DropShadow {
+                offset: shadow.offset * scale,
+                color: shadow.color,
+                blur: shadow.blur * scale,
+            });
             match &node.draw {
                 NodeDraw::None => (),
                 NodeDraw::Rect(texture, color) => {
-                    let (rect, z) = node.draw_rect();
-                    self.rect_renderer.queue(TextureRect {
-                        texture: texture.clone(),
-                        rect: rect.as_rect(),
-                        uv_rect: Rect::ONE,
-                        color: *color,
-                        z,
+                    let (rect, z) = node.draw_rect(scale);
+                    self.queue_rect(
+                        shadow,
+                        TextureRect {
+                            texture: texture.clone(),
+                            rect: rect.as_rect(),
+                            uv_rect: Rect::ONE,
+                            color: color.multiply_alpha(opacity),
+                            gradient: None,
+                            corner_radius: 0.0,
+                            grayscale: false,
+                            premultiplied: false,
+                            clip,
+                            z,
+                            opaque: false,
+                        },
+                    );
+                }
+                NodeDraw::RoundedRect(texture, color, corner_radius) => {
+                    let (rect, z) = node.draw_rect(scale);
+                    self.queue_rect(
+                        shadow,
+                        TextureRect {
+                            texture: texture.clone(),
+                            rect: rect.as_rect(),
+                            uv_rect: Rect::ONE,
+                            color: color.multiply_alpha(opacity),
+                            gradient: None,
+                            corner_radius: *corner_radius * scale,
+                            grayscale: false,
+                            premultiplied: false,
+                            clip,
+                            z,
+                            opaque: false,
+                        },
+                    );
+                }
+                NodeDraw::Image {
+                    texture,
+                    color,
+                    corner_radius,
+                    scale_mode,
+                    grayscale,
+                    premultiplied,
+                    frame,
+                } => {
+                    let (rect, z) = node.draw_rect(scale);
+                    let frame = frame.unwrap_or(Rect::ONE);
+                    let texture_size = texture.as_ref().map_or(Vec2::ZERO, |texture| {
+                        texture.dimensions().as_vec2() * frame.size * scale
                     });
+                    let (rect, uv_rect) = scale_mode.draw_rect(rect.as_rect(), texture_size);
+                    let uv_rect = Rect {
+                        position: frame.position + uv_rect.position * frame.size,
+                        size: uv_rect.size * frame.size,
+                    };
+                    // Tiling samples past the edge of the texture, so it needs to wrap around.
+                    let texture = match scale_mode {
+                        ScaleMode::Tile => texture
+                            .as_ref()
+                            .map(|texture| texture.with_address_mode(TextureAddressMode::Repeat)),
+                        _ => texture.clone(),
+                    };
+                    self.queue_rect(
+                        shadow,
+                        TextureRect {
+                            texture,
+                            rect,
+                            uv_rect,
+                            color: color.multiply_alpha(opacity),
+                            gradient: None,
+                            corner_radius: *corner_radius * scale,
+                            grayscale: *grayscale,
+                            premultiplied: *premultiplied,
+                            clip,
+                            z,
+                            opaque: false,
+                        },
+                    );
                 }
-                NodeDraw::Text(owned_section) => {
-                    let (rect, z) = node.draw_rect();
-                    let mut section = owned_section.to_borrowed();
-                    section.screen_position =
-                        text_screen_position(rect, section.layout).as_vec2().into();
-                    section.bounds = rect.size.as_vec2().into();
-                    for text in section.text.iter_mut() {
-                        text.extra.z = z as f32;
+                NodeDraw::NineSlice {
+                    texture,
+                    color,
+                    border,
+                    tile,
+                } => {
+                    let (rect, z) = node.draw_rect(scale);
+                    let texture_size = texture
+                        .as_ref()
+                        .map_or(Vec2::ZERO, |texture| texture.dimensions().as_vec2());
+                    let quads =
+                        nine_slice_quads(texture_size, *border, rect.as_rect(), scale, *tile);
+                    let color = color.multiply_alpha(opacity);
+                    // One shadow for the whole node, rather than one per slice.
+                    if let Some(shadow) = shadow {
+                        self.rect_renderer.queue_all(shadow.quads(&TextureRect {
+                            texture: None,
+                            rect: rect.as_rect(),
+                            uv_rect: Rect::ONE,
+                            color,
+                            gradient: None,
+                            corner_radius: 0.0,
+                            grayscale: false,
+                            premultiplied: false,
+                            clip,
+                            z,
+                            opaque: false,
+                        }));
                     }
+                    self.rect_renderer
+                        .queue_all(quads.into_iter().map(|(rect, uv_rect)| TextureRect {
+                            texture: texture.clone(),
+                            rect,
+                            uv_rect,
+                            color,
+                            gradient: None,
+                            corner_radius: 0.0,
+                            grayscale: false,
+                            premultiplied: false,
+                            clip,
+                            z,
+                            opaque: false,
+                        }));
+                }
+                NodeDraw::Gradient {
+                    from,
+                    to,
+                    direction,
+                    corner_radius,
+                } => {
+                    let (rect, z) = node.draw_rect(scale);
+                    self.queue_rect(
+                        shadow,
+                        TextureRect {
+                            texture: None,
+                            rect: rect.as_rect(),
+                            uv_rect: Rect::ONE,
+                            color: from.multiply_alpha(opacity),
+                            gradient: Some((to.multiply_alpha(opacity), *direction)),
+                            corner_radius: *corner_radius * scale,
+                            grayscale: false,
+                            premultiplied: false,
+                            clip,
+                            z,
+                            opaque: false,
+                        },
+                    );
+                }
+                NodeDraw::Text(owned_section) => {
+                    let (rect, z) = node.draw_rect(scale);
+                    let section = Section {
+                        screen_position: text_screen_position(rect, owned_section.layout)
+                            .as_vec2()
+                            .into(),
+                        bounds: rect.size.as_vec2().into(),
+                        layout: owned_section.layout,
+                        text: owned_section
+                            .text
+                            .iter()
+                            .map(|text| Text {
+                                text: &text.text,
+                                scale: ab_glyph::PxScale {
+                                    x: text.scale.x * scale,
+                                    y: text.scale.y * scale,
+                                },
+                                font_id: text.font_id,
+                                extra: GlyphExtra {
+                                    color: Color::from(text.extra.color)
+                                        .multiply_alpha(opacity)
+                                        .into(),
+                                    z: z as f32,
+                                    clip,
+                                },
+                            })
+                            .collect(),
+                    };
                     self.glyph_brush.queue(section);
                 }
             }
@@ -198,3 +607,56 @@ impl GuiRenderer {
         self.rect_renderer.draw_all(context);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_font() -> ab_glyph::FontArc {
+        ab_glyph::FontArc::try_from_slice(include_bytes!("./OpenSans-Regular.ttf")).unwrap()
+    }
+
+    #[test]
+    fn point_maps_to_span() {
+        let fonts = [default_font()];
+        let section = OwnedSection::default()
+            .add_text(OwnedText::new("Hello ").with_scale(20.0))
+            .add_text(OwnedText::new("world").with_scale(20.0));
+        let rect = IRect::new(100, 50, 400, 40);
+        let span_at = |x: f32, y: f32| section_span_at(&fonts, &section, rect, Vec2::new(x, y));
+
+        // Find where each span's glyphs are, to aim between them.
+        let geometry = SectionGeometry {
+            screen_position: (100.0, 50.0),
+            bounds: (400.0, 40.0),
+        };
+        let glyphs = section
+            .layout
+            .calculate_glyphs(&fonts, &geometry, &section.text);
+        let bounds = |span: usize| {
+            glyphs
+                .iter()
+                .filter(|glyph| glyph.section_index == span)
+                .map(|glyph| ab_glyph::Font::glyph_bounds(&fonts[0], &glyph.glyph))
+                .reduce(|a, b| ab_glyph::Rect {
+                    min: ab_glyph::point(a.min.x.min(b.min.x), a.min.y.min(b.min.y)),
+                    max: ab_glyph::point(a.max.x.max(b.max.x), a.max.y.max(b.max.y)),
+                })
+                .unwrap()
+        };
+        let (hello, world) = (bounds(0), bounds(1));
+        assert!(hello.max.x <= world.min.x);
+        let middle_y = (hello.min.y + hello.max.y) / 2.0;
+
+        assert_eq!(span_at(hello.min.x + 1.0, middle_y), Some(0));
+        assert_eq!(span_at(world.min.x + 1.0, middle_y), Some(1));
+        assert_eq!(span_at(world.max.x - 1.0, middle_y), Some(1));
+        // Past the end of the text, and above it.
+        assert_eq!(span_at(world.max.x + 10.0, middle_y), None);
+        assert_eq!(span_at(hello.min.x + 1.0, hello.min.y - 10.0), None);
+        // Moving the rect moves the text with it.
+        let moved = IRect::new(0, 0, 400, 40);
+        let point = Vec2::new(hello.min.x - 99.0, middle_y - 50.0);
+        assert_eq!(section_span_at(&fonts, &section, moved, point), Some(0));
+    }
+}
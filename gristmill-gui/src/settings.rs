@@ -0,0 +1,314 @@
+//! Options menus generated from config structs.
+//!
+//! Derive `Settings` on a config struct and `SettingChoice` on the enums it uses, then show it
+//! with `SettingsMenu`. Each field becomes a row with a label and a control:
+//!
+//! - `bool` fields become a button that toggles between "On" and "Off".
+//! - Fields with `min` or `max` become a `NumberStepper`. Any numeric type works; the stepper
+//!   moves in whole numbers.
+//! - Any other field must be a `SettingChoice` enum, and becomes a button that cycles through
+//!   its variants.
+//!
+//! Fields are configured with `#[setting(...)]`: `label = "Volume"` (the default is the field
+//! name in sentence case), `min`, `max` and `step` for numbers, and `skip` to leave a field out.
+//! Variants take a `label` too.
+//!
+//! ```ignore
+//! #[derive(Settings, Serialize, Deserialize, Default)]
+//! struct Options {
+//!     #[setting(label = "Volume", min = 0, max = 100, step = 5)]
+//!     volume: u32,
+//!     fullscreen: bool,
+//!     window_mode: WindowMode,
+//!     #[setting(skip)]
+//!     last_save: String,
+//! }
+//! ```
+//!
+//! The menu's look comes from the "settings_menu" style class: `row_height`, `control_width` and
+//! `spacing`. Labels use the "text" class and controls the "button" and "number_stepper" classes.
+
+use crate::{
+    widget::{Button, NumberStepper, Text, WidgetNode, WidgetNodeExt, WidgetStyle},
+    Gui, GuiNode, GuiNodeExt, GuiNodeId, NodeLayout,
+};
+use gristmill_core::{
+    asset::{self, AssetError},
+    math::IVec2,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::any::Any;
+
+/// What control a setting gets.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SettingKind {
+    Toggle,
+    Number {
+        min: i32,
+        max: i32,
+        step: i32,
+    },
+    /// One of the labels, by index.
+    Choice(&'static [&'static str]),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SettingField {
+    /// The name of the struct field.
+    pub name: &'static str,
+    pub label: &'static str,
+    pub kind: SettingKind,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SettingValue {
+    Bool(bool),
+    Number(i32),
+    Choice(usize),
+}
+
+/// A config struct that can be shown as a `SettingsMenu`. Derive it with
+/// `#[derive(Settings)]`; settings are indexed in field order, leaving out skipped fields.
+pub trait Settings {
+    fn setting_fields() -> Vec<SettingField>;
+    fn setting(&self, index: usize) -> SettingValue;
+    /// Set a setting. A value of the wrong kind for the setting is ignored.
+    fn set_setting(&mut self, index: usize, value: SettingValue);
+}
+
+/// An enum with a fixed list of labeled variants. Derive it with `#[derive(SettingChoice)]`.
+pub trait SettingChoice {
+    fn choices() -> &'static [&'static str];
+    fn choice_index(&self) -> usize;
+    /// The variant at `index`, or the first variant if it's out of range.
+    fn from_choice_index(index: usize) -> Self;
+}
+
+/// The control made for a setting.
+pub enum SettingControl {
+    Toggle(Button),
+    Number(NumberStepper),
+    Choice(Button),
+}
+
+impl SettingControl {
+    pub fn node(&self) -> GuiNodeId {
+        match self {
+            SettingControl::Toggle(button) | SettingControl::Choice(button) => button.node(),
+            SettingControl::Number(stepper) => stepper.node(),
+        }
+    }
+}
+
+/// The value a click on a toggle or choice button sets: a toggle flips, and a choice moves on
+/// to the next label, wrapping around at the end.
+fn clicked_value(kind: SettingKind, value: SettingValue) -> Option<SettingValue> {
+    match (kind, value) {
+        (SettingKind::Toggle, SettingValue::Bool(value)) => Some(SettingValue::Bool(!value)),
+        (SettingKind::Choice(choices), SettingValue::Choice(value)) => {
+            Some(SettingValue::Choice((value + 1) % choices.len().max(1)))
+        }
+        _ => None,
+    }
+}
+
+/// Load a config saved by `SettingsMenu::save`, or the default config if there isn't one or it
+/// can't be read.
+pub fn load_settings<T: DeserializeOwned + Default>(file: &str) -> T {
+    match asset::load_yaml_file("config", file) {
+        Ok(config) => config,
+        Err(AssetError::Io(error)) if error.kind() == std::io::ErrorKind::NotFound => T::default(),
+        Err(error) => {
+            log::warn!("Failed to load settings \"{}\": {}", file, error);
+            T::default()
+        }
+    }
+}
+
+/// A vertical list of rows, one per setting of `T`. Changing a control writes the new value to
+/// the config straight away.
+///
+/// Like `Button`, the controls only respond while `interact` is called each frame.
+pub struct SettingsMenu<T> {
+    node: GuiNodeId,
+    config: T,
+    fields: Vec<SettingField>,
+    controls: Vec<SettingControl>,
+}
+
+impl<T: Settings + 'static> SettingsMenu<T> {
+    pub fn new(gui: &mut Gui, parent: GuiNodeId, config: T) -> Self {
+        let mut style = gui.styles.query(std::iter::once("settings_menu"));
+        let row_height = style.widget_value("row_height", 24);
+        let control_width = style.widget_value("control_width", 160);
+        let spacing = style.widget_value("spacing", 4);
+
+        let node = parent.add_child(
+            gui,
+            GuiNode {
+                layout: NodeLayout {
+                    child_layout: "vbox".to_owned(),
+                    child_spacing: spacing,
+                    fit_content: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let fields = T::setting_fields();
+        let controls = fields
+            .iter()
+            .map(|field| {
+                let row = node.add_child(
+                    gui,
+                    GuiNode {
+                        layout: NodeLayout {
+                            size: IVec2::new(0, row_height),
+                            child_layout: "hbox".to_owned(),
+                            child_spacing: spacing,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                );
+                let label = gui.create_widget::<Text>(row);
+                label.set_text_string(gui, field.label);
+                if let Some(label_node) = label.node_data(gui) {
+                    label_node.layout.grow = 1.0;
+                }
+                let control = match field.kind {
+                    SettingKind::Toggle => SettingControl::Toggle(gui.create_widget(row)),
+                    SettingKind::Number { min, max, step } => {
+                        let mut stepper: NumberStepper = gui.create_widget(row);
+                        stepper.set_range(gui, min, max, step);
+                        SettingControl::Number(stepper)
+                    }
+                    SettingKind::Choice(_) => SettingControl::Choice(gui.create_widget(row)),
+                };
+                control.node().set_layout_width(gui, control_width);
+                control
+            })
+            .collect();
+
+        let mut menu = SettingsMenu {
+            node,
+            config,
+            fields,
+            controls,
+        };
+        menu.refresh(gui);
+        menu
+    }
+
+    /// Keep the controls enabled for this frame. Returns true if any setting changed.
+    pub fn interact(&mut self, gui: &mut Gui) -> bool {
+        let mut changed = false;
+        for (index, (field, control)) in self.fields.iter().zip(&mut self.controls).enumerate() {
+            let value = match control {
+                SettingControl::Toggle(button) | SettingControl::Choice(button) => {
+                    if button.interact() {
+                        clicked_value(field.kind, self.config.setting(index))
+                    } else {
+                        None
+                    }
+                }
+                SettingControl::Number(stepper) => stepper.interact(gui).map(SettingValue::Number),
+            };
+            if let Some(value) = value {
+                self.config.set_setting(index, value);
+                changed = true;
+            }
+        }
+        if changed {
+            self.refresh(gui);
+        }
+        changed
+    }
+
+    pub fn config(&self) -> &T {
+        &self.config
+    }
+    pub fn into_config(self) -> T {
+        self.config
+    }
+    /// Replace the config and update the controls to show it.
+    pub fn set_config(&mut self, gui: &mut Gui, config: T) {
+        self.config = config;
+        self.refresh(gui);
+    }
+    pub fn fields(&self) -> &[SettingField] {
+        &self.fields
+    }
+    /// The controls, in the same order as `fields`.
+    pub fn controls(&self) -> &[SettingControl] {
+        &self.controls
+    }
+
+    /// Save the config to `file` in the "config" folder.
+    pub fn save(&self, file: &str) -> Result<(), AssetError>
+    where
+        T: Serialize,
+    {
+        asset::save_yaml_file("config", file, &self.config)
+    }
+
+    fn refresh(&mut self, gui: &mut Gui) {
+        for (index, (field, control)) in self.fields.iter().zip(&mut self.controls).enumerate() {
+            match (control, self.config.setting(index), field.kind) {
+                (SettingControl::Toggle(button), SettingValue::Bool(value), _) => {
+                    button.set_label_string(gui, if value { "On" } else { "Off" })
+                }
+                (SettingControl::Number(stepper), SettingValue::Number(value), _) => {
+                    stepper.set_value(gui, value)
+                }
+                (
+                    SettingControl::Choice(button),
+                    SettingValue::Choice(value),
+                    SettingKind::Choice(choices),
+                ) => button.set_label_string(gui, choices.get(value).copied().unwrap_or_default()),
+                _ => log::warn!("Setting \"{}\" has a value of the wrong kind", field.name),
+            }
+        }
+    }
+}
+
+impl<T: 'static> WidgetNode for SettingsMenu<T> {
+    fn as_any_box(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn node(&self) -> GuiNodeId {
+        self.node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggle_click_flips() {
+        assert_eq!(
+            clicked_value(SettingKind::Toggle, SettingValue::Bool(false)),
+            Some(SettingValue::Bool(true))
+        );
+        assert_eq!(
+            clicked_value(SettingKind::Toggle, SettingValue::Bool(true)),
+            Some(SettingValue::Bool(false))
+        );
+    }
+
+    #[test]
+    fn choice_click_cycles_and_wraps() {
+        let kind = SettingKind::Choice(&["Windowed", "Borderless", "Fullscreen"]);
+        assert_eq!(
+            clicked_value(kind, SettingValue::Choice(0)),
+            Some(SettingValue::Choice(1))
+        );
+        assert_eq!(
+            clicked_value(kind, SettingValue::Choice(2)),
+            Some(SettingValue::Choice(0))
+        );
+        // A value of the wrong kind is left alone.
+        assert_eq!(clicked_value(kind, SettingValue::Bool(true)), None);
+    }
+}
@@ -0,0 +1,70 @@
+//! Hit testing for runs of text, for clickable words in dialogue or highlighted controls in
+//! tutorial text.
+//!
+//! Each `OwnedText` in a text node's section is a span, identified by its index. Set the
+//! `text_spans` flag on a text node to make it report the span under the pointer: the span being
+//! hovered is available from `Gui::pointer_span`, and pressing "primary" over a span produces a
+//! `TextSpanEvent`. Wrapped text is hit tested as it is laid out.
+
+use crate::{Gui, GuiNodeId, NodeDraw};
+use gristmill_core::math::IVec2;
+
+/// The index of a run of text in a text node's section.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SpanId(pub usize);
+
+/// A span pressed with the "primary" action.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TextSpanEvent {
+    pub node: GuiNodeId,
+    pub span: SpanId,
+}
+
+impl Gui {
+    /// The span of `node`'s text that has a glyph under `point`, in GUI units. Uses the node's
+    /// rect from the last layout.
+    pub fn text_span_at(&mut self, node: GuiNodeId, point: IVec2) -> Option<SpanId> {
+        let node = self.nodes.get(node)?;
+        if !node.visible {
+            return None;
+        }
+        if let NodeDraw::Text(section) = &node.draw {
+            self.renderer
+                .text_span_at(section, node.rect, point.as_vec2())
+                .map(SpanId)
+        } else {
+            None
+        }
+    }
+    /// The text node and span the pointer was over at the last update, if the node has the
+    /// `text_spans` flag.
+    pub fn pointer_span(&self) -> Option<(GuiNodeId, SpanId)> {
+        self.pointer_span
+    }
+    /// The span pressed since this was last called, if any.
+    pub fn take_text_span_event(&mut self) -> Option<TextSpanEvent> {
+        self.text_span_event.take()
+    }
+
+    pub(crate) fn update_text_spans(
+        &mut self,
+        pointer: Option<IVec2>,
+        pointer_over: Option<GuiNodeId>,
+        pressed: bool,
+    ) {
+        self.pointer_span = match (pointer, pointer_over) {
+            (Some(pointer), Some(node))
+                if self
+                    .nodes
+                    .get(node)
+                    .map_or(false, |node| node.flags.text_spans) =>
+            {
+                self.text_span_at(node, pointer).map(|span| (node, span))
+            }
+            _ => None,
+        };
+        if let Some((node, span)) = self.pointer_span.filter(|_| pressed) {
+            self.text_span_event = Some(TextSpanEvent { node, span });
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Transient notifications.
+//!
+//! `Gui::notify` shows a short message in a corner of the screen, such as for achievements or
+//! an autosave indicator. New toasts appear at the top of the stack and push older ones down.
+//! Each fades in, stays for its duration, fades out and is removed, and the rest close the gap.
+//!
+//! The look comes from the "toast" style class: `corner` (such as "top_right"), `margin` from
+//! the screen edges, `spacing` between toasts, `width`, `height`, `padding`, `texture`, `color`,
+//! `text_color`, `fade_time` in seconds, and the shadow keys. Text uses the "text" class.
+
+use crate::{
+    widget::{StyleValues, Text, WidgetNodeExt, WidgetStyle},
+    Anchor, Corner, Gui, GuiNode, GuiNodeExt, GuiNodeId, GuiNodeStorage, NodeDraw, NodeLayout,
+};
+use gristmill_core::{geom2d::EdgeRect, math::IVec2, Color};
+use std::time::{Duration, Instant};
+
+pub(crate) struct Toast {
+    node: GuiNodeId,
+    shown: Instant,
+    duration: Duration,
+    fade: Duration,
+}
+
+/// A toast's opacity `elapsed` into being shown: fading in over `fade`, then fully visible, then
+/// fading out over the last `fade` of `duration`. `None` once the duration has passed.
+pub fn toast_opacity(elapsed: Duration, duration: Duration, fade: Duration) -> Option<f32> {
+    if elapsed >= duration {
+        return None;
+    }
+    let fade = fade.min(duration / 2);
+    if fade.is_zero() {
+        return Some(1.0);
+    }
+    let remaining = duration - elapsed;
+    let opacity = elapsed.min(remaining).as_secs_f32() / fade.as_secs_f32();
+    Some(opacity.min(1.0))
+}
+
+impl Gui {
+    /// Show `text` as a toast for `duration`, including its fade in and out. Returns the toast's
+    /// node, which can be passed to `dismiss_toast`.
+    pub fn notify<S: Into<String>>(&mut self, text: S, duration: Duration) -> GuiNodeId {
+        let mut style = self.styles.query(std::iter::once("toast"));
+        let container = self.toast_container(&mut style);
+
+        let width = style.widget_value("width", 240);
+        let height = style.widget_value("height", 40);
+        let padding = style.widget_value("padding", 8);
+        let texture = style.widget_value("texture", None);
+        let color = style.widget_value("color", Color::new(0.1, 0.1, 0.1, 0.85));
+        let text_color = style
+            .remove("text_color")
+            .and_then(|value| Color::try_from(value).ok());
+        let fade = style.widget_value("fade_time", 0.25);
+
+        let node = container.add_child(
+            self,
+            GuiNode {
+                layout: NodeLayout {
+                    size: IVec2::new(width, height),
+                    anchors: (Anchor::End, Anchor::Begin),
+                    ..Default::default()
+                },
+                draw: NodeDraw::Rect(texture, color),
+                shadow: style.widget_shadow(),
+                opacity: 0.0,
+                ..Default::default()
+            },
+        );
+        // Newest first, so it pushes the others down.
+        if let Some(children) = self.node_children.get_mut(container) {
+            children.pop();
+            children.insert(0, node);
+        }
+        let label = self.create_widget::<Text>(node);
+        label.set_text_align(self, (Anchor::Begin, Anchor::Middle), true);
+        label.set_text_string(self, text);
+        label.set_layout_margin(self, EdgeRect::splat(padding));
+        if let (Some(color), Some(label_node)) = (text_color, label.node_data(self)) {
+            if let NodeDraw::Text(section) = &mut label_node.draw {
+                for text in section.text.iter_mut() {
+                    text.extra.color = color.into();
+                }
+            }
+        }
+
+        self.toasts.push(Toast {
+            node,
+            shown: Instant::now(),
+            duration,
+            fade: Duration::from_secs_f32(fade.max(0.0)),
+        });
+        node
+    }
+    /// Remove a toast immediately, without fading out.
+    pub fn dismiss_toast(&mut self, toast: GuiNodeId) {
+        if let Some(index) = self.toasts.iter().position(|t| t.node == toast) {
+            self.toasts.remove(index);
+            self.remove_node(toast);
+        }
+    }
+    pub fn clear_toasts(&mut self) {
+        for toast in std::mem::take(&mut self.toasts) {
+            self.remove_node(toast.node);
+        }
+    }
+    pub fn toast_count(&self) -> usize {
+        self.toasts.len()
+    }
+
+    fn toast_container(&mut self, style: &mut StyleValues) -> GuiNodeId {
+        if let Some(container) = self
+            .toast_container
+            .filter(|node| self.nodes.contains_key(*node))
+        {
+            return container;
+        }
+        let anchors = match style.widget_value("corner", Corner::TopRight) {
+            Corner::TopLeft => (Anchor::Begin, Anchor::Begin),
+            Corner::TopRight => (Anchor::End, Anchor::Begin),
+            Corner::BottomLeft => (Anchor::Begin, Anchor::End),
+            Corner::BottomRight => (Anchor::End, Anchor::End),
+        };
+        let root = self.root;
+        let container = root.add_child(
+            self,
+            GuiNode {
+                layout: NodeLayout {
+                    margin: EdgeRect::splat(style.widget_value("margin", 16)),
+                    anchors,
+                    child_layout: "vbox".to_owned(),
+                    child_spacing: style.widget_value("spacing", 8),
+                    fit_content: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        self.toast_container = Some(container);
+        container
+    }
+
+    pub(crate) fn update_toasts(&mut self, now: Instant) {
+        for node in fade_toasts(&mut self.toasts, &mut self.nodes, now) {
+            self.remove_node(node);
+        }
+    }
+}
+
+/// Set the opacity of each toast for `now`, and drop the toasts whose time is up or whose node
+/// is gone. Returns the nodes of the expired toasts, to be removed.
+fn fade_toasts(
+    toasts: &mut Vec<Toast>,
+    nodes: &mut GuiNodeStorage,
+    now: Instant,
+) -> Vec<GuiNodeId> {
+    let mut expired = Vec::new();
+    toasts.retain(|toast| {
+        let opacity = toast_opacity(
+            now.saturating_duration_since(toast.shown),
+            toast.duration,
+            toast.fade,
+        );
+        match (nodes.get_mut(toast.node), opacity) {
+            (Some(node), Some(opacity)) => {
+                node.opacity = opacity;
+                true
+            }
+            (Some(_), None) => {
+                expired.push(toast.node);
+                false
+            }
+            (None, _) => false,
+        }
+    });
+    expired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn opacity_fades_in_and_out() {
+        let fade = SECOND / 4;
+        let duration = SECOND * 2;
+        assert_eq!(toast_opacity(Duration::ZERO, duration, fade), Some(0.0));
+        assert_eq!(toast_opacity(fade / 2, duration, fade), Some(0.5));
+        assert_eq!(toast_opacity(SECOND, duration, fade), Some(1.0));
+        assert_eq!(
+            toast_opacity(duration - fade / 2, duration, fade),
+            Some(0.5)
+        );
+        assert_eq!(toast_opacity(duration, duration, fade), None);
+        // No fade, and a fade longer than half the duration.
+        assert_eq!(
+            toast_opacity(Duration::ZERO, duration, Duration::ZERO),
+            Some(1.0)
+        );
+        assert_eq!(toast_opacity(SECOND, duration, SECOND * 5), Some(1.0));
+    }
+
+    #[test]
+    fn expired_toasts_are_dropped() {
+        let mut nodes = GuiNodeStorage::default();
+        let shown = Instant::now();
+        let toast = |node, seconds| Toast {
+            node,
+            shown,
+            duration: SECOND * seconds,
+            fade: SECOND / 4,
+        };
+        let short = nodes.insert(GuiNode::default());
+        let long = nodes.insert(GuiNode::default());
+        let removed = nodes.insert(GuiNode::default());
+        let mut toasts = vec![toast(short, 1), toast(long, 3), toast(removed, 3)];
+        nodes.remove(removed);
+
+        let expired = fade_toasts(&mut toasts, &mut nodes, shown + SECOND / 2);
+        assert!(expired.is_empty());
+        assert_eq!(toasts.len(), 2);
+        assert_eq!(nodes[short].opacity, 1.0);
+
+        let expired = fade_toasts(&mut toasts, &mut nodes, shown + SECOND * 2);
+        assert_eq!(expired, vec![short]);
+        assert_eq!(toasts.len(), 1);
+        assert_eq!(toasts[0].node, long);
+        assert_eq!(nodes[long].opacity, 1.0);
+        assert_eq!(
+            fade_toasts(&mut toasts, &mut nodes, shown + SECOND * 3),
+            vec![long]
+        );
+        assert!(toasts.is_empty());
+    }
+}
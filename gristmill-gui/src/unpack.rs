@@ -26,10 +26,12 @@ impl PackedNode {
         Ok(packed)
     }
 
-    fn unpack_widget<W: Widget>(&self, gui: &mut Gui, parent: GuiNodeId) -> W {
+    fn unpack_widget<W: Widget + WidgetNode>(&self, gui: &mut Gui, parent: GuiNodeId) -> W {
         let mut style = gui.styles.query(self.class.iter().map(|s| -> &str { s }));
         style.extend(self.extra.clone());
-        W::new(gui, parent, style)
+        let widget = W::new(gui, parent, style);
+        gui.register_styled(&widget, self.class.clone(), self.extra.clone());
+        widget
     }
     fn unpack(
         &self,
@@ -74,10 +76,20 @@ impl Unpacker {
         unpacker.types.insert(String::new(), |gui, parent, _class| {
             Box::new(parent.add_child(gui, GuiNode::default()))
         });
+        unpacker.register_widget::<AnimatedImage>();
         unpacker.register_widget::<Button>();
         unpacker.register_widget::<Image>();
+        unpacker.register_widget::<ListView>();
+        unpacker.register_widget::<Marquee>();
+        unpacker.register_widget::<NumberStepper>();
         unpacker.register_widget::<Panel>();
+        unpacker.register_widget::<ProgressBar>();
+        unpacker.register_widget::<RadialMenu>();
+        unpacker.register_widget::<Scrollbar>();
+        unpacker.register_widget::<Separator>();
+        unpacker.register_widget::<Spacer>();
         unpacker.register_widget::<Text>();
+        unpacker.register_widget::<FloatingWindow>();
         unpacker
     }
 
@@ -0,0 +1,259 @@
+use crate::{
+    widget::{
+        image::image_draw, StyleValues, Widget, WidgetBehavior, WidgetInput, WidgetNode,
+        WidgetNodeExt, WidgetStyle,
+    },
+    Gui, GuiNode, GuiNodeExt, GuiNodeId, GuiNodeStorage, NodeDraw,
+};
+use gristmill_core::geom2d::Rect;
+use gristmill_render::Texture;
+use std::{
+    any::Any,
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// What an animation does after its last frame.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum LoopMode {
+    /// Start over from the first frame.
+    #[default]
+    Loop,
+    /// Stop on the last frame.
+    Once,
+    /// Play backwards to the first frame, then forwards again.
+    PingPong,
+}
+
+impl LoopMode {
+    /// The frame shown `elapsed` seconds into an animation of `count` frames at `fps`.
+    pub fn frame(self, elapsed: f32, fps: f32, count: usize) -> usize {
+        if count <= 1 || fps <= 0.0 || elapsed <= 0.0 {
+            return 0;
+        }
+        let step = (elapsed * fps) as usize;
+        match self {
+            LoopMode::Loop => step % count,
+            LoopMode::Once => step.min(count - 1),
+            LoopMode::PingPong => {
+                let period = 2 * (count - 1);
+                let step = step % period;
+                if step < count {
+                    step
+                } else {
+                    period - step
+                }
+            }
+        }
+    }
+    /// Whether an animation that plays this way has stopped for good.
+    pub fn is_finished(self, elapsed: f32, fps: f32, count: usize) -> bool {
+        self == LoopMode::Once && (elapsed * fps.max(0.0)) as usize >= count.saturating_sub(1)
+    }
+}
+
+impl std::str::FromStr for LoopMode {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "loop" | "Loop" => Ok(LoopMode::Loop),
+            "once" | "Once" => Ok(LoopMode::Once),
+            "ping_pong" | "ping-pong" | "PingPong" => Ok(LoopMode::PingPong),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A sprite sheet laid out as a grid of equally sized frames, read left to right, top to bottom.
+#[derive(Copy, Clone, Debug)]
+pub struct SheetLayout {
+    pub columns: usize,
+    pub rows: usize,
+    /// The number of frames used, for sheets whose last row isn't full.
+    pub frame_count: usize,
+}
+
+impl SheetLayout {
+    pub fn new(columns: usize, rows: usize) -> Self {
+        SheetLayout {
+            columns,
+            rows,
+            frame_count: columns * rows,
+        }
+    }
+    /// The UV rect of frame `index`.
+    pub fn frame_uv(&self, index: usize) -> Rect {
+        let columns = self.columns.max(1);
+        let rows = self.rows.max(1);
+        let width = 1.0 / columns as f32;
+        let height = 1.0 / rows as f32;
+        Rect::new(
+            (index % columns) as f32 * width,
+            (index / columns % rows) as f32 * height,
+            width,
+            height,
+        )
+    }
+}
+
+struct AnimatedImageBehavior {
+    node: GuiNodeId,
+    sheet: Cell<SheetLayout>,
+    fps: Cell<f32>,
+    loop_mode: Cell<LoopMode>,
+    playing: Cell<bool>,
+    elapsed: Cell<f32>,
+    last_update: Cell<Option<Instant>>,
+}
+
+impl AnimatedImageBehavior {
+    fn frame(&self) -> usize {
+        self.loop_mode.get().frame(
+            self.elapsed.get(),
+            self.fps.get(),
+            self.sheet.get().frame_count,
+        )
+    }
+    fn advance(&self, dt: Duration) {
+        if self.playing.get() {
+            self.elapsed.set(self.elapsed.get() + dt.as_secs_f32());
+        }
+    }
+}
+
+impl WidgetBehavior for AnimatedImageBehavior {
+    fn update(&self, nodes: &mut GuiNodeStorage, _input: &WidgetInput) {
+        let now = Instant::now();
+        if let Some(last_update) = self.last_update.replace(Some(now)) {
+            self.advance(now - last_update);
+        }
+        let frame = self.sheet.get().frame_uv(self.frame());
+        if let Some(NodeDraw::Image {
+            frame: node_frame, ..
+        }) = nodes.get_mut(self.node).map(|node| &mut node.draw)
+        {
+            *node_frame = Some(frame);
+        }
+    }
+}
+
+/// An image that flips through the frames of a sprite sheet, for loading spinners and animated
+/// icons. Its look comes from the "animated_image" style class: the image keys, plus `columns`,
+/// `rows` and `frame_count` to describe the sheet, `fps`, `loop_mode` ("loop", "once" or
+/// "ping_pong"), and `playing`.
+///
+/// The animation advances by real time each `Gui::update`, so it keeps playing while the game is
+/// paused.
+pub struct AnimatedImage {
+    node: GuiNodeId,
+    behavior: Rc<AnimatedImageBehavior>,
+}
+
+impl AnimatedImage {
+    pub fn set_texture(&self, gui: &mut Gui, texture: Option<Texture>, sheet: SheetLayout) {
+        if let Some(node) = self.node_data(gui) {
+            if let NodeDraw::Image {
+                texture: old_texture,
+                ..
+            } = &mut node.draw
+            {
+                *old_texture = texture;
+            }
+        }
+        self.behavior.sheet.set(sheet);
+    }
+    pub fn sheet(&self) -> SheetLayout {
+        self.behavior.sheet.get()
+    }
+    pub fn fps(&self) -> f32 {
+        self.behavior.fps.get()
+    }
+    pub fn set_fps(&self, fps: f32) {
+        // Keep the current frame when the speed changes.
+        let fps = fps.max(0.0);
+        let old_fps = self.behavior.fps.replace(fps);
+        if old_fps > 0.0 && fps > 0.0 {
+            self.behavior
+                .elapsed
+                .set(self.behavior.elapsed.get() * old_fps / fps);
+        }
+    }
+    pub fn loop_mode(&self) -> LoopMode {
+        self.behavior.loop_mode.get()
+    }
+    pub fn set_loop_mode(&self, loop_mode: LoopMode) {
+        self.behavior.loop_mode.set(loop_mode);
+    }
+    pub fn play(&self) {
+        self.behavior.playing.set(true);
+    }
+    pub fn pause(&self) {
+        self.behavior.playing.set(false);
+    }
+    pub fn is_playing(&self) -> bool {
+        self.behavior.playing.get()
+    }
+    /// Go back to the first frame.
+    pub fn restart(&self) {
+        self.behavior.elapsed.set(0.0);
+    }
+    /// Jump to frame `index`, counted from the start of the animation.
+    pub fn set_frame(&self, index: usize) {
+        let fps = self.behavior.fps.get();
+        if fps > 0.0 {
+            self.behavior.elapsed.set(index as f32 / fps);
+        }
+    }
+    pub fn frame(&self) -> usize {
+        self.behavior.frame()
+    }
+    /// Whether a `LoopMode::Once` animation has reached its last frame.
+    pub fn is_finished(&self) -> bool {
+        self.behavior.loop_mode.get().is_finished(
+            self.behavior.elapsed.get(),
+            self.behavior.fps.get(),
+            self.behavior.sheet.get().frame_count,
+        )
+    }
+}
+
+impl Widget for AnimatedImage {
+    fn class_name() -> &'static str {
+        "animated_image"
+    }
+    fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
+        let mut sheet = SheetLayout::new(
+            style.widget_value("columns", 1u32) as usize,
+            style.widget_value("rows", 1u32) as usize,
+        );
+        sheet.frame_count = style.widget_value("frame_count", sheet.frame_count as u32) as usize;
+        let fps = style.widget_value("fps", 12.0);
+        let loop_mode = style.widget_value("loop_mode", LoopMode::Loop);
+        let playing = style.widget_value("playing", true);
+        let mut draw = image_draw(&mut style, None);
+        if let NodeDraw::Image { frame, .. } = &mut draw {
+            *frame = Some(sheet.frame_uv(0));
+        }
+        let node = parent.add_child(gui, GuiNode::new(style.widget_layout(), draw));
+        let behavior = gui.register_behavior(AnimatedImageBehavior {
+            node,
+            sheet: Cell::new(sheet),
+            fps: Cell::new(fps),
+            loop_mode: Cell::new(loop_mode),
+            playing: Cell::new(playing),
+            elapsed: Cell::new(0.0),
+            last_update: Cell::new(None),
+        });
+        AnimatedImage { node, behavior }
+    }
+}
+
+impl WidgetNode for AnimatedImage {
+    fn as_any_box(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn node(&self) -> GuiNodeId {
+        self.node
+    }
+}
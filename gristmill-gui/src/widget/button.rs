@@ -1,13 +1,19 @@
 use crate::{
     widget::{
         Image, StyleValues, Text, Widget, WidgetBehavior, WidgetInput, WidgetNode, WidgetNodeExt,
-        WidgetStyle,
+        WidgetRestyle, WidgetStyle,
     },
     Anchor, Gui, GuiNodeId, GuiNodeStorage, NodeDraw,
 };
-use gristmill_core::Color;
+use gristmill_core::{input::ActionState, Color};
 use gristmill_render::Texture;
-use std::{any::Any, cell::Cell, rc::Rc};
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use winit::window::CursorIcon;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum ButtonState {
@@ -31,6 +37,11 @@ impl ButtonState {
 
 struct ButtonDraw {
     texture: Option<Texture>,
+    /// Per-state images. A state with its own image is drawn untinted, otherwise `texture` is
+    /// tinted with the state's color.
+    disabled_texture: Option<Texture>,
+    hovered_texture: Option<Texture>,
+    pressed_texture: Option<Texture>,
     //animate_texture: bool,
     pub disabled: Color,
     pub normal: Color,
@@ -42,6 +53,9 @@ impl Default for ButtonDraw {
     fn default() -> Self {
         ButtonDraw {
             texture: None,
+            disabled_texture: None,
+            hovered_texture: None,
+            pressed_texture: None,
             disabled: Color::new(0.75, 0.75, 0.75, 0.5),
             normal: Color::new_value(0.75),
             hovered: Color::new_value(0.8),
@@ -54,6 +68,9 @@ impl ButtonDraw {
     pub fn with_texture(texture: Texture) -> Self {
         ButtonDraw {
             texture: Some(texture),
+            disabled_texture: None,
+            hovered_texture: None,
+            pressed_texture: None,
             disabled: Color::new(1.0, 1.0, 1.0, 0.5),
             normal: Color::new_value(1.0),
             hovered: Color::new_value(0.9),
@@ -61,65 +78,175 @@ impl ButtonDraw {
         }
     }
 
+    fn from_style(style: &mut StyleValues) -> Self {
+        let mut draw = if let Some(texture) = style.widget_value("texture", None) {
+            ButtonDraw::with_texture(texture)
+        } else {
+            ButtonDraw::default()
+        };
+        draw.disabled_texture = style.widget_value("disabled_texture", None);
+        draw.hovered_texture = style.widget_value("hovered_texture", None);
+        draw.pressed_texture = style.widget_value("pressed_texture", None);
+        draw
+    }
+
     fn draw(&self, state: ButtonState) -> NodeDraw {
-        let color = match state {
-            ButtonState::Disabled => self.disabled,
-            ButtonState::Normal => self.normal,
-            ButtonState::Hovered => self.hovered,
-            ButtonState::Pressed => self.pressed,
+        let (state_texture, color) = match state {
+            ButtonState::Disabled => (&self.disabled_texture, self.disabled),
+            ButtonState::Normal => (&None, self.normal),
+            ButtonState::Hovered => (&self.hovered_texture, self.hovered),
+            ButtonState::Pressed => (&self.pressed_texture, self.pressed),
         };
-        NodeDraw::Rect(self.texture.clone(), color)
+        if let Some(texture) = state_texture {
+            NodeDraw::Rect(Some(texture.clone()), Color::WHITE)
+        } else {
+            NodeDraw::Rect(self.texture.clone(), color)
+        }
     }
 }
 
 struct ButtonBehavior {
     node: GuiNodeId,
-    draw: ButtonDraw,
+    draw: RefCell<ButtonDraw>,
     state: Cell<ButtonState>,
     interactable: Cell<bool>,
     just_released: Cell<bool>,
+    // A click only fires if it was pressed and released on this button. Pressing arms the button,
+    // and moving the pointer off (or focus away) while held disarms it.
+    pointer_armed: Cell<bool>,
+    confirm_armed: Cell<bool>,
+    // Clicks within `debounce` of the last accepted click are ignored.
+    debounce: Cell<Duration>,
+    last_click: Cell<Option<Instant>>,
+}
+
+impl ButtonBehavior {
+    fn update_armed(armed: &Cell<bool>, over: bool, action: &ActionState) -> bool {
+        if over && action.just_pressed() {
+            armed.set(true);
+        }
+        if !over {
+            armed.set(false);
+        }
+        if armed.get() && !action.pressed() {
+            armed.set(false);
+            return true;
+        }
+        false
+    }
+    fn debounce_allows(last_click: Option<Instant>, click: Instant, debounce: Duration) -> bool {
+        last_click.map_or(true, |last| {
+            click.saturating_duration_since(last) >= debounce
+        })
+    }
 }
 
 impl WidgetBehavior for ButtonBehavior {
     fn update(&self, nodes: &mut GuiNodeStorage, input: &WidgetInput) {
+        let hovered = input.pointer_over == Some(self.node);
+        let focused = input.focused == Some(self.node);
+        let mut clicked = false;
+        let mut click_time = None;
         let new_state = if self.interactable.get() {
-            if input.pointer_over == Some(self.node) {
-                if input.state.pressed() {
-                    ButtonState::Pressed
-                } else {
-                    ButtonState::Hovered
-                }
+            if Self::update_armed(&self.pointer_armed, hovered, &input.state) {
+                clicked = true;
+                click_time = input.state.timestamp();
+            }
+            if Self::update_armed(&self.confirm_armed, focused, &input.confirm) {
+                clicked = true;
+                click_time = click_time.or(input.confirm.timestamp());
+            }
+            if self.pointer_armed.get() || self.confirm_armed.get() {
+                ButtonState::Pressed
+            } else if hovered || focused {
+                ButtonState::Hovered
             } else {
                 ButtonState::Normal
             }
         } else {
+            self.pointer_armed.set(false);
+            self.confirm_armed.set(false);
             ButtonState::Disabled
         };
+        if let Some(node) = nodes.get_mut(self.node) {
+            node.flags.focusable = self.interactable.get();
+            node.flags.disabled = !self.interactable.get();
+        }
         self.interactable.set(false);
+        if clicked {
+            let click_time = click_time.unwrap_or_else(Instant::now);
+            clicked = Self::debounce_allows(self.last_click.get(), click_time, self.debounce.get());
+            if clicked {
+                self.last_click.set(Some(click_time));
+            }
+        }
+        self.just_released.set(clicked);
         if new_state != self.state.get() {
-            self.just_released
-                .set(self.state.get() == ButtonState::Pressed && new_state == ButtonState::Hovered);
             self.state.set(new_state);
             if let Some(node) = nodes.get_mut(self.node) {
-                node.draw = self.draw.draw(new_state);
+                node.draw = self.draw.borrow().draw(new_state);
             }
-        } else {
-            self.just_released.set(false);
         }
     }
 }
 
+impl WidgetRestyle for ButtonBehavior {
+    fn restyle(&self, nodes: &mut GuiNodeStorage, mut style: StyleValues) {
+        let draw = ButtonDraw::from_style(&mut style);
+        if let Some(node) = nodes.get_mut(self.node) {
+            node.draw = draw.draw(self.state.get());
+        }
+        *self.draw.borrow_mut() = draw;
+    }
+}
+
 pub struct Button {
     node: GuiNodeId,
     label: Text,
     behavior: Rc<ButtonBehavior>,
+    payload: Option<Rc<dyn Any>>,
 }
 
 impl Button {
+    /// Keep the button enabled for this frame. Returns true if the button was clicked, meaning it
+    /// was both pressed and released while over (or focused on) this button.
     pub fn interact(&mut self) -> bool {
         self.behavior.interactable.set(true);
         self.behavior.just_released.get()
     }
+    /// Like `interact`, but returns the button's payload when it was clicked. See
+    /// `payload_as` for how a payload of the wrong type is handled.
+    pub fn interact_payload<T: Any>(&mut self) -> Option<&T> {
+        if self.interact() {
+            self.payload_as()
+        } else {
+            None
+        }
+    }
+    /// Attach a value to the button, such as the id of the item it stands for, so a handler
+    /// shared by many buttons can tell them apart.
+    pub fn set_payload<T: Any>(&mut self, payload: T) {
+        self.payload = Some(Rc::new(payload));
+    }
+    pub fn set_payload_any(&mut self, payload: Option<Rc<dyn Any>>) {
+        self.payload = payload;
+    }
+    pub fn payload(&self) -> Option<&Rc<dyn Any>> {
+        self.payload.as_ref()
+    }
+    /// The payload as a `T`. Returns `None` if there is no payload, or logs a warning and returns
+    /// `None` if it has a different type.
+    pub fn payload_as<T: Any>(&self) -> Option<&T> {
+        let payload = self.payload.as_ref()?;
+        let value = payload.downcast_ref();
+        if value.is_none() {
+            log::warn!(
+                "Button payload is not a {}, ignoring it.",
+                std::any::type_name::<T>()
+            );
+        }
+        value
+    }
     pub fn state(&self) -> ButtonState {
         self.behavior.state.get()
     }
@@ -129,23 +256,30 @@ impl Button {
     {
         self.label.set_text_string(gui, text);
     }
+    /// Ignore clicks that come less than `debounce` after the previous one, such as an accidental
+    /// double-tap. Off (zero) by default. Clicks are timed with `ActionState::timestamp` if the
+    /// input system stamps events, or else the frame they were seen.
+    pub fn set_debounce(&self, debounce: Duration) {
+        self.behavior.debounce.set(debounce);
+    }
 }
 
 impl Widget for Button {
     fn class_name() -> &'static str {
         "button"
     }
+    #[cfg(feature = "accessibility")]
+    fn access_role() -> Option<accesskit::Role> {
+        Some(accesskit::Role::Button)
+    }
     fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
-        let draw = if let Some(texture) = style.widget_value("texture", None) {
-            ButtonDraw::with_texture(texture)
-        } else {
-            ButtonDraw::default()
-        };
+        let draw = ButtonDraw::from_style(&mut style);
         let label_text = style.widget_value("label", String::new());
 
         let image = Image::new(gui, parent, StyleValues::new());
         let image_node = image.node_data(gui).unwrap();
         image_node.flags.pointer_opaque = true;
+        image_node.cursor = Some(CursorIcon::Hand);
         image_node.layout = style.widget_layout();
         image_node.draw = draw.draw(ButtonState::Disabled);
         let label = Text::new(gui, image.node(), StyleValues::new());
@@ -154,17 +288,27 @@ impl Widget for Button {
 
         let behavior = gui.register_behavior(ButtonBehavior {
             node: image.node(),
-            draw,
+            draw: RefCell::new(draw),
             state: Cell::new(ButtonState::Disabled),
             interactable: Cell::new(false),
             just_released: Cell::new(false),
+            pointer_armed: Cell::new(false),
+            confirm_armed: Cell::new(false),
+            debounce: Cell::new(Duration::from_millis(
+                style.widget_value("debounce", 0u32).into(),
+            )),
+            last_click: Cell::new(None),
         });
         Button {
             node: image.node(),
             label,
             behavior,
+            payload: None,
         }
     }
+    fn restyle_handle(&self) -> Option<Rc<dyn WidgetRestyle>> {
+        Some(self.behavior.clone())
+    }
 }
 
 impl WidgetNode for Button {
@@ -1,22 +1,104 @@
 use crate::{
-    widget::{StyleValues, Widget, WidgetNode, WidgetNodeExt, WidgetStyle},
-    Gui, GuiNode, GuiNodeExt, GuiNodeId, NodeDraw,
+    widget::{StyleValues, Widget, WidgetNode, WidgetNodeExt, WidgetRestyle, WidgetStyle},
+    Gui, GuiNode, GuiNodeExt, GuiNodeId, GuiNodeStorage, NodeDraw, ScaleMode,
 };
-use gristmill_core::Color;
+use gristmill_core::{geom2d::EdgeRect, Color};
 use gristmill_render::Texture;
-use std::any::Any;
+use std::{any::Any, rc::Rc};
+
+pub(super) fn image_draw(style: &mut StyleValues, texture: Option<Texture>) -> NodeDraw {
+    let texture = style.widget_value("texture", texture);
+    let color = style.widget_value("color", Color::WHITE);
+    if style.contains_key("slice_border") {
+        NodeDraw::NineSlice {
+            texture,
+            color,
+            border: style.widget_value("slice_border", EdgeRect::ZERO),
+            tile: style.widget_value("slice_tile", false),
+        }
+    } else {
+        NodeDraw::Image {
+            texture,
+            color,
+            corner_radius: style.widget_value("corner_radius", 0.0),
+            scale_mode: style.widget_value("scale_mode", ScaleMode::Stretch),
+            grayscale: style.widget_value("grayscale", false),
+            premultiplied: style.widget_value("premultiplied", false),
+            frame: None,
+        }
+    }
+}
+
+struct ImageRestyle(GuiNodeId);
+
+impl WidgetRestyle for ImageRestyle {
+    fn restyle(&self, nodes: &mut GuiNodeStorage, mut style: StyleValues) {
+        // The texture is usually set from code, so keep it unless the style has one.
+        if let Some(node) = nodes.get_mut(self.0) {
+            let texture = match &node.draw {
+                NodeDraw::Image { texture, .. } | NodeDraw::NineSlice { texture, .. } => {
+                    texture.clone()
+                }
+                _ => None,
+            };
+            node.draw = image_draw(&mut style, texture);
+        }
+    }
+}
 
 pub struct Image(GuiNodeId);
 
 impl Image {
     pub fn set_texture(&self, gui: &mut Gui, texture: Option<Texture>) {
+        self.set_texture_and_color(gui, texture, Color::WHITE);
+    }
+    pub fn set_texture_and_color(&self, gui: &mut Gui, texture: Option<Texture>, color: Color) {
         if let Some(node) = self.node_data(gui) {
-            node.draw = NodeDraw::Rect(texture, Color::WHITE);
+            if let NodeDraw::Image {
+                texture: old_texture,
+                color: old_color,
+                ..
+            }
+            | NodeDraw::NineSlice {
+                texture: old_texture,
+                color: old_color,
+                ..
+            } = &mut node.draw
+            {
+                *old_texture = texture;
+                *old_color = color;
+            }
         }
     }
-    pub fn set_texture_and_color(&self, gui: &mut Gui, texture: Option<Texture>, color: Color) {
+    /// Set the color the texture is multiplied by.
+    pub fn set_tint(&self, gui: &mut Gui, tint: Color) {
+        if let Some(node) = self.node_data(gui) {
+            if let NodeDraw::Image { color, .. } | NodeDraw::NineSlice { color, .. } =
+                &mut node.draw
+            {
+                *color = tint;
+            }
+        }
+    }
+    pub fn set_grayscale(&self, gui: &mut Gui, enabled: bool) {
+        if let Some(node) = self.node_data(gui) {
+            if let NodeDraw::Image { grayscale, .. } = &mut node.draw {
+                *grayscale = enabled;
+            }
+        }
+    }
+    pub fn set_premultiplied(&self, gui: &mut Gui, enabled: bool) {
+        if let Some(node) = self.node_data(gui) {
+            if let NodeDraw::Image { premultiplied, .. } = &mut node.draw {
+                *premultiplied = enabled;
+            }
+        }
+    }
+    pub fn set_scale_mode(&self, gui: &mut Gui, mode: ScaleMode) {
         if let Some(node) = self.node_data(gui) {
-            node.draw = NodeDraw::Rect(texture, color);
+            if let NodeDraw::Image { scale_mode, .. } = &mut node.draw {
+                *scale_mode = mode;
+            }
         }
     }
 }
@@ -25,15 +107,18 @@ impl Widget for Image {
     fn class_name() -> &'static str {
         "image"
     }
+    #[cfg(feature = "accessibility")]
+    fn access_role() -> Option<accesskit::Role> {
+        Some(accesskit::Role::Image)
+    }
     fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
-        let texture = style.widget_value("texture", None);
-        let color = style.widget_value("color", Color::WHITE);
-        let node = parent.add_child(
-            gui,
-            GuiNode::new(style.widget_layout(), NodeDraw::Rect(texture, color)),
-        );
+        let draw = image_draw(&mut style, None);
+        let node = parent.add_child(gui, GuiNode::new(style.widget_layout(), draw));
         Image(node)
     }
+    fn restyle_handle(&self) -> Option<Rc<dyn WidgetRestyle>> {
+        Some(Rc::new(ImageRestyle(self.0)))
+    }
 }
 
 impl WidgetNode for Image {
@@ -0,0 +1,240 @@
+use crate::{
+    widget::{Scrollbar, StyleValues, Widget, WidgetNode, WidgetStyle},
+    Gui, GuiNode, GuiNodeExt, GuiNodeId, NodeFlags,
+};
+use std::{any::Any, collections::BTreeMap, ops::Range};
+
+/// A scrolling list of equally tall rows. Only the rows overlapping the view (plus a few extra
+/// above and below) exist as nodes, so the row count can be arbitrarily large.
+pub struct ListView {
+    node: GuiNodeId,
+    spacer: GuiNodeId,
+    row_height: i32,
+    overscan: usize,
+    row_count: usize,
+    scroll: i32,
+    rows: BTreeMap<usize, Box<dyn WidgetNode>>,
+}
+
+impl ListView {
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+    /// Rows past the new count are removed. Rows that stay in view are kept as they are; call
+    /// `refresh` if their contents changed.
+    pub fn set_row_count(&mut self, gui: &mut Gui, row_count: usize) {
+        self.row_count = row_count;
+        let removed: Vec<usize> = self.rows.range(row_count..).map(|(i, _)| *i).collect();
+        self.remove_rows(gui, removed);
+    }
+    pub fn row_height(&self) -> i32 {
+        self.row_height
+    }
+    pub fn set_row_height(&mut self, gui: &mut Gui, row_height: i32) {
+        self.row_height = row_height.max(1);
+        self.refresh(gui);
+    }
+    /// Remove all rows, so they are recreated on the next `update`.
+    pub fn refresh(&mut self, gui: &mut Gui) {
+        let removed: Vec<usize> = self.rows.keys().copied().collect();
+        self.remove_rows(gui, removed);
+    }
+
+    pub fn scroll(&self) -> i32 {
+        self.scroll
+    }
+    pub fn set_scroll(&mut self, scroll: i32) {
+        self.scroll = scroll.max(0);
+    }
+    pub fn scroll_by(&mut self, delta: i32) {
+        self.set_scroll(self.scroll.saturating_add(delta));
+    }
+    pub fn scroll_to_row(&mut self, index: usize) {
+        self.set_scroll(self.row_top(index));
+    }
+    /// The total height of all rows, or `i32::MAX` if that's taller.
+    pub fn content_height(&self) -> i32 {
+        self.row_top(self.row_count)
+    }
+    fn row_top(&self, index: usize) -> i32 {
+        i32::try_from(index)
+            .unwrap_or(i32::MAX)
+            .saturating_mul(self.row_height)
+    }
+    /// Scroll to where `scrollbar` was dragged, then show this list's scroll position on it. Call
+    /// once per frame, before `update`.
+    pub fn sync_scrollbar(&mut self, gui: &Gui, scrollbar: &Scrollbar) {
+        if let Some(scroll) = scrollbar.take_dragged_scroll() {
+            self.set_scroll(scroll);
+        }
+        let view_height = gui
+            .nodes
+            .get(self.node)
+            .map_or(0, |node| node.rect.height());
+        scrollbar.set_view(view_height, self.content_height(), self.scroll);
+    }
+
+    /// The rows that currently exist as nodes.
+    pub fn instantiated_rows(&self) -> impl Iterator<Item = (usize, GuiNodeId)> + '_ {
+        self.rows.iter().map(|(index, row)| (*index, row.node()))
+    }
+    fn visible_range(&self, view_height: i32) -> Range<usize> {
+        let (scroll, row_height) = (self.scroll as i64, self.row_height as i64);
+        let first = (scroll / row_height) as usize;
+        let last = ((scroll + view_height.max(0) as i64 + row_height - 1) / row_height) as usize;
+        first.saturating_sub(self.overscan)..(last + self.overscan).min(self.row_count)
+    }
+    fn remove_rows(&mut self, gui: &mut Gui, indices: Vec<usize>) {
+        for index in indices {
+            if let Some(row) = self.rows.remove(&index) {
+                gui.remove_node(row.node());
+            }
+        }
+    }
+
+    /// Create rows that scrolled into view with `row_fn(gui, parent, index)`, and remove rows
+    /// that scrolled out. Call once per frame, before `Gui::update`.
+    pub fn update<W, F>(&mut self, gui: &mut Gui, mut row_fn: F)
+    where
+        W: WidgetNode,
+        F: FnMut(&mut Gui, GuiNodeId, usize) -> W,
+    {
+        let view_height = gui
+            .nodes
+            .get(self.node)
+            .map(|node| node.rect.height())
+            .unwrap_or(0);
+        let max_scroll = (self.content_height() - view_height).max(0);
+        self.scroll = self.scroll.min(max_scroll);
+
+        let range = self.visible_range(view_height);
+        let removed: Vec<usize> = self
+            .rows
+            .keys()
+            .filter(|index| !range.contains(index))
+            .copied()
+            .collect();
+        self.remove_rows(gui, removed);
+        for index in range.clone() {
+            if self.rows.contains_key(&index) {
+                continue;
+            }
+            let row = row_fn(gui, self.node, index);
+            if let Some(node) = gui.nodes.get_mut(row.node()) {
+                node.layout.size.y = self.row_height;
+            }
+            self.rows.insert(index, Box::new(row));
+        }
+
+        // The spacer pushes the first row to its scrolled position.
+        if let Some(spacer) = gui.nodes.get_mut(self.spacer) {
+            spacer.layout.size.y = self.row_top(range.start) - self.scroll;
+        }
+        if let Some(children) = gui.node_children.get_mut(self.node) {
+            children.clear();
+            children.push(self.spacer);
+            children.extend(self.rows.values().map(|row| row.node()));
+        }
+    }
+}
+
+impl Widget for ListView {
+    fn class_name() -> &'static str {
+        "list"
+    }
+    #[cfg(feature = "accessibility")]
+    fn access_role() -> Option<accesskit::Role> {
+        Some(accesskit::Role::List)
+    }
+    fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
+        let mut layout = style.widget_layout();
+        layout.child_layout = "vbox".to_owned();
+        layout.child_spacing = 0;
+        let node = parent.add_child(
+            gui,
+            GuiNode {
+                flags: NodeFlags {
+                    clip_children: true,
+                    ..Default::default()
+                },
+                layout,
+                ..Default::default()
+            },
+        );
+        let spacer = node.add_child(gui, GuiNode::default());
+        let row_height: i32 = style.widget_value("row_height", 24);
+        let overscan: i32 = style.widget_value("overscan", 2);
+        ListView {
+            node,
+            spacer,
+            row_height: row_height.max(1),
+            overscan: overscan.max(0) as usize,
+            row_count: 0,
+            scroll: 0,
+            rows: BTreeMap::new(),
+        }
+    }
+}
+
+impl WidgetNode for ListView {
+    fn as_any_box(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn node(&self) -> GuiNodeId {
+        self.node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(row_count: usize, scroll: i32) -> ListView {
+        ListView {
+            node: GuiNodeId::default(),
+            spacer: GuiNodeId::default(),
+            row_height: 24,
+            overscan: 2,
+            row_count,
+            scroll,
+            rows: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn visible_range_is_view_plus_overscan() {
+        assert_eq!(list(100, 0).visible_range(100), 0..7);
+        // Scrolled to row 10 exactly, then partway into it: rows 10 to 14 show either way.
+        assert_eq!(list(100, 240).visible_range(100), 8..17);
+        assert_eq!(list(100, 250).visible_range(100), 8..17);
+        assert_eq!(list(100, 270).visible_range(100), 9..18);
+        // Clipped to the rows that exist.
+        assert_eq!(list(12, 240).visible_range(100), 8..12);
+        assert!(list(0, 0).visible_range(100).is_empty());
+        assert!(list(100, 0).visible_range(-5).len() <= 3);
+    }
+
+    #[test]
+    fn visible_row_count_stays_bounded() {
+        let view_height = 100;
+        let max_rows = (view_height / 24 + 2) as usize + 2 * 2;
+        for row_count in [10, 1_000, 10_000_000, usize::MAX] {
+            let content_height = list(row_count, 0).content_height();
+            for scroll in [0, 5_000, content_height - view_height, i32::MAX] {
+                let range = list(row_count, scroll.max(0)).visible_range(view_height);
+                assert!(range.len() <= max_rows, "{row_count} rows at {scroll}");
+                assert!(range.end <= row_count);
+            }
+        }
+    }
+
+    #[test]
+    fn content_height_saturates() {
+        assert_eq!(list(10, 0).content_height(), 240);
+        assert_eq!(list(100_000_000, 0).content_height(), i32::MAX);
+        assert_eq!(list(usize::MAX, 0).content_height(), i32::MAX);
+        let mut far = list(usize::MAX, 0);
+        far.scroll_to_row(usize::MAX - 1);
+        assert_eq!(far.scroll(), i32::MAX);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{
+    widget::{
+        StyleValues, Text, Widget, WidgetBehavior, WidgetInput, WidgetNode, WidgetNodeExt,
+        WidgetStyle,
+    },
+    Anchor, Gui, GuiNode, GuiNodeExt, GuiNodeId, GuiNodeStorage, NodeDraw, NodeFlags,
+};
+use std::{any::Any, cell::Cell, rc::Rc, time::Instant};
+
+/// How far text that overflows by `overflow` has scrolled after `elapsed` seconds, when it pauses
+/// at each end and then scrolls back.
+fn bounce_offset(elapsed: f32, overflow: f32, speed: f32, pause: f32) -> f32 {
+    if overflow <= 0.0 || speed <= 0.0 {
+        return 0.0;
+    }
+    let travel = overflow / speed;
+    let t = elapsed % (2.0 * (pause + travel));
+    if t < pause {
+        0.0
+    } else if t < pause + travel {
+        (t - pause) * speed
+    } else if t < 2.0 * pause + travel {
+        overflow
+    } else {
+        overflow - (t - 2.0 * pause - travel) * speed
+    }
+}
+/// How far looping text has scrolled after `elapsed` seconds. It pauses at the start of each
+/// loop, which is seamless because the second copy of the text ends up where the first started.
+fn loop_offset(elapsed: f32, period: f32, speed: f32, pause: f32) -> f32 {
+    if period <= 0.0 || speed <= 0.0 {
+        return 0.0;
+    }
+    let travel = period / speed;
+    let t = elapsed % (pause + travel);
+    if t < pause {
+        0.0
+    } else {
+        (t - pause) * speed
+    }
+}
+
+struct MarqueeBehavior {
+    node: GuiNodeId,
+    texts: [GuiNodeId; 2],
+    text_width: Cell<f32>,
+    start: Cell<Instant>,
+    speed: Cell<f32>,
+    pause: Cell<f32>,
+    wrap: bool,
+    gap: f32,
+}
+
+impl WidgetBehavior for MarqueeBehavior {
+    fn update(&self, nodes: &mut GuiNodeStorage, _input: &WidgetInput) {
+        let width = if let Some(node) = nodes.get(self.node).filter(|node| node.visible) {
+            node.rect.width() as f32
+        } else {
+            return;
+        };
+        let text_width = self.text_width.get();
+        let overflow = text_width - width;
+        let elapsed = self.start.get().elapsed().as_secs_f32();
+        let (speed, pause) = (self.speed.get(), self.pause.get());
+        let offset = if overflow <= 0.0 {
+            0.0
+        } else if self.wrap {
+            loop_offset(elapsed, text_width + self.gap, speed, pause)
+        } else {
+            bounce_offset(elapsed, overflow, speed, pause)
+        };
+
+        let [first, second] = self.texts;
+        if let Some(node) = nodes.get_mut(first) {
+            node.layout.margin.left = -offset.round() as i32;
+        }
+        if let Some(node) = nodes.get_mut(second) {
+            node.flags.visible = self.wrap && overflow > 0.0;
+            node.layout.margin.left = (text_width + self.gap - offset).round() as i32;
+        }
+    }
+}
+
+/// A single line of text that scrolls back and forth (or loops, with the "wrap" style) when it is
+/// wider than the node. Text that fits stays still.
+pub struct Marquee {
+    node: GuiNodeId,
+    texts: [Text; 2],
+    behavior: Rc<MarqueeBehavior>,
+}
+
+impl Marquee {
+    pub fn set_text_string<S>(&self, gui: &mut Gui, text: S)
+    where
+        S: Into<String>,
+    {
+        let text = text.into();
+        for widget in self.texts.iter() {
+            widget.set_text_string(gui, text.clone());
+        }
+        let text_width = self.texts[0].measure(gui).x;
+        for widget in self.texts.iter() {
+            if let Some(node) = widget.node_data(gui) {
+                node.layout.size.x = (text_width.ceil() as i32).max(1);
+            }
+        }
+        self.behavior.text_width.set(text_width);
+        self.restart();
+    }
+    /// Scroll from the start again.
+    pub fn restart(&self) {
+        self.behavior.start.set(Instant::now());
+    }
+    /// Scroll speed in GUI units per second.
+    pub fn set_speed(&self, speed: f32) {
+        self.behavior.speed.set(speed);
+    }
+    /// How long to wait at each end, in seconds.
+    pub fn set_pause(&self, pause: f32) {
+        self.behavior.pause.set(pause.max(0.0));
+    }
+}
+
+impl Widget for Marquee {
+    fn class_name() -> &'static str {
+        "marquee"
+    }
+    #[cfg(feature = "accessibility")]
+    fn access_role() -> Option<accesskit::Role> {
+        Some(accesskit::Role::StaticText)
+    }
+    fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
+        let text = style.widget_value("text", String::new());
+        let node = parent.add_child(
+            gui,
+            GuiNode {
+                flags: NodeFlags {
+                    clip_children: true,
+                    ..Default::default()
+                },
+                layout: style.widget_layout(),
+                draw: NodeDraw::None,
+                ..Default::default()
+            },
+        );
+        let texts = [gui.create_widget::<Text>(node), gui.create_widget(node)];
+        for widget in texts.iter() {
+            widget.set_text_align(gui, (Anchor::Begin, Anchor::Middle), false);
+        }
+        texts[1].set_visible(gui, false);
+        let behavior = gui.register_behavior(MarqueeBehavior {
+            node,
+            texts: [texts[0].node(), texts[1].node()],
+            text_width: Cell::new(0.0),
+            start: Cell::new(Instant::now()),
+            speed: Cell::new(style.widget_value("speed", 30.0)),
+            pause: Cell::new(style.widget_value("pause", 1.0)),
+            wrap: style.widget_value("wrap", false),
+            gap: style.widget_value("gap", 32.0),
+        });
+        let marquee = Marquee {
+            node,
+            texts,
+            behavior,
+        };
+        marquee.set_text_string(gui, text);
+        marquee
+    }
+}
+
+impl WidgetNode for Marquee {
+    fn as_any_box(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn node(&self) -> GuiNodeId {
+        self.node
+    }
+}
@@ -1,27 +1,61 @@
+mod animated_image;
 mod button;
 mod image;
+mod list;
+mod marquee;
 mod panel;
+mod progress;
+mod radial;
+mod scrollbar;
+mod separator;
+mod spacer;
+mod stepper;
 mod style;
 mod text;
+mod window;
 
+pub use animated_image::*;
 pub use button::*;
 pub use image::*;
+pub use list::*;
+pub use marquee::*;
 pub use panel::*;
+pub use progress::*;
+pub use radial::*;
+pub use scrollbar::*;
+pub use separator::*;
+pub use spacer::*;
+pub use stepper::*;
 pub use style::*;
 pub use text::*;
+pub use window::*;
 
-use crate::{Gui, GuiNode, GuiNodeId, GuiNodeStorage};
+use crate::{Distribution, Gui, GuiNode, GuiNodeId, GuiNodeStorage};
 use gristmill_core::{geom2d::EdgeRect, input::ActionState, math::IVec2};
-use std::any::Any;
+use std::{any::Any, rc::Rc};
 
 pub struct WidgetInput {
     pub state: ActionState,
+    /// The pointer position in GUI units, the same space as node rects.
+    pub pointer: Option<IVec2>,
     pub pointer_over: Option<GuiNodeId>,
+    pub focused: Option<GuiNodeId>,
+    /// The "confirm" action, used to activate the focused node.
+    pub confirm: ActionState,
 }
 
 pub trait Widget: Sized {
     fn class_name() -> &'static str;
     fn new(gui: &mut Gui, parent: GuiNodeId, style: StyleValues) -> Self;
+    /// Return a handle to re-apply style values when `Gui::set_styles` swaps the styles.
+    fn restyle_handle(&self) -> Option<Rc<dyn WidgetRestyle>> {
+        None
+    }
+    /// The role screen readers announce this widget as; see the `access` module.
+    #[cfg(feature = "accessibility")]
+    fn access_role() -> Option<accesskit::Role> {
+        None
+    }
 }
 
 pub trait WidgetNode: 'static {
@@ -32,8 +66,11 @@ pub trait WidgetNode: 'static {
 pub trait WidgetNodeExt {
     fn node_data<'a>(&self, gui: &'a mut Gui) -> Option<&'a mut GuiNode>;
     fn set_visible(&self, gui: &mut Gui, visible: bool);
+    fn set_opacity(&self, gui: &mut Gui, opacity: f32);
+    fn set_clip_children(&self, gui: &mut Gui, clip: bool);
     fn set_child_layout<S: Into<String>>(&self, gui: &mut Gui, layout: S);
     fn set_child_spacing(&self, gui: &mut Gui, spacing: i32);
+    fn set_child_distribution(&self, gui: &mut Gui, distribution: Distribution);
     fn set_layout_size(&self, gui: &mut Gui, size: IVec2);
     fn set_layout_margin(&self, gui: &mut Gui, margin: EdgeRect);
     fn set_layout_width(&self, gui: &mut Gui, width: i32);
@@ -48,6 +85,16 @@ impl<T: WidgetNode> WidgetNodeExt for T {
             node.flags.visible = visible;
         }
     }
+    fn set_opacity(&self, gui: &mut Gui, opacity: f32) {
+        if let Some(node) = self.node_data(gui) {
+            node.opacity = opacity.clamp(0.0, 1.0);
+        }
+    }
+    fn set_clip_children(&self, gui: &mut Gui, clip: bool) {
+        if let Some(node) = self.node_data(gui) {
+            node.flags.clip_children = clip;
+        }
+    }
     fn set_child_layout<S: Into<String>>(&self, gui: &mut Gui, layout: S) {
         if let Some(node) = self.node_data(gui) {
             node.layout.child_layout = layout.into();
@@ -58,6 +105,11 @@ impl<T: WidgetNode> WidgetNodeExt for T {
             node.layout.child_spacing = spacing;
         }
     }
+    fn set_child_distribution(&self, gui: &mut Gui, distribution: Distribution) {
+        if let Some(node) = self.node_data(gui) {
+            node.layout.child_distribution = distribution;
+        }
+    }
     fn set_layout_size(&self, gui: &mut Gui, size: IVec2) {
         if let Some(node) = self.node_data(gui) {
             node.layout.size = size;
@@ -92,3 +144,7 @@ impl WidgetNode for GuiNodeId {
 pub trait WidgetBehavior: 'static {
     fn update(&self, nodes: &mut GuiNodeStorage, input: &WidgetInput);
 }
+
+pub trait WidgetRestyle: 'static {
+    fn restyle(&self, nodes: &mut GuiNodeStorage, style: StyleValues);
+}
@@ -1,5 +1,7 @@
 use crate::{
-    widget::{StyleValues, Widget, WidgetBehavior, WidgetInput, WidgetNode, WidgetNodeExt},
+    widget::{
+        StyleValues, Widget, WidgetBehavior, WidgetInput, WidgetNode, WidgetNodeExt, WidgetRestyle,
+    },
     Gui, GuiNode, GuiNodeExt, GuiNodeId, GuiNodeStorage, NodeDraw, NodeFlags,
 };
 use std::{any::Any, rc::Rc};
@@ -17,6 +19,15 @@ impl WidgetBehavior for PanelBehavior {
     }
 }
 
+impl WidgetRestyle for PanelBehavior {
+    fn restyle(&self, nodes: &mut GuiNodeStorage, mut style: StyleValues) {
+        if let Some(node) = nodes.get_mut(self.0) {
+            node.flags.clip_children = style.widget_value("clip_children", false);
+            node.shadow = style.widget_shadow();
+        }
+    }
+}
+
 pub struct Panel(GuiNodeId, Rc<PanelBehavior>);
 
 impl Panel {
@@ -39,16 +50,21 @@ impl Widget for Panel {
             gui,
             GuiNode {
                 flags: NodeFlags {
-                    visible: true,
                     pointer_opaque: true,
+                    clip_children: style.widget_value("clip_children", false),
+                    ..Default::default()
                 },
                 layout: style.widget_layout(),
+                shadow: style.widget_shadow(),
                 ..Default::default()
             },
         );
         let behavior = gui.register_behavior(PanelBehavior(node));
         Panel(node, behavior)
     }
+    fn restyle_handle(&self) -> Option<Rc<dyn WidgetRestyle>> {
+        Some(self.1.clone())
+    }
 }
 
 impl WidgetNode for Panel {
@@ -0,0 +1,140 @@
+use crate::{
+    widget::{
+        StyleValues, Text, Widget, WidgetBehavior, WidgetInput, WidgetNode, WidgetNodeExt,
+        WidgetStyle,
+    },
+    Anchor, Gui, GuiNode, GuiNodeExt, GuiNodeId, GuiNodeStorage, NodeDraw, NodeLayout,
+};
+use gristmill_core::{asset::LoadingProgress, math::IVec2, Color};
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+fn fill_width(track: i32, fraction: f32) -> i32 {
+    (track as f32 * fraction.clamp(0.0, 1.0)).round() as i32
+}
+
+struct ProgressBarBehavior {
+    node: GuiNodeId,
+    fill: GuiNodeId,
+    label: GuiNodeId,
+    source: RefCell<Option<LoadingProgress>>,
+    value: Cell<f32>,
+    status: RefCell<String>,
+}
+
+impl WidgetBehavior for ProgressBarBehavior {
+    fn update(&self, nodes: &mut GuiNodeStorage, _input: &WidgetInput) {
+        if let Some(source) = self.source.borrow().as_ref() {
+            self.value.set(source.fraction());
+            *self.status.borrow_mut() = source.status();
+        }
+        let track = if let Some(node) = nodes.get(self.node).filter(|node| node.visible) {
+            node.rect.width()
+        } else {
+            return;
+        };
+        if let Some(node) = nodes.get_mut(self.fill) {
+            // A width of 0 would fill the whole track, so hide the fill instead.
+            let width = fill_width(track, self.value.get());
+            node.flags.visible = width > 0;
+            node.layout.size.x = width.max(1);
+        }
+        if let Some(NodeDraw::Text(section)) = nodes.get_mut(self.label).map(|node| &mut node.draw)
+        {
+            let status = self.status.borrow();
+            if let Some(text) = section.text.first_mut().filter(|text| text.text != *status) {
+                text.text.clone_from(&status);
+            }
+        }
+    }
+}
+
+/// A horizontal bar that fills from the left, with status text centered on it. Its look comes
+/// from the "progress_bar" style class: track and fill colors and textures, and whether to show
+/// the status text.
+///
+/// Set the value directly, or `bind` a `LoadingProgress` to follow a background load.
+pub struct ProgressBar {
+    node: GuiNodeId,
+    behavior: Rc<ProgressBarBehavior>,
+}
+
+impl ProgressBar {
+    /// Follow `progress` every frame, replacing the value and status.
+    pub fn bind(&self, progress: Option<LoadingProgress>) {
+        *self.behavior.source.borrow_mut() = progress;
+    }
+    /// Set the fill from 0 to 1. Overwritten while a `LoadingProgress` is bound.
+    pub fn set_value(&self, value: f32) {
+        self.behavior.value.set(value.clamp(0.0, 1.0));
+    }
+    pub fn value(&self) -> f32 {
+        self.behavior.value.get()
+    }
+    pub fn set_status<S: Into<String>>(&self, status: S) {
+        *self.behavior.status.borrow_mut() = status.into();
+    }
+}
+
+impl Widget for ProgressBar {
+    fn class_name() -> &'static str {
+        "progress_bar"
+    }
+    fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
+        let mut layout = style.widget_layout();
+        if layout.size.y == 0 {
+            layout.size.y = 24;
+        }
+        let track_texture = style.widget_value("track_texture", None);
+        let track_color = style.widget_value("track_color", Color::new_value(0.15));
+        let fill_texture = style.widget_value("fill_texture", None);
+        let fill_color = style.widget_value("fill_color", Color::new_opaque(0.3, 0.6, 0.9));
+        let show_status = style.widget_value("show_status", true);
+
+        let node = parent.add_child(
+            gui,
+            GuiNode {
+                layout,
+                draw: NodeDraw::Rect(track_texture, track_color),
+                ..Default::default()
+            },
+        );
+        let fill = node.add_child(
+            gui,
+            GuiNode {
+                layout: NodeLayout {
+                    size: IVec2::new(1, 0),
+                    ..Default::default()
+                },
+                draw: NodeDraw::Rect(fill_texture, fill_color),
+                ..Default::default()
+            },
+        );
+        let label = gui.create_widget::<Text>(node);
+        label.set_text_align(gui, (Anchor::Middle, Anchor::Middle), false);
+        label.set_text_string(gui, "");
+        label.set_visible(gui, show_status);
+
+        let behavior = gui.register_behavior(ProgressBarBehavior {
+            node,
+            fill,
+            label: label.node(),
+            source: RefCell::new(None),
+            value: Cell::new(style.widget_value("value", 0.0)),
+            status: RefCell::new(String::new()),
+        });
+        ProgressBar { node, behavior }
+    }
+}
+
+impl WidgetNode for ProgressBar {
+    fn as_any_box(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn node(&self) -> GuiNodeId {
+        self.node
+    }
+}
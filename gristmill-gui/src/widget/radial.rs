@@ -0,0 +1,204 @@
+use crate::{
+    layout::Radial,
+    widget::{StyleValues, Widget, WidgetBehavior, WidgetInput, WidgetNode, WidgetStyle},
+    Gui, GuiNode, GuiNodeExt, GuiNodeId, GuiNodeStorage, NodeDraw, NodeFlags,
+};
+use gristmill_core::{
+    math::{IVec2, Vec2},
+    Color,
+};
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+struct RadialBehavior {
+    node: GuiNodeId,
+    items: RefCell<Vec<GuiNodeId>>,
+    open: Cell<bool>,
+    close_on_release: Cell<bool>,
+    selected: Cell<Option<usize>>,
+    activated: Cell<Option<usize>>,
+    direction: Cell<Option<Vec2>>,
+    last_pointer: Cell<Option<IVec2>>,
+    dead_zone: f32,
+    unselected_opacity: f32,
+}
+
+impl RadialBehavior {
+    fn close(&self, nodes: &mut GuiNodeStorage) -> Option<usize> {
+        let selected = self.selected.take();
+        self.open.set(false);
+        self.activated.set(selected);
+        if let Some(node) = nodes.get_mut(self.node) {
+            node.flags.visible = false;
+        }
+        selected
+    }
+}
+
+impl WidgetBehavior for RadialBehavior {
+    fn update(&self, nodes: &mut GuiNodeStorage, input: &WidgetInput) {
+        self.activated.set(None);
+        if input.pointer.is_some() {
+            self.last_pointer.set(input.pointer);
+        }
+        if !self.open.get() {
+            return;
+        }
+
+        let items = self.items.borrow();
+        let center = nodes.get(self.node).map(|node| node.rect.center());
+        if let Some(direction) = self.direction.take() {
+            if direction.length() >= 0.5 {
+                self.selected.set(Radial::sector(direction, items.len()));
+            }
+        } else if let (Some(pointer), Some(center)) = (input.pointer, center) {
+            let offset = (pointer - center).as_vec2();
+            self.selected.set(if offset.length() >= self.dead_zone {
+                Radial::sector(offset, items.len())
+            } else {
+                None
+            });
+        }
+        for (index, item) in items.iter().enumerate() {
+            if let Some(node) = nodes.get_mut(*item) {
+                node.opacity = if self.selected.get() == Some(index) {
+                    1.0
+                } else {
+                    self.unselected_opacity
+                };
+            }
+        }
+        drop(items);
+
+        if self.close_on_release.get() && input.state.just_released() {
+            self.close(nodes);
+        }
+    }
+}
+
+/// A ring of items, selected by the direction of the pointer (or a stick) from the center. Items
+/// are placed with the "radial" layout, starting at the top and going clockwise.
+///
+/// The menu starts closed. Create it under the root node so that `open_at` positions are in
+/// screen space.
+pub struct RadialMenu {
+    node: GuiNodeId,
+    behavior: Rc<RadialBehavior>,
+}
+
+impl RadialMenu {
+    pub fn add_item(&self, gui: &mut Gui, item: GuiNode) -> GuiNodeId {
+        let node = self.node.add_child(gui, item);
+        self.behavior.items.borrow_mut().push(node);
+        node
+    }
+    pub fn item_count(&self) -> usize {
+        self.behavior.items.borrow().len()
+    }
+    pub fn selected(&self) -> Option<usize> {
+        self.behavior.selected.get()
+    }
+    /// The item that was chosen when the menu closed this frame, if any.
+    pub fn activated(&self) -> Option<usize> {
+        self.behavior.activated.get()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.behavior.open.get()
+    }
+    /// Open the menu where it was last placed.
+    pub fn open(&self, gui: &mut Gui) {
+        self.behavior.open.set(true);
+        self.behavior.selected.set(None);
+        if let Some(node) = gui.nodes_mut().get_mut(self.node) {
+            node.flags.visible = true;
+        }
+    }
+    /// Open the menu centered on `center`, relative to the parent's top-left corner.
+    pub fn open_at(&self, gui: &mut Gui, center: IVec2) {
+        if let Some(node) = gui.nodes_mut().get_mut(self.node) {
+            let position = center - node.layout.size / 2;
+            node.layout.margin.left = position.x;
+            node.layout.margin.top = position.y;
+        }
+        self.open(gui);
+    }
+    /// Open the menu centered on the pointer's last known position.
+    pub fn open_at_pointer(&self, gui: &mut Gui) {
+        if let Some(pointer) = self.behavior.last_pointer.get() {
+            self.open_at(gui, pointer);
+        } else {
+            self.open(gui);
+        }
+    }
+    /// Close the menu, activating and returning the selected item.
+    pub fn close(&self, gui: &mut Gui) -> Option<usize> {
+        self.behavior.close(gui.nodes_mut())
+    }
+    /// Close the menu when the primary pointer button is released. On by default, for menus that
+    /// are held open.
+    pub fn set_close_on_release(&self, close_on_release: bool) {
+        self.behavior.close_on_release.set(close_on_release);
+    }
+    /// Select with a direction instead of the pointer this frame, such as a gamepad stick.
+    /// Directions shorter than 0.5 keep the current selection.
+    pub fn select_direction(&self, direction: Vec2) {
+        self.behavior.direction.set(Some(direction));
+    }
+}
+
+impl Widget for RadialMenu {
+    fn class_name() -> &'static str {
+        "radial_menu"
+    }
+    #[cfg(feature = "accessibility")]
+    fn access_role() -> Option<accesskit::Role> {
+        Some(accesskit::Role::Menu)
+    }
+    fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
+        let mut layout = style.widget_layout();
+        layout.child_layout = "radial".to_owned();
+        let texture = style.widget_value("texture", None);
+        let color = style.widget_value("color", Color::new(0.0, 0.0, 0.0, 0.5));
+        let corner_radius =
+            style.widget_value("corner_radius", layout.size.min_element() as f32 / 2.0);
+        let node = parent.add_child(
+            gui,
+            GuiNode {
+                flags: NodeFlags {
+                    visible: false,
+                    pointer_opaque: true,
+                    ..Default::default()
+                },
+                layout,
+                draw: NodeDraw::RoundedRect(texture, color, corner_radius),
+                ..Default::default()
+            },
+        );
+        let behavior = gui.register_behavior(RadialBehavior {
+            node,
+            items: RefCell::new(Vec::new()),
+            open: Cell::new(false),
+            close_on_release: Cell::new(true),
+            selected: Cell::new(None),
+            activated: Cell::new(None),
+            direction: Cell::new(None),
+            last_pointer: Cell::new(None),
+            dead_zone: style.widget_value("dead_zone", 16.0),
+            unselected_opacity: style.widget_value("unselected_opacity", 0.5),
+        });
+        RadialMenu { node, behavior }
+    }
+}
+
+impl WidgetNode for RadialMenu {
+    fn as_any_box(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn node(&self) -> GuiNodeId {
+        self.node
+    }
+}
@@ -0,0 +1,242 @@
+use crate::{
+    widget::{StyleValues, Widget, WidgetBehavior, WidgetInput, WidgetNode, WidgetStyle},
+    Anchor, Gui, GuiNode, GuiNodeExt, GuiNodeId, GuiNodeStorage, NodeDraw, NodeFlags, NodeLayout,
+};
+use gristmill_core::{math::IVec2, Color};
+use std::{any::Any, cell::Cell, rc::Rc};
+
+/// The thumb's length: the visible fraction of the track, but at least `min_size` so it stays
+/// grabbable on long content.
+fn thumb_size(track: i32, visible: i32, content: i32, min_size: i32) -> i32 {
+    if content <= visible || content <= 0 {
+        return track;
+    }
+    let size = (track as i64 * visible.max(0) as i64 / content as i64) as i32;
+    size.max(min_size).min(track)
+}
+fn thumb_offset(track: i32, thumb: i32, scroll: i32, max_scroll: i32) -> i32 {
+    if max_scroll <= 0 {
+        0
+    } else {
+        ((track - thumb) as i64 * scroll.clamp(0, max_scroll) as i64 / max_scroll as i64) as i32
+    }
+}
+fn offset_scroll(track: i32, thumb: i32, offset: i32, max_scroll: i32) -> i32 {
+    let range = track - thumb;
+    if range <= 0 {
+        0
+    } else {
+        (max_scroll as i64 * offset.clamp(0, range) as i64 / range as i64) as i32
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+struct ScrollView {
+    visible: i32,
+    content: i32,
+    scroll: i32,
+}
+
+impl ScrollView {
+    fn max_scroll(self) -> i32 {
+        (self.content - self.visible).max(0)
+    }
+}
+
+struct ScrollbarBehavior {
+    node: GuiNodeId,
+    thumb: GuiNodeId,
+    min_thumb_size: i32,
+    view: Cell<ScrollView>,
+    // The pointer's y and the thumb's offset when the drag started.
+    drag: Cell<Option<(i32, i32)>>,
+    dragged: Cell<Option<i32>>,
+}
+
+impl WidgetBehavior for ScrollbarBehavior {
+    fn update(&self, nodes: &mut GuiNodeStorage, input: &WidgetInput) {
+        let track = if let Some(node) = nodes.get(self.node).filter(|node| node.visible) {
+            node.rect.height()
+        } else {
+            self.drag.set(None);
+            return;
+        };
+        let mut view = self.view.get();
+        let thumb = thumb_size(track, view.visible, view.content, self.min_thumb_size);
+        let offset = thumb_offset(track, thumb, view.scroll, view.max_scroll());
+
+        if input.state.just_pressed() && input.pointer_over == Some(self.thumb) {
+            self.drag
+                .set(input.pointer.map(|pointer| (pointer.y, offset)));
+        } else if !input.state.pressed() {
+            self.drag.set(None);
+        }
+        let offset = match (self.drag.get(), input.pointer) {
+            (Some((start_y, start_offset)), Some(pointer)) => {
+                let offset = (start_offset + pointer.y - start_y).clamp(0, track - thumb);
+                view.scroll = offset_scroll(track, thumb, offset, view.max_scroll());
+                self.view.set(view);
+                self.dragged.set(Some(view.scroll));
+                offset
+            }
+            _ => offset,
+        };
+
+        if let Some(node) = nodes.get_mut(self.thumb) {
+            node.layout.size.y = thumb.max(1);
+            node.layout.margin.top = offset;
+        }
+    }
+}
+
+/// A vertical scrollbar with a draggable thumb. Its look comes from the "scrollbar" style class:
+/// track and thumb colors and textures, width, and the minimum thumb size.
+///
+/// The scrollbar doesn't scroll anything by itself. Tell it what is visible with `set_view`, and
+/// apply drags from `take_dragged_scroll`; `ListView::sync_scrollbar` does both.
+pub struct Scrollbar {
+    node: GuiNodeId,
+    behavior: Rc<ScrollbarBehavior>,
+}
+
+impl Scrollbar {
+    /// Set the length of the visible area, the length of the content, and the current scroll
+    /// position. Ignored while the thumb is being dragged.
+    pub fn set_view(&self, visible: i32, content: i32, scroll: i32) {
+        if self.behavior.drag.get().is_none() {
+            self.behavior.view.set(ScrollView {
+                visible,
+                content,
+                scroll,
+            });
+        }
+    }
+    pub fn is_dragging(&self) -> bool {
+        self.behavior.drag.get().is_some()
+    }
+    /// The scroll position the thumb was dragged to since this was last called, if it moved.
+    pub fn take_dragged_scroll(&self) -> Option<i32> {
+        self.behavior.dragged.take()
+    }
+}
+
+impl Widget for Scrollbar {
+    fn class_name() -> &'static str {
+        "scrollbar"
+    }
+    fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
+        let mut layout = style.widget_layout();
+        if layout.size.x == 0 {
+            layout.size.x = style.widget_value("width", 12);
+        }
+        let track_texture = style.widget_value("track_texture", None);
+        let track_color = style.widget_value("track_color", Color::new_value(0.15));
+        let thumb_texture = style.widget_value("thumb_texture", None);
+        let thumb_color = style.widget_value("thumb_color", Color::new_value(0.5));
+        let min_thumb_size = style.widget_value("min_thumb_size", 16);
+
+        let node = parent.add_child(
+            gui,
+            GuiNode {
+                flags: NodeFlags {
+                    pointer_opaque: true,
+                    ..Default::default()
+                },
+                layout,
+                draw: NodeDraw::Rect(track_texture, track_color),
+                ..Default::default()
+            },
+        );
+        let thumb = node.add_child(
+            gui,
+            GuiNode {
+                flags: NodeFlags {
+                    pointer_opaque: true,
+                    ..Default::default()
+                },
+                layout: NodeLayout {
+                    size: IVec2::new(0, 1),
+                    anchors: (Anchor::Begin, Anchor::Begin),
+                    ..Default::default()
+                },
+                draw: NodeDraw::Rect(thumb_texture, thumb_color),
+                ..Default::default()
+            },
+        );
+        let behavior = gui.register_behavior(ScrollbarBehavior {
+            node,
+            thumb,
+            min_thumb_size,
+            view: Cell::new(ScrollView::default()),
+            drag: Cell::new(None),
+            dragged: Cell::new(None),
+        });
+        Scrollbar { node, behavior }
+    }
+}
+
+impl WidgetNode for Scrollbar {
+    fn as_any_box(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn node(&self) -> GuiNodeId {
+        self.node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thumb_size_is_visible_fraction_of_track() {
+        assert_eq!(thumb_size(200, 100, 400, 20), 50);
+        assert_eq!(thumb_size(200, 300, 400, 20), 150);
+        // Long content hits the minimum size.
+        assert_eq!(thumb_size(200, 100, 10_000, 20), 20);
+        assert_eq!(thumb_size(10, 100, 10_000, 20), 10);
+        // Content that fits fills the track.
+        assert_eq!(thumb_size(200, 400, 400, 20), 200);
+        assert_eq!(thumb_size(200, 400, 0, 20), 200);
+        // No overflow on huge content.
+        assert_eq!(
+            thumb_size(i32::MAX, i32::MAX - 1, i32::MAX, 0),
+            i32::MAX - 1
+        );
+    }
+
+    #[test]
+    fn thumb_offset_spans_track() {
+        assert_eq!(thumb_offset(200, 40, 0, 800), 0);
+        assert_eq!(thumb_offset(200, 40, 400, 800), 80);
+        assert_eq!(thumb_offset(200, 40, 800, 800), 160);
+        assert_eq!(thumb_offset(200, 40, 900, 800), 160);
+        assert_eq!(thumb_offset(200, 40, -10, 800), 0);
+        assert_eq!(thumb_offset(200, 200, 10, 0), 0);
+    }
+
+    #[test]
+    fn offset_and_scroll_round_trip() {
+        for (track, thumb, max_scroll) in [(200, 40, 800), (200, 40, 160), (200, 40, 100)] {
+            let range = track - thumb;
+            assert_eq!(offset_scroll(track, thumb, 0, max_scroll), 0);
+            assert_eq!(offset_scroll(track, thumb, range, max_scroll), max_scroll);
+            // Scroll positions between two thumb pixels snap back to the lower one, by less than
+            // one pixel's worth of scrolling.
+            for scroll in 0..=max_scroll {
+                let offset = thumb_offset(track, thumb, scroll, max_scroll);
+                let back = offset_scroll(track, thumb, offset, max_scroll);
+                assert!(back <= scroll && (scroll - back) * range < max_scroll + range);
+            }
+            // Dragging the thumb to an offset keeps it there, unless scroll steps are coarser
+            // than thumb pixels.
+            if max_scroll >= range {
+                for offset in 0..=range {
+                    let scroll = offset_scroll(track, thumb, offset, max_scroll);
+                    assert_eq!(thumb_offset(track, thumb, scroll, max_scroll), offset);
+                }
+            }
+        }
+        assert_eq!(offset_scroll(100, 100, 20, 50), 0);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{
+    widget::{StyleValues, Widget, WidgetNode, WidgetRestyle, WidgetStyle},
+    Gui, GuiNode, GuiNodeExt, GuiNodeId, GuiNodeStorage, NodeDraw, NodeLayout,
+};
+use gristmill_core::{math::IVec2, Color};
+use std::{any::Any, rc::Rc};
+
+fn separator_layout(style: &mut StyleValues) -> NodeLayout {
+    let thickness = style.widget_value("thickness", 1);
+    let vertical = style.widget_value("vertical", false);
+    let mut layout = style.widget_layout();
+    // The other axis is left at 0 so the line spans the container.
+    layout.size = if vertical {
+        IVec2::new(thickness, 0)
+    } else {
+        IVec2::new(0, thickness)
+    };
+    layout
+}
+fn separator_draw(style: &mut StyleValues) -> NodeDraw {
+    NodeDraw::Rect(
+        style.widget_value("texture", None),
+        style.widget_value("color", Color::new(0.5, 0.5, 0.5, 0.5)),
+    )
+}
+
+struct SeparatorRestyle(GuiNodeId);
+
+impl WidgetRestyle for SeparatorRestyle {
+    fn restyle(&self, nodes: &mut GuiNodeStorage, mut style: StyleValues) {
+        if let Some(node) = nodes.get_mut(self.0) {
+            node.layout = separator_layout(&mut style);
+            node.draw = separator_draw(&mut style);
+        }
+    }
+}
+
+/// A thin line between items in a box layout. It's horizontal, for a vbox, unless `vertical` is
+/// set in the style. Its look comes from the "separator" style class: `thickness`, `color` and
+/// `texture`, plus a `margin` to leave space around it.
+pub struct Separator(GuiNodeId);
+
+impl Widget for Separator {
+    fn class_name() -> &'static str {
+        "separator"
+    }
+    fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
+        let node = parent.add_child(
+            gui,
+            GuiNode {
+                layout: separator_layout(&mut style),
+                draw: separator_draw(&mut style),
+                ..Default::default()
+            },
+        );
+        Separator(node)
+    }
+    fn restyle_handle(&self) -> Option<Rc<dyn WidgetRestyle>> {
+        Some(Rc::new(SeparatorRestyle(self.0)))
+    }
+}
+
+impl WidgetNode for Separator {
+    fn as_any_box(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn node(&self) -> GuiNodeId {
+        self.0
+    }
+}
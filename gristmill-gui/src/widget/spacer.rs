@@ -0,0 +1,39 @@
+use crate::{
+    widget::{StyleValues, Widget, WidgetNode, WidgetStyle},
+    Gui, GuiNode, GuiNodeExt, GuiNodeId,
+};
+use std::any::Any;
+
+/// An empty node that takes up the leftover space in an hbox or vbox, pushing the siblings on
+/// either side of it apart. Its `grow` weight defaults to 1; set it to 0 and give the spacer a
+/// size for a fixed gap instead.
+pub struct Spacer(GuiNodeId);
+
+impl Widget for Spacer {
+    fn class_name() -> &'static str {
+        "spacer"
+    }
+    fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
+        let grow_set = style.contains_key("grow");
+        let mut layout = style.widget_layout();
+        if !grow_set {
+            layout.grow = 1.0;
+        }
+        Spacer(parent.add_child(
+            gui,
+            GuiNode {
+                layout,
+                ..Default::default()
+            },
+        ))
+    }
+}
+
+impl WidgetNode for Spacer {
+    fn as_any_box(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn node(&self) -> GuiNodeId {
+        self.0
+    }
+}
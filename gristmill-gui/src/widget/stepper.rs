@@ -0,0 +1,180 @@
+use crate::{
+    widget::{Button, StyleValues, Text, Widget, WidgetNode, WidgetNodeExt, WidgetStyle},
+    Anchor, Gui, GuiNode, GuiNodeExt, GuiNodeId,
+};
+use gristmill_core::math::IVec2;
+use std::any::Any;
+
+/// `value` moved by `delta` steps of `step`, kept within `min..=max`.
+fn step_value(value: i32, delta: i32, step: i32, (min, max): (i32, i32)) -> i32 {
+    value
+        .saturating_add(delta.saturating_mul(step))
+        .clamp(min, max)
+}
+/// Whether the - and + buttons are enabled at `value`: each is disabled at its end of the range.
+fn buttons_enabled(value: i32, (min, max): (i32, i32)) -> (bool, bool) {
+    (value > min, value < max)
+}
+
+/// A number with - and + buttons on either side, for integer settings like "Lives: 3" that
+/// don't need a text field. Its layout comes from the "number_stepper" style class, along with
+/// `button_width`, `min`, `max`, `step`, `value` and a `prefix` shown before the number. The
+/// buttons use the "button" class and the number uses the "text" class.
+///
+/// Like `Button`, the stepper only responds while `interact` is called each frame.
+pub struct NumberStepper {
+    node: GuiNodeId,
+    decrease: Button,
+    increase: Button,
+    label: Text,
+    prefix: String,
+    value: i32,
+    range: (i32, i32),
+    step: i32,
+}
+
+impl NumberStepper {
+    /// Keep the stepper enabled for this frame. Returns the new value if a button changed it.
+    /// A button is disabled while the value is at that end of the range.
+    pub fn interact(&mut self, gui: &mut Gui) -> Option<i32> {
+        let (decrease, increase) = buttons_enabled(self.value, self.range);
+        let mut delta = 0;
+        if decrease && self.decrease.interact() {
+            delta -= 1;
+        }
+        if increase && self.increase.interact() {
+            delta += 1;
+        }
+        let value = step_value(self.value, delta, self.step, self.range);
+        if value != self.value {
+            self.set_value(gui, value);
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+    /// Set the value, clamped to the range. Doesn't count as a change for `interact`.
+    pub fn set_value(&mut self, gui: &mut Gui, value: i32) {
+        self.value = value.clamp(self.range.0, self.range.1);
+        self.update_label(gui);
+    }
+    pub fn range(&self) -> (i32, i32) {
+        self.range
+    }
+    pub fn step(&self) -> i32 {
+        self.step
+    }
+    /// Set the allowed values and how far each click moves. The value is clamped to the new
+    /// range; a step below 1 is treated as 1.
+    pub fn set_range(&mut self, gui: &mut Gui, min: i32, max: i32, step: i32) {
+        self.range = (min, max.max(min));
+        self.step = step.max(1);
+        self.set_value(gui, self.value);
+    }
+    pub fn set_prefix<S: Into<String>>(&mut self, gui: &mut Gui, prefix: S) {
+        self.prefix = prefix.into();
+        self.update_label(gui);
+    }
+    fn update_label(&self, gui: &mut Gui) {
+        self.label
+            .set_text_string(gui, format!("{}{}", self.prefix, self.value));
+    }
+}
+
+impl Widget for NumberStepper {
+    fn class_name() -> &'static str {
+        "number_stepper"
+    }
+    fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
+        let mut layout = style.widget_layout();
+        layout.child_layout = "hbox".to_owned();
+        if layout.size.y == 0 {
+            layout.size.y = 24;
+        }
+        let button_width = style.widget_value("button_width", layout.size.y);
+        let min = style.widget_value("min", 0);
+        let max = style.widget_value("max", 10).max(min);
+        let step = style.widget_value("step", 1).max(1);
+        let value = style.widget_value("value", min);
+        let prefix = style.widget_value("prefix", String::new());
+
+        let node = parent.add_child(
+            gui,
+            GuiNode {
+                layout,
+                ..Default::default()
+            },
+        );
+        let decrease = gui.create_widget::<Button>(node);
+        decrease.set_label_string(gui, "-");
+        decrease.set_layout_size(gui, IVec2::new(button_width, 0));
+        let label = gui.create_widget::<Text>(node);
+        label.set_text_align(gui, (Anchor::Middle, Anchor::Middle), false);
+        if let Some(label_node) = label.node_data(gui) {
+            label_node.layout.grow = 1.0;
+        }
+        let increase = gui.create_widget::<Button>(node);
+        increase.set_label_string(gui, "+");
+        increase.set_layout_size(gui, IVec2::new(button_width, 0));
+
+        let mut stepper = NumberStepper {
+            node,
+            decrease,
+            increase,
+            label,
+            prefix,
+            value,
+            range: (min, max),
+            step,
+        };
+        stepper.set_value(gui, value);
+        stepper
+    }
+}
+
+impl WidgetNode for NumberStepper {
+    fn as_any_box(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn node(&self) -> GuiNodeId {
+        self.node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plus_at_max_does_nothing() {
+        let range = (0, 10);
+        assert_eq!(buttons_enabled(10, range), (true, false));
+        assert_eq!(step_value(10, 1, 1, range), 10);
+        assert_eq!(buttons_enabled(0, range), (false, true));
+        assert_eq!(step_value(0, -1, 1, range), 0);
+        assert_eq!(buttons_enabled(5, range), (true, true));
+        assert_eq!(buttons_enabled(3, (3, 3)), (false, false));
+    }
+
+    #[test]
+    fn steps_are_clamped() {
+        let range = (0, 10);
+        assert_eq!(step_value(4, 1, 3, range), 7);
+        assert_eq!(step_value(9, 1, 5, range), 10);
+        assert_eq!(step_value(2, -1, 5, range), 0);
+        // Both buttons in one frame cancel out.
+        assert_eq!(step_value(4, 0, 3, range), 4);
+        assert_eq!(
+            step_value(i32::MAX - 1, 1, i32::MAX, (i32::MIN, i32::MAX)),
+            i32::MAX
+        );
+        assert_eq!(
+            step_value(i32::MIN + 1, -2, i32::MAX, (i32::MIN, i32::MAX)),
+            i32::MIN
+        );
+    }
+}
@@ -1,14 +1,16 @@
+use glyph_brush::FontId;
 use gristmill_core::{
     asset::{self, AssetError, AssetResult},
     geom2d::EdgeRect,
     math::IVec2,
     Color,
 };
-use gristmill_render::{RenderContext, Texture};
+use gristmill_render::{texture_rect::DropShadow, RenderContext, Texture};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Read};
 
-use crate::{Anchor, NodeLayout};
+use super::LoopMode;
+use crate::{Anchor, Corner, Distribution, NodeLayout, ScaleMode};
 
 #[derive(Clone, Deserialize)]
 #[serde(try_from = "toml::Value")]
@@ -18,8 +20,22 @@ pub enum StyleValue {
     Float(f32),
     Boolean(bool),
     Texture(Option<Texture>),
+    Font(StyleFont),
     IntegerArray(Vec<i32>),
     FloatArray(Vec<f32>),
+    /// A table, such as `{all: 8}` for a margin.
+    Table(toml::Table),
+}
+
+/// A font reference: a font registered with `Gui::load_font` or the `[fonts]` table of
+/// gui_styles.toml, and optionally a size that overrides "font_size".
+///
+/// Written as the font's name (`font = "title"`), or as a table with a name and size
+/// (`font = { name = "title", size = 32 }`). Either part of the table can be left out.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StyleFont {
+    pub id: FontId,
+    pub size: Option<i32>,
 }
 
 impl TryFrom<toml::Value> for StyleValue {
@@ -47,7 +63,7 @@ impl TryFrom<toml::Value> for StyleValue {
                     Err("style array must only contain ints or floats")
                 }
             }
-            toml::Value::Table(_value) => Err("table not valid as style value"),
+            toml::Value::Table(value) => Ok(StyleValue::Table(value)),
         }
     }
 }
@@ -83,6 +99,16 @@ impl TryFrom<StyleValue> for u32 {
         Err(())
     }
 }
+impl TryFrom<StyleValue> for f32 {
+    type Error = ();
+    fn try_from(value: StyleValue) -> Result<Self, Self::Error> {
+        match value {
+            StyleValue::Float(value) => Ok(value),
+            StyleValue::Integer(value) => Ok(value as f32),
+            _ => Err(()),
+        }
+    }
+}
 impl TryFrom<StyleValue> for Option<Texture> {
     type Error = ();
     fn try_from(value: StyleValue) -> Result<Self, Self::Error> {
@@ -93,28 +119,60 @@ impl TryFrom<StyleValue> for Option<Texture> {
         }
     }
 }
+impl TryFrom<StyleValue> for StyleFont {
+    type Error = ();
+    fn try_from(value: StyleValue) -> Result<Self, Self::Error> {
+        match value {
+            StyleValue::Font(value) => Ok(value),
+            StyleValue::Integer(size) => Ok(StyleFont {
+                size: Some(size),
+                ..Default::default()
+            }),
+            // A table with a name is only left unresolved if the name is unknown.
+            StyleValue::Table(value) if !value.contains_key("name") => {
+                let size = value.get("size").and_then(toml::Value::as_integer);
+                Ok(StyleFont {
+                    size: size.map(|size| size as i32),
+                    ..Default::default()
+                })
+            }
+            _ => Err(()),
+        }
+    }
+}
 impl TryFrom<StyleValue> for IVec2 {
     type Error = ();
     fn try_from(value: StyleValue) -> Result<Self, Self::Error> {
-        if let StyleValue::IntegerArray(value) = value {
-            if let [x, y] = value[..] {
-                return Ok(IVec2 { x, y });
+        match value {
+            StyleValue::IntegerArray(value) => match value[..] {
+                [x, y] => Ok(IVec2 { x, y }),
+                _ => Err(()),
+            },
+            StyleValue::Table(value) => {
+                let get = |keys: [&str; 2]| {
+                    keys.iter()
+                        .find_map(|key| value.get(*key))
+                        .and_then(toml::Value::as_integer)
+                        .map(|value| value as i32)
+                };
+                Ok(IVec2::new(
+                    get(["x", "width"]).ok_or(())?,
+                    get(["y", "height"]).ok_or(())?,
+                ))
             }
+            _ => Err(()),
         }
-        Err(())
     }
 }
 impl TryFrom<StyleValue> for EdgeRect {
     type Error = ();
     fn try_from(value: StyleValue) -> Result<Self, Self::Error> {
-        if let StyleValue::Integer(value) = value {
-            return Ok(EdgeRect::splat(value));
-        } else if let StyleValue::IntegerArray(value) = value {
-            if let [top, right, bottom, left] = value[..] {
-                return Ok(EdgeRect::new(top, right, bottom, left));
-            }
+        match value {
+            StyleValue::Integer(value) => Ok(EdgeRect::splat(value)),
+            StyleValue::IntegerArray(value) => EdgeRect::from_values(&value).ok_or(()),
+            StyleValue::Table(value) => toml::Value::Table(value).try_into().map_err(|_| ()),
+            _ => Err(()),
         }
-        Err(())
     }
 }
 impl TryFrom<StyleValue> for Color {
@@ -133,7 +191,11 @@ impl TryFrom<StyleValue> for Color {
 impl TryFrom<StyleValue> for bool {
     type Error = ();
     fn try_from(value: StyleValue) -> Result<Self, Self::Error> {
-        String::try_from(value)?.parse().map_err(|_| ())
+        if let StyleValue::Boolean(value) = value {
+            Ok(value)
+        } else {
+            String::try_from(value)?.parse().map_err(|_| ())
+        }
     }
 }
 impl TryFrom<StyleValue> for Anchor {
@@ -142,30 +204,82 @@ impl TryFrom<StyleValue> for Anchor {
         String::try_from(value)?.parse()
     }
 }
+impl TryFrom<StyleValue> for Corner {
+    type Error = ();
+    fn try_from(value: StyleValue) -> Result<Self, Self::Error> {
+        String::try_from(value)?.parse()
+    }
+}
+impl TryFrom<StyleValue> for Distribution {
+    type Error = ();
+    fn try_from(value: StyleValue) -> Result<Self, Self::Error> {
+        String::try_from(value)?.parse()
+    }
+}
+impl TryFrom<StyleValue> for ScaleMode {
+    type Error = ();
+    fn try_from(value: StyleValue) -> Result<Self, Self::Error> {
+        String::try_from(value)?.parse()
+    }
+}
+
+impl TryFrom<StyleValue> for LoopMode {
+    type Error = ();
+    fn try_from(value: StyleValue) -> Result<Self, Self::Error> {
+        String::try_from(value)?.parse()
+    }
+}
 
 pub type StyleValues = HashMap<String, StyleValue>;
 
 pub trait WidgetStyle {
+    /// Remove `key` and convert it to `T`. Returns `default` if the key is missing, or logs a
+    /// warning and returns `default` if the value is the wrong type.
     fn widget_value<T: TryFrom<StyleValue>>(&mut self, key: &str, default: T) -> T;
     fn widget_layout(&mut self) -> NodeLayout;
+    /// A drop shadow from the "shadow_offset", "shadow_color" and "shadow_blur" keys, if any of
+    /// them are set.
+    fn widget_shadow(&mut self) -> Option<DropShadow>;
+
+    fn color(&mut self, key: &str, default: Color) -> Color {
+        self.widget_value(key, default)
+    }
+    fn texture(&mut self, key: &str) -> Option<Texture> {
+        self.widget_value(key, None)
+    }
+    fn font(&mut self, key: &str, default: StyleFont) -> StyleFont {
+        self.widget_value(key, default)
+    }
+    fn boolean(&mut self, key: &str, default: bool) -> bool {
+        self.widget_value(key, default)
+    }
 }
 
 impl WidgetStyle for StyleValues {
     fn widget_value<T: TryFrom<StyleValue>>(&mut self, key: &str, default: T) -> T {
-        self.remove(key)
-            .and_then(|val| val.try_into().ok())
-            .unwrap_or(default)
+        match self.remove(key).map(T::try_from) {
+            Some(Ok(value)) => value,
+            Some(Err(_)) => {
+                log::warn!("Style value \"{key}\" has the wrong type, using the default.");
+                default
+            }
+            None => default,
+        }
     }
     fn widget_layout(&mut self) -> NodeLayout {
         let mut layout = NodeLayout {
             child_layout: self.widget_value("child_layout", String::new()),
             child_spacing: self.widget_value("child_spacing", 0),
+            child_distribution: self.widget_value("child_distribution", Distribution::Packed),
+            fit_content: self.widget_value("fit_content", false),
+            grow: self.widget_value("grow", 0.0),
             size: self.widget_value("size", IVec2::ZERO),
             margin: self.widget_value("margin", EdgeRect::ZERO),
             anchors: (
                 self.widget_value("hanchor", Anchor::Begin),
                 self.widget_value("vanchor", Anchor::Begin),
             ),
+            ..Default::default()
         };
         if self.contains_key("width") {
             layout.size.x = self.widget_value("width", 0);
@@ -175,22 +289,72 @@ impl WidgetStyle for StyleValues {
         }
         layout
     }
+    fn widget_shadow(&mut self) -> Option<DropShadow> {
+        if !["shadow_offset", "shadow_color", "shadow_blur"]
+            .iter()
+            .any(|key| self.contains_key(*key))
+        {
+            return None;
+        }
+        let default = DropShadow::default();
+        Some(DropShadow {
+            offset: self
+                .widget_value("shadow_offset", default.offset.as_ivec2())
+                .as_vec2(),
+            color: self.widget_value("shadow_color", default.color),
+            blur: self.widget_value("shadow_blur", default.blur),
+        })
+    }
 }
 
+/// The style classes from gui_styles.toml. Each top-level table is a class, except `[fonts]`,
+/// which maps font names to font files to load with the styles.
+///
+/// Values under keys ending in "texture", or tables with a `texture` path anywhere, are loaded as
+/// textures. Values under keys ending in "font" are resolved to a `StyleFont` when the styles are
+/// given to a `Gui`, which loads the fonts.
 #[derive(Default)]
-pub struct WidgetStyles(HashMap<String, StyleValues>);
+pub struct WidgetStyles {
+    classes: HashMap<String, StyleValues>,
+    fonts: Vec<(String, Vec<u8>)>,
+}
 
 impl WidgetStyles {
     pub fn load_asset(context: &mut RenderContext) -> AssetResult<Self> {
         let contents = asset::load_text_file("assets", "gui_styles.toml")?;
-        let table =
+        let mut table: toml::Table =
             toml::from_str(&contents).map_err(|err| AssetError::InvalidFormat(err.to_string()))?;
-        let mut styles = WidgetStyles(table);
+        let fonts = match table.remove("fonts") {
+            Some(toml::Value::Table(fonts)) => Self::load_fonts(fonts)?,
+            Some(_) => {
+                return Err(AssetError::InvalidFormat(
+                    "fonts must be a table".to_owned(),
+                ))
+            }
+            None => Vec::new(),
+        };
+        let classes = toml::Value::Table(table)
+            .try_into()
+            .map_err(|err: toml::de::Error| AssetError::InvalidFormat(err.to_string()))?;
+        let mut styles = WidgetStyles { classes, fonts };
         styles.load_textures(context)?;
         Ok(styles)
     }
+    fn load_fonts(fonts: toml::Table) -> AssetResult<Vec<(String, Vec<u8>)>> {
+        let mut loaded = Vec::new();
+        for (name, file) in fonts {
+            let file = file.as_str().ok_or_else(|| {
+                AssetError::InvalidFormat(format!("font \"{name}\" must be a file path"))
+            })?;
+            let mut reader = asset::open_reader(&asset::get_path("assets", file))?;
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            loaded.push((name, data));
+        }
+        Ok(loaded)
+    }
     fn load_textures(&mut self, context: &mut RenderContext) -> AssetResult<()> {
-        for group in self.0.values_mut() {
+        for group in self.classes.values_mut() {
             for (key, value) in group.iter_mut() {
                 if key.ends_with("texture") {
                     if let StyleValue::String(file) = value {
@@ -198,19 +362,62 @@ impl WidgetStyles {
                     } else {
                         *value = StyleValue::Texture(None);
                     }
+                } else if let StyleValue::Table(table) = value {
+                    if let Some(file) = table.get("texture").and_then(toml::Value::as_str) {
+                        *value = StyleValue::Texture(Some(context.load_texture(file)?));
+                    }
                 }
             }
         }
         Ok(())
     }
 
+    /// The fonts listed in `[fonts]`, by name, with their file contents.
+    pub fn fonts(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.fonts
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+    }
+    /// Turn font names into `StyleFont`s, using `font_id` to look them up. Unknown names are
+    /// left as they are, and warn when a widget reads them.
+    pub fn resolve_fonts<F>(&mut self, font_id: F)
+    where
+        F: Fn(&str) -> Option<FontId>,
+    {
+        for group in self.classes.values_mut() {
+            for (key, value) in group.iter_mut() {
+                if !key.ends_with("font") {
+                    continue;
+                }
+                let (name, size) = match value {
+                    StyleValue::String(name) => (Some(name.as_str()), None),
+                    StyleValue::Table(table) => (
+                        table.get("name").and_then(toml::Value::as_str),
+                        table.get("size").and_then(toml::Value::as_integer),
+                    ),
+                    _ => continue,
+                };
+                let name = match name {
+                    Some(name) => name,
+                    None => continue,
+                };
+                if let Some(id) = font_id(name) {
+                    *value = StyleValue::Font(StyleFont {
+                        id,
+                        size: size.map(|size| size as i32),
+                    });
+                }
+            }
+        }
+    }
+
     pub fn query<'a, I>(&self, class: I) -> StyleValues
     where
         I: Iterator<Item = &'a str>,
     {
         StyleValues::from_iter(
             class
-                .filter_map(|class| self.0.get(class))
+                .filter_map(|class| self.classes.get(class))
                 .flat_map(Clone::clone),
         )
     }
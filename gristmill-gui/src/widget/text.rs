@@ -1,9 +1,12 @@
 use crate::{
-    widget::{StyleValues, Widget, WidgetNode, WidgetNodeExt, WidgetStyle},
-    Anchor, Gui, GuiNode, GuiNodeExt, GuiNodeId, NodeDraw,
+    widget::{
+        StyleFont, StyleValues, Widget, WidgetNode, WidgetNodeExt, WidgetRestyle, WidgetStyle,
+    },
+    Anchor, Gui, GuiNode, GuiNodeExt, GuiNodeId, GuiNodeStorage, NodeDraw,
 };
 use glyph_brush::*;
-use std::any::Any;
+use gristmill_core::math::Vec2;
+use std::{any::Any, cell::RefCell, rc::Rc};
 
 struct TextStyle {
     pub font: FontId,
@@ -21,8 +24,43 @@ impl Default for TextStyle {
     }
 }
 
+impl TextStyle {
+    fn from_style(style: &mut StyleValues) -> TextStyle {
+        let mut text_style = TextStyle::default();
+        text_style.font_size = style.widget_value("font_size", text_style.font_size);
+        let font = style.font("font", StyleFont::default());
+        text_style.font = font.id;
+        if let Some(size) = font.size {
+            text_style.font_size = size;
+        }
+        text_style.color = style.color("color", text_style.color);
+        text_style
+    }
+}
+
+struct TextRestyle {
+    node: GuiNodeId,
+    style: RefCell<TextStyle>,
+}
+
+impl WidgetRestyle for TextRestyle {
+    fn restyle(&self, nodes: &mut GuiNodeStorage, mut style: StyleValues) {
+        let text_style = TextStyle::from_style(&mut style);
+        if let Some(node) = nodes.get_mut(self.node) {
+            if let NodeDraw::Text(section) = &mut node.draw {
+                for text in section.text.iter_mut() {
+                    text.font_id = text_style.font;
+                    text.scale = (text_style.font_size as f32).into();
+                    text.extra.color = text_style.color.into();
+                }
+            }
+        }
+        *self.style.borrow_mut() = text_style;
+    }
+}
+
 pub struct Text {
-    style: TextStyle,
+    style: Rc<TextRestyle>,
     node: GuiNodeId,
 }
 
@@ -64,12 +102,28 @@ impl Text {
     where
         S: Into<String>,
     {
+        let style = self.style.style.borrow();
         let text = OwnedText::new(text)
-            .with_font_id(self.style.font)
-            .with_scale(self.style.font_size as f32)
-            .with_color(<[f32; 4]>::from(self.style.color));
+            .with_font_id(style.font)
+            .with_scale(style.font_size as f32)
+            .with_color(<[f32; 4]>::from(style.color));
+        drop(style);
         self.set_text(gui, vec![text]);
     }
+    /// The size of the text on a single line, in unscaled GUI units.
+    pub fn measure(&self, gui: &mut Gui) -> Vec2 {
+        let text = match self.node_data(gui).map(|node| &node.draw) {
+            Some(NodeDraw::Text(section)) => section.text.clone(),
+            _ => return Vec2::ZERO,
+        };
+        gui.measure_text(&text)
+    }
+    /// Report which run of text the pointer is over; see `Gui::pointer_span`.
+    pub fn set_span_hit_testing(&self, gui: &mut Gui, enabled: bool) {
+        if let Some(node) = self.node_data(gui) {
+            node.flags.text_spans = enabled;
+        }
+    }
     pub fn set_text_align(&self, gui: &mut Gui, align: (Anchor, Anchor), wrap: bool) {
         if let Some(node) = self.node_data(gui) {
             if let NodeDraw::Text(section) = &mut node.draw {
@@ -83,10 +137,12 @@ impl Widget for Text {
     fn class_name() -> &'static str {
         "text"
     }
+    #[cfg(feature = "accessibility")]
+    fn access_role() -> Option<accesskit::Role> {
+        Some(accesskit::Role::StaticText)
+    }
     fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
-        let mut text_style = TextStyle::default(); // TODO font
-        text_style.font_size = style.widget_value("font_size", text_style.font_size);
-        text_style.color = style.widget_value("color", text_style.color);
+        let text_style = TextStyle::from_style(&mut style);
         let h_align = style.widget_value("halign", Anchor::Begin);
         let v_align = style.widget_value("valign", Anchor::Begin);
         let wrap = style.widget_value("wrap", false);
@@ -101,12 +157,18 @@ impl Widget for Text {
             ),
         );
         let widget = Text {
-            style: text_style,
+            style: Rc::new(TextRestyle {
+                node,
+                style: RefCell::new(text_style),
+            }),
             node,
         };
         widget.set_text_string(gui, text);
         widget
     }
+    fn restyle_handle(&self) -> Option<Rc<dyn WidgetRestyle>> {
+        Some(self.style.clone())
+    }
 }
 
 impl WidgetNode for Text {
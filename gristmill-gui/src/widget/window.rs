@@ -0,0 +1,276 @@
+use crate::{
+    widget::{
+        StyleValues, Text, Widget, WidgetBehavior, WidgetInput, WidgetNode, WidgetNodeExt,
+        WidgetStyle,
+    },
+    Anchor, Gui, GuiNode, GuiNodeExt, GuiNodeId, GuiNodeStorage, NodeDraw, NodeFlags, NodeLayout,
+};
+use gristmill_core::{
+    geom2d::{EdgeRect, IRect},
+    math::IVec2,
+    Color,
+};
+use std::{any::Any, cell::Cell, rc::Rc};
+use winit::window::CursorIcon;
+
+#[derive(Copy, Clone)]
+enum DragMode {
+    Move,
+    /// Resize along x, y, or both.
+    Resize(bool, bool),
+}
+
+#[derive(Copy, Clone)]
+struct Drag {
+    mode: DragMode,
+    start_pointer: IVec2,
+    start_rect: IRect,
+}
+
+/// Where a window ends up when dragged by `delta`, kept inside `bounds` (which is relative to the
+/// window's parent).
+fn dragged_position(start: IVec2, delta: IVec2, size: IVec2, bounds: IVec2) -> IVec2 {
+    (start + delta).min(bounds - size).max(IVec2::ZERO)
+}
+/// The size of a window resized by `delta`, at least `min_size` and no larger than the space left
+/// in `bounds`.
+fn resized_size(start: IRect, delta: IVec2, min_size: IVec2, bounds: IVec2) -> IVec2 {
+    let max_size = (bounds - start.position).max(min_size);
+    (start.size + delta).clamp(min_size, max_size)
+}
+
+struct WindowBehavior {
+    node: GuiNodeId,
+    parent: GuiNodeId,
+    title_bar: GuiNodeId,
+    drag: Cell<Option<Drag>>,
+    min_size: IVec2,
+    resize_border: i32,
+}
+
+impl WindowBehavior {
+    fn hover_mode(&self, rect: IRect, pointer: IVec2, over_title: bool) -> Option<DragMode> {
+        let resize_x = pointer.x >= rect.x() + rect.width() - self.resize_border;
+        let resize_y = pointer.y >= rect.y() + rect.height() - self.resize_border;
+        if resize_x || resize_y {
+            Some(DragMode::Resize(resize_x, resize_y))
+        } else if over_title {
+            Some(DragMode::Move)
+        } else {
+            None
+        }
+    }
+}
+
+impl WidgetBehavior for WindowBehavior {
+    fn update(&self, nodes: &mut GuiNodeStorage, input: &WidgetInput) {
+        let (rect, parent_rect) = match (nodes.get(self.node), nodes.get(self.parent)) {
+            (Some(node), Some(parent)) if node.visible => (node.rect, parent.rect),
+            _ => {
+                self.drag.set(None);
+                return;
+            }
+        };
+        let pointer = if let Some(pointer) = input.pointer {
+            pointer
+        } else {
+            return;
+        };
+
+        let over_window = input.pointer_over == Some(self.node);
+        let over_title = input.pointer_over == Some(self.title_bar);
+        let hover_mode = if over_window || over_title {
+            self.hover_mode(rect, pointer, over_title)
+        } else {
+            None
+        };
+        if let Some(node) = nodes.get_mut(self.node) {
+            node.cursor = match hover_mode {
+                Some(DragMode::Resize(true, true)) => Some(CursorIcon::SeResize),
+                Some(DragMode::Resize(true, false)) => Some(CursorIcon::EResize),
+                Some(DragMode::Resize(false, true)) => Some(CursorIcon::SResize),
+                _ => None,
+            };
+        }
+
+        if input.state.just_pressed() {
+            self.drag.set(hover_mode.map(|mode| Drag {
+                mode,
+                start_pointer: pointer,
+                start_rect: rect,
+            }));
+        } else if !input.state.pressed() {
+            self.drag.set(None);
+        }
+        let drag = if let Some(drag) = self.drag.get() {
+            drag
+        } else {
+            return;
+        };
+        let node = if let Some(node) = nodes.get_mut(self.node) {
+            node
+        } else {
+            return;
+        };
+        let delta = pointer - drag.start_pointer;
+        // Node rects are in screen space, but the window's position is relative to its parent.
+        let bounds = parent_rect.size;
+        let start = IRect {
+            position: drag.start_rect.position - parent_rect.position,
+            size: drag.start_rect.size,
+        };
+        match drag.mode {
+            DragMode::Move => {
+                let position = dragged_position(start.position, delta, start.size, bounds);
+                node.layout.margin.left = position.x;
+                node.layout.margin.top = position.y;
+            }
+            DragMode::Resize(resize_x, resize_y) => {
+                let delta = IVec2::new(
+                    if resize_x { delta.x } else { 0 },
+                    if resize_y { delta.y } else { 0 },
+                );
+                node.layout.size = resized_size(start, delta, self.min_size, bounds);
+            }
+        }
+    }
+}
+
+/// A panel that can be moved by dragging its title bar and resized from its right and bottom
+/// edges. It is kept inside its parent, and comes to the front when clicked.
+pub struct FloatingWindow {
+    node: GuiNodeId,
+    content: GuiNodeId,
+    title: Text,
+    _behavior: Rc<WindowBehavior>,
+}
+
+impl FloatingWindow {
+    /// The node that the window's contents should be added to.
+    pub fn content(&self) -> GuiNodeId {
+        self.content
+    }
+    pub fn set_title<S: Into<String>>(&self, gui: &mut Gui, title: S) {
+        let title = title.into();
+        #[cfg(feature = "accessibility")]
+        gui.set_access_label(self.node, Some(title.clone()));
+        self.title.set_text_string(gui, title);
+    }
+
+    /// The window's top-left corner, relative to its parent.
+    pub fn position(&self, gui: &Gui) -> IVec2 {
+        gui.nodes().get(self.node).map_or(IVec2::ZERO, |node| {
+            IVec2::new(node.layout.margin.left, node.layout.margin.top)
+        })
+    }
+    pub fn set_position(&self, gui: &mut Gui, position: IVec2) {
+        if let Some(node) = self.node_data(gui) {
+            node.layout.margin.left = position.x;
+            node.layout.margin.top = position.y;
+        }
+    }
+    pub fn size(&self, gui: &Gui) -> IVec2 {
+        gui.nodes()
+            .get(self.node)
+            .map_or(IVec2::ZERO, |node| node.layout.size)
+    }
+    pub fn set_size(&self, gui: &mut Gui, size: IVec2) {
+        if let Some(node) = self.node_data(gui) {
+            node.layout.size = size;
+        }
+    }
+}
+
+impl Widget for FloatingWindow {
+    fn class_name() -> &'static str {
+        "window"
+    }
+    #[cfg(feature = "accessibility")]
+    fn access_role() -> Option<accesskit::Role> {
+        Some(accesskit::Role::Window)
+    }
+    fn new(gui: &mut Gui, parent: GuiNodeId, mut style: StyleValues) -> Self {
+        let mut layout = style.widget_layout();
+        layout.anchors = (Anchor::Begin, Anchor::Begin);
+        let title_height = style.widget_value("title_height", 24);
+        let texture = style.widget_value("texture", None);
+        let color = style.widget_value("color", Color::new_value(0.2));
+        let title_color = style.widget_value("title_color", Color::new_value(0.3));
+        let min_size = style.widget_value("min_size", IVec2::new(64, title_height * 2));
+        let resize_border = style.widget_value("resize_border", 6);
+
+        let node = parent.add_child(
+            gui,
+            GuiNode {
+                flags: NodeFlags {
+                    pointer_opaque: true,
+                    raise_on_press: true,
+                    clip_children: true,
+                    ..Default::default()
+                },
+                layout,
+                draw: NodeDraw::Rect(texture, color),
+                ..Default::default()
+            },
+        );
+        let title_bar = node.add_child(
+            gui,
+            GuiNode {
+                flags: NodeFlags {
+                    pointer_opaque: true,
+                    ..Default::default()
+                },
+                layout: NodeLayout {
+                    size: IVec2::new(0, title_height),
+                    ..Default::default()
+                },
+                draw: NodeDraw::Rect(None, title_color),
+                cursor: Some(CursorIcon::Move),
+                ..Default::default()
+            },
+        );
+        let title: Text = gui.create_widget(title_bar);
+        title.set_text_align(gui, (Anchor::Begin, Anchor::Middle), false);
+        if let Some(title_node) = title.node_data(gui) {
+            title_node.layout.margin = EdgeRect::new(0, 6, 0, 6);
+        }
+        let title_text = style.widget_value("title", String::new());
+        #[cfg(feature = "accessibility")]
+        gui.set_access_label(node, Some(title_text.clone()));
+        title.set_text_string(gui, title_text);
+        let content = node.add_child(
+            gui,
+            GuiNode::new(
+                NodeLayout {
+                    margin: EdgeRect::new(title_height, 0, 0, 0),
+                    ..Default::default()
+                },
+                NodeDraw::None,
+            ),
+        );
+
+        let behavior = gui.register_behavior(WindowBehavior {
+            node,
+            parent,
+            title_bar,
+            drag: Cell::new(None),
+            min_size,
+            resize_border,
+        });
+        FloatingWindow {
+            node,
+            content,
+            title,
+            _behavior: behavior,
+        }
+    }
+}
+
+impl WidgetNode for FloatingWindow {
+    fn as_any_box(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn node(&self) -> GuiNodeId {
+        self.node
+    }
+}
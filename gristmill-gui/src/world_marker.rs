@@ -0,0 +1,124 @@
+//! GUI nodes that follow points in the world, for health bars and name tags over entities.
+//!
+//! Attach a node with `Gui::attach_to_world`, and give the GUI the game's camera with
+//! `Gui::set_world_camera` whenever it moves. Every `update`, each attached node is moved so the
+//! middle of its bottom edge sits over its world point, plus an offset in GUI units. Markers
+//! should be direct children of the root with a fixed size, since their margin is overwritten.
+
+use crate::{Anchor, Gui, GuiNodeId};
+use gristmill_core::{
+    geom2d::CameraTransform,
+    math::{IVec2, Vec2},
+};
+
+/// What happens to a marker whose world point is off the screen.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Offscreen {
+    #[default]
+    Hide,
+    /// Keep the marker inside the screen, along the edge nearest its point.
+    Clamp,
+}
+
+pub(crate) struct WorldMarker {
+    node: GuiNodeId,
+    source: Box<dyn FnMut() -> Vec2>,
+    offset: IVec2,
+    offscreen: Offscreen,
+}
+
+/// Where to put the top-left corner of a marker of `size`, so the middle of its bottom edge is
+/// over `world` plus `offset`. `scale` is the GUI scale and `bounds` the screen size, both in GUI
+/// units. Returns `None` if the marker should be hidden.
+pub fn world_marker_position(
+    camera: &CameraTransform,
+    world: Vec2,
+    offset: IVec2,
+    size: IVec2,
+    scale: f32,
+    bounds: IVec2,
+    offscreen: Offscreen,
+) -> Option<IVec2> {
+    let point = (camera.world_to_screen(world) / scale).round().as_ivec2() + offset;
+    let position = point - IVec2::new(size.x / 2, size.y);
+    let on_screen = point.cmpge(IVec2::ZERO).all() && point.cmplt(bounds).all();
+    match offscreen {
+        _ if on_screen => Some(position),
+        Offscreen::Hide => None,
+        Offscreen::Clamp => Some(position.min(bounds - size).max(IVec2::ZERO)),
+    }
+}
+
+impl Gui {
+    /// Move `node` to follow the world point returned by `source` every `update`. Replaces any
+    /// source the node already has, and is dropped when the node is removed.
+    pub fn attach_to_world<F>(&mut self, node: GuiNodeId, source: F)
+    where
+        F: FnMut() -> Vec2 + 'static,
+    {
+        self.detach_from_world(node);
+        self.world_markers.push(WorldMarker {
+            node,
+            source: Box::new(source),
+            offset: IVec2::ZERO,
+            offscreen: Offscreen::default(),
+        });
+    }
+    pub fn detach_from_world(&mut self, node: GuiNodeId) {
+        self.world_markers.retain(|marker| marker.node != node);
+    }
+    /// Move the marker by `offset` GUI units from its world point, such as `(0, -8)` to float a
+    /// health bar above an entity.
+    pub fn set_world_marker_offset(&mut self, node: GuiNodeId, offset: IVec2) {
+        if let Some(marker) = self.world_markers.iter_mut().find(|m| m.node == node) {
+            marker.offset = offset;
+        }
+    }
+    pub fn set_world_marker_offscreen(&mut self, node: GuiNodeId, offscreen: Offscreen) {
+        if let Some(marker) = self.world_markers.iter_mut().find(|m| m.node == node) {
+            marker.offscreen = offscreen;
+        }
+    }
+    /// The camera markers are projected through. Markers stay where they are until one is set.
+    pub fn set_world_camera(&mut self, camera: CameraTransform) {
+        self.world_camera = Some(camera);
+    }
+    pub fn world_camera(&self) -> Option<&CameraTransform> {
+        self.world_camera.as_ref()
+    }
+
+    pub(crate) fn update_world_markers(&mut self) {
+        let camera = match self.world_camera {
+            Some(camera) => camera,
+            None => return,
+        };
+        let scale = self.scale();
+        let bounds = (self.viewport_size.as_vec2() / scale).as_ivec2();
+        // Margins are relative to the root, which the safe area insets.
+        let safe_area = self.safe_area();
+        let origin = IVec2::new(safe_area.left, safe_area.top);
+        let nodes = &mut self.nodes;
+        self.world_markers.retain_mut(|marker| {
+            let node = match nodes.get_mut(marker.node) {
+                Some(node) => node,
+                None => return false,
+            };
+            let position = world_marker_position(
+                &camera,
+                (marker.source)(),
+                marker.offset,
+                node.layout.size,
+                scale,
+                bounds,
+                marker.offscreen,
+            );
+            node.flags.visible = position.is_some();
+            if let Some(position) = position {
+                node.layout.anchors = (Anchor::Begin, Anchor::Begin);
+                node.layout.margin.left = position.x - origin.x;
+                node.layout.margin.top = position.y - origin.y;
+            }
+            true
+        });
+    }
+}
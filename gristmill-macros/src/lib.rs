@@ -2,8 +2,12 @@ use proc_macro::TokenStream;
 use proc_macro2::Span;
 use quote::{quote, quote_spanned};
 use syn::{
-    parse_macro_input, spanned::Spanned, Attribute, Data, DeriveInput, Fields, Ident, Lit, Meta,
-    MetaNameValue,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute, Data, DataStruct, DeriveInput, Expr, Fields, Ident, Lit, Meta, MetaNameValue, Token,
+    Type,
 };
 
 fn find_category_attribute(attrs: Vec<Attribute>) -> syn::parse::Result<Ident> {
@@ -60,7 +64,7 @@ pub fn derive_asset_write(input: TokenStream) -> TokenStream {
 pub fn derive_packed_widget(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident;
-    let asset_path = format!("gui/{}.yaml", name.to_string());
+    let asset_path = format!("gui/{}.yaml", name);
     let widget_init = if let Data::Struct(ref data) = input.data {
         if let Fields::Named(ref fields) = data.fields {
             let recurse = fields.named.iter().map(|field| {
@@ -96,3 +100,210 @@ pub fn derive_packed_widget(input: TokenStream) -> TokenStream {
     };
     TokenStream::from(output)
 }
+
+/// One `name` or `name = value` in a `#[setting(...)]` attribute.
+struct SettingArg {
+    name: Ident,
+    value: Option<Expr>,
+}
+
+impl Parse for SettingArg {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let name = input.parse()?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(SettingArg { name, value })
+    }
+}
+
+fn setting_args(attrs: &[Attribute]) -> syn::parse::Result<Vec<SettingArg>> {
+    let mut args = Vec::new();
+    for attr in attrs {
+        if attr.path.is_ident("setting") {
+            args.extend(
+                attr.parse_args_with(Punctuated::<SettingArg, Token![,]>::parse_terminated)?,
+            );
+        }
+    }
+    Ok(args)
+}
+
+fn setting_arg<'a>(args: &'a [SettingArg], name: &str) -> Option<&'a SettingArg> {
+    args.iter().find(|arg| arg.name == name)
+}
+
+/// "master_volume" becomes "Master volume".
+fn default_setting_label(name: &str) -> String {
+    let label = name.replace('_', " ");
+    let mut chars = label.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn setting_label(args: &[SettingArg], name: &str) -> proc_macro2::TokenStream {
+    match setting_arg(args, "label").and_then(|arg| arg.value.as_ref()) {
+        Some(label) => quote! { #label },
+        None => {
+            let label = default_setting_label(name);
+            quote! { #label }
+        }
+    }
+}
+
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("bool"))
+}
+
+#[proc_macro_derive(Settings, attributes(setting))]
+pub fn derive_settings(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = input.ident;
+    let fields = match input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => fields.named,
+        _ => panic!("Settings can only be derived for structs with named fields"),
+    };
+
+    let mut field_infos = Vec::new();
+    let mut getters = Vec::new();
+    let mut setters = Vec::new();
+    for field in fields {
+        let args = match setting_args(&field.attrs) {
+            Ok(args) => args,
+            Err(error) => return TokenStream::from(error.to_compile_error()),
+        };
+        if setting_arg(&args, "skip").is_some() {
+            continue;
+        }
+        let index = field_infos.len();
+        let ident = field.ident.unwrap();
+        let field_name = ident.to_string();
+        let label = setting_label(&args, &field_name);
+        let ty = field.ty;
+        let arg_value = |arg: &str, default: i32| match setting_arg(&args, arg)
+            .and_then(|arg| arg.value.as_ref())
+        {
+            Some(value) => quote! { (#value) as i32 },
+            None => quote! { #default },
+        };
+
+        let kind = if is_bool(&ty) {
+            getters.push(quote! { #index => SettingValue::Bool(self.#ident) });
+            setters.push(quote! { (#index, SettingValue::Bool(value)) => self.#ident = value });
+            quote! { SettingKind::Toggle }
+        } else if setting_arg(&args, "min").is_some() || setting_arg(&args, "max").is_some() {
+            let (min, max, step) = (
+                arg_value("min", 0),
+                arg_value("max", 100),
+                arg_value("step", 1),
+            );
+            getters.push(quote! { #index => SettingValue::Number(self.#ident as i32) });
+            setters
+                .push(quote! { (#index, SettingValue::Number(value)) => self.#ident = value as _ });
+            quote! { SettingKind::Number { min: #min, max: #max, step: #step } }
+        } else {
+            getters.push(quote! {
+                #index => SettingValue::Choice(SettingChoice::choice_index(&self.#ident))
+            });
+            setters.push(quote! {
+                (#index, SettingValue::Choice(value)) => {
+                    self.#ident = <#ty as SettingChoice>::from_choice_index(value)
+                }
+            });
+            quote! { SettingKind::Choice(<#ty as SettingChoice>::choices()) }
+        };
+        field_infos.push(quote! {
+            SettingField { name: #field_name, label: #label, kind: #kind }
+        });
+    }
+
+    let output = quote! {
+        impl gristmill::gui::settings::Settings for #name {
+            fn setting_fields() -> Vec<gristmill::gui::settings::SettingField> {
+                use gristmill::gui::settings::*;
+                vec![#(#field_infos,)*]
+            }
+            fn setting(&self, index: usize) -> gristmill::gui::settings::SettingValue {
+                use gristmill::gui::settings::*;
+                match index {
+                    #(#getters,)*
+                    _ => panic!("setting index {} out of range", index),
+                }
+            }
+            fn set_setting(&mut self, index: usize, value: gristmill::gui::settings::SettingValue) {
+                use gristmill::gui::settings::*;
+                match (index, value) {
+                    #(#setters,)*
+                    // A value of the wrong kind is ignored.
+                    _ => {}
+                }
+            }
+        }
+    };
+    TokenStream::from(output)
+}
+
+#[proc_macro_derive(SettingChoice, attributes(setting))]
+pub fn derive_setting_choice(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = input.ident;
+    let variants = match input.data {
+        Data::Enum(data) => data.variants,
+        _ => panic!("SettingChoice can only be derived for enums"),
+    };
+
+    let mut labels = Vec::new();
+    let mut idents = Vec::new();
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            panic!("SettingChoice variants can't have fields");
+        }
+        let args = match setting_args(&variant.attrs) {
+            Ok(args) => args,
+            Err(error) => return TokenStream::from(error.to_compile_error()),
+        };
+        labels.push(
+            match setting_arg(&args, "label").and_then(|arg| arg.value.as_ref()) {
+                Some(label) => quote! { #label },
+                None => {
+                    let label = variant.ident.to_string();
+                    quote! { #label }
+                }
+            },
+        );
+        idents.push(variant.ident);
+    }
+    let indices = 0..idents.len();
+    let from_indices = 0..idents.len();
+    let first = idents
+        .first()
+        .expect("SettingChoice needs at least one variant");
+
+    let output = quote! {
+        impl gristmill::gui::settings::SettingChoice for #name {
+            fn choices() -> &'static [&'static str] {
+                &[#(#labels,)*]
+            }
+            fn choice_index(&self) -> usize {
+                match self {
+                    #(#name::#idents => #indices,)*
+                }
+            }
+            fn from_choice_index(index: usize) -> Self {
+                match index {
+                    #(#from_indices => #name::#idents,)*
+                    _ => #name::#first,
+                }
+            }
+        }
+    };
+    TokenStream::from(output)
+}
@@ -0,0 +1,385 @@
+use gristmill_core::asset::{AssetError, AssetResult};
+use image::{codecs::gif::GifEncoder, Delay, Frame, RgbaImage};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use vulkano::{
+    buffer::{BufferUsage, CpuAccessibleBuffer},
+    command_buffer::{AutoCommandBufferBuilder, CopyImageToBufferInfo, PrimaryAutoCommandBuffer},
+    format::Format,
+    image::{ImageAccess, SwapchainImage},
+    memory::allocator::StandardMemoryAllocator,
+};
+
+/// A fixed-size queue that drops its oldest item to make room for a new one.
+#[derive(Clone, Debug)]
+pub struct FrameRing<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> FrameRing<T> {
+    pub fn new(capacity: usize) -> Self {
+        FrameRing {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Add `item` as the newest, returning the oldest item if it had to be dropped.
+    pub fn push(&mut self, item: T) -> Option<T> {
+        if self.capacity == 0 {
+            return Some(item);
+        }
+        let dropped = if self.items.len() >= self.capacity {
+            self.items.pop_front()
+        } else {
+            None
+        };
+        self.items.push_back(item);
+        dropped
+    }
+    /// Change the capacity, dropping the oldest items if there are more than fit.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.items.len() > capacity {
+            self.items.pop_front();
+        }
+    }
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+    /// The items from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+}
+
+/// How much of the recent past a clip recorder keeps. The recorder keeps the last `seconds` of
+/// frames, captured `fps` times per second and shrunk by `downscale`, but drops older frames early
+/// if they would take more than `memory_budget` bytes.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ClipSettings {
+    pub seconds: f32,
+    pub fps: u32,
+    /// Keep one pixel out of each `downscale` by `downscale` block, averaged. 1 keeps full size.
+    pub downscale: u32,
+    pub memory_budget: usize,
+}
+
+impl Default for ClipSettings {
+    fn default() -> Self {
+        ClipSettings {
+            seconds: 15.0,
+            fps: 15,
+            downscale: 2,
+            memory_budget: 256 * 1024 * 1024,
+        }
+    }
+}
+
+impl ClipSettings {
+    /// How many frames of `frame_bytes` each to keep.
+    pub fn frame_capacity(&self, frame_bytes: usize) -> usize {
+        let frames = (self.seconds.max(0.0) * self.fps as f32).ceil() as usize;
+        frames.min(self.memory_budget / frame_bytes.max(1)).max(1)
+    }
+    fn interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.fps.max(1) as f32)
+    }
+}
+
+/// One captured frame, as 8-bit RGBA.
+#[derive(Clone)]
+pub struct ClipFrame {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+    pub time: Instant,
+}
+
+/// Shrink an image of 4-byte pixels by averaging each `factor` by `factor` block, and swap the
+/// red and blue channels if `bgra` is set.
+pub fn downscale_rgba(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    factor: u32,
+    bgra: bool,
+) -> (u32, u32, Vec<u8>) {
+    let factor = factor.max(1);
+    let (out_width, out_height) = ((width / factor).max(1), (height / factor).max(1));
+    let mut out = Vec::with_capacity(out_width as usize * out_height as usize * 4);
+    for out_y in 0..out_height {
+        for out_x in 0..out_width {
+            let mut sum = [0u32; 4];
+            let mut count = 0;
+            for y in (out_y * factor)..((out_y + 1) * factor).min(height) {
+                for x in (out_x * factor)..((out_x + 1) * factor).min(width) {
+                    let i = (y as usize * width as usize + x as usize) * 4;
+                    for (channel, sum) in sum.iter_mut().enumerate() {
+                        *sum += data[i + channel] as u32;
+                    }
+                    count += 1;
+                }
+            }
+            let pixel = sum.map(|sum| (sum / count.max(1)) as u8);
+            if bgra {
+                out.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 255]);
+            } else {
+                out.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 255]);
+            }
+        }
+    }
+    (out_width, out_height, out)
+}
+
+struct PendingCapture {
+    buffer: Arc<CpuAccessibleBuffer<[u8]>>,
+    width: u32,
+    height: u32,
+    bgra: bool,
+    time: Instant,
+}
+
+/// Keeps the last few seconds of frames, for "save last 15 seconds" clips. Each captured frame is
+/// copied into a buffer during the frame, then read and downscaled once the GPU has finished it,
+/// so capturing never stalls the GPU.
+pub(crate) struct ClipRecorder {
+    settings: ClipSettings,
+    frames: FrameRing<ClipFrame>,
+    last_capture: Option<Instant>,
+    pending: Option<PendingCapture>,
+}
+
+impl ClipRecorder {
+    pub fn new(settings: ClipSettings) -> Self {
+        ClipRecorder {
+            settings,
+            frames: FrameRing::new(1),
+            last_capture: None,
+            pending: None,
+        }
+    }
+
+    pub fn settings(&self) -> ClipSettings {
+        self.settings
+    }
+    pub fn frames(&self) -> &FrameRing<ClipFrame> {
+        &self.frames
+    }
+
+    /// Whether the format of an image can be captured, and whether it needs the red and blue
+    /// channels swapped.
+    pub fn format_order(format: Format) -> Option<bool> {
+        match format {
+            Format::B8G8R8A8_SRGB | Format::B8G8R8A8_UNORM => Some(true),
+            Format::R8G8B8A8_SRGB | Format::R8G8B8A8_UNORM => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Copy `image` into a readback buffer, if it's time for another frame.
+    pub fn capture(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        allocator: &StandardMemoryAllocator,
+        image: Arc<SwapchainImage>,
+    ) {
+        let now = Instant::now();
+        if self.pending.is_some()
+            || self
+                .last_capture
+                .map_or(false, |last| now - last < self.settings.interval())
+        {
+            return;
+        }
+        let bgra = match Self::format_order(image.format()) {
+            Some(bgra) => bgra,
+            None => return,
+        };
+        let [width, height] = image.dimensions().width_height();
+        let buffer = match CpuAccessibleBuffer::from_iter(
+            allocator,
+            BufferUsage {
+                transfer_dst: true,
+                ..BufferUsage::empty()
+            },
+            true,
+            std::iter::repeat(0u8).take(width as usize * height as usize * 4),
+        ) {
+            Ok(buffer) => buffer,
+            Err(error) => {
+                log::warn!("Failed to allocate clip frame: {}", error);
+                return;
+            }
+        };
+        if let Err(error) =
+            builder.copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(image, buffer.clone()))
+        {
+            log::warn!("Failed to capture clip frame: {}", error);
+            return;
+        }
+        self.last_capture = Some(now);
+        self.pending = Some(PendingCapture {
+            buffer,
+            width,
+            height,
+            bgra,
+            time: now,
+        });
+    }
+    /// Read the frame captured last frame. Call this once the previous frame has finished.
+    pub fn read_previous_frame(&mut self) {
+        let pending = match self.pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+        let data = match pending.buffer.read() {
+            Ok(data) => data,
+            Err(_) => {
+                // Still in use by the GPU; try again next frame.
+                self.pending = Some(pending);
+                return;
+            }
+        };
+        let (width, height, rgba) = downscale_rgba(
+            &data,
+            pending.width,
+            pending.height,
+            self.settings.downscale,
+            pending.bgra,
+        );
+        // A resize changes the frame size, and a clip can't mix sizes.
+        if self.frames.iter().next().map_or(false, |frame| {
+            frame.width != width || frame.height != height
+        }) {
+            self.frames.clear();
+        }
+        self.frames
+            .set_capacity(self.settings.frame_capacity(rgba.len()));
+        self.frames.push(ClipFrame {
+            width,
+            height,
+            rgba,
+            time: pending.time,
+        });
+    }
+
+    /// Write the kept frames to an animated GIF, timed by when they were captured.
+    pub fn save_gif(&self, path: &Path) -> AssetResult<()> {
+        if self.frames.is_empty() {
+            return Err(AssetError::Other("no frames to save".to_owned()));
+        }
+        let mut encoder = GifEncoder::new(File::create(path)?);
+        encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
+        let frames: Vec<&ClipFrame> = self.frames.iter().collect();
+        for (index, frame) in frames.iter().enumerate() {
+            let delay = frames
+                .get(index + 1)
+                .map_or(self.settings.interval(), |next| next.time - frame.time);
+            let image = RgbaImage::from_raw(frame.width, frame.height, frame.rgba.clone())
+                .expect("clip frame has the wrong size");
+            encoder.encode_frame(Frame::from_parts(
+                image,
+                0,
+                0,
+                Delay::from_saturating_duration(delay),
+            ))?;
+        }
+        Ok(())
+    }
+    /// Write the kept frames to `dir` as numbered PNG files (frame_0000.png, frame_0001.png, …),
+    /// for encoding to video with an external tool. Returns the number of frames written.
+    pub fn save_frames(&self, dir: &Path) -> AssetResult<usize> {
+        std::fs::create_dir_all(dir)?;
+        for (index, frame) in self.frames.iter().enumerate() {
+            image::save_buffer(
+                dir.join(format!("frame_{index:04}.png")),
+                &frame.rgba,
+                frame.width,
+                frame.height,
+                image::ColorType::Rgba8,
+            )?;
+        }
+        Ok(self.frames.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_drops_oldest() {
+        let mut ring = FrameRing::new(3);
+        assert_eq!(ring.push(1), None);
+        assert_eq!(ring.push(2), None);
+        assert_eq!(ring.push(3), None);
+        assert_eq!(ring.push(4), Some(1));
+        assert_eq!(ring.push(5), Some(2));
+        assert_eq!(ring.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5]);
+
+        ring.set_capacity(2);
+        assert_eq!(ring.iter().copied().collect::<Vec<_>>(), vec![4, 5]);
+        let mut empty = FrameRing::new(0);
+        assert_eq!(empty.push(1), Some(1));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn frame_capacity_limited_by_time_and_memory() {
+        let settings = ClipSettings {
+            seconds: 10.0,
+            fps: 15,
+            downscale: 1,
+            memory_budget: 1000,
+        };
+        assert_eq!(settings.frame_capacity(1), 150);
+        assert_eq!(settings.frame_capacity(10), 100);
+        // Always keeps at least one frame, even one bigger than the budget.
+        assert_eq!(settings.frame_capacity(5000), 1);
+        assert_eq!(settings.frame_capacity(0), 150);
+        let no_time = ClipSettings {
+            seconds: -1.0,
+            ..settings
+        };
+        assert_eq!(no_time.frame_capacity(1), 1);
+    }
+
+    #[test]
+    fn downscale_averages_blocks() {
+        #[rustfmt::skip]
+        let data = [
+            0, 0, 0, 255,    100, 0, 0, 255,    7, 7, 7, 255,
+            0, 200, 0, 255,  0, 0, 40, 255,     7, 7, 7, 255,
+        ];
+        // The odd column left over is dropped.
+        assert_eq!(
+            downscale_rgba(&data, 3, 2, 2, false),
+            (1, 1, vec![25, 50, 10, 255])
+        );
+        assert_eq!(
+            downscale_rgba(&data, 3, 2, 2, true),
+            (1, 1, vec![10, 50, 25, 255])
+        );
+        let (width, height, same) = downscale_rgba(&data, 3, 2, 1, false);
+        assert_eq!((width, height), (3, 2));
+        assert_eq!(same, data);
+        // A factor bigger than the image still leaves one pixel.
+        assert_eq!(downscale_rgba(&data, 3, 2, 8, false).0, 1);
+    }
+}
@@ -0,0 +1,144 @@
+use gristmill_core::{
+    asset::{self, AssetError},
+    Color,
+};
+use serde::{Deserialize, Serialize};
+
+/// Player-adjustable output settings, applied to the clear color and everything drawn with a
+/// `TextureRectRenderer`. Saved in the "config" prefix, so the options menu can change them and
+/// have them stick.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    /// Applied as `color^(1 / gamma)`. Values above 1 lighten dark and middle tones without
+    /// washing out highlights; values below 1 darken them. 1 leaves colors unchanged; 0.5 to 2.5
+    /// is a sensible slider range.
+    pub gamma: f32,
+    /// Multiplies colors before gamma is applied. 1 leaves colors unchanged; 0.5 to 1.5 is a
+    /// sensible slider range. Values above 1 clip bright colors to white.
+    pub brightness: f32,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            gamma: 1.0,
+            brightness: 1.0,
+        }
+    }
+}
+
+impl DisplaySettings {
+    pub const MIN_GAMMA: f32 = 0.1;
+    pub const MAX_GAMMA: f32 = 4.0;
+    pub const MAX_BRIGHTNESS: f32 = 4.0;
+
+    pub fn load_config() -> Result<DisplaySettings, AssetError> {
+        asset::load_yaml_file("config", "display.yaml").map(DisplaySettings::clamped)
+    }
+    pub fn save_config(&self) -> Result<(), AssetError> {
+        asset::save_yaml_file("config", "display.yaml", self)
+    }
+
+    /// These settings with out-of-range or non-finite values replaced.
+    pub fn clamped(self) -> Self {
+        let default = DisplaySettings::default();
+        let clamp = |value: f32, min: f32, max: f32, default: f32| {
+            if value.is_finite() {
+                value.clamp(min, max)
+            } else {
+                default
+            }
+        };
+        DisplaySettings {
+            gamma: clamp(self.gamma, Self::MIN_GAMMA, Self::MAX_GAMMA, default.gamma),
+            brightness: clamp(
+                self.brightness,
+                0.0,
+                Self::MAX_BRIGHTNESS,
+                default.brightness,
+            ),
+        }
+    }
+    pub fn is_identity(&self) -> bool {
+        *self == DisplaySettings::default()
+    }
+    /// The adjustment the shader applies, for colors drawn some other way. Alpha is unchanged.
+    pub fn apply(&self, color: Color) -> Color {
+        if self.is_identity() {
+            return color;
+        }
+        let [r, g, b, a]: [f32; 4] = color.into();
+        let adjust = |c: f32| (c * self.brightness).clamp(0.0, 1.0).powf(1.0 / self.gamma);
+        Color::new(adjust(r), adjust(g), adjust(b), a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(color: Color) -> [f32; 4] {
+        color.into()
+    }
+
+    #[test]
+    fn defaults_are_identity() {
+        let settings = DisplaySettings::default();
+        assert!(settings.is_identity());
+        for color in [
+            Color::new(0.0, 0.0, 0.0, 1.0),
+            Color::new(0.2, 0.5, 0.8, 0.3),
+            Color::new(1.0, 1.0, 1.0, 0.0),
+        ] {
+            assert_eq!(channels(settings.apply(color)), channels(color));
+        }
+    }
+
+    #[test]
+    fn gamma_and_brightness_adjust_colors() {
+        let gamma = DisplaySettings {
+            gamma: 2.0,
+            brightness: 1.0,
+        };
+        let [r, g, b, a] = channels(gamma.apply(Color::new(0.25, 0.0, 1.0, 0.5)));
+        assert!((r - 0.5).abs() < 1e-6);
+        assert_eq!([g, b, a], [0.0, 1.0, 0.5]);
+        let brightness = DisplaySettings {
+            gamma: 1.0,
+            brightness: 2.0,
+        };
+        assert_eq!(
+            channels(brightness.apply(Color::new(0.25, 0.75, 0.0, 1.0))),
+            [0.5, 1.0, 0.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn clamped_replaces_bad_values() {
+        let default = DisplaySettings::default();
+        for bad in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let settings = DisplaySettings {
+                gamma: bad,
+                brightness: bad,
+            }
+            .clamped();
+            assert_eq!(settings, default);
+        }
+        let out_of_range = DisplaySettings {
+            gamma: 0.0,
+            brightness: 10.0,
+        }
+        .clamped();
+        assert_eq!(out_of_range.gamma, DisplaySettings::MIN_GAMMA);
+        assert_eq!(out_of_range.brightness, DisplaySettings::MAX_BRIGHTNESS);
+        let negative = DisplaySettings {
+            gamma: 100.0,
+            brightness: -1.0,
+        }
+        .clamped();
+        assert_eq!(negative.gamma, DisplaySettings::MAX_GAMMA);
+        assert_eq!(negative.brightness, 0.0);
+        assert_eq!(default.clamped(), default);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::texture_rect::DrawMetrics;
+use gristmill_core::asset::{self, AssetError};
+use serde::{Deserialize, Serialize};
+
+/// Per-frame limits for a `TextureRectRenderer`, to catch batching regressions and accidental
+/// per-entity draws during development. In debug builds, a renderer logs one warning for each
+/// frame that goes over a limit, so even a one-frame spike is reported. Release builds don't
+/// check.
+///
+/// Renderers load their budget from "config/draw_budget.yaml" in debug builds, if it exists.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DrawBudget {
+    pub max_draw_calls: Option<usize>,
+    pub max_rects: Option<usize>,
+    /// Bytes of instance data uploaded in one frame.
+    pub max_buffer_bytes: Option<usize>,
+}
+
+impl DrawBudget {
+    pub fn load_config() -> Result<DrawBudget, AssetError> {
+        asset::load_yaml_file("config", "draw_budget.yaml")
+    }
+    /// The budget from `load_config` in debug builds, or `None` if there isn't one or this is a
+    /// release build.
+    pub(crate) fn load_dev_config() -> Option<DrawBudget> {
+        if !cfg!(debug_assertions) {
+            return None;
+        }
+        match DrawBudget::load_config() {
+            Ok(budget) => Some(budget),
+            Err(error) => {
+                if error.io_kind() != Some(std::io::ErrorKind::NotFound) {
+                    log::warn!("{}", error);
+                }
+                None
+            }
+        }
+    }
+
+    /// A description of each limit `metrics` goes over.
+    pub fn exceeded(&self, metrics: &DrawMetrics) -> Vec<String> {
+        [
+            ("draw calls", metrics.draw_calls, self.max_draw_calls),
+            ("rects", metrics.rects, self.max_rects),
+            ("buffer bytes", metrics.buffer_bytes, self.max_buffer_bytes),
+        ]
+        .into_iter()
+        .filter_map(|(name, value, max)| {
+            max.filter(|max| value > *max)
+                .map(|max| format!("{value} {name} (budget {max})"))
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(draw_calls: usize, rects: usize, buffer_bytes: usize) -> DrawMetrics {
+        DrawMetrics {
+            rects,
+            draw_calls,
+            buffer_bytes,
+        }
+    }
+
+    #[test]
+    fn within_budget() {
+        let budget = DrawBudget {
+            max_draw_calls: Some(10),
+            max_rects: Some(100),
+            max_buffer_bytes: None,
+        };
+        assert!(budget.exceeded(&metrics(10, 100, usize::MAX)).is_empty());
+        assert!(DrawBudget::default()
+            .exceeded(&metrics(1000, 1000, 1000))
+            .is_empty());
+    }
+
+    #[test]
+    fn each_exceeded_limit_is_listed() {
+        let budget = DrawBudget {
+            max_draw_calls: Some(10),
+            max_rects: Some(100),
+            max_buffer_bytes: Some(4096),
+        };
+        assert_eq!(
+            budget.exceeded(&metrics(11, 100, 4096)),
+            vec!["11 draw calls (budget 10)"]
+        );
+        assert_eq!(
+            budget.exceeded(&metrics(12, 101, 5000)),
+            vec![
+                "12 draw calls (budget 10)",
+                "101 rects (budget 100)",
+                "5000 buffer bytes (budget 4096)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::{sync::Arc, time::Duration};
+use vulkano::{
+    command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer},
+    device::Device,
+    query::{QueryPool, QueryPoolCreateInfo, QueryResultFlags, QueryType},
+    sync::PipelineStage,
+};
+
+/// Measures how long the GPU spends on each frame with a pair of timestamp queries.
+pub(crate) struct GpuTimer {
+    query_pool: Arc<QueryPool>,
+    timestamp_period: f32,
+    timestamp_mask: u64,
+    pending: bool,
+    frame_time: Option<Duration>,
+}
+
+impl GpuTimer {
+    /// Returns `None` if the queue family doesn't support timestamps.
+    pub fn new(device: &Arc<Device>, queue_family_index: u32) -> Option<Self> {
+        let physical_device = device.physical_device();
+        let valid_bits = physical_device.queue_family_properties()[queue_family_index as usize]
+            .timestamp_valid_bits?;
+        let query_pool = QueryPool::new(
+            device.clone(),
+            QueryPoolCreateInfo {
+                query_count: 2,
+                ..QueryPoolCreateInfo::query_type(QueryType::Timestamp)
+            },
+        )
+        .ok()?;
+        Some(GpuTimer {
+            query_pool,
+            timestamp_period: physical_device.properties().timestamp_period,
+            timestamp_mask: if valid_bits >= 64 {
+                u64::MAX
+            } else {
+                (1 << valid_bits) - 1
+            },
+            pending: false,
+            frame_time: None,
+        })
+    }
+
+    pub fn frame_time(&self) -> Option<Duration> {
+        self.frame_time
+    }
+
+    pub fn begin(&self, builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>) {
+        unsafe {
+            builder
+                .reset_query_pool(self.query_pool.clone(), 0..2)
+                .unwrap()
+                .write_timestamp(self.query_pool.clone(), 0, PipelineStage::TopOfPipe)
+                .unwrap();
+        }
+    }
+    pub fn end(&self, builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>) {
+        unsafe {
+            builder
+                .write_timestamp(self.query_pool.clone(), 1, PipelineStage::BottomOfPipe)
+                .unwrap();
+        }
+    }
+
+    /// Read the timestamps written by the previous frame. Must only be called once that frame has
+    /// finished executing, and before the next frame is submitted.
+    pub fn read_previous_frame(&mut self) {
+        if self.pending {
+            let mut timestamps = [0u64; 2];
+            let available = self
+                .query_pool
+                .queries_range(0..2)
+                .unwrap()
+                .get_results(&mut timestamps, QueryResultFlags::empty())
+                .unwrap_or(false);
+            if available {
+                let ticks = timestamps[1].wrapping_sub(timestamps[0]) & self.timestamp_mask;
+                let nanos = ticks as f64 * self.timestamp_period as f64;
+                self.frame_time = Some(Duration::from_nanos(nanos as u64));
+            }
+        }
+        self.pending = true;
+    }
+}
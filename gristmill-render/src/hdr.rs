@@ -0,0 +1,374 @@
+use gristmill_core::{geom2d::IRect, Color};
+use std::sync::Arc;
+use vulkano::{
+    command_buffer::{
+        AutoCommandBufferBuilder, PrimaryAutoCommandBuffer, RenderPassBeginInfo, SubpassContents,
+    },
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet, WriteDescriptorSet,
+    },
+    device::{physical::PhysicalDevice, Device},
+    format::{ClearValue, Format},
+    image::{view::ImageView, AttachmentImage, SwapchainImage},
+    pipeline::{
+        graphics::{
+            input_assembly::InputAssemblyState,
+            vertex_input::BuffersDefinition,
+            viewport::{Viewport, ViewportState},
+        },
+        GraphicsPipeline, Pipeline, PipelineBindPoint,
+    },
+    render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass},
+    sampler::{Filter, Sampler, SamplerAddressMode, SamplerCreateInfo},
+};
+
+mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+            #version 450
+            layout(location = 0) out vec2 v_uv;
+
+            // One triangle that covers the viewport.
+            void main() {
+                v_uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+                gl_Position = vec4(v_uv * 2.0 - 1.0, 0.0, 1.0);
+            }"
+    }
+}
+mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+            #version 450
+            layout(location = 0) in vec2 v_uv;
+            layout(location = 0) out vec4 f_color;
+
+            layout(set = 0, binding = 0) uniform sampler2D hdr;
+            layout(push_constant) uniform Params {
+                float exposure;
+                // 0 = none, 1 = Reinhard, 2 = ACES
+                int operator;
+                float bloom_threshold;
+                float bloom_intensity;
+                float bloom_radius;
+            } params;
+
+            vec3 bright(vec2 uv) {
+                vec3 color = texture(hdr, uv).rgb;
+                return max(color - params.bloom_threshold, 0.0);
+            }
+
+            vec3 aces(vec3 x) {
+                return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+            }
+
+            void main() {
+                vec3 color = texture(hdr, v_uv).rgb;
+                if (params.bloom_intensity > 0.0) {
+                    // Gather the bright parts of the image from rings of samples around the pixel,
+                    // weighted towards the center.
+                    vec2 texel = params.bloom_radius / vec2(textureSize(hdr, 0));
+                    vec3 bloom = vec3(0.0);
+                    float total = 0.0;
+                    for (int ring = 1; ring <= 3; ring++) {
+                        float weight = 1.0 / float(ring);
+                        for (int i = 0; i < 8; i++) {
+                            float angle = (float(i) + float(ring) * 0.5) * 0.785398;
+                            vec2 offset = vec2(cos(angle), sin(angle)) * float(ring) / 3.0;
+                            bloom += bright(v_uv + offset * texel) * weight;
+                            total += weight;
+                        }
+                    }
+                    color += bloom / total * params.bloom_intensity;
+                }
+                color *= params.exposure;
+                if (params.operator == 1) {
+                    color = color / (1.0 + color);
+                } else if (params.operator == 2) {
+                    color = aces(color);
+                }
+                f_color = vec4(clamp(color, 0.0, 1.0), 1.0);
+            }"
+    }
+}
+
+/// How HDR colors are mapped to the displayable 0..1 range.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Tonemap {
+    /// Clip everything above 1.
+    None,
+    /// `c / (1 + c)`. Gentle, but washes out bright colors a little.
+    Reinhard,
+    /// A fit of the ACES filmic curve, with more contrast and saturated highlights.
+    #[default]
+    Aces,
+}
+
+impl Tonemap {
+    /// The displayed value of an HDR channel, as the tonemap pass computes it.
+    pub fn apply(self, value: f32, exposure: f32) -> f32 {
+        let x = value * exposure;
+        let mapped = match self {
+            Tonemap::None => x,
+            Tonemap::Reinhard => x / (1.0 + x),
+            Tonemap::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+        };
+        mapped.clamp(0.0, 1.0)
+    }
+    /// `apply` to each color channel. Alpha is unchanged.
+    pub fn apply_color(self, color: Color, exposure: f32) -> Color {
+        let [r, g, b, a]: [f32; 4] = color.into();
+        Color::new(
+            self.apply(r, exposure),
+            self.apply(g, exposure),
+            self.apply(b, exposure),
+            a,
+        )
+    }
+    fn shader_value(self) -> i32 {
+        match self {
+            Tonemap::None => 0,
+            Tonemap::Reinhard => 1,
+            Tonemap::Aces => 2,
+        }
+    }
+}
+
+/// A glow around parts of the image brighter than `threshold`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Bloom {
+    /// Only the part of each channel above this value glows.
+    pub threshold: f32,
+    pub intensity: f32,
+    /// How far the glow spreads, in pixels.
+    pub radius: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom {
+            threshold: 1.0,
+            intensity: 0.5,
+            radius: 12.0,
+        }
+    }
+}
+
+/// How the HDR image is turned into the final image. Can be changed at any time with
+/// `RenderContext::set_hdr_settings`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct HdrSettings {
+    pub tonemap: Tonemap,
+    /// Multiplies colors before tonemapping. 1 is unchanged; 0 is black.
+    pub exposure: f32,
+    pub bloom: Option<Bloom>,
+}
+
+impl Default for HdrSettings {
+    fn default() -> Self {
+        HdrSettings {
+            tonemap: Tonemap::default(),
+            exposure: 1.0,
+            bloom: None,
+        }
+    }
+}
+
+pub(crate) const HDR_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+
+/// Whether the device can render to and sample from `HDR_FORMAT`.
+pub(crate) fn hdr_supported(physical_device: &PhysicalDevice) -> bool {
+    physical_device
+        .format_properties(HDR_FORMAT)
+        .map_or(false, |properties| {
+            let features = properties.optimal_tiling_features;
+            features.color_attachment && features.color_attachment_blend && features.sampled_image
+        })
+}
+
+/// The pass that tonemaps the HDR image onto the swapchain image.
+pub(crate) struct TonemapPass {
+    render_pass: Arc<RenderPass>,
+    pipeline: Arc<GraphicsPipeline>,
+    sampler: Arc<Sampler>,
+    framebuffers: Vec<Arc<Framebuffer>>,
+    descriptor_set: Option<Arc<PersistentDescriptorSet>>,
+}
+
+impl TonemapPass {
+    pub fn new(device: &Arc<Device>, swapchain_format: Format) -> Self {
+        let render_pass = vulkano::single_pass_renderpass!(
+            device.clone(),
+            attachments: {
+                color: {
+                    load: Clear,
+                    store: Store,
+                    format: swapchain_format,
+                    samples: 1,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {}
+            }
+        )
+        .unwrap();
+        let vs = vs::load(device.clone()).unwrap();
+        let fs = fs::load(device.clone()).unwrap();
+        let pipeline = GraphicsPipeline::start()
+            .vertex_input_state(BuffersDefinition::new())
+            .vertex_shader(vs.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .fragment_shader(fs.entry_point("main").unwrap(), ())
+            .render_pass(Subpass::from(render_pass.clone(), 0).unwrap())
+            .build(device.clone())
+            .unwrap();
+        // Nearest filtering, so an internal resolution is upscaled with crisp pixels.
+        let sampler = Sampler::new(
+            device.clone(),
+            SamplerCreateInfo {
+                mag_filter: Filter::Nearest,
+                min_filter: Filter::Nearest,
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        TonemapPass {
+            render_pass,
+            pipeline,
+            sampler,
+            framebuffers: Vec::new(),
+            descriptor_set: None,
+        }
+    }
+
+    /// Recreate the framebuffers and descriptor set after the swapchain or HDR image changed.
+    pub fn resize(
+        &mut self,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        images: &[Arc<SwapchainImage>],
+        hdr_image: Arc<AttachmentImage>,
+    ) {
+        self.framebuffers = images
+            .iter()
+            .map(|image| {
+                Framebuffer::new(
+                    self.render_pass.clone(),
+                    FramebufferCreateInfo {
+                        attachments: vec![ImageView::new_default(image.clone()).unwrap()],
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+            })
+            .collect();
+        let layout = self.pipeline.layout().set_layouts().get(0).unwrap().clone();
+        self.descriptor_set = Some(
+            PersistentDescriptorSet::new(
+                descriptor_set_allocator,
+                layout,
+                [WriteDescriptorSet::image_view_sampler(
+                    0,
+                    ImageView::new_default(hdr_image).unwrap(),
+                    self.sampler.clone(),
+                )],
+            )
+            .unwrap(),
+        );
+    }
+
+    /// Tonemap into the swapchain image `index`, drawing to `dest` and filling the rest with
+    /// `letterbox_color`.
+    pub fn draw(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        index: usize,
+        dest: IRect,
+        letterbox_color: Color,
+        settings: &HdrSettings,
+    ) {
+        let descriptor_set = self.descriptor_set.clone().expect("HDR pass not set up");
+        let bloom = settings.bloom.unwrap_or(Bloom {
+            intensity: 0.0,
+            ..Default::default()
+        });
+        builder
+            .begin_render_pass(
+                RenderPassBeginInfo {
+                    clear_values: vec![Some(ClearValue::Float(letterbox_color.into()))],
+                    ..RenderPassBeginInfo::framebuffer(self.framebuffers[index].clone())
+                },
+                SubpassContents::Inline,
+            )
+            .unwrap()
+            .set_viewport(
+                0,
+                [Viewport {
+                    origin: dest.position.as_vec2().into(),
+                    dimensions: dest.size.as_vec2().into(),
+                    depth_range: 0.0..1.0,
+                }],
+            )
+            .bind_pipeline_graphics(self.pipeline.clone())
+            .bind_descriptor_sets(
+                PipelineBindPoint::Graphics,
+                self.pipeline.layout().clone(),
+                0,
+                descriptor_set,
+            )
+            .push_constants(
+                self.pipeline.layout().clone(),
+                0,
+                fs::ty::Params {
+                    exposure: settings.exposure.max(0.0),
+                    operator: settings.tonemap.shader_value(),
+                    bloom_threshold: bloom.threshold,
+                    bloom_intensity: bloom.intensity.max(0.0),
+                    bloom_radius: bloom.radius.max(0.0),
+                },
+            )
+            .draw(3, 1, 0, 0)
+            .unwrap()
+            .end_render_pass()
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TONEMAPS: [Tonemap; 3] = [Tonemap::None, Tonemap::Reinhard, Tonemap::Aces];
+
+    #[test]
+    fn zero_exposure_is_black() {
+        for tonemap in TONEMAPS {
+            for value in [0.0, 0.5, 1.0, 100.0] {
+                assert_eq!(tonemap.apply(value, 0.0), 0.0, "{tonemap:?}");
+            }
+            let color = tonemap.apply_color(Color::new(4.0, 1.0, 0.25, 0.5), 0.0);
+            assert_eq!(<[f32; 4]>::from(color), [0.0, 0.0, 0.0, 0.5]);
+        }
+    }
+
+    #[test]
+    fn curves_stay_in_range_and_rise() {
+        assert_eq!(Tonemap::None.apply(0.5, 1.0), 0.5);
+        assert_eq!(Tonemap::None.apply(3.0, 1.0), 1.0);
+        assert_eq!(Tonemap::Reinhard.apply(1.0, 1.0), 0.5);
+        assert_eq!(Tonemap::Reinhard.apply(0.5, 2.0), 0.5);
+        for tonemap in TONEMAPS {
+            let mut previous = 0.0;
+            for step in 1..=100 {
+                let mapped = tonemap.apply(step as f32 * 0.1, 1.0);
+                assert!((0.0..=1.0).contains(&mapped), "{tonemap:?}");
+                assert!(mapped >= previous, "{tonemap:?}");
+                previous = mapped;
+            }
+        }
+        assert!(Tonemap::Aces.apply(10.0, 1.0) > 0.99);
+    }
+}
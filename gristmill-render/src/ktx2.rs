@@ -0,0 +1,194 @@
+use gristmill_core::asset::{AssetError, AssetResult};
+use std::ops::Range;
+
+/// The first bytes of every KTX2 file.
+pub const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+const HEADER_LEN: usize = 80;
+const LEVEL_INDEX_ENTRY_LEN: usize = 24;
+const SUPERCOMPRESSION_NONE: u32 = 0;
+const SUPERCOMPRESSION_BASIS_LZ: u32 = 1;
+const VK_FORMAT_UNDEFINED: u32 = 0;
+/// The DFD color model of UASTC, the other Basis Universal encoding.
+const KHR_DF_MODEL_UASTC: u8 = 166;
+
+/// A 2D texture in a KTX2 container, with its mip levels still encoded in `vk_format`.
+#[derive(Clone, Debug)]
+pub struct Ktx2<'a> {
+    /// The `VkFormat` of the texel blocks.
+    pub vk_format: u32,
+    pub width: u32,
+    pub height: u32,
+    data: &'a [u8],
+    /// Byte ranges of each mip level in `data`, largest first.
+    levels: Vec<Range<usize>>,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+fn invalid(message: &str) -> AssetError {
+    AssetError::InvalidFormat(format!("KTX2: {message}"))
+}
+
+impl<'a> Ktx2<'a> {
+    pub fn is_ktx2(data: &[u8]) -> bool {
+        data.starts_with(&KTX2_IDENTIFIER)
+    }
+
+    /// Read the header and level index of a KTX2 file. Only single 2D images without
+    /// supercompression are accepted; Basis Universal files (BasisLZ or UASTC) are rejected, since
+    /// they need transcoding first.
+    pub fn parse(data: &'a [u8]) -> AssetResult<Self> {
+        if !Ktx2::is_ktx2(data) {
+            return Err(invalid("not a KTX2 file"));
+        }
+        if data.len() < HEADER_LEN {
+            return Err(invalid("file is truncated"));
+        }
+        let vk_format = read_u32(data, 12);
+        let width = read_u32(data, 20);
+        let height = read_u32(data, 24);
+        let depth = read_u32(data, 28);
+        let layers = read_u32(data, 32);
+        let faces = read_u32(data, 36);
+        let level_count = read_u32(data, 40).max(1) as usize;
+        let supercompression = read_u32(data, 44);
+        let dfd_offset = read_u32(data, 48) as usize;
+
+        let is_uastc = vk_format == VK_FORMAT_UNDEFINED
+            && data.get(dfd_offset + 12).copied() == Some(KHR_DF_MODEL_UASTC);
+        if supercompression == SUPERCOMPRESSION_BASIS_LZ || is_uastc {
+            return Err(invalid(
+                "Basis Universal textures must be transcoded to a block format before loading",
+            ));
+        }
+        if supercompression != SUPERCOMPRESSION_NONE {
+            return Err(invalid(&format!(
+                "unsupported supercompression scheme {supercompression}"
+            )));
+        }
+        if vk_format == VK_FORMAT_UNDEFINED {
+            return Err(invalid("format is undefined"));
+        }
+        if width == 0 || height == 0 || depth > 1 || layers > 1 || faces != 1 {
+            return Err(invalid("only single 2D images are supported"));
+        }
+        if level_count > 32 {
+            return Err(invalid("too many mip levels"));
+        }
+
+        let index_end = HEADER_LEN + level_count * LEVEL_INDEX_ENTRY_LEN;
+        if data.len() < index_end {
+            return Err(invalid("level index is truncated"));
+        }
+        let levels = (0..level_count)
+            .map(|level| {
+                let entry = HEADER_LEN + level * LEVEL_INDEX_ENTRY_LEN;
+                let offset = read_u64(data, entry) as usize;
+                let length = read_u64(data, entry + 8) as usize;
+                match offset.checked_add(length) {
+                    Some(end) if end <= data.len() => Ok(offset..end),
+                    _ => Err(invalid("level data is out of bounds")),
+                }
+            })
+            .collect::<AssetResult<_>>()?;
+        Ok(Ktx2 {
+            vk_format,
+            width,
+            height,
+            data,
+            levels,
+        })
+    }
+
+    pub fn level_count(&self) -> u32 {
+        self.levels.len() as u32
+    }
+    /// The encoded data of mip `level`, 0 being the full size image.
+    pub fn level_data(&self, level: u32) -> &'a [u8] {
+        &self.data[self.levels[level as usize].clone()]
+    }
+    /// The size of mip `level` in pixels.
+    pub fn level_size(&self, level: u32) -> [u32; 2] {
+        [(self.width >> level).max(1), (self.height >> level).max(1)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VK_FORMAT_BC7_SRGB_BLOCK: u32 = 146;
+
+    /// A 4x4 BC7 texture: one 16-byte block, in one mip level.
+    fn bc7_file() -> Vec<u8> {
+        let mut data = vec![0; HEADER_LEN + LEVEL_INDEX_ENTRY_LEN];
+        data[..12].copy_from_slice(&KTX2_IDENTIFIER);
+        let mut write_u32 = |offset: usize, value: u32| {
+            data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        };
+        write_u32(12, VK_FORMAT_BC7_SRGB_BLOCK);
+        write_u32(16, 1); // typeSize
+        write_u32(20, 4); // width
+        write_u32(24, 4); // height
+        write_u32(36, 1); // faceCount
+        write_u32(40, 1); // levelCount
+        let level_offset = data.len() as u64;
+        data[HEADER_LEN..HEADER_LEN + 8].copy_from_slice(&level_offset.to_le_bytes());
+        data[HEADER_LEN + 8..HEADER_LEN + 16].copy_from_slice(&16u64.to_le_bytes());
+        data.extend(0..16);
+        data
+    }
+
+    fn error_message(data: &[u8]) -> String {
+        match Ktx2::parse(data) {
+            Err(AssetError::InvalidFormat(message)) => message,
+            other => panic!("expected an invalid format error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_single_level_bc7() {
+        let data = bc7_file();
+        let ktx2 = Ktx2::parse(&data).unwrap();
+        assert_eq!(ktx2.vk_format, VK_FORMAT_BC7_SRGB_BLOCK);
+        assert_eq!((ktx2.width, ktx2.height), (4, 4));
+        assert_eq!(ktx2.level_count(), 1);
+        assert_eq!(ktx2.level_size(0), [4, 4]);
+        assert_eq!(ktx2.level_data(0), (0..16).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn rejects_truncated() {
+        let data = bc7_file();
+        assert!(error_message(&data[..40]).contains("file is truncated"));
+        assert!(error_message(&data[..HEADER_LEN + 8]).contains("level index is truncated"));
+        assert!(error_message(&data[..data.len() - 1]).contains("out of bounds"));
+        assert!(error_message(&data[1..]).contains("not a KTX2 file"));
+    }
+
+    #[test]
+    fn rejects_basis_lz() {
+        let mut data = bc7_file();
+        data[12..16].copy_from_slice(&VK_FORMAT_UNDEFINED.to_le_bytes());
+        data[44..48].copy_from_slice(&SUPERCOMPRESSION_BASIS_LZ.to_le_bytes());
+        assert!(error_message(&data).contains("Basis Universal"));
+    }
+
+    #[test]
+    fn rejects_level_out_of_bounds() {
+        let mut data = bc7_file();
+        let entry = HEADER_LEN + 8;
+        data[entry..entry + 8].copy_from_slice(&17u64.to_le_bytes());
+        assert!(error_message(&data).contains("out of bounds"));
+        // An offset and length that overflow when added.
+        data[HEADER_LEN..HEADER_LEN + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(error_message(&data).contains("out of bounds"));
+    }
+}
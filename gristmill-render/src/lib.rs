@@ -1,102 +1,301 @@
+#[cfg(feature = "clip-capture")]
+mod clip;
+mod display;
+mod draw_budget;
+mod gpu_timer;
+mod hdr;
+mod ktx2;
+mod static_commands;
+mod surface_format;
 mod texture;
 pub mod texture_rect;
+mod window_state;
+mod window_target;
 
-use gristmill_core::{asset::AssetResult, geom2d::Rect, math::Vec2, Color};
-use std::{collections::HashMap, sync::Arc};
+#[cfg(feature = "clip-capture")]
+use clip::ClipRecorder;
+use gpu_timer::GpuTimer;
+use gristmill_core::{
+    asset::AssetResult,
+    geom2d::{IRect, Rect},
+    math::{IVec2, Vec2},
+    util::Symbol,
+    Color,
+};
+use hdr::HDR_FORMAT;
+use static_commands::{StaticCommands, StaticKey};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use vulkano::{
     command_buffer::{
-        allocator::StandardCommandBufferAllocator, AutoCommandBufferBuilder, CommandBufferUsage,
-        RenderPassBeginInfo, SubpassContents,
+        allocator::StandardCommandBufferAllocator, AutoCommandBufferBuilder, BlitImageInfo,
+        ClearAttachment, ClearColorImageInfo, ClearRect, CommandBufferInheritanceInfo,
+        CommandBufferUsage, ImageBlit, RenderPassBeginInfo, SubpassContents,
     },
     command_buffer::{PrimaryAutoCommandBuffer, PrimaryCommandBufferAbstract},
     descriptor_set::allocator::StandardDescriptorSetAllocator,
     device::Queue,
     device::{
-        physical::PhysicalDeviceType, Device, DeviceCreateInfo, DeviceExtensions, QueueCreateInfo,
+        physical::PhysicalDeviceType, Device, DeviceCreateInfo, DeviceExtensions, Features,
+        QueueCreateInfo,
+    },
+    format::{ClearColorValue, ClearValue, Format},
+    image::{
+        view::{ImageView, ImageViewAbstract},
+        AttachmentImage, ImageAccess, ImageUsage, SwapchainImage,
     },
-    format::{ClearValue, Format},
-    image::{view::ImageView, AttachmentImage, ImageAccess, ImageUsage, SwapchainImage},
     instance::{Instance, InstanceCreateInfo},
     memory::allocator::StandardMemoryAllocator,
     pipeline::graphics::viewport::Viewport,
     render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass},
-    swapchain::Surface,
+    sampler::Filter,
     swapchain::{
-        acquire_next_image, AcquireError, Swapchain, SwapchainCreateInfo, SwapchainCreationError,
+        acquire_next_image, AcquireError, ColorSpace, SwapchainCreateInfo, SwapchainCreationError,
         SwapchainPresentInfo,
     },
     sync::{self, FlushError, GpuFuture},
     VulkanLibrary,
 };
 use vulkano_win::VkSurfaceBuild;
+use window_target::{create_swapchain, secondary_window_format, WindowTarget};
 use winit::{
-    event_loop::EventLoop,
-    window::{Window, WindowBuilder},
+    event_loop::{EventLoop, EventLoopWindowTarget},
+    window::{Window, WindowBuilder, WindowId},
 };
 
+#[cfg(feature = "clip-capture")]
+pub use clip::{downscale_rgba, ClipFrame, ClipSettings, FrameRing};
+pub use display::DisplaySettings;
+pub use draw_budget::DrawBudget;
+pub use hdr::{Bloom, HdrSettings, Tonemap};
+pub use ktx2::{Ktx2, KTX2_IDENTIFIER};
+pub use static_commands::StaticBuilder;
+pub use surface_format::{select_swapchain_format, SwapchainFormat};
 pub use texture::*;
+pub use window_state::WindowState;
+
+/// What happens to the color attachment's previous contents at the start of each frame.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum ColorLoad {
+    /// Fill with the clear color.
+    #[default]
+    Clear,
+    /// Leave undefined. Use this when the game covers the whole screen every frame.
+    DontCare,
+    /// Keep the previous contents. The swapchain cycles through several images, so these are the
+    /// contents from the last time this image was presented, not necessarily the previous frame.
+    Load,
+}
 
+#[derive(Clone)]
+pub struct RenderSettings {
+    pub color_load: ColorLoad,
+    /// Measure GPU frame time with timestamp queries. Off by default since the queries have a
+    /// small cost. See `RenderContext::gpu_frame_time`.
+    pub gpu_timing: bool,
+    /// Reopen the window with the position, size and maximized state it had when the game last
+    /// exited, saved in the "config" prefix. On by default; turn it off for games that always want
+    /// a specific window size.
+    pub remember_window: bool,
+    /// Anisotropic filtering level for textures, such as 4.0 or 16.0. Off by default, since most
+    /// 2D scenes draw textures face-on where it makes no difference. It sharpens textures drawn at
+    /// grazing angles, like 2.5D ground planes, at the cost of more texture samples per pixel,
+    /// which mostly matters on integrated GPUs. Can be changed later with
+    /// `RenderContext::set_anisotropy`.
+    pub anisotropy: Option<f32>,
+    /// Whether to render to an sRGB or UNORM swapchain image. If the device doesn't support the
+    /// requested kind, the first format it lists is used and a warning is logged.
+    pub swapchain_format: SwapchainFormat,
+    /// Render to a 16-bit float image, so colors can go above 1, then tonemap it onto the
+    /// swapchain image. Needed for bloom; see `RenderContext::set_hdr_settings`. Off by default,
+    /// since the extra pass costs fill rate. Falls back to normal rendering with a warning if the
+    /// device can't render to float images.
+    pub hdr: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            color_load: ColorLoad::default(),
+            gpu_timing: false,
+            remember_window: true,
+            anisotropy: None,
+            swapchain_format: SwapchainFormat::default(),
+            hdr: false,
+        }
+    }
+}
+
+/// How a fixed internal resolution is scaled up to fill the window.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum UpscaleMode {
+    /// Scale by the largest whole number that fits, so every internal pixel covers the same
+    /// number of window pixels. The remainder becomes letterbox bars.
+    #[default]
+    Integer,
+    /// Scale as large as fits while keeping the aspect ratio. Some pixels end up a window pixel
+    /// wider than others, which can shimmer when things move.
+    Fit,
+}
+
+impl UpscaleMode {
+    /// The rect within a window of `window_size` that an image of `internal_size` is drawn to.
+    /// Everything outside it is letterbox bars.
+    pub fn dest_rect(self, window_size: IVec2, internal_size: IVec2) -> IRect {
+        let internal_size = internal_size.max(IVec2::ONE);
+        match self {
+            UpscaleMode::Integer => {
+                let scale = (window_size / internal_size).min_element().max(1);
+                let size = internal_size * scale;
+                IRect {
+                    position: (window_size - size) / 2,
+                    size,
+                }
+            }
+            UpscaleMode::Fit => {
+                let aspect = internal_size.x as f32 / internal_size.y as f32;
+                IRect::from_size(window_size)
+                    .as_rect()
+                    .fit_aspect(aspect)
+                    .as_irect()
+            }
+        }
+    }
+}
+
+/// Something `RenderContext::render_game` can draw.
+///
+/// Content that rarely changes can be drawn in `render_static` instead of `render`. It is
+/// recorded once into a secondary command buffer and replayed every frame, underneath what
+/// `render` draws. It is recorded again when:
+/// - `static_version` returns a different number, which the game should do whenever the static
+///   content changes,
+/// - the viewport changes, such as after a resize or a change of internal resolution or fixed
+///   aspect,
+/// - the display settings change, or
+/// - `RenderContext::invalidate_static` is called, for anything else the commands depend on.
 pub trait Renderable {
     fn pre_render(&mut self, context: &mut RenderContext);
     fn render(&mut self, context: &mut RenderContext);
+    /// A number that changes whenever the static content does, or `None` if there is no static
+    /// content. Defaults to `None`.
+    fn static_version(&self) -> Option<u64> {
+        None
+    }
+    /// Record the static content into `builder`. The viewport is already set.
+    fn render_static(&mut self, _context: &mut RenderContext, _builder: &mut StaticBuilder) {}
 }
 
 /// This method is called once during initialization, then again whenever the window is resized
+/// With an internal resolution or HDR, every framebuffer renders to the same offscreen image, which
+/// is returned so it can be copied or tonemapped to the swapchain image afterwards.
 fn window_size_dependent_setup(
     memory_allocator: &StandardMemoryAllocator,
     images: &[Arc<SwapchainImage>],
     render_pass: Arc<RenderPass>,
     viewport: &mut Viewport,
-) -> Vec<Arc<Framebuffer>> {
-    let dimensions = images[0].dimensions().width_height();
-    viewport.dimensions = [dimensions[0] as f32, dimensions[1] as f32];
+    fixed_aspect: Option<f32>,
+    internal_resolution: Option<IVec2>,
+    hdr: bool,
+) -> (Vec<Arc<Framebuffer>>, Option<Arc<AttachmentImage>>) {
+    let mut dimensions = images[0].dimensions().width_height();
+    if let Some(resolution) = internal_resolution {
+        dimensions = resolution.max(IVec2::ONE).as_uvec2().into();
+    }
+    let mut viewport_rect = Rect::new(0.0, 0.0, dimensions[0] as f32, dimensions[1] as f32);
+    if let (Some(aspect), None) = (fixed_aspect, internal_resolution) {
+        viewport_rect = viewport_rect.fit_aspect(aspect);
+    }
+    viewport.origin = viewport_rect.position.into();
+    viewport.dimensions = viewport_rect.size.into();
 
     let depth_buffer = ImageView::new_default(
         AttachmentImage::transient(memory_allocator, dimensions, Format::D16_UNORM).unwrap(),
     )
     .unwrap();
+    let framebuffer = |view: Arc<dyn ImageViewAbstract>| {
+        Framebuffer::new(
+            render_pass.clone(),
+            FramebufferCreateInfo {
+                attachments: vec![view, depth_buffer.clone()],
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    };
 
-    images
-        .iter()
-        .map(|image| {
-            let view = ImageView::new_default(image.clone()).unwrap();
-            Framebuffer::new(
-                render_pass.clone(),
-                FramebufferCreateInfo {
-                    attachments: vec![view, depth_buffer.clone()],
-                    ..Default::default()
-                },
-            )
-            .unwrap()
-        })
-        .collect::<Vec<_>>()
+    // An HDR image is tonemapped onto the swapchain image, while an internal resolution image is
+    // copied to it.
+    if internal_resolution.is_some() || hdr {
+        let offscreen = AttachmentImage::with_usage(
+            memory_allocator,
+            dimensions,
+            if hdr { HDR_FORMAT } else { images[0].format() },
+            ImageUsage {
+                color_attachment: true,
+                transfer_src: !hdr,
+                sampled: hdr,
+                ..ImageUsage::empty()
+            },
+        )
+        .unwrap();
+        let offscreen_framebuffer = framebuffer(ImageView::new_default(offscreen.clone()).unwrap());
+        (vec![offscreen_framebuffer; images.len()], Some(offscreen))
+    } else {
+        let framebuffers = images
+            .iter()
+            .map(|image| framebuffer(ImageView::new_default(image.clone()).unwrap()))
+            .collect();
+        (framebuffers, None)
+    }
 }
 
+/// Renders to the main window, and any secondary windows opened with
+/// `create_secondary_window`.
+///
+/// Every window shares the device, queue, allocators and render passes, so textures, pipelines
+/// and other resources made with the context can be drawn in any of them. Each window has its own
+/// swapchain, framebuffers, viewport and frame in flight, and the window-specific settings below
+/// (clear color, fixed aspect, internal resolution, letterbox color and static content) apply to
+/// the window being rendered, or to the main window between frames.
 pub struct RenderContext {
-    surface: Arc<Surface>,
     device: Arc<Device>,
     queue: Arc<Queue>,
     memory_allocator: Arc<StandardMemoryAllocator>,
     descriptor_set_allocator: StandardDescriptorSetAllocator,
     command_buffer_allocator: StandardCommandBufferAllocator,
     render_pass: Arc<RenderPass>,
-    viewport: Viewport,
+    static_render_passes: (Arc<RenderPass>, Arc<RenderPass>),
+    static_record_count: usize,
+    color_load: ColorLoad,
 
-    swapchain: Arc<Swapchain>,
-    framebuffers: Vec<Arc<Framebuffer>>,
-    clear_color: Color,
-    recreate_swapchain: bool,
-    previous_frame_end: Option<Box<dyn GpuFuture>>,
+    /// The window being rendered, or the main window between frames. A secondary window is
+    /// swapped in here while it renders.
+    target: WindowTarget,
+    main_window: WindowId,
+    secondary_windows: HashMap<WindowId, WindowTarget>,
+    current_secondary: Option<WindowId>,
 
     current_builder: Option<AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>>,
-    current_framebuffer_index: usize,
-    recently_resized: bool,
+    gpu_timer: Option<GpuTimer>,
+    #[cfg(feature = "clip-capture")]
+    clip_recorder: Option<ClipRecorder>,
+    remember_window: bool,
+    window_state: Option<WindowState>,
 
-    texture_cache: HashMap<String, Texture>,
+    texture_cache: HashMap<Symbol, Texture>,
+    anisotropy: Option<f32>,
+    display: DisplaySettings,
+    hdr_settings: HdrSettings,
 }
 
 impl RenderContext {
     pub fn create_window(event_loop: &EventLoop<()>) -> Self {
+        Self::create_window_with_settings(event_loop, RenderSettings::default())
+    }
+    pub fn create_window_with_settings(
+        event_loop: &EventLoop<()>,
+        settings: RenderSettings,
+    ) -> Self {
         let library = VulkanLibrary::new().unwrap();
         let required_extensions = vulkano_win::required_extensions(&library);
         let instance = Instance::new(
@@ -109,7 +308,33 @@ impl RenderContext {
         )
         .unwrap();
 
-        let surface = WindowBuilder::new()
+        let window_state = if settings.remember_window {
+            match WindowState::load_config() {
+                Ok(window_state) => Some(window_state),
+                Err(error) => {
+                    if error.io_kind() != Some(std::io::ErrorKind::NotFound) {
+                        log::warn!("{}", error);
+                    }
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let display = match DisplaySettings::load_config() {
+            Ok(display) => display,
+            Err(error) => {
+                if error.io_kind() != Some(std::io::ErrorKind::NotFound) {
+                    log::warn!("{}", error);
+                }
+                DisplaySettings::default()
+            }
+        };
+        let mut window_builder = WindowBuilder::new();
+        if let Some(window_state) = window_state.as_ref() {
+            window_builder = window_state.apply(window_builder, event_loop);
+        }
+        let surface = window_builder
             .build_vk_surface(event_loop, instance.clone())
             .unwrap();
 
@@ -150,10 +375,21 @@ impl RenderContext {
             physical_device.properties().device_type,
         );
 
+        // Enable anisotropy whenever it's available, so it can be turned on later. Block
+        // compressed formats can only be used with their feature enabled.
+        let supported_features = physical_device.supported_features();
+        let enabled_features = Features {
+            sampler_anisotropy: supported_features.sampler_anisotropy,
+            texture_compression_bc: supported_features.texture_compression_bc,
+            texture_compression_etc2: supported_features.texture_compression_etc2,
+            texture_compression_astc_ldr: supported_features.texture_compression_astc_ldr,
+            ..Features::empty()
+        };
         let (device, mut queues) = Device::new(
             physical_device,
             DeviceCreateInfo {
                 enabled_extensions: device_extensions,
+                enabled_features,
                 queue_create_infos: vec![QueueCreateInfo {
                     queue_family_index,
                     ..Default::default()
@@ -165,77 +401,99 @@ impl RenderContext {
         let queue = queues.next().unwrap();
 
         let (swapchain, images) = {
-            let surface_capabilities = device
+            let surface_formats = device
                 .physical_device()
-                .surface_capabilities(&surface, Default::default())
+                .surface_formats(&surface, Default::default())
                 .unwrap();
-            let image_format = Some(
-                device
-                    .physical_device()
-                    .surface_formats(&surface, Default::default())
-                    .unwrap()[0]
-                    .0,
-            );
-            let window = surface.object().unwrap().downcast_ref::<Window>().unwrap();
-
-            Swapchain::new(
-                device.clone(),
-                surface.clone(),
-                SwapchainCreateInfo {
-                    min_image_count: surface_capabilities.min_image_count,
-                    image_format,
-                    image_extent: window.inner_size().into(),
-                    image_usage: ImageUsage {
-                        color_attachment: true,
-                        ..ImageUsage::empty()
-                    },
-                    composite_alpha: surface_capabilities
-                        .supported_composite_alpha
-                        .iter()
-                        .next()
-                        .unwrap(),
-                    ..Default::default()
-                },
-            )
-            .unwrap()
+            let (image_format, image_color_space) =
+                select_swapchain_format(&surface_formats, settings.swapchain_format)
+                    .expect("Surface has no supported formats");
+            log::debug!("Using swapchain format: {:?}", image_format);
+            create_swapchain(&device, surface.clone(), image_format, image_color_space).unwrap()
         };
 
-        let render_pass = vulkano::single_pass_renderpass!(
-            device.clone(),
-            attachments: {
-                color: {
-                    load: Clear,
-                    store: Store,
-                    format: swapchain.image_format(),
-                    samples: 1,
-                },
-                depth: {
-                    load: Clear,
-                    store: DontCare,
-                    format: Format::D16_UNORM,
-                    samples: 1,
-                }
-            },
-            pass: {
-                color: [color],
-                depth_stencil: {depth}
+        let hdr = settings.hdr && {
+            let supported = hdr::hdr_supported(device.physical_device());
+            if !supported {
+                log::warn!("HDR rendering is not supported by this device.");
             }
-        )
+            supported
+        };
+        let color_format = if hdr {
+            HDR_FORMAT
+        } else {
+            swapchain.image_format()
+        };
+
+        macro_rules! create_render_pass {
+            ($color_load:ident) => {
+                create_render_pass!($color_load, Clear, DontCare)
+            };
+            ($color_load:ident, $depth_load:ident, $depth_store:ident) => {
+                vulkano::single_pass_renderpass!(
+                    device.clone(),
+                    attachments: {
+                        color: {
+                            load: $color_load,
+                            store: Store,
+                            format: color_format,
+                            samples: 1,
+                        },
+                        depth: {
+                            load: $depth_load,
+                            store: $depth_store,
+                            format: Format::D16_UNORM,
+                            samples: 1,
+                        }
+                    },
+                    pass: {
+                        color: [color],
+                        depth_stencil: {depth}
+                    }
+                )
+            };
+        }
+        let render_pass = match settings.color_load {
+            ColorLoad::Clear => create_render_pass!(Clear),
+            ColorLoad::DontCare => create_render_pass!(DontCare),
+            ColorLoad::Load => create_render_pass!(Load),
+        }
         .unwrap();
+        // With static content, the frame is split into a pass that replays the static commands
+        // and keeps the depth buffer, then a pass that draws on top of it.
+        let static_render_passes = (
+            match settings.color_load {
+                ColorLoad::Clear => create_render_pass!(Clear, Clear, Store),
+                ColorLoad::DontCare => create_render_pass!(DontCare, Clear, Store),
+                ColorLoad::Load => create_render_pass!(Load, Clear, Store),
+            }
+            .unwrap(),
+            create_render_pass!(Load, Load, DontCare).unwrap(),
+        );
 
-        let mut viewport = Viewport {
-            origin: [0.0, 0.0],
-            dimensions: [0.0, 0.0],
-            depth_range: 0.0..1.0,
-        };
         let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(device.clone()));
-        let framebuffers = window_size_dependent_setup(
+        let descriptor_set_allocator = StandardDescriptorSetAllocator::new(device.clone());
+        let target = WindowTarget::new(
+            &device,
             &memory_allocator,
-            &images,
+            &descriptor_set_allocator,
             render_pass.clone(),
-            &mut viewport,
+            surface,
+            swapchain,
+            images,
+            hdr,
         );
 
+        let gpu_timer = if settings.gpu_timing {
+            let gpu_timer = GpuTimer::new(&device, queue.queue_family_index());
+            if gpu_timer.is_none() {
+                log::warn!("GPU timing is not supported by this device.");
+            }
+            gpu_timer
+        } else {
+            None
+        };
+
         let command_buffer_allocator =
             StandardCommandBufferAllocator::new(device.clone(), Default::default());
         let uploads = AutoCommandBufferBuilder::primary(
@@ -245,40 +503,64 @@ impl RenderContext {
         )
         .unwrap();
 
-        RenderContext {
-            surface,
-            device: device.clone(),
+        let mut context = RenderContext {
+            device,
             queue,
             memory_allocator,
-            descriptor_set_allocator: StandardDescriptorSetAllocator::new(device),
+            descriptor_set_allocator,
             command_buffer_allocator,
             render_pass,
-            viewport,
-            swapchain,
-            framebuffers,
-            clear_color: Color::WHITE,
-            recreate_swapchain: false,
-            previous_frame_end: None,
+            static_render_passes,
+            static_record_count: 0,
+            color_load: settings.color_load,
             current_builder: Some(uploads),
-            current_framebuffer_index: 0,
-            recently_resized: false,
+            gpu_timer,
+            #[cfg(feature = "clip-capture")]
+            clip_recorder: None,
+            remember_window: settings.remember_window,
+            window_state,
             texture_cache: HashMap::new(),
-        }
+            anisotropy: None,
+            display,
+            hdr_settings: HdrSettings::default(),
+            main_window: target.window().id(),
+            target,
+            secondary_windows: HashMap::new(),
+            current_secondary: None,
+        };
+        context.set_anisotropy(settings.anisotropy);
+        context
     }
+    /// The window being rendered, or the main window between frames.
     pub fn window(&self) -> &Window {
-        self.surface
-            .object()
-            .unwrap()
-            .downcast_ref::<Window>()
-            .unwrap()
+        self.target.window()
     }
+    /// Recreate the swapchain of the window being rendered, or the main window between frames.
     pub fn on_resize(&mut self) {
-        self.recreate_swapchain = true;
-        self.recently_resized = true;
+        self.target.recreate_swapchain = true;
+        self.target.recently_resized = true;
+        // Remember the restored size, since it can't be read back while maximized.
+        if self.remember_window && self.current_secondary.is_none() && !self.window().is_maximized()
+        {
+            let window_state = WindowState::from_window(self.window(), self.window_state.as_ref());
+            self.window_state = Some(window_state);
+        }
+    }
+    /// Save the window's state for the next launch, if `RenderSettings::remember_window` is on.
+    /// The game loop calls this on exit.
+    pub fn save_window_state(&mut self) {
+        if !self.remember_window {
+            return;
+        }
+        let window_state = WindowState::from_window(self.window(), self.window_state.as_ref());
+        if let Err(error) = window_state.save_config() {
+            log::warn!("{}", error);
+        }
+        self.window_state = Some(window_state);
     }
     pub fn finish_setup(&mut self) {
         let uploads = self.current_builder.take().unwrap();
-        self.previous_frame_end = Some(
+        self.target.previous_frame_end = Some(
             uploads
                 .build()
                 .unwrap()
@@ -289,27 +571,187 @@ impl RenderContext {
     }
 
     fn begin_render_pass(&mut self) {
-        self.current_builder
-            .as_mut()
-            .expect("not rendering")
-            .begin_render_pass(
-                RenderPassBeginInfo {
-                    clear_values: vec![
-                        Some(ClearValue::Float(self.clear_color.into())),
-                        Some(ClearValue::Depth(1.0)),
-                    ],
-                    ..RenderPassBeginInfo::framebuffer(
-                        self.framebuffers[self.current_framebuffer_index].clone(),
-                    )
-                },
-                SubpassContents::Inline,
-            )
+        let framebuffer = self.target.framebuffers[self.target.current_framebuffer_index].clone();
+        let [width, height] = framebuffer.extent();
+        let bars = if self.target.fixed_aspect.is_some() {
+            Rect::new(0.0, 0.0, width as f32, height as f32).margins_outside(self.viewport())
+        } else {
+            Vec::new()
+        };
+        let clear_values = vec![
+            if self.color_load == ColorLoad::Clear {
+                Some(ClearValue::Float(
+                    self.display.apply(self.target.clear_color).into(),
+                ))
+            } else {
+                None
+            },
+            Some(ClearValue::Depth(1.0)),
+        ];
+        let builder = self.current_builder.as_mut().expect("not rendering");
+        let begin_info = if let Some(static_commands) = self.target.static_commands.as_ref() {
+            let (static_pass, after_pass) = &self.static_render_passes;
+            builder
+                .begin_render_pass(
+                    RenderPassBeginInfo {
+                        render_pass: static_pass.clone(),
+                        clear_values,
+                        ..RenderPassBeginInfo::framebuffer(framebuffer.clone())
+                    },
+                    SubpassContents::SecondaryCommandBuffers,
+                )
+                .unwrap()
+                .execute_commands(static_commands.buffer.clone())
+                .unwrap()
+                .end_render_pass()
+                .unwrap();
+            RenderPassBeginInfo {
+                render_pass: after_pass.clone(),
+                clear_values: vec![None, None],
+                ..RenderPassBeginInfo::framebuffer(framebuffer)
+            }
+        } else {
+            RenderPassBeginInfo {
+                clear_values,
+                ..RenderPassBeginInfo::framebuffer(framebuffer)
+            }
+        };
+        builder
+            .begin_render_pass(begin_info, SubpassContents::Inline)
             .unwrap()
-            .set_viewport(0, [self.viewport.clone()]);
+            .set_viewport(0, [self.target.viewport.clone()]);
+        // Nothing is drawn outside the viewport, so fill the letterbox bars explicitly instead of
+        // leaving them the clear color.
+        if !bars.is_empty() {
+            builder
+                .clear_attachments(
+                    [ClearAttachment::Color {
+                        color_attachment: 0,
+                        clear_value: ClearColorValue::Float(self.target.letterbox_color.into()),
+                    }],
+                    bars.into_iter().map(|bar| {
+                        let bar = bar.as_irect();
+                        ClearRect {
+                            offset: [bar.x() as u32, bar.y() as u32],
+                            extent: [bar.width() as u32, bar.height() as u32],
+                            array_layers: 0..1,
+                        }
+                    }),
+                )
+                .unwrap();
+        }
     }
     fn end_render_pass(&mut self) {
         self.builder().end_render_pass().unwrap();
     }
+    /// Copy the offscreen image to the swapchain image, if rendering at an internal resolution.
+    fn upscale(&mut self) {
+        let (offscreen, (_, mode)) = match (
+            self.target.offscreen.clone(),
+            self.target.internal_resolution,
+        ) {
+            (Some(offscreen), Some(internal_resolution)) => (offscreen, internal_resolution),
+            _ => return,
+        };
+        let target = self.target.swapchain_images[self.target.current_framebuffer_index].clone();
+        let [width, height] = target.dimensions().width_height();
+        let [internal_width, internal_height] = offscreen.dimensions().width_height();
+        let dest = mode.dest_rect(
+            IVec2::new(width as i32, height as i32),
+            IVec2::new(internal_width as i32, internal_height as i32),
+        );
+        let dest_min = dest.position.max(IVec2::ZERO).as_uvec2();
+        let dest_max = (dest.position + dest.size)
+            .as_uvec2()
+            .min([width, height].into());
+        let letterbox_color = self.target.letterbox_color;
+        let builder = self.builder();
+        builder
+            .clear_color_image(ClearColorImageInfo {
+                clear_value: ClearColorValue::Float(letterbox_color.into()),
+                ..ClearColorImageInfo::image(target.clone())
+            })
+            .unwrap();
+        builder
+            .blit_image(BlitImageInfo {
+                regions: [ImageBlit {
+                    src_subresource: offscreen.subresource_layers(),
+                    src_offsets: [[0, 0, 0], [internal_width, internal_height, 1]],
+                    dst_subresource: target.subresource_layers(),
+                    dst_offsets: [[dest_min.x, dest_min.y, 0], [dest_max.x, dest_max.y, 1]],
+                    ..Default::default()
+                }]
+                .into(),
+                filter: Filter::Nearest,
+                ..BlitImageInfo::images(offscreen, target)
+            })
+            .unwrap();
+    }
+    /// Tonemap the HDR image onto the swapchain image, scaling it up if rendering at an internal
+    /// resolution.
+    fn tonemap(&mut self) {
+        let target = &self.target.swapchain_images[self.target.current_framebuffer_index];
+        let [width, height] = target.dimensions().width_height();
+        let window_size = IVec2::new(width as i32, height as i32);
+        let dest = match self.target.internal_resolution {
+            Some((resolution, mode)) => mode.dest_rect(window_size, resolution),
+            None => IRect::from_size(window_size),
+        };
+        let builder = self.current_builder.as_mut().expect("not rendering");
+        if let Some(tonemap_pass) = self.target.tonemap_pass.as_ref() {
+            tonemap_pass.draw(
+                builder,
+                self.target.current_framebuffer_index,
+                dest,
+                self.target.letterbox_color,
+                &self.hdr_settings,
+            );
+        }
+    }
+    /// Re-record `game`'s static content if it changed, or drop it if there isn't any.
+    fn update_static<R: Renderable>(&mut self, game: &mut R) {
+        let version = match game.static_version() {
+            Some(version) => version,
+            None => {
+                self.target.static_commands = None;
+                return;
+            }
+        };
+        let key = StaticKey {
+            version,
+            viewport: self.viewport(),
+            display: self.display,
+        };
+        if self
+            .target
+            .static_commands
+            .as_ref()
+            .map_or(false, |commands| commands.is_valid(key))
+        {
+            return;
+        }
+        let mut builder = AutoCommandBufferBuilder::secondary(
+            &self.command_buffer_allocator,
+            self.queue.queue_family_index(),
+            CommandBufferUsage::MultipleSubmit,
+            CommandBufferInheritanceInfo {
+                render_pass: Some(
+                    Subpass::from(self.static_render_passes.0.clone(), 0)
+                        .unwrap()
+                        .into(),
+                ),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        builder.set_viewport(0, [self.target.viewport.clone()]);
+        game.render_static(self, &mut builder);
+        self.target.static_commands = Some(StaticCommands {
+            buffer: Arc::new(builder.build().unwrap()),
+            key,
+        });
+        self.static_record_count += 1;
+    }
     pub fn render_game<R: Renderable>(&mut self, game: &mut R) {
         if self.current_builder.is_some() {
             panic!("Do not call render_game here!");
@@ -322,41 +764,59 @@ impl RenderContext {
         }
 
         // Clean up GPU resources that are no longer needed.
-        self.previous_frame_end.as_mut().unwrap().cleanup_finished();
+        self.target
+            .previous_frame_end
+            .as_mut()
+            .unwrap()
+            .cleanup_finished();
 
         // Recreate the swapchain if desired.
-        if self.recreate_swapchain {
-            let (new_swapchain, new_images) = match self.swapchain.recreate(SwapchainCreateInfo {
-                image_extent: dimensions.into(),
-                ..self.swapchain.create_info()
-            }) {
-                Ok(recreate) => recreate,
-                Err(SwapchainCreationError::ImageExtentNotSupported { .. }) => return,
-                Err(error) => panic!("Failed to recreate swapchain: {error:?}"),
-            };
+        if self.target.recreate_swapchain {
+            let (new_swapchain, new_images) =
+                match self.target.swapchain.recreate(SwapchainCreateInfo {
+                    image_extent: dimensions.into(),
+                    ..self.target.swapchain.create_info()
+                }) {
+                    Ok(recreate) => recreate,
+                    Err(SwapchainCreationError::ImageExtentNotSupported { .. }) => return,
+                    Err(error) => panic!("Failed to recreate swapchain: {error:?}"),
+                };
 
-            self.swapchain = new_swapchain;
-            self.framebuffers = window_size_dependent_setup(
+            self.target.swapchain = new_swapchain;
+            (self.target.framebuffers, self.target.offscreen) = window_size_dependent_setup(
                 &self.memory_allocator,
                 &new_images,
                 self.render_pass.clone(),
-                &mut self.viewport,
+                &mut self.target.viewport,
+                self.target.fixed_aspect,
+                self.target
+                    .internal_resolution
+                    .map(|(resolution, _)| resolution),
+                self.target.tonemap_pass.is_some(),
             );
-            self.recreate_swapchain = false;
+            if let Some(tonemap_pass) = self.target.tonemap_pass.as_mut() {
+                tonemap_pass.resize(
+                    &self.descriptor_set_allocator,
+                    &new_images,
+                    self.target.offscreen.clone().unwrap(),
+                );
+            }
+            self.target.swapchain_images = new_images;
+            self.target.recreate_swapchain = false;
         }
 
         // Acquire an image for rendering.
         let (image_index, suboptimal, acquire_future) =
-            match acquire_next_image(self.swapchain.clone(), None) {
+            match acquire_next_image(self.target.swapchain.clone(), None) {
                 Ok(r) => r,
                 Err(AcquireError::OutOfDate) => {
-                    self.recreate_swapchain = true;
+                    self.target.recreate_swapchain = true;
                     return;
                 }
                 Err(error) => panic!("Failed to acquire next image: {error:?}"),
             };
         if suboptimal {
-            self.recreate_swapchain = true;
+            self.target.recreate_swapchain = true;
         }
 
         self.current_builder = Some(
@@ -367,33 +827,65 @@ impl RenderContext {
             )
             .unwrap(),
         );
-        self.current_framebuffer_index = image_index as usize;
+        self.target.current_framebuffer_index = image_index as usize;
+        // GPU timing and clips only follow the main window.
+        let main_window = self.current_secondary.is_none();
+        if let Some(gpu_timer) = self.gpu_timer.as_ref().filter(|_| main_window) {
+            gpu_timer.begin(self.current_builder.as_mut().unwrap());
+        }
         game.pre_render(self);
+        self.update_static(game);
         self.begin_render_pass();
         game.render(self);
         self.end_render_pass();
+        if self.target.tonemap_pass.is_some() {
+            self.tonemap();
+        } else {
+            self.upscale();
+        }
+        #[cfg(feature = "clip-capture")]
+        if let Some(clip_recorder) = self.clip_recorder.as_mut().filter(|_| main_window) {
+            clip_recorder.capture(
+                self.current_builder.as_mut().unwrap(),
+                &self.memory_allocator,
+                self.target.swapchain_images[self.target.current_framebuffer_index].clone(),
+            );
+        }
+        if let Some(gpu_timer) = self.gpu_timer.as_ref().filter(|_| main_window) {
+            gpu_timer.end(self.current_builder.as_mut().unwrap());
+        }
         let command_buffer = self.current_builder.take().unwrap().build().unwrap();
-        self.recently_resized = false;
+        self.target.recently_resized = false;
 
         // Block until the previous frame is finished rendering.
-        drop(self.previous_frame_end.take());
+        drop(self.target.previous_frame_end.take());
+        if let Some(gpu_timer) = self.gpu_timer.as_mut().filter(|_| main_window) {
+            gpu_timer.read_previous_frame();
+        }
+        #[cfg(feature = "clip-capture")]
+        if let Some(clip_recorder) = self.clip_recorder.as_mut().filter(|_| main_window) {
+            clip_recorder.read_previous_frame();
+        }
 
         let future = acquire_future
             .then_execute(self.queue.clone(), command_buffer)
             .unwrap()
             .then_swapchain_present(
                 self.queue.clone(),
-                SwapchainPresentInfo::swapchain_image_index(self.swapchain.clone(), image_index),
+                SwapchainPresentInfo::swapchain_image_index(
+                    self.target.swapchain.clone(),
+                    image_index,
+                ),
             )
             .then_signal_fence_and_flush();
 
         match future {
             Ok(future) => {
-                self.previous_frame_end = Some(future.boxed());
+                self.target.previous_frame_end = Some(future.boxed());
             }
             Err(FlushError::OutOfDate) => {
-                self.recreate_swapchain = true;
-                self.previous_frame_end = Some(sync::now(self.device.clone()).boxed());
+                self.target.recreate_swapchain = true;
+                self.target.previous_frame_end = Some(sync::now(self.device.clone()).boxed());
             }
             Err(error) => {
                 panic!("Failed to flush future: {error:?}");
@@ -401,6 +893,122 @@ impl RenderContext {
         }
     }
 
+    /// Open another window that renders through this context, such as a tool palette or an
+    /// editor inspector. Render it each frame with `render_window`, after `render_game` renders the
+    /// main window. Returns `None` with a warning if the window can't be opened.
+    ///
+    /// The window shares the main window's render pass, so its swapchain must support the same
+    /// image format (with HDR, any format works). Its window-specific settings start at their
+    /// defaults.
+    pub fn create_secondary_window(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<()>,
+        window_builder: WindowBuilder,
+    ) -> Option<WindowId> {
+        let instance = self.device.physical_device().instance().clone();
+        let surface = match window_builder.build_vk_surface(event_loop, instance) {
+            Ok(surface) => surface,
+            Err(error) => {
+                log::warn!("Failed to open window: {}", error);
+                return None;
+            }
+        };
+        let physical_device = self.device.physical_device();
+        if !physical_device
+            .surface_support(self.queue.queue_family_index(), &surface)
+            .unwrap_or(false)
+        {
+            log::warn!("Failed to open window: the device can't present to it.");
+            return None;
+        }
+        let surface_formats = physical_device
+            .surface_formats(&surface, Default::default())
+            .unwrap_or_default();
+        let main_format = self.swapchain_format();
+        let (image_format, image_color_space) =
+            match secondary_window_format(&surface_formats, main_format, self.is_hdr()) {
+                Some(format) => format,
+                None => {
+                    log::warn!(
+                        "Failed to open window: its surface doesn't support {:?}.",
+                        main_format
+                    );
+                    return None;
+                }
+            };
+        let (swapchain, images) = match create_swapchain(
+            &self.device,
+            surface.clone(),
+            image_format,
+            image_color_space,
+        ) {
+            Ok(swapchain) => swapchain,
+            Err(error) => {
+                log::warn!("Failed to open window: {}", error);
+                return None;
+            }
+        };
+        let mut target = WindowTarget::new(
+            &self.device,
+            &self.memory_allocator,
+            &self.descriptor_set_allocator,
+            self.render_pass.clone(),
+            surface,
+            swapchain,
+            images,
+            self.is_hdr(),
+        );
+        target.previous_frame_end = Some(sync::now(self.device.clone()).boxed());
+        let id = target.window().id();
+        self.secondary_windows.insert(id, target);
+        Some(id)
+    }
+    /// Close a secondary window, waiting for its last frame to finish. The shared resources it
+    /// used stay alive in the context.
+    pub fn close_secondary_window(&mut self, id: WindowId) {
+        if self.current_secondary == Some(id) {
+            panic!("Do not close a window while rendering it!");
+        }
+        self.secondary_windows.remove(&id);
+    }
+    pub fn secondary_window_ids(&self) -> Vec<WindowId> {
+        self.secondary_windows.keys().copied().collect()
+    }
+    pub fn secondary_window(&self, id: WindowId) -> Option<&Window> {
+        self.secondary_windows.get(&id).map(WindowTarget::window)
+    }
+    pub fn main_window_id(&self) -> WindowId {
+        self.main_window
+    }
+    /// The secondary window being rendered, if any.
+    pub fn current_secondary_window(&self) -> Option<WindowId> {
+        self.current_secondary
+    }
+    /// Handle a resize of any window, by its id.
+    pub fn on_window_resize(&mut self, id: WindowId) {
+        if id == self.main_window {
+            self.on_resize();
+        } else if let Some(target) = self.secondary_windows.get_mut(&id) {
+            target.recreate_swapchain = true;
+            target.recently_resized = true;
+        }
+    }
+    /// Render `renderable` to a secondary window, like `render_game` does for the main window.
+    /// While it renders, `window`, `viewport` and the window-specific settings refer to this
+    /// window. Does nothing if there's no such window.
+    pub fn render_window<R: Renderable>(&mut self, id: WindowId, renderable: &mut R) {
+        let mut target = match self.secondary_windows.remove(&id) {
+            Some(target) => target,
+            None => return,
+        };
+        std::mem::swap(&mut self.target, &mut target);
+        self.current_secondary = Some(id);
+        self.render_game(renderable);
+        self.current_secondary = None;
+        std::mem::swap(&mut self.target, &mut target);
+        self.secondary_windows.insert(id, target);
+    }
+
     pub fn device(&self) -> Arc<Device> {
         self.device.clone()
     }
@@ -416,33 +1024,326 @@ impl RenderContext {
     pub fn descriptor_set_allocator(&self) -> &StandardDescriptorSetAllocator {
         &self.descriptor_set_allocator
     }
+    /// How long the GPU spent on the most recently measured frame, from the start of `pre_render`
+    /// to the end of the render pass. Results lag one frame behind and aren't averaged. `None` if
+    /// `RenderSettings::gpu_timing` is off, unsupported, or no frame has been measured yet.
+    pub fn gpu_frame_time(&self) -> Option<Duration> {
+        self.gpu_timer.as_ref().and_then(GpuTimer::frame_time)
+    }
+    /// The highest anisotropic filtering level the device supports, or `None` if it doesn't
+    /// support anisotropic filtering.
+    pub fn max_anisotropy(&self) -> Option<f32> {
+        if self.device.enabled_features().sampler_anisotropy {
+            Some(
+                self.device
+                    .physical_device()
+                    .properties()
+                    .max_sampler_anisotropy,
+            )
+        } else {
+            None
+        }
+    }
+    /// The anisotropic filtering level textures are sampled with, if any.
+    pub fn anisotropy(&self) -> Option<f32> {
+        self.anisotropy
+    }
+    /// Set the anisotropic filtering level, or turn it off with `None` or any level of 1 or less.
+    /// Levels above what the device supports are lowered to its maximum, and it stays off on
+    /// devices without support. Renderers pick up the change the next time they draw.
+    pub fn set_anisotropy(&mut self, anisotropy: Option<f32>) {
+        let anisotropy = anisotropy.filter(|level| *level > 1.0);
+        self.anisotropy = match (anisotropy, self.max_anisotropy()) {
+            (Some(level), Some(max)) => Some(level.min(max)),
+            (Some(_), None) => {
+                log::warn!("Anisotropic filtering is not supported by this device.");
+                None
+            }
+            (None, _) => None,
+        };
+    }
+    /// The formats the window's surface supports, in the order the driver lists them.
+    pub fn supported_swapchain_formats(&self) -> Vec<(Format, ColorSpace)> {
+        self.device
+            .physical_device()
+            .surface_formats(&self.target.surface, Default::default())
+            .unwrap_or_default()
+    }
+    pub fn swapchain_format(&self) -> Format {
+        self.target.swapchain.image_format()
+    }
+    /// Start keeping the last few seconds of frames, so `save_clip` can save them. Does nothing
+    /// with a warning if the swapchain images can't be read back.
+    #[cfg(feature = "clip-capture")]
+    pub fn start_clip_recording(&mut self, settings: ClipSettings) {
+        let image_usage = self.target.swapchain.create_info().image_usage;
+        if !image_usage.transfer_src
+            || ClipRecorder::format_order(self.swapchain_format()).is_none()
+        {
+            log::warn!("Clip recording is not supported by this surface.");
+            return;
+        }
+        self.clip_recorder = Some(ClipRecorder::new(settings));
+    }
+    /// Stop recording and free the kept frames.
+    #[cfg(feature = "clip-capture")]
+    pub fn stop_clip_recording(&mut self) {
+        self.clip_recorder = None;
+    }
+    #[cfg(feature = "clip-capture")]
+    pub fn clip_settings(&self) -> Option<ClipSettings> {
+        self.clip_recorder.as_ref().map(ClipRecorder::settings)
+    }
+    /// The frames kept so far, oldest first.
+    #[cfg(feature = "clip-capture")]
+    pub fn clip_frames(&self) -> Option<&FrameRing<ClipFrame>> {
+        self.clip_recorder.as_ref().map(ClipRecorder::frames)
+    }
+    /// Save the kept frames as an animated GIF in the "save" prefix, under "clips/", named by the
+    /// current time. Returns the file's path.
+    #[cfg(feature = "clip-capture")]
+    pub fn save_clip(&self) -> AssetResult<std::path::PathBuf> {
+        let clip_recorder = self.clip_recorder.as_ref().ok_or_else(|| {
+            gristmill_core::asset::AssetError::Other("clip recording is not started".to_owned())
+        })?;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = gristmill_core::asset::get_path("save", &format!("clips/clip_{timestamp}.gif"));
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        clip_recorder.save_gif(&path)?;
+        log::info!("Saved clip to {}", path.to_string_lossy());
+        Ok(path)
+    }
+    /// Save the kept frames to `dir` as numbered PNG files, to encode to video externally.
+    /// Returns the number of frames saved.
+    #[cfg(feature = "clip-capture")]
+    pub fn save_clip_frames(&self, dir: &std::path::Path) -> AssetResult<usize> {
+        match self.clip_recorder.as_ref() {
+            Some(clip_recorder) => clip_recorder.save_frames(dir),
+            None => Ok(0),
+        }
+    }
+
+    pub fn display_settings(&self) -> DisplaySettings {
+        self.display
+    }
+    /// Replace the gamma and brightness, clamping them to their valid ranges. Call
+    /// `save_display_settings` to keep them for the next launch.
+    pub fn set_display_settings(&mut self, display: DisplaySettings) {
+        self.display = display.clamped();
+    }
+    pub fn gamma(&self) -> f32 {
+        self.display.gamma
+    }
+    /// See `DisplaySettings::gamma`. 1 is unchanged.
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.set_display_settings(DisplaySettings {
+            gamma,
+            ..self.display
+        });
+    }
+    pub fn brightness(&self) -> f32 {
+        self.display.brightness
+    }
+    /// See `DisplaySettings::brightness`. 1 is unchanged.
+    pub fn set_brightness(&mut self, brightness: f32) {
+        self.set_display_settings(DisplaySettings {
+            brightness,
+            ..self.display
+        });
+    }
+    pub fn save_display_settings(&self) {
+        if let Err(error) = self.display.save_config() {
+            log::warn!("Failed to save display settings: {}", error);
+        }
+    }
+    /// Whether rendering goes through an HDR image. See `RenderSettings::hdr`.
+    pub fn is_hdr(&self) -> bool {
+        self.target.tonemap_pass.is_some()
+    }
+    pub fn hdr_settings(&self) -> HdrSettings {
+        self.hdr_settings
+    }
+    /// Change the tonemap operator, exposure and bloom. Has no effect unless `is_hdr`.
+    pub fn set_hdr_settings(&mut self, settings: HdrSettings) {
+        self.hdr_settings = settings;
+    }
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.hdr_settings.exposure = exposure.max(0.0);
+    }
+    /// Record the static content again next frame. See `Renderable` for when this happens
+    /// automatically.
+    pub fn invalidate_static(&mut self) {
+        self.target.static_commands = None;
+    }
+    /// How many times static content has been recorded, to check that it isn't being recorded
+    /// every frame.
+    pub fn static_record_count(&self) -> usize {
+        self.static_record_count
+    }
     pub fn was_resized(&self) -> bool {
-        self.recently_resized
+        self.target.recently_resized
     }
     pub fn viewport(&self) -> Rect {
         Rect {
-            position: Vec2::from(self.viewport.origin),
-            size: Vec2::from(self.viewport.dimensions),
+            position: Vec2::from(self.target.viewport.origin),
+            size: Vec2::from(self.target.viewport.dimensions),
         }
     }
+    pub(crate) fn take_builder(&mut self) -> AutoCommandBufferBuilder<PrimaryAutoCommandBuffer> {
+        self.current_builder.take().expect("not rendering")
+    }
+    pub(crate) fn restore_builder(
+        &mut self,
+        builder: AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        self.current_builder = Some(builder);
+    }
     pub fn builder(&mut self) -> &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer> {
         self.current_builder.as_mut().expect("not rendering")
     }
 
+    pub fn color_load(&self) -> ColorLoad {
+        self.color_load
+    }
     pub fn clear_color(&self) -> Color {
-        self.clear_color
+        self.target.clear_color
     }
     pub fn set_clear_color(&mut self, clear_color: Color) {
-        self.clear_color = clear_color;
+        self.target.clear_color = clear_color;
+    }
+    pub fn fixed_aspect(&self) -> Option<f32> {
+        self.target.fixed_aspect
+    }
+    /// Keep the viewport at this aspect ratio (width / height), centered in the window, with bars
+    /// in the letterbox color filling the rest. `viewport()` and everything drawn is relative to
+    /// the viewport. Window events are still relative to the window, but the game loop converts
+    /// cursor positions with `window_to_viewport`.
+    pub fn set_fixed_aspect(&mut self, aspect: Option<f32>) {
+        self.target.fixed_aspect = aspect.filter(|aspect| *aspect > 0.0);
+        self.on_resize();
+    }
+    pub fn internal_resolution(&self) -> Option<(IVec2, UpscaleMode)> {
+        self.target.internal_resolution
+    }
+    /// Render at a fixed resolution, such as 320x180, and scale the result up to the window with
+    /// nearest-neighbor filtering. `viewport()` is then always this size, so gameplay and GUI
+    /// layout don't depend on the window size. Bars around the image use the letterbox color, and
+    /// `set_fixed_aspect` is ignored. Pass `None` to render at the window's resolution again.
+    ///
+    /// Window events are still in window pixels. The game loop converts cursor positions with
+    /// `window_to_viewport`, so input actions and the GUI see internal resolution pixels.
+    pub fn set_internal_resolution(&mut self, resolution: Option<(IVec2, UpscaleMode)>) {
+        let supported =
+            self.is_hdr() || self.target.swapchain.create_info().image_usage.transfer_dst;
+        if resolution.is_some() && !supported {
+            log::warn!("Internal resolution is not supported by this surface.");
+            return;
+        }
+        self.target.internal_resolution =
+            resolution.map(|(resolution, mode)| (resolution.max(IVec2::ONE), mode));
+        self.on_resize();
+    }
+    /// Convert a position in window pixels to internal resolution pixels. Unchanged when not
+    /// rendering at an internal resolution.
+    pub fn window_to_internal(&self, position: Vec2) -> Vec2 {
+        match self.target.internal_resolution {
+            Some((resolution, mode)) => {
+                let size = self.window().inner_size();
+                let window_size = IVec2::new(size.width as i32, size.height as i32);
+                let dest = mode.dest_rect(window_size, resolution).as_rect();
+                (position - dest.position) * resolution.as_vec2() / dest.size.max(Vec2::ONE)
+            }
+            None => position,
+        }
+    }
+    /// The part of the window the viewport is shown in, in window pixels. With a fixed aspect or
+    /// an internal resolution, the rest of the window is letterbox bars.
+    pub fn viewport_in_window(&self) -> Rect {
+        let size = self.window().inner_size();
+        let window_size = IVec2::new(size.width as i32, size.height as i32);
+        match (self.target.internal_resolution, self.target.fixed_aspect) {
+            (Some((resolution, mode)), _) => mode.dest_rect(window_size, resolution).as_rect(),
+            (None, Some(aspect)) => IRect::from_size(window_size).as_rect().fit_aspect(aspect),
+            (None, None) => IRect::from_size(window_size).as_rect(),
+        }
+    }
+    /// The size of the viewport in its own pixels: the internal resolution if there is one, or
+    /// else the size of `viewport_in_window`.
+    pub fn viewport_pixel_size(&self) -> Vec2 {
+        match self.target.internal_resolution {
+            Some((resolution, _)) => resolution.as_vec2(),
+            None => self.viewport_in_window().size,
+        }
+    }
+    /// Convert a position in window pixels, such as a cursor position, to viewport pixels:
+    /// relative to the top-left of the viewport, and scaled to the internal resolution if there
+    /// is one. Positions over the letterbox bars end up outside the viewport.
+    pub fn window_to_viewport(&self, position: Vec2) -> Vec2 {
+        let rect = self.viewport_in_window();
+        (position - rect.position) * self.viewport_pixel_size() / rect.size.max(Vec2::ONE)
+    }
+    pub fn letterbox_color(&self) -> Color {
+        self.target.letterbox_color
+    }
+    /// The color of the bars outside a fixed-aspect viewport. Black by default.
+    pub fn set_letterbox_color(&mut self, color: Color) {
+        self.target.letterbox_color = color;
     }
 
     pub fn load_texture(&mut self, file: &str) -> AssetResult<Texture> {
-        if let Some(texture) = self.texture_cache.get(file) {
+        let key = Symbol::new(file);
+        if let Some(texture) = self.texture_cache.get(&key) {
             Ok(texture.clone())
         } else {
             let texture = Texture::load_asset(self, file)?;
-            self.texture_cache.insert(file.to_owned(), texture.clone());
+            self.texture_cache.insert(key, texture.clone());
             Ok(texture)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_upscale_fills_exact_multiple() {
+        let window = IVec2::new(1280, 720);
+        let internal = IVec2::new(320, 180);
+        assert_eq!(
+            UpscaleMode::Integer.dest_rect(window, internal),
+            IRect::new(0, 0, 1280, 720)
+        );
+    }
+
+    #[test]
+    fn integer_upscale_centers_with_bars() {
+        // 3x fits (960x540); 4x would be too wide and tall.
+        let rect = UpscaleMode::Integer.dest_rect(IVec2::new(1000, 700), IVec2::new(320, 180));
+        assert_eq!(rect, IRect::new(20, 80, 960, 540));
+    }
+
+    #[test]
+    fn integer_upscale_uses_smaller_axis() {
+        // 4x fits horizontally but only 2x vertically.
+        let rect = UpscaleMode::Integer.dest_rect(IVec2::new(1280, 400), IVec2::new(320, 180));
+        assert_eq!(rect, IRect::new(320, 20, 640, 360));
+    }
+
+    #[test]
+    fn integer_upscale_never_scales_below_one() {
+        let rect = UpscaleMode::Integer.dest_rect(IVec2::new(200, 100), IVec2::new(320, 180));
+        assert_eq!(rect, IRect::new(-60, -40, 320, 180));
+    }
+
+    #[test]
+    fn fit_upscale_keeps_aspect() {
+        let rect = UpscaleMode::Fit.dest_rect(IVec2::new(1000, 700), IVec2::new(320, 180));
+        assert_eq!(rect, IRect::new(0, 68, 1000, 563));
+    }
+}
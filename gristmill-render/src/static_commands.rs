@@ -0,0 +1,77 @@
+use crate::DisplaySettings;
+use gristmill_core::geom2d::Rect;
+use std::sync::Arc;
+use vulkano::command_buffer::{AutoCommandBufferBuilder, SecondaryAutoCommandBuffer};
+
+/// The builder `Renderable::render_static` records into.
+pub type StaticBuilder = AutoCommandBufferBuilder<SecondaryAutoCommandBuffer>;
+
+/// What static content was recorded with: its version, the viewport and the display settings.
+/// Recorded commands are replayed every frame until this changes.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) struct StaticKey {
+    pub version: u64,
+    pub viewport: Rect,
+    pub display: DisplaySettings,
+}
+
+/// Static content recorded into a secondary command buffer.
+pub(crate) struct StaticCommands {
+    pub buffer: Arc<SecondaryAutoCommandBuffer>,
+    pub key: StaticKey,
+}
+
+impl StaticCommands {
+    /// Whether the buffer can be replayed for `key`.
+    pub fn is_valid(&self, key: StaticKey) -> bool {
+        self.key.is_valid(key)
+    }
+}
+
+impl StaticKey {
+    /// Whether commands recorded with this key can be replayed for `key`.
+    pub fn is_valid(&self, key: StaticKey) -> bool {
+        *self == key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> StaticKey {
+        StaticKey {
+            version: 3,
+            viewport: Rect::new(0.0, 0.0, 640.0, 360.0),
+            display: DisplaySettings::default(),
+        }
+    }
+
+    #[test]
+    fn unchanged_content_is_replayed() {
+        assert!(key().is_valid(key()));
+    }
+
+    #[test]
+    fn any_change_records_again() {
+        let recorded = key();
+        let version = StaticKey {
+            version: 4,
+            ..recorded
+        };
+        let viewport = StaticKey {
+            viewport: Rect::new(0.0, 0.0, 1280.0, 720.0),
+            ..recorded
+        };
+        let display = StaticKey {
+            display: DisplaySettings {
+                gamma: 2.0,
+                ..recorded.display
+            },
+            ..recorded
+        };
+        for changed in [version, viewport, display] {
+            assert!(!recorded.is_valid(changed));
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use vulkano::{
+    format::{Format, NumericType},
+    swapchain::ColorSpace,
+};
+
+/// Which kind of swapchain format to render to. Drivers list formats in different orders, so
+/// asking for one explicitly keeps colors the same on every machine.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum SwapchainFormat {
+    /// Colors are written in linear space and converted to sRGB by the GPU. Textures loaded by
+    /// `Texture` are sRGB, so this is the one that shows them as authored.
+    #[default]
+    Srgb,
+    /// Colors are written as-is. Use this when shaders do their own gamma correction; otherwise
+    /// everything looks darker.
+    Unorm,
+}
+
+impl SwapchainFormat {
+    pub fn matches(self, format: Format) -> bool {
+        let numeric_type = match self {
+            SwapchainFormat::Srgb => NumericType::SRGB,
+            SwapchainFormat::Unorm => NumericType::UNORM,
+        };
+        format.type_color() == Some(numeric_type)
+    }
+}
+
+/// Pick a format from what the surface supports: the first one of the `preferred` kind in the
+/// standard color space, otherwise the first one listed. Returns `None` if `supported` is empty.
+pub fn select_swapchain_format(
+    supported: &[(Format, ColorSpace)],
+    preferred: SwapchainFormat,
+) -> Option<(Format, ColorSpace)> {
+    let selected = supported.iter().copied().find(|(format, color_space)| {
+        *color_space == ColorSpace::SrgbNonLinear && preferred.matches(*format)
+    });
+    if selected.is_none() {
+        if let Some((format, _)) = supported.first() {
+            log::warn!(
+                "No {:?} swapchain format is supported, falling back to {:?}.",
+                preferred,
+                format
+            );
+        }
+    }
+    selected.or_else(|| supported.first().copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUPPORTED: [(Format, ColorSpace); 3] = [
+        (Format::B8G8R8A8_UNORM, ColorSpace::SrgbNonLinear),
+        (Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear),
+        (Format::R16G16B16A16_SFLOAT, ColorSpace::ExtendedSrgbLinear),
+    ];
+
+    #[test]
+    fn picks_preferred_kind() {
+        assert_eq!(
+            select_swapchain_format(&SUPPORTED, SwapchainFormat::Srgb),
+            Some((Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear))
+        );
+        assert_eq!(
+            select_swapchain_format(&SUPPORTED, SwapchainFormat::Unorm),
+            Some((Format::B8G8R8A8_UNORM, ColorSpace::SrgbNonLinear))
+        );
+    }
+
+    #[test]
+    fn skips_other_color_spaces() {
+        let supported = [
+            (Format::R8G8B8A8_SRGB, ColorSpace::DisplayP3NonLinear),
+            (Format::R8G8B8A8_SRGB, ColorSpace::SrgbNonLinear),
+        ];
+        assert_eq!(
+            select_swapchain_format(&supported, SwapchainFormat::Srgb),
+            Some((Format::R8G8B8A8_SRGB, ColorSpace::SrgbNonLinear))
+        );
+    }
+
+    #[test]
+    fn falls_back_to_first() {
+        let supported = [
+            (Format::R16G16B16A16_SFLOAT, ColorSpace::ExtendedSrgbLinear),
+            (Format::B8G8R8A8_UNORM, ColorSpace::SrgbNonLinear),
+        ];
+        assert_eq!(
+            select_swapchain_format(&supported, SwapchainFormat::Srgb),
+            Some(supported[0])
+        );
+        assert_eq!(select_swapchain_format(&[], SwapchainFormat::Srgb), None);
+    }
+}
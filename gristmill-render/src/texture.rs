@@ -1,19 +1,53 @@
-use crate::RenderContext;
+use crate::{ktx2::Ktx2, RenderContext};
 use gristmill_core::{
     asset::{self, image::DynamicImage, AssetError, AssetResult},
+    geom2d::IRect,
     math::IVec2,
 };
-use std::{hash::Hash, sync::Arc};
+use std::{hash::Hash, io::Read, path::Path, sync::Arc};
 use vulkano::{
+    buffer::{BufferUsage, CpuAccessibleBuffer},
+    command_buffer::{
+        AutoCommandBufferBuilder, BufferImageCopy, CopyBufferToImageInfo, PrimaryAutoCommandBuffer,
+    },
     format::Format,
     image::view::{ImageView, ImageViewCreateInfo},
-    image::{ImageAccess, ImageDimensions, ImageViewAbstract, ImmutableImage, MipmapsCount},
-    sampler::{ComponentMapping, ComponentSwizzle},
+    image::{
+        ImageAccess, ImageCreateFlags, ImageDimensions, ImageLayout, ImageUsage, ImageViewAbstract,
+        ImmutableImage, MipmapsCount, StorageImage,
+    },
+    memory::allocator::StandardMemoryAllocator,
+    sampler::{ComponentMapping, ComponentSwizzle, SamplerAddressMode},
 };
 
+/// What a texture samples outside of the 0..1 UV range.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum TextureAddressMode {
+    /// Repeat the edge pixels. Keeps sprites and atlas regions from picking up pixels from the
+    /// other side of the texture when their UVs reach slightly past the edge.
+    #[default]
+    Clamp,
+    /// Tile the texture.
+    Repeat,
+    /// Tile the texture, flipping every other copy.
+    Mirror,
+}
+
+impl TextureAddressMode {
+    pub fn sampler_address_mode(self) -> SamplerAddressMode {
+        match self {
+            TextureAddressMode::Clamp => SamplerAddressMode::ClampToEdge,
+            TextureAddressMode::Repeat => SamplerAddressMode::Repeat,
+            TextureAddressMode::Mirror => SamplerAddressMode::MirroredRepeat,
+        }
+    }
+}
+
+/// An image on the GPU and how it's sampled. Copies share the image, so a copy with a different
+/// address mode is cheap, and is drawn as a separate texture.
 #[allow(clippy::derive_hash_xor_eq)]
 #[derive(Clone, Hash)]
-pub struct Texture(Arc<dyn ImageViewAbstract>);
+pub struct Texture(Arc<dyn ImageViewAbstract>, TextureAddressMode);
 
 impl Texture {
     pub fn load_image(context: &mut RenderContext, image: &DynamicImage) -> AssetResult<Self> {
@@ -36,19 +70,227 @@ impl Texture {
         image_info.component_mapping = component_mapping;
         let image_view = ImageView::new(vk_image, image_info)
             .map_err(|error| AssetError::Other(error.to_string()))?;
-        Ok(Texture(image_view))
+        Ok(Texture(image_view, TextureAddressMode::default()))
+    }
+    /// Like `load_image`, but the texture can be modified later with `update_region`.
+    pub fn load_image_dynamic(
+        context: &mut RenderContext,
+        image: &DynamicImage,
+    ) -> AssetResult<Self> {
+        let (format, component_mapping) = Self::format_info(image);
+        let vk_image = StorageImage::with_usage(
+            context.allocator(),
+            ImageDimensions::Dim2d {
+                width: image.width(),
+                height: image.height(),
+                array_layers: 1,
+            },
+            format,
+            ImageUsage {
+                transfer_dst: true,
+                sampled: true,
+                ..ImageUsage::empty()
+            },
+            ImageCreateFlags::empty(),
+            [context.queue().queue_family_index()],
+        )
+        .map_err(|error| AssetError::Other(error.to_string()))?;
+        let mut image_info = ImageViewCreateInfo::from_image(&vk_image);
+        image_info.component_mapping = component_mapping;
+        let image_view: Arc<dyn ImageViewAbstract> = ImageView::new(vk_image, image_info)
+            .map_err(|error| AssetError::Other(error.to_string()))?;
+        let texture = Texture(image_view, TextureAddressMode::default());
+        let size = IVec2::new(image.width() as i32, image.height() as i32);
+        texture.update_region(context, IRect::from_size(size), image.as_bytes())?;
+        Ok(texture)
+    }
+    /// Load a compressed texture from the contents of a KTX2 file, uploading its blocks and mip
+    /// levels as they are. See `compressed_format` for the formats that can be loaded.
+    pub fn load_ktx2(context: &mut RenderContext, data: &[u8]) -> AssetResult<Self> {
+        let ktx2 = Ktx2::parse(data)?;
+        let format = Self::compressed_format(ktx2.vk_format).ok_or_else(|| {
+            AssetError::InvalidFormat(format!("KTX2: unsupported VkFormat {}", ktx2.vk_format))
+        })?;
+        if !Self::supports_format(context, format) {
+            return Err(AssetError::InvalidFormat(format!(
+                "KTX2: {:?} is not supported by this device",
+                format
+            )));
+        }
+
+        // Check each level has exactly one block layer of data, so the copy can't read past it.
+        let [block_width, block_height, _] = format.block_extent();
+        let block_size = format.block_size().unwrap_or(0) as usize;
+        let mut bytes = Vec::new();
+        let mut level_offsets = Vec::new();
+        for level in 0..ktx2.level_count() {
+            let [width, height] = ktx2.level_size(level);
+            let expected_len = ((width + block_width - 1) / block_width) as usize
+                * ((height + block_height - 1) / block_height) as usize
+                * block_size;
+            let level_data = ktx2.level_data(level);
+            if level_data.len() != expected_len {
+                return Err(AssetError::InvalidFormat(format!(
+                    "KTX2: mip level {} needs {} bytes of data, got {}",
+                    level,
+                    expected_len,
+                    level_data.len()
+                )));
+            }
+            level_offsets.push(bytes.len() as u64);
+            bytes.extend_from_slice(level_data);
+        }
+
+        let transfer_buffer = CpuAccessibleBuffer::from_iter(
+            context.allocator(),
+            BufferUsage {
+                transfer_src: true,
+                ..BufferUsage::empty()
+            },
+            false,
+            bytes,
+        )
+        .map_err(|error| AssetError::Other(error.to_string()))?;
+        let (vk_image, initializer) = ImmutableImage::uninitialized(
+            context.allocator(),
+            ImageDimensions::Dim2d {
+                width: ktx2.width,
+                height: ktx2.height,
+                array_layers: 1,
+            },
+            format,
+            MipmapsCount::Specific(ktx2.level_count()),
+            ImageUsage {
+                transfer_dst: true,
+                sampled: true,
+                ..ImageUsage::empty()
+            },
+            ImageCreateFlags::empty(),
+            ImageLayout::ShaderReadOnlyOptimal,
+            [context.queue().queue_family_index()],
+        )
+        .map_err(|error| AssetError::Other(error.to_string()))?;
+        let mut copy_info = CopyBufferToImageInfo::buffer_image(transfer_buffer, initializer);
+        let base_region = copy_info.regions[0].clone();
+        copy_info.regions = (0..ktx2.level_count())
+            .map(|level| {
+                let [width, height] = ktx2.level_size(level);
+                let mut region = BufferImageCopy {
+                    buffer_offset: level_offsets[level as usize],
+                    image_extent: [width, height, 1],
+                    ..base_region.clone()
+                };
+                region.image_subresource.mip_level = level;
+                region
+            })
+            .collect();
+        context
+            .builder()
+            .copy_buffer_to_image(copy_info)
+            .map_err(|error| AssetError::Other(error.to_string()))?;
+        let image_view = ImageView::new_default(vk_image)
+            .map_err(|error| AssetError::Other(error.to_string()))?;
+        Ok(Texture(image_view, TextureAddressMode::default()))
     }
+    /// Load a texture from the assets folder. KTX2 files are uploaded compressed with
+    /// `load_ktx2`; anything else is decoded to pixels. If this device can't sample a KTX2 file's
+    /// format, a PNG with the same name is loaded in its place, so ship one alongside any
+    /// texture compressed for only some devices.
     pub fn load_asset(context: &mut RenderContext, file: &str) -> AssetResult<Self> {
-        let image = asset::load_image_file("assets", file)?;
+        let is_ktx2 = Path::new(file)
+            .extension()
+            .map_or(false, |extension| extension.eq_ignore_ascii_case("ktx2"));
+        if !is_ktx2 {
+            let image = asset::load_image_file("assets", file)?;
+            return Self::load_image(context, &image);
+        }
+
+        let mut data = Vec::new();
+        asset::open_reader(&asset::get_path("assets", file))?.read_to_end(&mut data)?;
+        let ktx2 = Ktx2::parse(&data)?;
+        let supported = Self::compressed_format(ktx2.vk_format)
+            .map_or(false, |format| Self::supports_format(context, format));
+        if supported {
+            return Self::load_ktx2(context, &data);
+        }
+        let fallback = Path::new(file).with_extension("png");
+        log::warn!(
+            "Texture {} has VkFormat {}, which this device can't sample. Loading {} instead.",
+            file,
+            ktx2.vk_format,
+            fallback.to_string_lossy()
+        );
+        let image = asset::load_image_file("assets", &fallback.to_string_lossy())?;
         Self::load_image(context, &image)
     }
 
+    /// The format of a `VkFormat` value that `load_ktx2` can upload. These are the block
+    /// compressed formats with wide support, plus plain RGBA:
+    ///
+    /// - BC1, BC3, BC4, BC5 and BC7, supported by desktop GPUs on Windows and Linux, and Intel
+    ///   Macs.
+    /// - ETC2 and ASTC (4x4, 6x6 and 8x8 blocks), supported by mobile GPUs and Apple Silicon.
+    ///
+    /// Basis Universal textures aren't transcoded at load time; transcode them offline to one of
+    /// these (for example with `basisu -unpack` or `toktx`) and ship one file per family.
+    pub fn compressed_format(vk_format: u32) -> Option<Format> {
+        Some(match vk_format {
+            37 => Format::R8G8B8A8_UNORM,
+            43 => Format::R8G8B8A8_SRGB,
+            131 => Format::BC1_RGB_UNORM_BLOCK,
+            132 => Format::BC1_RGB_SRGB_BLOCK,
+            133 => Format::BC1_RGBA_UNORM_BLOCK,
+            134 => Format::BC1_RGBA_SRGB_BLOCK,
+            137 => Format::BC3_UNORM_BLOCK,
+            138 => Format::BC3_SRGB_BLOCK,
+            139 => Format::BC4_UNORM_BLOCK,
+            140 => Format::BC4_SNORM_BLOCK,
+            141 => Format::BC5_UNORM_BLOCK,
+            142 => Format::BC5_SNORM_BLOCK,
+            145 => Format::BC7_UNORM_BLOCK,
+            146 => Format::BC7_SRGB_BLOCK,
+            147 => Format::ETC2_R8G8B8_UNORM_BLOCK,
+            148 => Format::ETC2_R8G8B8_SRGB_BLOCK,
+            149 => Format::ETC2_R8G8B8A1_UNORM_BLOCK,
+            150 => Format::ETC2_R8G8B8A1_SRGB_BLOCK,
+            151 => Format::ETC2_R8G8B8A8_UNORM_BLOCK,
+            152 => Format::ETC2_R8G8B8A8_SRGB_BLOCK,
+            157 => Format::ASTC_4x4_UNORM_BLOCK,
+            158 => Format::ASTC_4x4_SRGB_BLOCK,
+            165 => Format::ASTC_6x6_UNORM_BLOCK,
+            166 => Format::ASTC_6x6_SRGB_BLOCK,
+            171 => Format::ASTC_8x8_UNORM_BLOCK,
+            172 => Format::ASTC_8x8_SRGB_BLOCK,
+            _ => return None,
+        })
+    }
+    /// Whether textures of `format` can be sampled on this device.
+    pub fn supports_format(context: &RenderContext, format: Format) -> bool {
+        context
+            .device()
+            .physical_device()
+            .format_properties(format)
+            .optimal_tiling_features
+            .sampled_image
+    }
+
     pub fn image(&self) -> Arc<dyn ImageAccess> {
         self.0.image()
     }
     pub fn image_view(&self) -> &Arc<dyn ImageViewAbstract> {
         &self.0
     }
+    pub fn address_mode(&self) -> TextureAddressMode {
+        self.1
+    }
+    /// A copy of this texture sampled with `address_mode`. Textures are clamped by default; use
+    /// `Repeat` for backgrounds that tile.
+    pub fn with_address_mode(&self, address_mode: TextureAddressMode) -> Self {
+        Texture(self.0.clone(), address_mode)
+    }
+    pub fn set_address_mode(&mut self, address_mode: TextureAddressMode) {
+        self.1 = address_mode;
+    }
     pub fn dimensions(&self) -> IVec2 {
         if let ImageDimensions::Dim2d { width, height, .. } = self.0.dimensions() {
             IVec2::new(width as i32, height as i32)
@@ -57,6 +299,69 @@ impl Texture {
         }
     }
 
+    /// Overwrite a region of the texture. `data` is tightly packed rows of pixels in the
+    /// texture's format, so its length must be `width * height * bytes per pixel`. Only textures
+    /// created with `load_image_dynamic` (or another image with transfer destination usage) can
+    /// be updated.
+    pub fn update_region(
+        &self,
+        context: &mut RenderContext,
+        region: IRect,
+        data: &[u8],
+    ) -> AssetResult<()> {
+        let allocator = context.allocator().clone();
+        self.record_update_region(context.builder(), &allocator, region, data)
+    }
+    fn record_update_region(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        allocator: &StandardMemoryAllocator,
+        region: IRect,
+        data: &[u8],
+    ) -> AssetResult<()> {
+        self.check_region(region, data.len())?;
+        let transfer_buffer = CpuAccessibleBuffer::from_iter(
+            allocator,
+            BufferUsage {
+                transfer_src: true,
+                ..BufferUsage::empty()
+            },
+            false,
+            data.iter().cloned(),
+        )
+        .map_err(|error| AssetError::Other(error.to_string()))?;
+        let mut copy_info = CopyBufferToImageInfo::buffer_image(transfer_buffer, self.image());
+        copy_info.regions[0].image_offset = [region.x() as u32, region.y() as u32, 0];
+        copy_info.regions[0].image_extent = [region.width() as u32, region.height() as u32, 1];
+        builder
+            .copy_buffer_to_image(copy_info)
+            .map_err(|error| AssetError::Other(error.to_string()))?;
+        Ok(())
+    }
+    /// Check that `region` is inside the texture and that `data_len` bytes exactly fill it.
+    fn check_region(&self, region: IRect, data_len: usize) -> AssetResult<()> {
+        let bounds = IRect::from_size(self.dimensions());
+        if region.width() <= 0 || region.height() <= 0 || bounds.intersection(region) != region {
+            return Err(AssetError::Other(format!(
+                "texture region {:?} is outside of texture bounds {:?}",
+                region, bounds.size
+            )));
+        }
+        let bytes_per_pixel = self
+            .0
+            .format()
+            .and_then(|format| format.block_size())
+            .ok_or_else(|| AssetError::Other("texture has unknown format".to_owned()))?;
+        let expected_len = region.width() as u64 * region.height() as u64 * bytes_per_pixel;
+        if data_len as u64 != expected_len {
+            return Err(AssetError::Other(format!(
+                "texture region needs {} bytes of data, got {}",
+                expected_len, data_len
+            )));
+        }
+        Ok(())
+    }
+
     fn format_info(image: &DynamicImage) -> (Format, ComponentMapping) {
         match *image {
             DynamicImage::ImageLuma8(_) => (
@@ -114,13 +419,243 @@ impl Texture {
 
 impl From<Arc<dyn ImageViewAbstract>> for Texture {
     fn from(image_view: Arc<dyn ImageViewAbstract>) -> Self {
-        Texture(image_view)
+        Texture(image_view, TextureAddressMode::default())
     }
 }
 
 impl PartialEq for Texture {
     fn eq(&self, other: &Self) -> bool {
-        PartialEq::eq(&self.0, &other.0)
+        PartialEq::eq(&self.0, &other.0) && self.1 == other.1
     }
 }
 impl Eq for Texture {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use vulkano::{
+        command_buffer::{
+            allocator::StandardCommandBufferAllocator, CommandBufferUsage, CopyImageToBufferInfo,
+            PrimaryCommandBufferAbstract,
+        },
+        device::{Device, DeviceCreateInfo, Queue, QueueCreateInfo},
+        instance::{Instance, InstanceCreateInfo},
+        sync::GpuFuture,
+        VulkanLibrary,
+    };
+
+    struct TestDevice {
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        allocator: StandardMemoryAllocator,
+    }
+
+    /// The first Vulkan device, or `None` if this machine has no Vulkan driver.
+    fn test_device() -> Option<TestDevice> {
+        let library = VulkanLibrary::new().ok()?;
+        let instance = Instance::new(
+            library,
+            InstanceCreateInfo {
+                enumerate_portability: true,
+                ..Default::default()
+            },
+        )
+        .ok()?;
+        let physical_device = instance.enumerate_physical_devices().ok()?.next()?;
+        let (device, mut queues) = Device::new(
+            physical_device,
+            DeviceCreateInfo {
+                queue_create_infos: vec![QueueCreateInfo {
+                    queue_family_index: 0,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        )
+        .ok()?;
+        Some(TestDevice {
+            queue: queues.next()?,
+            allocator: StandardMemoryAllocator::new_default(device.clone()),
+            device,
+        })
+    }
+
+    impl TestDevice {
+        /// A 4x4 RGBA image.
+        fn image_view(&self) -> Option<Arc<dyn ImageViewAbstract>> {
+            let image = StorageImage::new(
+                &self.allocator,
+                ImageDimensions::Dim2d {
+                    width: 4,
+                    height: 4,
+                    array_layers: 1,
+                },
+                Format::R8G8B8A8_UNORM,
+                [self.queue.queue_family_index()],
+            )
+            .ok()?;
+            let image_view: Arc<dyn ImageViewAbstract> = ImageView::new_default(image).ok()?;
+            Some(image_view)
+        }
+    }
+
+    /// A small image on the first Vulkan device, or `None` if this machine has no Vulkan driver.
+    fn test_image_view() -> Option<Arc<dyn ImageViewAbstract>> {
+        test_device()?.image_view()
+    }
+
+    fn error_message(result: AssetResult<()>) -> String {
+        match result {
+            Err(AssetError::Other(message)) => message,
+            other => panic!("expected an error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn address_modes_map_to_sampler_modes() {
+        assert_eq!(
+            TextureAddressMode::Clamp.sampler_address_mode(),
+            SamplerAddressMode::ClampToEdge
+        );
+        assert_eq!(
+            TextureAddressMode::Repeat.sampler_address_mode(),
+            SamplerAddressMode::Repeat
+        );
+        assert_eq!(
+            TextureAddressMode::Mirror.sampler_address_mode(),
+            SamplerAddressMode::MirroredRepeat
+        );
+        assert_eq!(TextureAddressMode::default(), TextureAddressMode::Clamp);
+    }
+
+    #[test]
+    fn address_mode_makes_a_separate_texture() {
+        let image_view = match test_image_view() {
+            Some(image_view) => image_view,
+            None => {
+                eprintln!("No Vulkan device, skipping.");
+                return;
+            }
+        };
+        let clamp = Texture::from(image_view);
+        let repeat = clamp.with_address_mode(TextureAddressMode::Repeat);
+        assert_eq!(clamp.address_mode(), TextureAddressMode::Clamp);
+        assert_eq!(clamp, clamp.clone());
+        assert_ne!(clamp, repeat);
+        assert_eq!(repeat, clamp.with_address_mode(TextureAddressMode::Repeat));
+        assert_eq!(clamp, repeat.with_address_mode(TextureAddressMode::Clamp));
+
+        let textures: HashSet<Texture> = [
+            clamp.clone(),
+            repeat.clone(),
+            repeat.with_address_mode(TextureAddressMode::Clamp),
+            clamp.with_address_mode(TextureAddressMode::Mirror),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(textures.len(), 3);
+        assert!(textures.contains(&repeat));
+    }
+
+    #[test]
+    fn update_region_checks_bounds_and_length() {
+        let texture = match test_image_view() {
+            Some(image_view) => Texture::from(image_view),
+            None => {
+                eprintln!("No Vulkan device, skipping.");
+                return;
+            }
+        };
+        assert!(texture.check_region(IRect::new(0, 0, 4, 4), 64).is_ok());
+        assert!(texture.check_region(IRect::new(2, 2, 2, 2), 16).is_ok());
+        for region in [
+            IRect::new(3, 0, 2, 2),
+            IRect::new(-1, 0, 2, 2),
+            IRect::new(0, 0, 0, 4),
+            IRect::new(0, 0, 4, -1),
+        ] {
+            assert!(error_message(texture.check_region(region, 16)).contains("outside"));
+        }
+        // Rows are tightly packed, so there's no room for padding.
+        assert!(
+            error_message(texture.check_region(IRect::new(0, 0, 2, 2), 15))
+                .contains("needs 16 bytes of data, got 15")
+        );
+        assert!(
+            error_message(texture.check_region(IRect::new(0, 0, 2, 2), 32))
+                .contains("needs 16 bytes of data, got 32")
+        );
+    }
+
+    #[test]
+    fn update_region_writes_only_the_region() {
+        let test = match test_device() {
+            Some(test) => test,
+            None => {
+                eprintln!("No Vulkan device, skipping.");
+                return;
+            }
+        };
+        let texture = Texture::from(test.image_view().unwrap());
+        let command_allocator =
+            StandardCommandBufferAllocator::new(test.device.clone(), Default::default());
+        let mut builder = AutoCommandBufferBuilder::primary(
+            &command_allocator,
+            test.queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .unwrap();
+        let red = [255, 0, 0, 255];
+        texture
+            .record_update_region(
+                &mut builder,
+                &test.allocator,
+                IRect::new(0, 0, 4, 4),
+                &[0; 64],
+            )
+            .unwrap();
+        texture
+            .record_update_region(
+                &mut builder,
+                &test.allocator,
+                IRect::new(2, 2, 2, 2),
+                &red.repeat(4),
+            )
+            .unwrap();
+        let readback = CpuAccessibleBuffer::from_iter(
+            &test.allocator,
+            BufferUsage {
+                transfer_dst: true,
+                ..BufferUsage::empty()
+            },
+            false,
+            [0u8; 64],
+        )
+        .unwrap();
+        builder
+            .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(
+                texture.image(),
+                readback.clone(),
+            ))
+            .unwrap();
+        builder
+            .build()
+            .unwrap()
+            .execute(test.queue.clone())
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .unwrap()
+            .wait(None)
+            .unwrap();
+
+        let pixels = readback.read().unwrap();
+        for y in 0..4 {
+            for x in 0..4 {
+                let expected = if x >= 2 && y >= 2 { red } else { [0; 4] };
+                let i = (y * 4 + x) * 4;
+                assert_eq!(pixels[i..i + 4], expected, "pixel ({x}, {y})");
+            }
+        }
+    }
+}
@@ -1,27 +1,32 @@
-use crate::{RenderContext, Texture};
+use crate::{DrawBudget, RenderContext, StaticBuilder, Texture, TextureAddressMode};
 use bytemuck::{Pod, Zeroable};
 use gristmill_core::{
     asset::image::{Rgba, RgbaImage},
-    geom2d::Rect,
+    geom2d::{CameraTransform, IRect, Rect},
+    math::Vec2,
+    util::FramePool,
     Color,
 };
 use std::{cmp::Ordering, collections::HashMap, ptr::null, sync::Arc};
 use vulkano::{
     buffer::{BufferUsage, CpuBufferPool, DeviceLocalBuffer},
+    command_buffer::AutoCommandBufferBuilder,
     descriptor_set::{DescriptorSetWithOffsets, PersistentDescriptorSet, WriteDescriptorSet},
     image::ImageAccess,
     impl_vertex,
     memory::allocator::MemoryUsage,
     pipeline::{
         graphics::{
-            color_blend::ColorBlendState,
+            color_blend::{AttachmentBlend, BlendFactor, BlendOp, ColorBlendState},
+            depth_stencil::{CompareOp, DepthState, DepthStencilState},
             input_assembly::{InputAssemblyState, PrimitiveTopology},
             vertex_input::BuffersDefinition,
-            viewport::ViewportState,
+            viewport::{Scissor, ViewportState},
         },
-        GraphicsPipeline, Pipeline, PipelineBindPoint,
+        GraphicsPipeline, Pipeline, PipelineBindPoint, PipelineLayout, StateMode,
     },
-    sampler::Sampler,
+    sampler::{Filter, Sampler, SamplerCreateInfo},
+    shader::ShaderModule,
 };
 
 mod vs {
@@ -35,14 +40,28 @@ mod vs {
             layout(location = 1) in vec4 rect;
             layout(location = 2) in vec4 uv_rect;
             layout(location = 3) in vec4 color;
+            layout(location = 4) in vec4 color_end;
+            // pixel width, pixel height, corner radius, gradient axis (0 = horizontal, 1 = vertical)
+            layout(location = 5) in vec4 shape;
+            layout(location = 6) in float grayscale;
+            layout(location = 7) in float premultiplied;
+            layout(location = 8) in float depth;
 
             layout(location = 0) out vec2 v_uv;
             layout(location = 1) out vec4 v_color;
+            layout(location = 2) out vec2 v_local;
+            layout(location = 3) flat out vec3 v_shape;
+            layout(location = 4) flat out float v_grayscale;
+            layout(location = 5) flat out float v_premultiplied;
 
             void main() {
-                gl_Position = vec4(rect.xy + (position * rect.zw), 0, 1);
+                gl_Position = vec4(rect.xy + (position * rect.zw), depth, 1);
                 v_uv = uv_rect.xy + (abs(position) * uv_rect.zw);
-                v_color = color;
+                v_color = mix(color, color_end, mix(position.x, position.y, shape.w));
+                v_local = position * shape.xy;
+                v_shape = shape.xyz;
+                v_grayscale = grayscale;
+                v_premultiplied = premultiplied;
             }"
     }
 }
@@ -53,13 +72,46 @@ mod fs {
             #version 450
             layout(location = 0) in vec2 v_uv;
             layout(location = 1) in vec4 v_color;
+            layout(location = 2) in vec2 v_local;
+            layout(location = 3) flat in vec3 v_shape;
+            layout(location = 4) flat in float v_grayscale;
+            layout(location = 5) flat in float v_premultiplied;
 
             layout(location = 0) out vec4 f_color;
 
             layout(set = 0, binding = 0) uniform sampler2D tex;
+            layout(push_constant) uniform Params {
+                float gamma;
+                float brightness;
+                // Fragments less opaque than this are discarded, so they don't write depth.
+                float alpha_cutoff;
+            } params;
 
             void main() {
-                f_color = texture(tex, v_uv) * v_color;
+                // Blending expects premultiplied alpha, so convert straight alpha textures here.
+                vec4 tex_color = texture(tex, v_uv);
+                if (v_premultiplied == 0.0) {
+                    tex_color.rgb *= tex_color.a;
+                }
+                if (v_grayscale > 0.0) {
+                    float luma = dot(tex_color.rgb, vec3(0.299, 0.587, 0.114));
+                    tex_color.rgb = mix(tex_color.rgb, vec3(luma), v_grayscale);
+                }
+                f_color = tex_color * vec4(v_color.rgb * v_color.a, v_color.a);
+                if (f_color.a > 0.0 && (params.gamma != 1.0 || params.brightness != 1.0)) {
+                    vec3 rgb = clamp(f_color.rgb / f_color.a * params.brightness, 0.0, 1.0);
+                    f_color.rgb = pow(rgb, vec3(1.0 / params.gamma)) * f_color.a;
+                }
+                float radius = v_shape.z;
+                if (radius > 0.0) {
+                    vec2 half_size = v_shape.xy * 0.5;
+                    vec2 q = abs(v_local - half_size) - (half_size - radius);
+                    float dist = length(max(q, 0.0)) + min(max(q.x, q.y), 0.0) - radius;
+                    f_color *= clamp(0.5 - dist, 0.0, 1.0);
+                }
+                if (f_color.a < params.alpha_cutoff) {
+                    discard;
+                }
             }"
     }
 }
@@ -77,12 +129,29 @@ struct Instance {
     rect: [f32; 4],
     uv_rect: [f32; 4],
     color: [f32; 4],
+    color_end: [f32; 4],
+    shape: [f32; 4],
+    grayscale: f32,
+    premultiplied: f32,
+    depth: f32,
 }
-impl_vertex!(Instance, rect, uv_rect, color);
+impl_vertex!(
+    Instance,
+    rect,
+    uv_rect,
+    color,
+    color_end,
+    shape,
+    grayscale,
+    premultiplied,
+    depth
+);
 
 #[derive(Clone)]
 pub struct TextureRectPipeline {
     pipeline: Arc<GraphicsPipeline>,
+    // The same shaders, but writing depth, for opaque rects when depth sorting is on.
+    opaque_pipeline: Arc<GraphicsPipeline>,
     vertex_buffer: Arc<DeviceLocalBuffer<[Vertex; 4]>>,
     none_texture: Texture,
 }
@@ -122,8 +191,36 @@ impl TextureRectPipeline {
         let vs = vs::load(context.device()).unwrap();
         let fs = fs::load(context.device()).unwrap();
 
+        let pipeline = Self::build_pipeline(context, &vs, &fs, false, None);
+        let opaque_pipeline =
+            Self::build_pipeline(context, &vs, &fs, true, Some(pipeline.layout().clone()));
+
+        TextureRectPipeline {
+            pipeline,
+            opaque_pipeline,
+            vertex_buffer,
+            none_texture,
+        }
+    }
+    fn build_pipeline(
+        context: &mut RenderContext,
+        vs: &ShaderModule,
+        fs: &ShaderModule,
+        depth_write: bool,
+        layout: Option<Arc<PipelineLayout>>,
+    ) -> Arc<GraphicsPipeline> {
         let subpass = context.render_pass();
-        let pipeline = GraphicsPipeline::start()
+        // Rects are always depth tested, but nothing writes depth unless depth sorting is on, so
+        // the test always passes in plain 2D.
+        let depth_stencil_state = DepthStencilState {
+            depth: Some(DepthState {
+                enable_dynamic: false,
+                write_enable: StateMode::Fixed(depth_write),
+                compare_op: StateMode::Fixed(CompareOp::LessOrEqual),
+            }),
+            ..DepthStencilState::disabled()
+        };
+        let builder = GraphicsPipeline::start()
             .vertex_input_state(
                 BuffersDefinition::new()
                     .vertex::<Vertex>()
@@ -133,33 +230,136 @@ impl TextureRectPipeline {
             .input_assembly_state(
                 InputAssemblyState::new().topology(PrimitiveTopology::TriangleStrip),
             )
-            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
             .fragment_shader(fs.entry_point("main").unwrap(), ())
-            .color_blend_state(ColorBlendState::new(subpass.num_color_attachments()).blend_alpha())
-            .render_pass(subpass)
-            .build(context.device())
-            .unwrap();
-
-        TextureRectPipeline {
-            pipeline,
-            vertex_buffer,
-            none_texture,
+            .color_blend_state(ColorBlendState::new(subpass.num_color_attachments()).blend(
+                AttachmentBlend {
+                    color_op: BlendOp::Add,
+                    color_source: BlendFactor::One,
+                    color_destination: BlendFactor::OneMinusSrcAlpha,
+                    alpha_op: BlendOp::Add,
+                    alpha_source: BlendFactor::One,
+                    alpha_destination: BlendFactor::OneMinusSrcAlpha,
+                },
+            ))
+            .depth_stencil_state(depth_stencil_state)
+            .render_pass(subpass);
+        match layout {
+            Some(layout) => builder.with_pipeline_layout(context.device(), layout),
+            None => builder.build(context.device()),
         }
+        .unwrap()
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum GradientDirection {
+    #[default]
+    Horizontal,
+    Vertical,
+}
+
 #[derive(Clone)]
 pub struct TextureRect {
     pub texture: Option<Texture>,
     pub rect: Rect,
+    /// UVs outside 0..1 follow the texture's `TextureAddressMode`. Textures are clamped by
+    /// default (they used to repeat), so UVs past the edge stretch the edge pixels instead of
+    /// tiling. Draw tiling backgrounds with `with_address_mode(TextureAddressMode::Repeat)`.
     pub uv_rect: Rect,
     pub color: Color,
+    /// If set, the color fades from `color` to this color along the given direction.
+    pub gradient: Option<(Color, GradientDirection)>,
+    pub corner_radius: f32,
+    /// Desaturate the texture before the color is applied.
+    pub grayscale: bool,
+    /// Whether the texture's colors are already multiplied by its alpha. Images exported with
+    /// straight alpha (most PNGs from image editors) leave this unset. Set it for textures from
+    /// tools that export premultiplied alpha, such as texture packers with a "premultiply alpha"
+    /// option or Spine/TexturePacker atlases, or they will be drawn with bright fringes.
+    pub premultiplied: bool,
+    /// If set, only the part of the rect inside this area (in pixels) is drawn.
+    pub clip: Option<IRect>,
+    /// Higher z draws on top.
     pub z: u16,
+    /// The rect has no partially transparent pixels, so with depth sorting on it can write
+    /// depth. See `TextureRectRenderer::set_depth_sorting`.
+    pub opaque: bool,
 }
 
 impl TextureRect {
+    /// The depth written for layer `z`, from 1 (the cleared depth) at z 0 to 0 at the top layer.
+    /// Every z maps to a distinct value in the 16-bit depth buffer.
+    pub fn depth(z: u16) -> f32 {
+        (u16::MAX - z) as f32 / u16::MAX as f32
+    }
+    /// This rect, given in world space, moved and scaled onto the screen by `camera`. With
+    /// `CameraTransform::pixel_snap` on, rects at whole world pixels land on whole screen pixels.
+    pub fn to_screen(mut self, camera: &CameraTransform) -> TextureRect {
+        self.rect = Rect {
+            position: camera.world_to_screen(self.rect.position),
+            size: self.rect.size * camera.zoom,
+        };
+        self
+    }
+    /// Four thin rects along the inside of `rect`, drawn on top of everything, for debug
+    /// outlines.
+    pub fn outline(rect: Rect, thickness: f32, color: Color) -> [TextureRect; 4] {
+        let thickness = thickness
+            .min(rect.size.x / 2.0)
+            .min(rect.size.y / 2.0)
+            .max(0.0);
+        let side = |position: Vec2, size: Vec2| TextureRect {
+            texture: None,
+            rect: Rect { position, size },
+            uv_rect: Rect::ONE,
+            color,
+            gradient: None,
+            corner_radius: 0.0,
+            grayscale: false,
+            premultiplied: false,
+            clip: None,
+            z: u16::MAX,
+            opaque: false,
+        };
+        let Rect { position, size } = rect;
+        let inner_height = size.y - thickness * 2.0;
+        [
+            side(position, Vec2::new(size.x, thickness)),
+            side(
+                Vec2::new(position.x, position.y + size.y - thickness),
+                Vec2::new(size.x, thickness),
+            ),
+            side(
+                Vec2::new(position.x, position.y + thickness),
+                Vec2::new(thickness, inner_height),
+            ),
+            side(
+                Vec2::new(position.x + size.x - thickness, position.y + thickness),
+                Vec2::new(thickness, inner_height),
+            ),
+        ]
+    }
+    /// A color for outlining things at layer `z`. Neighboring layers get very different hues, so
+    /// overlapping outlines are easy to tell apart.
+    pub fn wireframe_color(z: u16) -> Color {
+        // Step around the hue circle by the golden angle.
+        let hue = (z as f32 * 0.618_034).fract() * 6.0;
+        let channel = |offset: f32| {
+            let distance = ((hue - offset).rem_euclid(6.0) - 3.0).abs();
+            (distance - 1.0).clamp(0.0, 1.0)
+        };
+        Color::new_opaque(channel(0.0), channel(2.0), channel(4.0))
+    }
     fn draw(&self, viewport: Rect) -> Instance {
         let viewport_extents = viewport.size / 2.0;
+        let (color_end, direction) = self
+            .gradient
+            .unwrap_or((self.color, GradientDirection::Horizontal));
+        let gradient_axis = match direction {
+            GradientDirection::Horizontal => 0.0,
+            GradientDirection::Vertical => 1.0,
+        };
         Instance {
             rect: [
                 (self.rect.position.x / viewport_extents.x) - 1.0,
@@ -169,10 +369,83 @@ impl TextureRect {
             ],
             uv_rect: self.uv_rect.into(),
             color: self.color.into(),
+            color_end: color_end.into(),
+            shape: [
+                self.rect.size.x,
+                self.rect.size.y,
+                self.corner_radius,
+                gradient_axis,
+            ],
+            grayscale: if self.grayscale { 1.0 } else { 0.0 },
+            premultiplied: if self.premultiplied { 1.0 } else { 0.0 },
+            depth: Self::depth(self.z),
         }
     }
 }
 
+/// A darkened copy of a rect drawn beneath it, offset to give it some depth.
+#[derive(Copy, Clone, Debug)]
+pub struct DropShadow {
+    /// In pixels; positive values move the shadow right and down.
+    pub offset: Vec2,
+    pub color: Color,
+    /// How far the edge of the shadow fades out, in pixels. 0 gives a hard edge.
+    pub blur: f32,
+}
+
+impl Default for DropShadow {
+    fn default() -> Self {
+        DropShadow {
+            offset: Vec2::new(4.0, 4.0),
+            color: Color::new(0.0, 0.0, 0.0, 0.5),
+            blur: 0.0,
+        }
+    }
+}
+
+impl DropShadow {
+    const BLUR_LAYERS: usize = 4;
+
+    /// The quads that draw this shadow for `rect`, one z below it. The shadow keeps the rect's
+    /// texture, so sprites cast a shadow in their own shape. A blurred shadow is drawn as a stack
+    /// of growing, fainter copies, which reach the shadow's full alpha where they all overlap.
+    ///
+    /// Callers must leave room below `rect.z` for the shadow, or it may draw on top of whatever
+    /// shares its z.
+    pub fn quads(&self, rect: &TextureRect) -> Vec<TextureRect> {
+        let alpha = self.color.alpha() * rect.color.alpha();
+        let layers = if self.blur > 0.0 {
+            Self::BLUR_LAYERS
+        } else {
+            1
+        };
+        let [r, g, b, _]: [f32; 4] = self.color.into();
+        let color = Color::new(r, g, b, 1.0 - (1.0 - alpha).powf(1.0 / layers as f32));
+        (0..layers)
+            .map(|layer| {
+                // Spread the layers evenly from half the blur inside the edge to half outside.
+                let grow = if layers > 1 {
+                    self.blur * ((layer as f32 + 0.5) / layers as f32 - 0.5)
+                } else {
+                    0.0
+                };
+                let mut shadow = rect.clone();
+                shadow.rect = Rect {
+                    position: rect.rect.position + self.offset - Vec2::splat(grow),
+                    size: (rect.rect.size + Vec2::splat(grow * 2.0)).max(Vec2::ZERO),
+                };
+                shadow.color = color;
+                shadow.gradient = None;
+                shadow.corner_radius = (rect.corner_radius + grow).max(0.0);
+                shadow.grayscale = false;
+                shadow.opaque = false;
+                shadow.z = rect.z.saturating_sub(1);
+                shadow
+            })
+            .collect()
+    }
+}
+
 impl PartialEq for TextureRect {
     fn eq(&self, other: &Self) -> bool {
         self.texture == other.texture && self.z == other.z
@@ -207,12 +480,39 @@ impl Ord for TextureRect {
     }
 }
 
+/// Counts from the most recent `TextureRectRenderer::draw_all`.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct DrawMetrics {
+    pub rects: usize,
+    pub draw_calls: usize,
+    /// Bytes of instance data uploaded.
+    pub buffer_bytes: usize,
+}
+
+struct Batch {
+    texture: Option<Texture>,
+    clip: Option<IRect>,
+    bounds: Rect,
+    instances: Vec<Instance>,
+}
+
 pub struct TextureRectRenderer {
     pipeline: TextureRectPipeline,
     texture_descriptors: HashMap<Texture, DescriptorSetWithOffsets>,
+    // One per filter and address mode, shared by every texture's descriptor set.
+    samplers: HashMap<(Filter, TextureAddressMode), Arc<Sampler>>,
+    filter: Filter,
+    // The anisotropy the cached samplers and descriptor sets were created with.
+    descriptor_anisotropy: Option<f32>,
     buffer_pool: CpuBufferPool<Instance>,
-    instances: Vec<Instance>,
     draw_queue: Vec<TextureRect>,
+    // Kept between frames so they don't reallocate.
+    batches: Vec<Batch>,
+    instance_pool: FramePool<Instance>,
+    depth_sorting: bool,
+    wireframe: bool,
+    metrics: DrawMetrics,
+    budget: Option<DrawBudget>,
 }
 
 impl TextureRectRenderer {
@@ -220,6 +520,9 @@ impl TextureRectRenderer {
         TextureRectRenderer {
             pipeline: TextureRectPipeline::new(context),
             texture_descriptors: HashMap::new(),
+            samplers: HashMap::new(),
+            filter: Filter::Nearest,
+            descriptor_anisotropy: context.anisotropy(),
             buffer_pool: CpuBufferPool::new(
                 context.allocator().clone(),
                 BufferUsage {
@@ -228,13 +531,79 @@ impl TextureRectRenderer {
                 },
                 MemoryUsage::Upload,
             ),
-            instances: Vec::new(),
             draw_queue: Vec::new(),
+            batches: Vec::new(),
+            instance_pool: FramePool::new(),
+            depth_sorting: false,
+            wireframe: false,
+            metrics: DrawMetrics::default(),
+            budget: DrawBudget::load_dev_config(),
         }
     }
 
+    pub fn metrics(&self) -> DrawMetrics {
+        self.metrics
+    }
+
+    pub fn draw_budget(&self) -> Option<DrawBudget> {
+        self.budget
+    }
+    /// Replace the budget loaded from the config. Only checked in debug builds.
+    pub fn set_draw_budget(&mut self, budget: Option<DrawBudget>) {
+        self.budget = budget;
+    }
+    /// Log one warning, listing every limit, if the last frame went over budget.
+    fn check_budget(&self) {
+        let exceeded = match self.budget {
+            Some(budget) => budget.exceeded(&self.metrics),
+            None => return,
+        };
+        if !exceeded.is_empty() {
+            log::warn!("Over draw budget: {}.", exceeded.join(", "));
+        }
+    }
+
+    pub fn depth_sorting(&self) -> bool {
+        self.depth_sorting
+    }
+    /// Draw `opaque` rects with depth writes, letting the depth buffer sort them. They're drawn
+    /// first, in any order, so they can be batched by texture no matter how they overlap, and
+    /// pixels less than half opaque are discarded so cutout sprites don't hide what's behind
+    /// them. The rest are drawn afterwards in z order, tested against the opaque rects' depth, so
+    /// opaque rects above them still cover them.
+    ///
+    /// Rects with soft edges, translucency or rounded corners must not be marked opaque, since
+    /// whatever is drawn behind them later would be hidden. Off by default.
+    pub fn set_depth_sorting(&mut self, enabled: bool) {
+        self.depth_sorting = enabled;
+    }
+
+    pub fn wireframe(&self) -> bool {
+        self.wireframe
+    }
+    /// Outline every queued rect, colored by its z with `TextureRect::wireframe_color`, to see
+    /// where things are and how they overlap. Off by default.
+    pub fn set_wireframe(&mut self, enabled: bool) {
+        self.wireframe = enabled;
+    }
+
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+    /// How textures are filtered when drawn larger or smaller than their size. `Nearest` keeps
+    /// pixel art sharp, `Linear` smooths it. Nearest by default.
+    pub fn set_filter(&mut self, filter: Filter) {
+        if self.filter != filter {
+            self.filter = filter;
+            // Descriptor sets hold their sampler, so they're rebuilt with the new filter.
+            self.texture_descriptors.clear();
+        }
+    }
+
+    /// Forget the descriptor sets of `texture`, with any address mode.
     pub fn remove(&mut self, texture: &Texture) {
-        self.texture_descriptors.remove(texture);
+        self.texture_descriptors
+            .retain(|key, _| key.image_view() != texture.image_view());
     }
 
     pub fn queue(&mut self, rect: TextureRect) {
@@ -249,11 +618,30 @@ impl TextureRectRenderer {
         }
     }
 
+    /// Queue `rect`, given in world space, drawn through `camera`. See `TextureRect::to_screen`.
+    pub fn queue_world(&mut self, camera: &CameraTransform, rect: TextureRect) {
+        self.draw_queue.push(rect.to_screen(camera));
+    }
+
+    /// Queue `rect` with `shadow` drawn beneath it.
+    pub fn queue_with_shadow(&mut self, rect: TextureRect, shadow: &DropShadow) {
+        self.draw_queue.extend(shadow.quads(&rect));
+        self.draw_queue.push(rect);
+    }
+
     fn get_descriptor_set(
         &mut self,
         context: &mut RenderContext,
         texture: Texture,
     ) -> DescriptorSetWithOffsets {
+        if self.descriptor_anisotropy != context.anisotropy() {
+            self.texture_descriptors.clear();
+            self.samplers.clear();
+            self.descriptor_anisotropy = context.anisotropy();
+        }
+        let anisotropy = self.descriptor_anisotropy;
+        let filter = self.filter;
+        let samplers = &mut self.samplers;
         self.texture_descriptors
             .entry(texture.clone())
             .or_insert_with(|| {
@@ -265,7 +653,23 @@ impl TextureRectRenderer {
                     .get(0)
                     .unwrap()
                     .clone();
-                let sampler = Sampler::new(context.device(), Default::default()).unwrap();
+                let address_mode = texture.address_mode();
+                let sampler = samplers
+                    .entry((filter, address_mode))
+                    .or_insert_with(|| {
+                        Sampler::new(
+                            context.device(),
+                            SamplerCreateInfo {
+                                mag_filter: filter,
+                                min_filter: filter,
+                                address_mode: [address_mode.sampler_address_mode(); 3],
+                                anisotropy,
+                                ..Default::default()
+                            },
+                        )
+                        .unwrap()
+                    })
+                    .clone();
                 PersistentDescriptorSet::new(
                     context.descriptor_set_allocator(),
                     layout,
@@ -280,22 +684,43 @@ impl TextureRectRenderer {
             })
             .clone()
     }
-    fn draw_instances(&mut self, context: &mut RenderContext, texture: Option<Texture>) {
+    fn scissor(clip: Option<IRect>, viewport: Rect) -> Scissor {
+        // Clip rects are relative to the viewport, but scissors are in framebuffer pixels.
+        let viewport = viewport.as_irect();
+        let bounds = IRect::from_size(viewport.size);
+        let rect = clip.map_or(bounds, |clip| clip.intersection(bounds));
+        Scissor {
+            origin: [
+                (viewport.x() + rect.x()) as u32,
+                (viewport.y() + rect.y()) as u32,
+            ],
+            dimensions: [rect.width() as u32, rect.height() as u32],
+        }
+    }
+    fn draw_instances<L>(
+        &mut self,
+        context: &mut RenderContext,
+        builder: &mut AutoCommandBufferBuilder<L>,
+        texture: Option<Texture>,
+        clip: Option<IRect>,
+        instances: &[Instance],
+    ) {
         const VERTEX_COUNT: u32 = 4;
-        if self.instances.is_empty() {
+        if instances.is_empty() {
             return;
         }
-        let instance_count = self.instances.len() as u32;
+        let instance_count = instances.len() as u32;
         let instance_buffer = self
             .buffer_pool
-            .from_iter(self.instances.drain(..))
+            .from_iter(instances.iter().copied())
             .unwrap();
         let descriptor_set = self.get_descriptor_set(
             context,
             texture.unwrap_or_else(|| self.pipeline.none_texture.clone()),
         );
-        context
-            .builder()
+        let scissor = Self::scissor(clip, context.viewport());
+        builder
+            .set_scissor(0, [scissor])
             .bind_vertex_buffers(0, (self.pipeline.vertex_buffer.clone(), instance_buffer))
             .bind_descriptor_sets(
                 PipelineBindPoint::Graphics,
@@ -305,23 +730,158 @@ impl TextureRectRenderer {
             )
             .draw(VERTEX_COUNT, instance_count, 0, 0)
             .unwrap();
+        self.metrics.draw_calls += 1;
+        self.metrics.buffer_bytes += std::mem::size_of_val(instances);
     }
-    pub fn draw_all(&mut self, context: &mut RenderContext) {
-        context
-            .builder()
-            .bind_pipeline_graphics(self.pipeline.pipeline.clone());
+    fn draw_batches<L, I>(
+        &mut self,
+        context: &mut RenderContext,
+        builder: &mut AutoCommandBufferBuilder<L>,
+        opaque: bool,
+        rects: I,
+    ) where
+        I: Iterator<Item = TextureRect>,
+    {
+        // How many batches back a rect can be moved to join a batch with the same texture and clip.
+        const MAX_BATCH_LOOKBACK: usize = 16;
 
-        self.draw_queue.sort_unstable();
-        let draw_queue = std::mem::take(&mut self.draw_queue);
+        let pipeline = if opaque {
+            self.pipeline.opaque_pipeline.clone()
+        } else {
+            self.pipeline.pipeline.clone()
+        };
+        let display = context.display_settings();
+        builder
+            .bind_pipeline_graphics(pipeline.clone())
+            .push_constants(
+                pipeline.layout().clone(),
+                0,
+                fs::ty::Params {
+                    gamma: display.gamma,
+                    brightness: display.brightness,
+                    alpha_cutoff: if opaque { 0.5 } else { 0.0 },
+                },
+            );
         let viewport = context.viewport();
-        let mut last_texture = None;
-        for rect in draw_queue {
-            if rect.texture != last_texture {
-                self.draw_instances(context, last_texture);
-                last_texture = rect.texture.clone();
+
+        // A rect can join an earlier batch if it doesn't overlap anything drawn after that batch,
+        // so reordering it doesn't change the result. Order within each batch is preserved.
+        // Opaque rects are sorted by depth, so they can join any batch.
+        let mut batches = std::mem::take(&mut self.batches);
+        for rect in rects {
+            let mut target = None;
+            let lookback = if opaque {
+                batches.len()
+            } else {
+                MAX_BATCH_LOOKBACK
+            };
+            for (index, batch) in batches.iter().enumerate().rev().take(lookback) {
+                if batch.texture == rect.texture && batch.clip == rect.clip {
+                    target = Some(index);
+                    break;
+                }
+                if !opaque && batch.bounds.intersects(rect.rect) {
+                    break;
+                }
+            }
+            let instance = rect.draw(viewport);
+            if let Some(batch) = target.map(|index| &mut batches[index]) {
+                batch.bounds = batch.bounds.union(rect.rect);
+                batch.instances.push(instance);
+            } else {
+                let mut instances = self.instance_pool.take();
+                instances.push(instance);
+                batches.push(Batch {
+                    texture: rect.texture,
+                    clip: rect.clip,
+                    bounds: rect.rect,
+                    instances,
+                });
             }
-            self.instances.push(rect.draw(viewport));
         }
-        self.draw_instances(context, last_texture);
+        for batch in batches.drain(..) {
+            self.draw_instances(
+                context,
+                builder,
+                batch.texture,
+                batch.clip,
+                &batch.instances,
+            );
+            self.instance_pool.give(batch.instances);
+        }
+        self.batches = batches;
+    }
+    pub fn draw_all(&mut self, context: &mut RenderContext) {
+        let mut builder = context.take_builder();
+        self.draw_queued(context, &mut builder);
+        context.restore_builder(builder);
+    }
+    /// Draw the queued rects into static content, from `Renderable::render_static`. The rects are
+    /// drawn once and replayed until the static content is recorded again.
+    pub fn draw_all_static(&mut self, context: &mut RenderContext, builder: &mut StaticBuilder) {
+        self.draw_queued(context, builder);
+    }
+    fn draw_queued<L>(
+        &mut self,
+        context: &mut RenderContext,
+        builder: &mut AutoCommandBufferBuilder<L>,
+    ) {
+        if self.wireframe {
+            let outlines: Vec<TextureRect> = self
+                .draw_queue
+                .iter()
+                .flat_map(|rect| {
+                    TextureRect::outline(rect.rect, 1.0, TextureRect::wireframe_color(rect.z))
+                })
+                .collect();
+            self.draw_queue.extend(outlines);
+        }
+        // With depth sorting, opaque rects come first.
+        let depth_sorting = self.depth_sorting;
+        self.draw_queue.sort_unstable_by(|a, b| {
+            let a_late = !(depth_sorting && a.opaque);
+            let b_late = !(depth_sorting && b.opaque);
+            a_late.cmp(&b_late).then_with(|| a.cmp(b))
+        });
+        let mut draw_queue = std::mem::take(&mut self.draw_queue);
+        self.metrics = DrawMetrics {
+            rects: draw_queue.len(),
+            draw_calls: 0,
+            buffer_bytes: 0,
+        };
+
+        let opaque_count = if depth_sorting {
+            draw_queue.iter().take_while(|rect| rect.opaque).count()
+        } else {
+            0
+        };
+        if opaque_count > 0 {
+            self.draw_batches(context, builder, true, draw_queue.drain(..opaque_count));
+        }
+        self.draw_batches(context, builder, false, draw_queue.drain(..));
+        self.draw_queue = draw_queue;
+        if cfg!(debug_assertions) {
+            self.check_budget();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sub_pixel_camera_snaps_rects() {
+        let mut camera = CameraTransform::new(Vec2::new(320.0, 180.0));
+        camera.center = Vec2::new(0.3, 0.7);
+        camera.pixel_snap = true;
+        for zoom in [1.0, 2.0, 3.0] {
+            camera.zoom = zoom;
+            let [sprite, ..] =
+                TextureRect::outline(Rect::new(5.0, 8.0, 16.0, 16.0), 1.0, Color::WHITE);
+            let screen = sprite.to_screen(&camera).rect;
+            assert_eq!(screen.position, screen.position.round());
+            assert_eq!(screen.size, Vec2::new(16.0 * zoom, zoom));
+        }
     }
 }
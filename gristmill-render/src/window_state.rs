@@ -0,0 +1,186 @@
+use gristmill_core::{
+    asset::{self, AssetError},
+    geom2d::IRect,
+    math::IVec2,
+};
+use serde::{Deserialize, Serialize};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event_loop::EventLoopWindowTarget,
+    window::{Window, WindowBuilder},
+};
+
+/// How much of the window's top edge must be on a monitor for it to be left where it is, so the
+/// title bar can still be grabbed.
+const MIN_VISIBLE: IVec2 = IVec2::new(64, 32);
+
+/// If a window at `position` would be (mostly) off every monitor, such as after a monitor was
+/// unplugged, move it onto the first one. A window bigger than that monitor goes to its top-left
+/// corner. Otherwise returns `position` unchanged.
+fn clamp_to_monitors(position: IVec2, size: IVec2, monitors: &[IRect]) -> IVec2 {
+    let title_bar = IRect {
+        position,
+        size: IVec2::new(size.x, MIN_VISIBLE.y),
+    };
+    let visible = monitors.iter().any(|monitor| {
+        let overlap = title_bar.intersection(*monitor);
+        overlap.width() >= MIN_VISIBLE.x.min(size.x) && overlap.height() >= MIN_VISIBLE.y
+    });
+    match monitors.first() {
+        Some(monitor) if !visible => {
+            let max = monitor.position + (monitor.size - size).max(IVec2::ZERO);
+            position.clamp(monitor.position, max)
+        }
+        _ => position,
+    }
+}
+
+/// The window's position, size and maximized state, saved when the game exits so it reopens the
+/// same way.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WindowState {
+    #[serde(default)]
+    pub position: Option<IVec2>,
+    pub size: IVec2,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+impl WindowState {
+    pub fn load_config() -> Result<WindowState, AssetError> {
+        asset::load_yaml_file("config", "window.yaml")
+    }
+    pub fn save_config(&self) -> Result<(), AssetError> {
+        asset::save_yaml_file("config", "window.yaml", self)
+    }
+
+    /// The current state of `window`. While maximized or minimized, the window's restored position
+    /// and size aren't available, so they're taken from `previous` if given.
+    pub fn from_window(window: &Window, previous: Option<&WindowState>) -> WindowState {
+        let maximized = window.is_maximized();
+        let size = window.inner_size();
+        let minimized = size.width == 0 || size.height == 0;
+        match previous {
+            Some(previous) if maximized || minimized => WindowState {
+                maximized,
+                ..previous.clone()
+            },
+            _ => WindowState {
+                position: window
+                    .outer_position()
+                    .ok()
+                    .map(|position| IVec2::new(position.x, position.y)),
+                size: IVec2::new(size.width as i32, size.height as i32),
+                maximized,
+            },
+        }
+    }
+
+    /// Apply this state to a window about to be created, moving it onto a monitor if its saved
+    /// position is no longer visible.
+    pub fn apply<T>(
+        &self,
+        builder: WindowBuilder,
+        target: &EventLoopWindowTarget<T>,
+    ) -> WindowBuilder {
+        let size = self.size.max(IVec2::ONE);
+        let mut builder = builder
+            .with_inner_size(PhysicalSize::new(size.x as u32, size.y as u32))
+            .with_maximized(self.maximized);
+        if let Some(position) = self.position {
+            // The primary monitor goes first, as the one to move a lost window onto.
+            let primary = target.primary_monitor();
+            let others = target
+                .available_monitors()
+                .filter(|monitor| Some(monitor) != primary.as_ref());
+            let monitors: Vec<IRect> = primary
+                .clone()
+                .into_iter()
+                .chain(others)
+                .map(|monitor| {
+                    let position = monitor.position();
+                    let size = monitor.size();
+                    IRect::new(
+                        position.x,
+                        position.y,
+                        size.width as i32,
+                        size.height as i32,
+                    )
+                })
+                .collect();
+            let position = clamp_to_monitors(position, size, &monitors);
+            builder = builder.with_position(PhysicalPosition::new(position.x, position.y));
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitors() -> [IRect; 2] {
+        [
+            IRect::new(0, 0, 1920, 1080),
+            IRect::new(1920, 0, 1280, 1024),
+        ]
+    }
+
+    #[test]
+    fn visible_window_stays() {
+        let size = IVec2::new(800, 600);
+        let position = IVec2::new(2000, 100);
+        assert_eq!(clamp_to_monitors(position, size, &monitors()), position);
+    }
+
+    #[test]
+    fn offscreen_window_moves_to_first_monitor() {
+        let size = IVec2::new(800, 600);
+        // Where a third monitor used to be.
+        assert_eq!(
+            clamp_to_monitors(IVec2::new(3500, 200), size, &monitors()),
+            IVec2::new(1120, 200)
+        );
+        assert_eq!(
+            clamp_to_monitors(IVec2::new(-900, -700), size, &monitors()),
+            IVec2::ZERO
+        );
+        // Too big for the monitor.
+        assert_eq!(
+            clamp_to_monitors(IVec2::new(-5000, 50), IVec2::new(2560, 1440), &monitors()),
+            IVec2::ZERO
+        );
+    }
+
+    #[test]
+    fn title_bar_must_be_visible() {
+        let size = IVec2::new(800, 600);
+        let monitors = monitors();
+        // Only the bottom of the window is on screen, so the title bar can't be grabbed.
+        let above = IVec2::new(100, -590);
+        assert_eq!(
+            clamp_to_monitors(above, size, &monitors),
+            IVec2::new(100, 0)
+        );
+        // Enough of the title bar hangs over the left edge to grab.
+        let left = IVec2::new(-800 + MIN_VISIBLE.x, 100);
+        assert_eq!(clamp_to_monitors(left, size, &monitors), left);
+        let too_far_left = IVec2::new(-800 + MIN_VISIBLE.x - 1, 100);
+        assert_eq!(
+            clamp_to_monitors(too_far_left, size, &monitors),
+            IVec2::new(0, 100)
+        );
+        // Spanning both monitors counts if either shows enough.
+        let spanning = IVec2::new(1900, 100);
+        assert_eq!(clamp_to_monitors(spanning, size, &monitors), spanning);
+    }
+
+    #[test]
+    fn no_monitors_leaves_position() {
+        let position = IVec2::new(-5000, -5000);
+        assert_eq!(
+            clamp_to_monitors(position, IVec2::new(800, 600), &[]),
+            position
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{
+    hdr::TonemapPass, static_commands::StaticCommands, window_size_dependent_setup, UpscaleMode,
+};
+use gristmill_core::{math::IVec2, Color};
+use std::sync::Arc;
+use vulkano::{
+    descriptor_set::allocator::StandardDescriptorSetAllocator,
+    device::Device,
+    format::Format,
+    image::{AttachmentImage, ImageUsage, SwapchainImage},
+    memory::allocator::StandardMemoryAllocator,
+    pipeline::graphics::viewport::Viewport,
+    render_pass::{Framebuffer, RenderPass},
+    swapchain::{ColorSpace, Surface, Swapchain, SwapchainCreateInfo, SwapchainCreationError},
+    sync::GpuFuture,
+};
+use winit::window::Window;
+
+/// Create a swapchain for the window of `surface`, at the window's current size.
+pub(crate) fn create_swapchain(
+    device: &Arc<Device>,
+    surface: Arc<Surface>,
+    image_format: Format,
+    image_color_space: ColorSpace,
+) -> Result<(Arc<Swapchain>, Vec<Arc<SwapchainImage>>), SwapchainCreationError> {
+    let surface_capabilities = device
+        .physical_device()
+        .surface_capabilities(&surface, Default::default())
+        .unwrap();
+    let window = surface.object().unwrap().downcast_ref::<Window>().unwrap();
+    let image_extent = window.inner_size().into();
+    Swapchain::new(
+        device.clone(),
+        surface,
+        SwapchainCreateInfo {
+            min_image_count: surface_capabilities.min_image_count,
+            image_format: Some(image_format),
+            image_color_space,
+            image_extent,
+            image_usage: ImageUsage {
+                color_attachment: true,
+                // Needed to upscale from an internal resolution.
+                transfer_dst: surface_capabilities.supported_usage_flags.transfer_dst,
+                // Needed to read frames back for clips.
+                transfer_src: cfg!(feature = "clip-capture")
+                    && surface_capabilities.supported_usage_flags.transfer_src,
+                ..ImageUsage::empty()
+            },
+            composite_alpha: surface_capabilities
+                .supported_composite_alpha
+                .iter()
+                .next()
+                .unwrap(),
+            ..Default::default()
+        },
+    )
+}
+
+/// The swapchain format for another window, from what its surface supports. Without HDR it
+/// renders straight into the swapchain through the main window's render pass, so it needs the
+/// main window's format; with HDR any format works, since the tonemap pass writes to it.
+pub(crate) fn secondary_window_format(
+    supported: &[(Format, ColorSpace)],
+    main_format: Format,
+    hdr: bool,
+) -> Option<(Format, ColorSpace)> {
+    if hdr {
+        supported.first().copied()
+    } else {
+        supported
+            .iter()
+            .copied()
+            .find(|(format, _)| *format == main_format)
+    }
+}
+
+/// Everything that belongs to one window: its swapchain and framebuffers, the frame in flight,
+/// and the settings that depend on the window's size. The device, queue, allocators and render
+/// passes are shared by every window, and stay in `RenderContext`.
+pub(crate) struct WindowTarget {
+    pub surface: Arc<Surface>,
+    pub swapchain: Arc<Swapchain>,
+    pub swapchain_images: Vec<Arc<SwapchainImage>>,
+    pub framebuffers: Vec<Arc<Framebuffer>>,
+    pub offscreen: Option<Arc<AttachmentImage>>,
+    pub tonemap_pass: Option<TonemapPass>,
+    pub static_commands: Option<StaticCommands>,
+    pub viewport: Viewport,
+
+    pub internal_resolution: Option<(IVec2, UpscaleMode)>,
+    pub clear_color: Color,
+    pub fixed_aspect: Option<f32>,
+    pub letterbox_color: Color,
+    pub recreate_swapchain: bool,
+    pub recently_resized: bool,
+    pub previous_frame_end: Option<Box<dyn GpuFuture>>,
+    pub current_framebuffer_index: usize,
+}
+
+impl WindowTarget {
+    /// Set up the framebuffers for a new swapchain. With `hdr`, the window renders to an HDR image
+    /// and gets its own tonemap pass.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        device: &Arc<Device>,
+        memory_allocator: &StandardMemoryAllocator,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        render_pass: Arc<RenderPass>,
+        surface: Arc<Surface>,
+        swapchain: Arc<Swapchain>,
+        images: Vec<Arc<SwapchainImage>>,
+        hdr: bool,
+    ) -> Self {
+        let mut viewport = Viewport {
+            origin: [0.0, 0.0],
+            dimensions: [0.0, 0.0],
+            depth_range: 0.0..1.0,
+        };
+        let (framebuffers, offscreen) = window_size_dependent_setup(
+            memory_allocator,
+            &images,
+            render_pass,
+            &mut viewport,
+            None,
+            None,
+            hdr,
+        );
+        let tonemap_pass = hdr.then(|| {
+            let mut pass = TonemapPass::new(device, swapchain.image_format());
+            pass.resize(
+                descriptor_set_allocator,
+                &images,
+                offscreen.clone().unwrap(),
+            );
+            pass
+        });
+        WindowTarget {
+            surface,
+            swapchain,
+            swapchain_images: images,
+            framebuffers,
+            offscreen,
+            tonemap_pass,
+            static_commands: None,
+            viewport,
+            internal_resolution: None,
+            clear_color: Color::WHITE,
+            fixed_aspect: None,
+            letterbox_color: Color::BLACK,
+            recreate_swapchain: false,
+            recently_resized: false,
+            previous_frame_end: None,
+            current_framebuffer_index: 0,
+        }
+    }
+
+    pub fn window(&self) -> &Window {
+        self.surface
+            .object()
+            .unwrap()
+            .downcast_ref::<Window>()
+            .unwrap()
+    }
+}
+
+// Opening a window needs an event loop, which winit only creates on the main thread, so
+// `RenderContext::create_secondary_window` can't run in a test. Its format choice can.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUPPORTED: [(Format, ColorSpace); 2] = [
+        (Format::B8G8R8A8_UNORM, ColorSpace::SrgbNonLinear),
+        (Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear),
+    ];
+
+    #[test]
+    fn secondary_window_matches_main_format() {
+        assert_eq!(
+            secondary_window_format(&SUPPORTED, Format::B8G8R8A8_SRGB, false),
+            Some(SUPPORTED[1])
+        );
+        assert_eq!(
+            secondary_window_format(&SUPPORTED, Format::R8G8B8A8_SRGB, false),
+            None
+        );
+    }
+
+    #[test]
+    fn hdr_secondary_window_takes_any_format() {
+        assert_eq!(
+            secondary_window_format(&SUPPORTED, Format::R8G8B8A8_SRGB, true),
+            Some(SUPPORTED[0])
+        );
+        assert_eq!(
+            secondary_window_format(&[], Format::B8G8R8A8_SRGB, true),
+            None
+        );
+    }
+}
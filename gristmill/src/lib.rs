@@ -3,90 +3,199 @@ pub use gristmill_gui as gui;
 pub use gristmill_macros::*;
 pub use gristmill_render as render;
 
-use gristmill_render::RenderContext;
+use gristmill_render::{RenderContext, RenderSettings};
+use std::cell::Cell;
 use winit::{
     event::{Event, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
-    window::{CursorGrabMode, Window},
+    event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget},
+    window::{CursorGrabMode, CursorIcon, WindowBuilder, WindowId},
 };
 
 pub trait Game: render::Renderable + 'static {
     fn input_system(&mut self) -> &mut input::InputSystem;
     fn update(&mut self, window: &mut GameWindow, delta: f64);
+    /// Render a window opened with `GameWindow::open_window`, usually with
+    /// `context.render_window(window, &mut renderable)`. Called for each secondary window after
+    /// the main window renders.
+    fn render_secondary_window(&mut self, _context: &mut RenderContext, _window: WindowId) {}
+    /// Handle an event from a window opened with `GameWindow::open_window`. These don't reach
+    /// the main `input_system`, so a window that takes input should pass them to its own
+    /// `InputSystem`. Device events such as raw mouse motion always go to the main one.
+    fn secondary_window_event(&mut self, _window: WindowId, _event: Event<()>) {}
 }
 
 pub struct GameWindow<'a> {
-    window: &'a Window,
+    context: &'a mut RenderContext,
+    event_loop: &'a EventLoopWindowTarget<()>,
     close: bool,
+    grab_request: Cell<Option<bool>>,
 }
 
 impl<'a> GameWindow<'a> {
-    fn new(window: &'a Window) -> Self {
+    fn new(context: &'a mut RenderContext, event_loop: &'a EventLoopWindowTarget<()>) -> Self {
         GameWindow {
-            window,
+            context,
+            event_loop,
             close: false,
+            grab_request: Cell::new(None),
         }
     }
     pub fn close(&mut self) {
         self.close = true;
     }
+    /// Open a secondary window, such as a tool palette. See
+    /// `RenderContext::create_secondary_window`. Closing it with its close button closes only that
+    /// window.
+    pub fn open_window(&mut self, window_builder: WindowBuilder) -> Option<WindowId> {
+        self.context
+            .create_secondary_window(self.event_loop, window_builder)
+    }
+    pub fn close_window(&mut self, window: WindowId) {
+        self.context.close_secondary_window(window);
+    }
+    /// Confine and hide the cursor, and mark the pointer grabbed in the input system so mouse
+    /// motion bindings receive motion. Unlike `InputSystem::set_pointer_grabbed`, the grab is kept
+    /// when a context without mouse look is pushed, until `ungrab_cursor` is called.
     pub fn grab_cursor(&self) {
-        self.window
-            .set_cursor_grab(CursorGrabMode::Confined)
-            .or_else(|_e| self.window.set_cursor_grab(CursorGrabMode::Locked))
-            .unwrap();
-        self.window.set_cursor_visible(false);
+        self.grab_request.set(Some(true));
+        self.set_cursor_grab(true);
     }
     pub fn ungrab_cursor(&self) {
-        self.window.set_cursor_grab(CursorGrabMode::None).unwrap();
-        self.window.set_cursor_visible(true);
+        self.grab_request.set(Some(false));
+        self.set_cursor_grab(false);
+    }
+    fn set_cursor_grab(&self, grabbed: bool) {
+        let window = self.context.window();
+        if grabbed {
+            window
+                .set_cursor_grab(CursorGrabMode::Confined)
+                .or_else(|_e| window.set_cursor_grab(CursorGrabMode::Locked))
+                .unwrap();
+        } else {
+            window.set_cursor_grab(CursorGrabMode::None).unwrap();
+        }
+        window.set_cursor_visible(!grabbed);
+    }
+    pub fn set_cursor_icon(&self, cursor: CursorIcon) {
+        self.context.window().set_cursor_icon(cursor);
     }
 }
 
 struct GameLoop<G: Game> {
     game: G,
     context: RenderContext,
+    pointer_grabbed: bool,
+    // Whether the game grabbed the cursor with `GameWindow::grab_cursor`.
+    grabbed_by_window: bool,
 }
 
 impl<G: Game> GameLoop<G> {
-    fn update(&mut self, delta: f64) -> bool {
+    fn update(&mut self, delta: f64, event_loop: &EventLoopWindowTarget<()>) -> bool {
         self.game.input_system().start_frame();
-        let mut window = GameWindow::new(self.context.window());
+        let mut window = GameWindow::new(&mut self.context, event_loop);
         self.game.update(&mut window, delta);
-        self.game.input_system().end_frame();
-        !window.close
+        let input = self.game.input_system();
+        if let Some(grabbed) = window.grab_request.take() {
+            input.set_pointer_grabbed(grabbed);
+            self.pointer_grabbed = grabbed;
+            self.grabbed_by_window = grabbed;
+        }
+        if input.pointer_grabbed() && !input.mouse_look_active() && !self.grabbed_by_window {
+            input.set_pointer_grabbed(false);
+        }
+        let pointer_grabbed = input.pointer_grabbed();
+        if pointer_grabbed != self.pointer_grabbed {
+            window.set_cursor_grab(pointer_grabbed);
+            self.pointer_grabbed = pointer_grabbed;
+            self.grabbed_by_window = false;
+        }
+        // Bind a "save_clip" action to save the last few seconds once clip recording is started.
+        #[cfg(feature = "clip-capture")]
+        let save_clip = input
+            .actions()
+            .try_get("save_clip")
+            .map_or(false, |state| state.just_pressed());
+        input.end_frame();
+        let running = !window.close;
+        // The game may have changed the internal resolution or fixed aspect.
+        self.update_pointer_area();
+        #[cfg(feature = "clip-capture")]
+        if save_clip && self.context.clip_settings().is_some() {
+            if let Err(error) = self.context.save_clip() {
+                log::warn!("Failed to save clip: {}", error);
+            }
+        }
+        running
     }
     fn event(&mut self, event: Event<()>) {
         match event {
             Event::WindowEvent {
+                window_id,
+                event: ref window_event,
+            } if window_id != self.context.main_window_id() => {
+                if let WindowEvent::Resized(_) = window_event {
+                    self.context.on_window_resize(window_id);
+                }
+                self.game.secondary_window_event(window_id, event);
+            }
+            Event::WindowEvent {
+                window_id,
                 event: WindowEvent::Resized(_),
+            } => {
+                self.context.on_window_resize(window_id);
+                self.update_pointer_area();
+            }
+            Event::WindowEvent {
+                event: WindowEvent::CursorMoved { position, .. },
                 ..
             } => {
-                self.context.on_resize();
+                let position = math::Vec2::new(position.x as f32, position.y as f32);
+                let position = self.context.window_to_viewport(position);
+                self.game
+                    .input_system()
+                    .handle_event(input::InputEvent::CursorMoved(position));
             }
             _ => self.game.input_system().input_event(event),
         }
     }
+    /// Give the input system the size of the viewport, which cursor positions are measured in.
+    fn update_pointer_area(&mut self) {
+        let size = self.context.viewport_pixel_size();
+        let input = self.game.input_system();
+        if input.window_size() != size {
+            input.set_window_size(size);
+        }
+    }
     fn render(&mut self) {
         self.context.render_game(&mut self.game);
+        for window in self.context.secondary_window_ids() {
+            self.game.render_secondary_window(&mut self.context, window);
+        }
     }
 
     fn start(self, event_loop: EventLoop<()>) -> ! {
         type InnerGameLoop<T> = game_loop::GameLoop<T, game_loop::Time, ()>;
         let mut game_loop = InnerGameLoop::new(self, 120, 0.1, ());
-        event_loop.run(move |event, _, control_flow| {
+        event_loop.run(move |event, event_loop, control_flow| {
             *control_flow = ControlFlow::Poll;
+            let main_window = game_loop.game.context.main_window_id();
             match event {
                 Event::WindowEvent {
+                    window_id,
                     event: WindowEvent::CloseRequested,
-                    ..
                 } => {
-                    *control_flow = ControlFlow::Exit;
+                    if window_id == main_window {
+                        *control_flow = ControlFlow::Exit;
+                    } else {
+                        game_loop.game.context.close_secondary_window(window_id);
+                    }
                 }
+                // Secondary windows are rendered along with the main window.
+                Event::RedrawRequested(window_id) if window_id != main_window => {}
                 Event::RedrawRequested(_) => {
                     if !game_loop.next_frame(
                         |g| {
-                            if !g.game.update(g.last_frame_time()) {
+                            if !g.game.update(g.last_frame_time(), event_loop) {
                                 g.exit();
                             }
                         },
@@ -98,6 +207,9 @@ impl<G: Game> GameLoop<G> {
                 Event::MainEventsCleared => {
                     game_loop.game.context.window().request_redraw();
                 }
+                Event::LoopDestroyed => {
+                    game_loop.game.context.save_window_state();
+                }
                 _ => {
                     game_loop.game.event(event);
                 }
@@ -118,6 +230,14 @@ fn init_logging() {
 }
 
 pub fn run_game<G, F>(f: F) -> !
+where
+    G: Game,
+    F: FnOnce(&mut RenderContext) -> G,
+{
+    run_game_with_settings(RenderSettings::default(), f)
+}
+
+pub fn run_game_with_settings<G, F>(settings: RenderSettings, f: F) -> !
 where
     G: Game,
     F: FnOnce(&mut RenderContext) -> G,
@@ -126,10 +246,18 @@ where
     log::info!("Starting up...");
 
     let event_loop = EventLoop::new();
-    let mut context = RenderContext::create_window(&event_loop);
+    let mut context = RenderContext::create_window_with_settings(&event_loop, settings);
     let game = f(&mut context);
     context.finish_setup();
 
     log::info!("Setup finished, entering main loop.");
-    GameLoop { game, context }.start(event_loop)
+    let mut game_loop = GameLoop {
+        game,
+        context,
+        pointer_grabbed: false,
+        grabbed_by_window: false,
+    };
+    // There's no resize event for the initial size.
+    game_loop.update_pointer_area();
+    game_loop.start(event_loop)
 }
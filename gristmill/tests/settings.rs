@@ -0,0 +1,81 @@
+use gristmill::gui::settings::{SettingField, SettingKind, SettingValue, Settings};
+use gristmill::{SettingChoice, Settings};
+
+#[derive(SettingChoice, Debug, PartialEq)]
+enum WindowMode {
+    Windowed,
+    #[setting(label = "Borderless window")]
+    Borderless,
+    Fullscreen,
+}
+
+#[derive(Settings)]
+struct Options {
+    #[setting(label = "Volume", min = 0, max = 100, step = 5)]
+    volume: u32,
+    show_fps: bool,
+    window_mode: WindowMode,
+    #[setting(skip)]
+    #[allow(dead_code)]
+    last_save: String,
+}
+
+fn options() -> Options {
+    Options {
+        volume: 80,
+        show_fps: false,
+        window_mode: WindowMode::Windowed,
+        last_save: String::new(),
+    }
+}
+
+#[test]
+fn fields_get_matching_controls() {
+    assert_eq!(
+        Options::setting_fields(),
+        vec![
+            SettingField {
+                name: "volume",
+                label: "Volume",
+                kind: SettingKind::Number {
+                    min: 0,
+                    max: 100,
+                    step: 5
+                },
+            },
+            SettingField {
+                name: "show_fps",
+                label: "Show fps",
+                kind: SettingKind::Toggle,
+            },
+            SettingField {
+                name: "window_mode",
+                label: "Window mode",
+                kind: SettingKind::Choice(&["Windowed", "Borderless window", "Fullscreen"]),
+            },
+        ]
+    );
+}
+
+#[test]
+fn settings_read_and_write_fields() {
+    let mut options = options();
+    assert_eq!(options.setting(0), SettingValue::Number(80));
+    assert_eq!(options.setting(1), SettingValue::Bool(false));
+    assert_eq!(options.setting(2), SettingValue::Choice(0));
+
+    options.set_setting(0, SettingValue::Number(35));
+    options.set_setting(1, SettingValue::Bool(true));
+    options.set_setting(2, SettingValue::Choice(1));
+    assert_eq!(options.volume, 35);
+    assert!(options.show_fps);
+    assert_eq!(options.window_mode, WindowMode::Borderless);
+
+    // Values of the wrong kind are ignored, and an out of range choice picks the first variant.
+    options.set_setting(0, SettingValue::Bool(false));
+    options.set_setting(1, SettingValue::Number(0));
+    assert_eq!(options.volume, 35);
+    assert!(options.show_fps);
+    options.set_setting(2, SettingValue::Choice(7));
+    assert_eq!(options.window_mode, WindowMode::Windowed);
+}